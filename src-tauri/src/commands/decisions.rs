@@ -1,10 +1,17 @@
-use crate::decisions::engine;
 use crate::decisions::model::{
-    DecisionAction, DecisionResult, PhotoDecisionStatus, PhotoDetail, PhotoSnapshot, RestoreResult,
-    RoundStatus, RoundSummary,
+    DecisionAction, DecisionResult, GemPromotion, GemStackSummary, PhotoDecisionStatus,
+    PhotoDetail, PhotoSnapshot, PreviewVariant, PromotionResult, QualityFlag, QualityFlagKind,
+    RestoreResult, RoundStatus, RoundSummary, SuggestionApplyResult,
 };
+use crate::decisions::{engine, gem, suggestions};
+use crate::import::{fullres, histogram, prefetch};
+use crate::photos::model::{PhotoFormat, PhotoHistogram};
+use crate::photos::repository;
 use crate::projects::manager;
 use crate::state::AppState;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
 use tauri::State;
 
 use super::with_open_project;
@@ -156,6 +163,136 @@ pub fn get_photo_detail(
     engine::get_photo_detail(conn, logical_photo_id, &cache_dir).map_err(|e| e.to_string())
 }
 
+/// Exposure histogram and clipping percentages of a logical photo's preview.
+/// Served from the thumbnail cache; computed from the source (and cached) for
+/// photos thumbnailed before histograms existed. None if the source is unreadable.
+#[tauri::command]
+pub fn get_photo_histogram(
+    slug: String,
+    logical_photo_id: i64,
+    state: State<'_, AppState>,
+) -> Result<Option<PhotoHistogram>, String> {
+    let cache_dir = manager::project_dir(&state.gemkeep_home, &slug)
+        .join("cache")
+        .join("thumbnails");
+
+    // Look up the source under the project lock, then release it: decoding a
+    // large source must not block every other command on the project DB
+    let (path, format) = {
+        let (db_guard, project_guard) = with_open_project(&state, &slug)?;
+        let conn = db_guard.as_ref().unwrap();
+        let project = project_guard.as_ref().unwrap();
+        if let Some(cached) = histogram::read_histogram(&cache_dir, logical_photo_id) {
+            return Ok(Some(cached));
+        }
+        let source = repository::list_representative_photos_for_lp_ids(
+            conn,
+            project.id,
+            &[logical_photo_id],
+        )
+        .map_err(|e| e.to_string())?;
+        let Some((_, path, format, _)) = source.into_iter().next() else {
+            return Err(format!("logical photo {} not found", logical_photo_id));
+        };
+        (path, format)
+    };
+    Ok(histogram::load_or_compute_histogram(
+        &cache_dir,
+        logical_photo_id,
+        &path,
+        &format,
+    ))
+}
+
+/// Path of one rendering of a logical photo, for SingleView's variant toggle.
+/// None if the photo has no such rendering (no paired JPEG, no RAW, no preview).
+///
+/// `raw_render` demosaics the RAW on first request, which takes seconds for a
+/// large file; it runs on the command thread pool and reuses `cache/fullres` after.
+#[tauri::command(async)]
+pub fn get_fullres_preview(
+    slug: String,
+    logical_photo_id: i64,
+    variant: PreviewVariant,
+    state: State<'_, AppState>,
+) -> Result<Option<String>, String> {
+    let project_dir = manager::project_dir(&state.gemkeep_home, &slug);
+    let format = match variant {
+        PreviewVariant::OocJpeg => PhotoFormat::Jpeg,
+        PreviewVariant::EmbeddedPreview | PreviewVariant::RawRender => PhotoFormat::Raw,
+    };
+    // Release the DB before a render so other commands are not blocked by it
+    let file = {
+        let (db_guard, _project_guard) = with_open_project(&state, &slug)?;
+        let conn = db_guard.as_ref().unwrap();
+        repository::find_photo_file(conn, logical_photo_id, &format).map_err(|e| e.to_string())?
+    };
+    let Some((path, orientation)) = file else {
+        return Ok(None);
+    };
+
+    let preview = match variant {
+        PreviewVariant::OocJpeg => Some(PathBuf::from(path)),
+        PreviewVariant::EmbeddedPreview => {
+            let preview = project_dir
+                .join("cache")
+                .join("thumbnails")
+                .join(format!("{}_preview.jpg", logical_photo_id));
+            preview.exists().then_some(preview)
+        }
+        PreviewVariant::RawRender => fullres::load_or_render_raw(
+            &fullres::fullres_dir(&project_dir),
+            logical_photo_id,
+            Path::new(&path),
+            orientation,
+            fullres::FULLRES_CACHE_MAX_BYTES,
+        ),
+    };
+    Ok(preview.map(|p| p.to_string_lossy().to_string()))
+}
+
+/// Warm the SingleView images around `logical_photo_id` in a round, nearest
+/// first, emitting `preview-ready` as each lands. Supersedes any earlier
+/// prefetch of the project, so the UI calls this on every focus change.
+#[tauri::command]
+pub fn prefetch_previews(
+    slug: String,
+    round_id: i64,
+    logical_photo_id: i64,
+    state: State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    let (db_guard, _project_guard) = with_open_project(&state, &slug)?;
+    let conn = db_guard.as_ref().unwrap();
+    let ctx = state.get_or_create_context(&slug);
+    let generation = ctx.prefetch_generation.fetch_add(1, Ordering::SeqCst) + 1;
+
+    let targets =
+        prefetch::prefetch_targets(conn, round_id, logical_photo_id, prefetch::PREFETCH_RADIUS)
+            .map_err(|e| e.to_string())?;
+    let cache_dir = manager::project_dir(&state.gemkeep_home, &slug)
+        .join("cache")
+        .join("thumbnails");
+    prefetch::spawn_prefetch(
+        targets,
+        cache_dir,
+        generation,
+        Arc::clone(&ctx.prefetch_generation),
+        Some(app_handle),
+    );
+    Ok(())
+}
+
+/// Drop all queued prefetch work of a project (e.g. when leaving SingleView).
+#[tauri::command]
+pub fn cancel_prefetch(slug: String, state: State<'_, AppState>) -> Result<(), String> {
+    state
+        .get_or_create_context(&slug)
+        .prefetch_generation
+        .fetch_add(1, Ordering::SeqCst);
+    Ok(())
+}
+
 /// Get round-scoped decisions for all photos in a specific round.
 /// Derives status from the decisions table per round, not from the materialized cache.
 #[tauri::command]
@@ -213,3 +350,265 @@ pub fn restore_eliminated_photo(
     engine::restore_eliminated_photo(conn, project.id, logical_photo_id, round_id)
         .map_err(|e| e.to_string())
 }
+
+/// Assign a custom tag to a logical photo (the tag is created on first use).
+#[tauri::command]
+pub fn add_photo_tag(
+    slug: String,
+    logical_photo_id: i64,
+    tag: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let (db_guard, project_guard) = with_open_project(&state, &slug)?;
+    let conn = db_guard.as_ref().unwrap();
+    let project = project_guard.as_ref().unwrap();
+
+    let tag = tag.trim();
+    if tag.is_empty() {
+        return Err("Tag must not be empty".to_string());
+    }
+    repository::add_tag_to_logical_photo(conn, project.id, logical_photo_id, tag)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// Remove a custom tag from a logical photo.
+#[tauri::command]
+pub fn remove_photo_tag(
+    slug: String,
+    logical_photo_id: i64,
+    tag: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let (db_guard, project_guard) = with_open_project(&state, &slug)?;
+    let conn = db_guard.as_ref().unwrap();
+    let project = project_guard.as_ref().unwrap();
+
+    repository::remove_tag_from_logical_photo(conn, project.id, logical_photo_id, tag.trim())
+        .map_err(|e| e.to_string())
+}
+
+/// List the tags assigned to a logical photo.
+#[tauri::command]
+pub fn list_photo_tags(
+    slug: String,
+    logical_photo_id: i64,
+    state: State<'_, AppState>,
+) -> Result<Vec<String>, String> {
+    let (db_guard, _project_guard) = with_open_project(&state, &slug)?;
+    let conn = db_guard.as_ref().unwrap();
+
+    repository::list_tags_for_logical_photo(conn, logical_photo_id).map_err(|e| e.to_string())
+}
+
+// ── GemStack ────────────────────────────────────────────────────────────────
+
+/// Promote the survivors of a stack's current round to the GemStack.
+/// Replaces any earlier promotions from the same stack.
+#[tauri::command]
+pub fn promote_survivors(
+    slug: String,
+    stack_id: i64,
+    state: State<'_, AppState>,
+) -> Result<PromotionResult, String> {
+    let (db_guard, project_guard) = with_open_project(&state, &slug)?;
+    let conn = db_guard.as_ref().unwrap();
+    let project = project_guard.as_ref().unwrap();
+
+    let result = gem::promote_survivors(conn, project.id, stack_id).map_err(|e| e.to_string())?;
+
+    manager::append_operation_log(
+        &state.gemkeep_home,
+        &slug,
+        &format!(
+            "SURVIVORS_PROMOTED stack={} round={} promoted={} removed={}",
+            stack_id,
+            result.source_round_id,
+            result.promoted.len(),
+            result.removed.len()
+        ),
+    );
+
+    Ok(result)
+}
+
+/// Get the project's GemStack id and member counts.
+#[tauri::command]
+pub fn get_gem_stack(slug: String, state: State<'_, AppState>) -> Result<GemStackSummary, String> {
+    let (db_guard, project_guard) = with_open_project(&state, &slug)?;
+    let conn = db_guard.as_ref().unwrap();
+    let project = project_guard.as_ref().unwrap();
+
+    gem::get_gem_stack_summary(conn, project.id).map_err(|e| e.to_string())
+}
+
+/// List provenance (source stack + round) for every GemStack member.
+#[tauri::command]
+pub fn list_gem_promotions(
+    slug: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<GemPromotion>, String> {
+    let (db_guard, project_guard) = with_open_project(&state, &slug)?;
+    let conn = db_guard.as_ref().unwrap();
+    let project = project_guard.as_ref().unwrap();
+
+    gem::list_gem_promotions(conn, project.id).map_err(|e| e.to_string())
+}
+
+/// Record a keep or eliminate decision in the open GemStack round. Only
+/// members of that round can be decided.
+#[tauri::command]
+pub fn make_gem_decision(
+    slug: String,
+    logical_photo_id: i64,
+    action: String,
+    state: State<'_, AppState>,
+) -> Result<DecisionResult, String> {
+    let (db_guard, project_guard) = with_open_project(&state, &slug)?;
+    let conn = db_guard.as_ref().unwrap();
+    let project = project_guard.as_ref().unwrap();
+
+    let decision_action = match action.as_str() {
+        "keep" => DecisionAction::Keep,
+        "eliminate" => DecisionAction::Eliminate,
+        _ => return Err(format!("Invalid action: {}", action)),
+    };
+
+    let (decision_id, round_id, was_created) =
+        gem::record_gem_decision(conn, project.id, logical_photo_id, &decision_action)
+            .map_err(|e| e.to_string())?;
+
+    // GemStack statuses live in the round's decisions, not in current_status
+    Ok(DecisionResult {
+        decision_id,
+        round_id,
+        action: decision_action.as_str().to_string(),
+        current_status: decision_action.as_str().to_string(),
+        round_auto_created: was_created,
+    })
+}
+
+/// Undo the last decision for a photo in the open GemStack round.
+#[tauri::command]
+pub fn undo_gem_decision(
+    slug: String,
+    logical_photo_id: i64,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let (db_guard, project_guard) = with_open_project(&state, &slug)?;
+    let conn = db_guard.as_ref().unwrap();
+    let project = project_guard.as_ref().unwrap();
+
+    gem::undo_gem_decision(conn, project.id, logical_photo_id).map_err(|e| e.to_string())
+}
+
+/// Commit (seal) the open GemStack round.
+#[tauri::command]
+pub fn commit_gem_round(slug: String, state: State<'_, AppState>) -> Result<(), String> {
+    let (db_guard, project_guard) = with_open_project(&state, &slug)?;
+    let conn = db_guard.as_ref().unwrap();
+    let project = project_guard.as_ref().unwrap();
+
+    let (round_id, _) =
+        gem::find_or_create_gem_round(conn, project.id).map_err(|e| e.to_string())?;
+
+    engine::commit_round(conn, round_id).map_err(|e| e.to_string())
+}
+
+/// Get the GemStack round status. Read-only: returns a "none" status before
+/// the first GemStack round exists.
+#[tauri::command]
+pub fn get_gem_round_status(
+    slug: String,
+    state: State<'_, AppState>,
+) -> Result<RoundStatus, String> {
+    let (db_guard, project_guard) = with_open_project(&state, &slug)?;
+    let conn = db_guard.as_ref().unwrap();
+    let project = project_guard.as_ref().unwrap();
+
+    gem::get_gem_round_status(conn, project.id).or_else(|_| {
+        Ok(RoundStatus {
+            round_id: 0,
+            round_number: 0,
+            state: "none".to_string(),
+            total_photos: 0,
+            decided: 0,
+            kept: 0,
+            eliminated: 0,
+            undecided: 0,
+            committed_at: None,
+        })
+    })
+}
+
+/// List all GemStack rounds with summary counts.
+#[tauri::command]
+pub fn list_gem_rounds(
+    slug: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<RoundSummary>, String> {
+    let (db_guard, project_guard) = with_open_project(&state, &slug)?;
+    let conn = db_guard.as_ref().unwrap();
+    let project = project_guard.as_ref().unwrap();
+
+    gem::list_gem_rounds(conn, project.id).map_err(|e| e.to_string())
+}
+
+// ── Quality suggestions ─────────────────────────────────────────────────────
+
+/// List the advisory quality flags on a stack's logical photos.
+#[tauri::command]
+pub fn list_quality_flags(
+    slug: String,
+    stack_id: i64,
+    state: State<'_, AppState>,
+) -> Result<Vec<QualityFlag>, String> {
+    let (db_guard, _project_guard) = with_open_project(&state, &slug)?;
+    let conn = db_guard.as_ref().unwrap();
+
+    suggestions::list_quality_flags_for_stack(conn, stack_id).map_err(|e| e.to_string())
+}
+
+/// Eliminate the undecided photos of a stack's open round that carry one of
+/// `kinds` with at least `min_confidence`. Opt-in: flags never decide on their own.
+#[tauri::command]
+pub fn apply_quality_suggestions(
+    slug: String,
+    stack_id: i64,
+    kinds: Vec<QualityFlagKind>,
+    min_confidence: f64,
+    state: State<'_, AppState>,
+) -> Result<SuggestionApplyResult, String> {
+    if !(0.0..=1.0).contains(&min_confidence) {
+        return Err(format!(
+            "min_confidence must be between 0 and 1, got {}",
+            min_confidence
+        ));
+    }
+    let (db_guard, project_guard) = with_open_project(&state, &slug)?;
+    let conn = db_guard.as_ref().unwrap();
+    let project = project_guard.as_ref().unwrap();
+
+    let result = suggestions::apply_suggestions(conn, project.id, stack_id, &kinds, min_confidence)
+        .map_err(|e| e.to_string())?;
+
+    manager::append_operation_log(
+        &state.gemkeep_home,
+        &slug,
+        &format!(
+            "SUGGESTIONS_APPLIED stack={} round={} kinds={} min_confidence={} eliminated={} skipped={}",
+            stack_id,
+            result.round_id,
+            kinds
+                .iter()
+                .map(|k| k.as_str())
+                .collect::<Vec<_>>()
+                .join(","),
+            min_confidence,
+            result.eliminated.len(),
+            result.skipped_decided.len()
+        ),
+    );
+
+    Ok(result)
+}
@@ -0,0 +1,137 @@
+use crate::export::engine;
+use crate::export::model::{ExportFilter, ExportMode, ExportOptions, ExportResult};
+use crate::projects::manager;
+use crate::state::AppState;
+use crate::xmp::model::{SidecarNaming, XmpWriteResult};
+use tauri::State;
+
+use super::with_open_project;
+
+/// Export survivors to a destination folder.
+/// `round_id` = None exports the last committed round of every active stack.
+/// With `dry_run` the plan is returned and nothing is written.
+/// `xmp_naming` ("append" | "replace") also writes a sidecar per exported file.
+/// `mode`, `filter` and `preserve_hierarchy` default to the project's export settings.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn export_survivors(
+    slug: String,
+    destination: String,
+    mode: Option<String>,
+    filter: Option<String>,
+    round_id: Option<i64>,
+    preserve_hierarchy: Option<bool>,
+    dry_run: bool,
+    xmp_naming: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<ExportResult, String> {
+    let (db_guard, project_guard) = with_open_project(&state, &slug)?;
+    let conn = db_guard.as_ref().unwrap();
+    let project = project_guard.as_ref().unwrap();
+
+    let settings = super::projects::effective_settings(conn, project.id, &state.gemkeep_home)?;
+    let mode = match mode {
+        Some(m) => ExportMode::parse(&m).ok_or_else(|| format!("Invalid export mode: {}", m))?,
+        None => settings.export_mode,
+    };
+    let filter = match filter {
+        Some(f) => {
+            ExportFilter::parse(&f).ok_or_else(|| format!("Invalid export filter: {}", f))?
+        }
+        None => settings.export_filter,
+    };
+    let preserve_hierarchy = preserve_hierarchy.unwrap_or(settings.export_preserve_hierarchy);
+    let sidecars = xmp_naming
+        .as_deref()
+        .map(|n| SidecarNaming::parse(n).ok_or_else(|| format!("Invalid XMP naming: {}", n)))
+        .transpose()?;
+    if destination.trim().is_empty() {
+        return Err("Export destination must not be empty".to_string());
+    }
+
+    let options = ExportOptions {
+        destination: std::path::PathBuf::from(&destination),
+        mode,
+        filter,
+        round_id,
+        preserve_hierarchy,
+        dry_run,
+        sidecars,
+    };
+    let result = engine::run_export(conn, project.id, &options).map_err(|e| e.to_string())?;
+
+    if !dry_run {
+        manager::append_operation_log(
+            &state.gemkeep_home,
+            &slug,
+            &format!(
+                "EXPORT dest={} mode={} filter={} files={} errors={}",
+                destination,
+                mode.as_str(),
+                filter.as_str(),
+                result.files_exported,
+                result.errors.len()
+            ),
+        );
+    }
+
+    tracing::info!(
+        "export_survivors: slug={} dry_run={} files={}",
+        slug,
+        dry_run,
+        result.items.len()
+    );
+    Ok(result)
+}
+
+/// Write XMP sidecars next to the source files of logical photos.
+/// `logical_photo_ids` = None covers every photo that has been part of a round.
+/// Existing sidecars are merged into, never replaced.
+#[tauri::command]
+pub fn write_xmp_sidecars(
+    slug: String,
+    logical_photo_ids: Option<Vec<i64>>,
+    naming: String,
+    state: State<'_, AppState>,
+) -> Result<XmpWriteResult, String> {
+    let (db_guard, project_guard) = with_open_project(&state, &slug)?;
+    let conn = db_guard.as_ref().unwrap();
+    let project = project_guard.as_ref().unwrap();
+
+    let naming =
+        SidecarNaming::parse(&naming).ok_or_else(|| format!("Invalid XMP naming: {}", naming))?;
+
+    let lp_ids = match logical_photo_ids {
+        Some(ids) => ids,
+        None => {
+            let mut stmt = conn
+                .prepare(
+                    "SELECT DISTINCT rp.logical_photo_id FROM round_photos rp
+                     JOIN rounds r ON r.id = rp.round_id
+                     WHERE r.project_id = ?1
+                     ORDER BY rp.logical_photo_id",
+                )
+                .map_err(|e| e.to_string())?;
+            let ids = stmt
+                .query_map(rusqlite::params![project.id], |row| row.get(0))
+                .map_err(|e| e.to_string())?
+                .collect::<rusqlite::Result<Vec<i64>>>()
+                .map_err(|e| e.to_string())?;
+            ids
+        }
+    };
+
+    let result = crate::xmp::writer::write_sidecars_for_logical_photos(conn, &lp_ids, naming)
+        .map_err(|e| e.to_string())?;
+
+    manager::append_operation_log(
+        &state.gemkeep_home,
+        &slug,
+        &format!(
+            "XMP_WRITTEN sidecars={} errors={}",
+            result.sidecars_written,
+            result.errors.len()
+        ),
+    );
+    Ok(result)
+}
@@ -1,11 +1,20 @@
 use crate::db::{open_connection, run_migrations};
-use crate::import::pipeline;
-use crate::photos::model::{IndexingStatus, LogicalPhotoSummary, SourceFolderRow, StackSummary};
+use crate::import::jobs::{self, JobState};
+use crate::import::stacks::StackingRules;
+use crate::import::{phash, pipeline, verify};
+use crate::photos::model::{
+    BurstGapSuggestion, ClockShift, IndexingStatus, LogicalPhotoSummary, PhotoOrder,
+    ReconnectedFolder, RelinkResult, RestackPreview, SourceFolderRow, SourceVerification,
+    StackSummary, StackingStrategy,
+};
 use crate::photos::repository;
 use crate::projects::manager;
+use crate::projects::model::ProjectSettingsOverrides;
+use crate::scheduler::{Cleanup, JobKind, JobPriority, JobSpec};
 use crate::state::AppState;
 use rusqlite::Connection;
-use std::sync::atomic::Ordering;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
 use tauri::{Manager, State};
 
 use super::with_open_project;
@@ -67,7 +76,9 @@ pub fn add_source_folder(
         return Err(format!("Folder already attached: {}", path));
     }
 
-    repository::add_source_folder(conn, project.id, &path).map_err(|e| e.to_string())?;
+    let folder_id =
+        repository::add_source_folder(conn, project.id, &path).map_err(|e| e.to_string())?;
+    verify::record_volume(conn, folder_id, p).map_err(|e| e.to_string())?;
 
     manager::append_operation_log(
         &state.gemkeep_home,
@@ -153,16 +164,127 @@ pub fn list_source_folders(
     repository::list_source_folders(conn, project.id).map_err(|e| e.to_string())
 }
 
+/// Re-find offline source folders whose volume is mounted again, possibly under
+/// a different mount point. Called by the frontend when a drive is plugged in.
+#[tauri::command]
+pub fn reconnect_sources(
+    slug: String,
+    state: State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<ReconnectedFolder>, String> {
+    let (db_guard, project_guard) = with_open_project(&state, &slug)?;
+    let conn = db_guard.as_ref().unwrap();
+    let project = project_guard.as_ref().unwrap();
+
+    let reconnected =
+        verify::reconnect_source_folders(conn, project.id).map_err(|e| e.to_string())?;
+    for folder in &reconnected {
+        expand_asset_scope(&app_handle, std::path::Path::new(&folder.new_path));
+        log_reconnected(&state.gemkeep_home, &slug, folder);
+    }
+    Ok(reconnected)
+}
+
+pub(crate) fn log_reconnected(home: &std::path::Path, slug: &str, folder: &ReconnectedFolder) {
+    manager::append_operation_log(
+        home,
+        slug,
+        &format!(
+            "SOURCE_FOLDER_RECONNECTED old={} new={} photos={}",
+            folder.old_path, folder.new_path, folder.photos_updated
+        ),
+    );
+}
+
+// ── Source verification ───────────────────────────────────────────────────────
+
+/// Search roots for moved files: every attached source folder plus `extra`.
+fn search_roots(
+    conn: &Connection,
+    project_id: i64,
+    extra: Option<Vec<String>>,
+) -> Result<Vec<std::path::PathBuf>, String> {
+    let folders = repository::list_source_folders(conn, project_id).map_err(|e| e.to_string())?;
+    Ok(folders
+        .into_iter()
+        .map(|f| f.path)
+        .chain(extra.unwrap_or_default())
+        .map(std::path::PathBuf::from)
+        .collect())
+}
+
+/// Report missing, moved (found elsewhere by fingerprint) and changed source files.
+/// `search_paths` are searched for moved files in addition to the source folders.
+#[tauri::command]
+pub fn verify_sources(
+    slug: String,
+    search_paths: Option<Vec<String>>,
+    state: State<'_, AppState>,
+) -> Result<SourceVerification, String> {
+    let (db_guard, project_guard) = with_open_project(&state, &slug)?;
+    let conn = db_guard.as_ref().unwrap();
+    let project = project_guard.as_ref().unwrap();
+
+    let roots = search_roots(conn, project.id, search_paths)?;
+    verify::verify_sources(conn, project.id, &roots).map_err(|e| e.to_string())
+}
+
+/// Point moved files at their new paths. Decisions, rounds and stacks are untouched.
+#[tauri::command]
+pub fn relink_sources(
+    slug: String,
+    search_paths: Option<Vec<String>>,
+    state: State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<RelinkResult, String> {
+    let (db_guard, project_guard) = with_open_project(&state, &slug)?;
+    let conn = db_guard.as_ref().unwrap();
+    let project = project_guard.as_ref().unwrap();
+
+    let roots = search_roots(conn, project.id, search_paths)?;
+    let result = verify::relink_moved_files(conn, project.id, &roots).map_err(|e| e.to_string())?;
+
+    for folder in &result.folders_added {
+        expand_asset_scope(&app_handle, std::path::Path::new(folder));
+    }
+    manager::append_operation_log(
+        &state.gemkeep_home,
+        &slug,
+        &format!(
+            "SOURCES_RELINKED relinked={} still_missing={}",
+            result.relinked, result.still_missing
+        ),
+    );
+    Ok(result)
+}
+
 // ── Indexing ──────────────────────────────────────────────────────────────────
 
+/// Index the project's source folders. Runs incrementally, keeping stacks,
+/// rounds and decisions, unless `full_rebuild` asks to rebuild the stacks and
+/// logical photos from scratch.
 #[tauri::command]
 pub fn start_indexing(
     slug: String,
+    full_rebuild: Option<bool>,
     state: State<'_, AppState>,
     app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    let incremental = !full_rebuild.unwrap_or(false);
+    spawn_indexing(&slug, &state, &app_handle, incremental, false)
+}
+
+/// Start an indexing run in the background, held at its first checkpoint if
+/// `paused` (a paused job resumed after a restart).
+fn spawn_indexing(
+    slug: &str,
+    state: &AppState,
+    app_handle: &tauri::AppHandle,
+    incremental: bool,
+    paused: bool,
 ) -> Result<(), String> {
     // Get per-project context
-    let ctx = state.get_or_create_context(&slug);
+    let ctx = state.get_or_create_context(slug);
 
     // Guard: already running?
     {
@@ -176,42 +298,46 @@ pub fn start_indexing(
     }
 
     // Collect everything needed for the background thread while locks are held.
-    let (project_id, project_dir, folder_paths, burst_gap_secs) = {
-        let (db_guard, project_guard) = with_open_project(&state, &slug)?;
+    let (project_id, project_dir, folder_paths, settings) = {
+        let (db_guard, project_guard) = with_open_project(state, slug)?;
         let conn = db_guard.as_ref().unwrap();
         let project = project_guard.as_ref().unwrap();
 
+        for folder in verify::reconnect_source_folders(conn, project.id).unwrap_or_default() {
+            expand_asset_scope(app_handle, std::path::Path::new(&folder.new_path));
+            log_reconnected(&state.gemkeep_home, slug, &folder);
+        }
+
         let folders =
             repository::list_source_folders(conn, project.id).map_err(|e| e.to_string())?;
 
         if folders.is_empty() {
             return Err("No source folders attached to this project".to_string());
         }
+        if !folders.iter().any(|f| f.online) {
+            return Err("All source folders are offline".to_string());
+        }
 
         let folder_paths: Vec<std::path::PathBuf> = folders
             .iter()
             .map(|f| std::path::PathBuf::from(&f.path))
             .collect();
 
-        let config = manager::read_config(&state.gemkeep_home).unwrap_or_default();
-        let burst_gap_secs = config.burst_gap_secs;
-        let project_dir = manager::project_dir(&state.gemkeep_home, &slug);
+        let settings = super::projects::effective_settings(conn, project.id, &state.gemkeep_home)?;
+        let project_dir = manager::project_dir(&state.gemkeep_home, slug);
         let project_id = project.id;
 
-        (project_id, project_dir, folder_paths, burst_gap_secs)
+        (project_id, project_dir, folder_paths, settings)
     };
 
-    // Clear stale thumbnail cache so re-index starts fresh
-    let thumb_dir = project_dir.join("cache").join("thumbnails");
-    if thumb_dir.exists() {
-        if let Err(e) = std::fs::remove_dir_all(&thumb_dir) {
-            tracing::warn!("start_indexing: cannot clear thumbnail cache: {}", e);
-        }
-    }
+    // The cache is kept: an incremental run keeps logical photo ids, and a full
+    // rebuild reuses thumbnails by source fingerprint.
+    std::fs::create_dir_all(project_dir.join("cache").join("thumbnails"))
+        .map_err(|e| e.to_string())?;
 
     // Reset cancel and pause flags, then mark as running
     ctx.cancel_indexing.store(false, Ordering::SeqCst);
-    ctx.pause_indexing.store(false, Ordering::SeqCst);
+    ctx.pause_indexing.store(paused, Ordering::SeqCst);
     ctx.thumbnails_done_counter.store(0, Ordering::SeqCst);
     {
         let mut status = ctx
@@ -225,7 +351,7 @@ pub fn start_indexing(
             processed: 0,
             errors: 0,
             cancelled: false,
-            paused: false,
+            paused,
             last_stats: None,
             thumbnails_total: 0,
             thumbnails_done: 0,
@@ -235,23 +361,36 @@ pub fn start_indexing(
     // Log start
     manager::append_operation_log(
         &state.gemkeep_home,
-        &slug,
+        slug,
         &format!(
             "INDEX_STARTED folders={} burst_gap_secs={}",
             folder_paths.len(),
-            burst_gap_secs
+            settings.burst_gap_secs
         ),
     );
 
-    // Clone Arcs for the background thread — no reference to AppState or State<> crosses the boundary
+    // Clone Arcs for the job — no reference to AppState or State<> crosses the boundary
     let status_arc = std::sync::Arc::clone(&ctx.indexing_status);
     let cancel_arc = std::sync::Arc::clone(&ctx.cancel_indexing);
     let pause_arc = std::sync::Arc::clone(&ctx.pause_indexing);
     let done_counter = std::sync::Arc::clone(&ctx.thumbnails_done_counter);
     let gemkeep_home = state.gemkeep_home.clone();
+    let slug = slug.to_string();
     let app_handle = app_handle.clone();
+    let spec = JobSpec {
+        slug: slug.clone(),
+        kind: JobKind::Indexing,
+        priority: JobPriority::Normal,
+        cancel: std::sync::Arc::clone(&cancel_arc),
+        app_handle: Some(app_handle.clone()),
+        on_cancel: Some(cancel_queued_indexing(
+            std::sync::Arc::clone(&status_arc),
+            project_dir.join("project.db"),
+            project_id,
+        )),
+    };
 
-    std::thread::spawn(move || {
+    state.scheduler.submit(spec, move |job| {
         // Open a fresh DB connection in the background thread.
         // rusqlite::Connection is !Send, so we cannot move the main connection.
         let db_path = project_dir.join("project.db");
@@ -265,27 +404,34 @@ pub fn start_indexing(
                     s.last_stats = Some(crate::photos::model::ImportStats {
                         errors: 1,
                         error_log: vec![format!("Cannot open DB: {}", e)],
+                        failed: true,
                         ..Default::default()
                     });
                 }
-                return;
+                return Err(format!("Cannot open DB: {}", e));
             }
         };
         if let Err(e) = run_migrations(&conn) {
             tracing::warn!("start_indexing background: migrations: {}", e);
         }
 
-        let stats = pipeline::run_pipeline(
-            &conn,
-            project_id,
-            &project_dir,
-            folder_paths.clone(),
-            burst_gap_secs,
-            std::sync::Arc::clone(&status_arc),
-            std::sync::Arc::clone(&cancel_arc),
-            std::sync::Arc::clone(&pause_arc),
-            Some(app_handle),
-            done_counter,
+        let stats = job.track(
+            || indexing_progress(&status_arc, &done_counter),
+            || {
+                pipeline::run_pipeline(
+                    &conn,
+                    project_id,
+                    &project_dir,
+                    folder_paths.clone(),
+                    &settings,
+                    incremental,
+                    std::sync::Arc::clone(&status_arc),
+                    std::sync::Arc::clone(&cancel_arc),
+                    std::sync::Arc::clone(&pause_arc),
+                    Some(app_handle),
+                    std::sync::Arc::clone(&done_counter),
+                )
+            },
         );
 
         // Log completion
@@ -294,6 +440,11 @@ pub fn start_indexing(
                 "INDEX_CANCELLED processed={} total={}",
                 stats.imported, stats.total_files_scanned
             )
+        } else if stats.failed {
+            format!(
+                "INDEX_FAILED processed={} errors={}",
+                stats.imported, stats.errors
+            )
         } else {
             format!(
                 "INDEX_COMPLETED photos={} logical_photos={} stacks={} errors={}",
@@ -305,17 +456,87 @@ pub fn start_indexing(
         };
         manager::append_operation_log(&gemkeep_home, &slug, &event);
 
+        // A run stopped by a stage error fails its job, with the error that stopped it
+        let result = if stats.failed {
+            Err(stats
+                .error_log
+                .last()
+                .cloned()
+                .unwrap_or_else(|| "indexing failed".to_string()))
+        } else {
+            Ok(())
+        };
+
         // Mark indexing as done
         if let Ok(mut s) = status_arc.lock() {
             s.running = false;
             s.cancelled = stats.cancelled;
             s.last_stats = Some(stats);
         }
+        result
     });
 
     Ok(())
 }
 
+/// `on_cancel` of an indexing job. A job cancelled while queued never runs the
+/// code that clears `running`, which would reject every later start.
+fn cancel_queued_indexing(
+    status: std::sync::Arc<Mutex<IndexingStatus>>,
+    db_path: std::path::PathBuf,
+    project_id: i64,
+) -> Cleanup {
+    Box::new(move || {
+        if let Ok(mut s) = status.lock() {
+            s.running = false;
+            s.cancelled = true;
+        }
+        cancel_unfinished_import_job(&db_path, project_id);
+    })
+}
+
+/// `on_cancel` of a thumbnail job, as `cancel_queued_indexing` for `thumbnails_running`.
+fn cancel_queued_thumbnails(
+    status: std::sync::Arc<Mutex<IndexingStatus>>,
+    db_path: std::path::PathBuf,
+    project_id: i64,
+) -> Cleanup {
+    Box::new(move || {
+        if let Ok(mut s) = status.lock() {
+            s.thumbnails_running = false;
+        }
+        cancel_unfinished_import_job(&db_path, project_id);
+    })
+}
+
+/// Mark the import job a cancelled queued run would have resumed as cancelled,
+/// as cancelling it midway does, so it is not resumed on the next open.
+fn cancel_unfinished_import_job(db_path: &std::path::Path, project_id: i64) {
+    let result = open_connection(db_path).and_then(|conn| {
+        jobs::set_unfinished_job_state(&conn, project_id, JobState::Cancelled)?;
+        Ok(())
+    });
+    if let Err(e) = result {
+        tracing::warn!("cannot cancel import job of project {}: {}", project_id, e);
+    }
+}
+
+/// `(done, total)` of an indexing run for its job: files until the thumbnail
+/// stage starts, then thumbnails.
+fn indexing_progress(
+    status: &Mutex<IndexingStatus>,
+    thumbnails_done: &AtomicUsize,
+) -> (usize, usize) {
+    let Ok(s) = status.lock() else {
+        return (0, 0);
+    };
+    if s.thumbnails_running {
+        (thumbnails_done.load(Ordering::Relaxed), s.thumbnails_total)
+    } else {
+        (s.processed, s.total)
+    }
+}
+
 #[tauri::command]
 pub fn cancel_indexing(slug: String, state: State<'_, AppState>) -> Result<(), String> {
     let ctx = state.get_or_create_context(&slug);
@@ -333,6 +554,7 @@ pub fn pause_indexing(slug: String, state: State<'_, AppState>) -> Result<(), St
     if let Ok(mut s) = ctx.indexing_status.lock() {
         s.paused = true;
     }
+    persist_job_state(&state, &slug, JobState::Paused);
     tracing::info!("pause_indexing: slug={} signal sent", slug);
     Ok(())
 }
@@ -344,10 +566,90 @@ pub fn resume_indexing(slug: String, state: State<'_, AppState>) -> Result<(), S
     if let Ok(mut s) = ctx.indexing_status.lock() {
         s.paused = false;
     }
+    persist_job_state(&state, &slug, JobState::Running);
     tracing::info!("resume_indexing: slug={} signal sent", slug);
     Ok(())
 }
 
+/// Record a pause or resume on the project's unfinished import job, so a job
+/// paused when the app exits comes back paused.
+fn persist_job_state(state: &AppState, slug: &str, job_state: JobState) {
+    let Ok((db_guard, project_guard)) = with_open_project(state, slug) else {
+        return;
+    };
+    let conn = db_guard.as_ref().unwrap();
+    let project = project_guard.as_ref().unwrap();
+    if let Err(e) = jobs::set_unfinished_job_state(conn, project.id, job_state) {
+        tracing::warn!("cannot record import job {}: {}", job_state.as_str(), e);
+    }
+}
+
+/// Resume the project's import job left running or paused by an earlier
+/// session, from the last stage it completed. Returns false if there was none.
+pub(crate) fn resume_interrupted_import(
+    slug: &str,
+    state: &AppState,
+    app_handle: &tauri::AppHandle,
+) -> Result<bool, String> {
+    {
+        let ctx = state.get_or_create_context(slug);
+        let status = ctx
+            .indexing_status
+            .lock()
+            .map_err(|_| "lock poisoned".to_string())?;
+        if status.running || status.thumbnails_running {
+            return Ok(false);
+        }
+    }
+    let job = {
+        let (db_guard, project_guard) = with_open_project(state, slug)?;
+        let conn = db_guard.as_ref().unwrap();
+        let project = project_guard.as_ref().unwrap();
+        jobs::unfinished_job(conn, project.id).map_err(|e| e.to_string())?
+    };
+    let Some(job) = job else {
+        return Ok(false);
+    };
+    let paused = job.state == JobState::Paused;
+    tracing::info!(
+        "resuming import job {} of {} after stage {:?} (paused={} full_rebuild={})",
+        job.id,
+        slug,
+        job.completed_stage.map(|s| s.as_str()),
+        paused,
+        job.full_rebuild
+    );
+    if job.is_persisted() {
+        spawn_thumbnails(slug, state, app_handle, paused)?;
+    } else {
+        // The earlier stages keep their results in memory only; re-running them
+        // skips every file already persisted. A full rebuild is run again as one.
+        spawn_indexing(slug, state, app_handle, !job.full_rebuild, paused)?;
+    }
+    Ok(true)
+}
+
+/// Finish the import job whose thumbnail stage a `spawn_thumbnails` run took over.
+fn finish_thumbnail_job(conn: &Connection, project_id: i64, cancelled: bool) {
+    let job = match jobs::unfinished_job(conn, project_id) {
+        Ok(Some(job)) if job.is_persisted() => job,
+        Ok(_) => return,
+        Err(e) => {
+            tracing::warn!("cannot load import job: {}", e);
+            return;
+        }
+    };
+    let result = if cancelled {
+        jobs::set_job_state(conn, job.id, JobState::Cancelled)
+    } else {
+        jobs::complete_stage(conn, job.id, jobs::ImportStage::Thumbnails)
+            .and_then(|_| jobs::set_job_state(conn, job.id, JobState::Completed))
+    };
+    if let Err(e) = result {
+        tracing::warn!("cannot finish import job {}: {}", job.id, e);
+    }
+}
+
 #[tauri::command]
 pub fn get_indexing_status(
     slug: String,
@@ -385,7 +687,10 @@ pub(crate) fn find_missing_thumbnail_targets(
     ),
     String,
 > {
-    pipeline::find_missing_thumbnail_targets(conn, project_id, cache_dir)
+    let pyramid = crate::import::pyramid::ThumbnailPyramid::from_settings(
+        &pipeline::pipeline_settings(conn, project_id),
+    );
+    pipeline::find_missing_thumbnail_targets(conn, project_id, cache_dir, &pyramid)
 }
 
 #[tauri::command]
@@ -393,9 +698,20 @@ pub fn resume_thumbnails(
     slug: String,
     state: State<'_, AppState>,
     app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    spawn_thumbnails(&slug, &state, &app_handle, false)
+}
+
+/// Generate missing thumbnails in the background, held before the first photo
+/// if `paused`. Completes an unfinished import job that was past its persist stage.
+fn spawn_thumbnails(
+    slug: &str,
+    state: &AppState,
+    app_handle: &tauri::AppHandle,
+    paused: bool,
 ) -> Result<(), String> {
     // Get per-project context
-    let ctx = state.get_or_create_context(&slug);
+    let ctx = state.get_or_create_context(slug);
 
     // Guard: already running?
     {
@@ -409,23 +725,35 @@ pub fn resume_thumbnails(
     }
 
     // Collect data while holding locks
-    let (lp_targets, cache_dir) = {
-        let (db_guard, project_guard) = with_open_project(&state, &slug)?;
+    let (lp_targets, cache_dir, db_path, pyramid, project_id) = {
+        let (db_guard, project_guard) = with_open_project(state, slug)?;
         let conn = db_guard.as_ref().unwrap();
         let project = project_guard.as_ref().unwrap();
-        let project_dir = manager::project_dir(&state.gemkeep_home, &slug);
+        let project_dir = manager::project_dir(&state.gemkeep_home, slug);
         let cache_dir = project_dir.join("cache").join("thumbnails");
 
+        // Reuse files made for the same content under earlier ids before
+        // deciding what is missing
+        if let Err(e) = crate::import::artifacts::reconcile_cache(conn, project.id, &project_dir) {
+            tracing::warn!("resume_thumbnails: reconcile thumbnail cache: {}", e);
+        }
+
         let (targets, total_lp_count) =
             find_missing_thumbnail_targets(conn, project.id, &cache_dir)?;
+        let pyramid = crate::import::pyramid::ThumbnailPyramid::from_settings(
+            &pipeline::pipeline_settings(conn, project.id),
+        );
 
         if targets.is_empty() {
+            finish_thumbnail_job(conn, project.id, false);
             return Ok(());
         }
 
         let existing_count = total_lp_count - targets.len();
 
         // Mark as running
+        ctx.cancel_indexing.store(false, Ordering::SeqCst);
+        ctx.pause_indexing.store(paused, Ordering::SeqCst);
         {
             let mut s = ctx
                 .indexing_status
@@ -434,34 +762,103 @@ pub fn resume_thumbnails(
             s.thumbnails_running = true;
             s.thumbnails_total = total_lp_count;
             s.thumbnails_done = 0;
+            s.paused = paused;
         }
         ctx.thumbnails_done_counter
             .store(existing_count, Ordering::SeqCst);
 
-        (targets, cache_dir)
+        (
+            targets,
+            cache_dir,
+            project_dir.join("project.db"),
+            pyramid,
+            project.id,
+        )
     };
     std::fs::create_dir_all(&cache_dir).ok();
 
-    // Clone Arcs for the background thread
+    // Clone Arcs for the job
     let status_arc = std::sync::Arc::clone(&ctx.indexing_status);
     let cancel_arc = std::sync::Arc::clone(&ctx.cancel_indexing);
+    let pause_arc = std::sync::Arc::clone(&ctx.pause_indexing);
     let done_counter = std::sync::Arc::clone(&ctx.thumbnails_done_counter);
     let app_handle = app_handle.clone();
+    let spec = JobSpec {
+        slug: slug.to_string(),
+        kind: JobKind::Thumbnails,
+        priority: JobPriority::Low,
+        cancel: std::sync::Arc::clone(&cancel_arc),
+        app_handle: Some(app_handle.clone()),
+        on_cancel: Some(cancel_queued_thumbnails(
+            std::sync::Arc::clone(&status_arc),
+            db_path.clone(),
+            project_id,
+        )),
+    };
 
-    std::thread::spawn(move || {
+    state.scheduler.submit(spec, move |job| {
         let n_threads = crate::import::util::capped_num_threads();
-        crate::import::pipeline::run_thumbnail_pool(
-            &lp_targets,
-            &cache_dir,
-            n_threads,
-            &cancel_arc,
-            &done_counter,
-            Some(&app_handle),
+        let analyses = job.track(
+            || indexing_progress(&status_arc, &done_counter),
+            || {
+                crate::import::pipeline::run_thumbnail_pool(
+                    &lp_targets,
+                    &cache_dir,
+                    &pyramid,
+                    n_threads,
+                    &cancel_arc,
+                    &pause_arc,
+                    &done_counter,
+                    Some(&app_handle),
+                )
+            },
         );
+        let cancelled = cancel_arc.load(Ordering::SeqCst);
+
+        // rusqlite::Connection is !Send: store the analyses over a fresh connection
+        let result = match open_connection(&db_path) {
+            Ok(conn) => {
+                if let Err(e) = crate::import::quality::store_image_analyses(&conn, &analyses) {
+                    tracing::warn!("resume_thumbnails: store image analyses: {}", e);
+                }
+                let thumbnailed: Vec<i64> = lp_targets.iter().map(|(id, ..)| *id).collect();
+                if let Err(e) = crate::import::artifacts::record_artifacts(
+                    &conn,
+                    project_id,
+                    &cache_dir,
+                    &thumbnailed,
+                    &analyses,
+                ) {
+                    tracing::warn!("resume_thumbnails: record thumbnail artifacts: {}", e);
+                }
+                let backfilled = crate::import::pipeline::backfill_image_analyses(
+                    &conn,
+                    project_id,
+                    &cache_dir,
+                    &pyramid,
+                    &cancel_arc,
+                );
+                if let Err(e) = crate::import::quality::store_image_analyses(&conn, &backfilled) {
+                    tracing::warn!("resume_thumbnails: store backfilled image analyses: {}", e);
+                }
+                finish_thumbnail_job(&conn, project_id, cancelled);
+                Ok(())
+            }
+            Err(e) => {
+                tracing::warn!("resume_thumbnails: cannot open DB: {}", e);
+                Err(format!("Cannot open DB: {}", e))
+            }
+        };
+        if !cancelled {
+            if let Err(e) = crate::import::pyramid::write_manifest(&cache_dir, &pyramid) {
+                tracing::warn!("resume_thumbnails: write thumbnail manifest: {}", e);
+            }
+        }
 
         if let Ok(mut s) = status_arc.lock() {
             s.thumbnails_running = false;
         }
+        result
     });
 
     Ok(())
@@ -469,17 +866,72 @@ pub fn resume_thumbnails(
 
 // ── Burst gap ─────────────────────────────────────────────────────────────────
 
+/// The burst gap of project `slug` when given, else the global default that
+/// projects inherit.
+#[tauri::command]
+pub fn get_burst_gap(slug: Option<String>, state: State<'_, AppState>) -> Result<u64, String> {
+    match slug {
+        Some(slug) => {
+            let (db_guard, project_guard) = with_open_project(&state, &slug)?;
+            let conn = db_guard.as_ref().unwrap();
+            let project = project_guard.as_ref().unwrap();
+            Ok(
+                super::projects::effective_settings(conn, project.id, &state.gemkeep_home)?
+                    .burst_gap_secs,
+            )
+        }
+        None => {
+            let config = manager::read_config(&state.gemkeep_home).map_err(|e| e.to_string())?;
+            Ok(config.burst_gap_secs)
+        }
+    }
+}
+
+/// Suggest a burst gap for project `slug` from the gaps between its shots,
+/// with a histogram of those gaps for the UI.
 #[tauri::command]
-pub fn get_burst_gap(state: State<'_, AppState>) -> Result<u64, String> {
-    let config = manager::read_config(&state.gemkeep_home).map_err(|e| e.to_string())?;
-    Ok(config.burst_gap_secs)
+pub fn suggest_burst_gap(
+    slug: String,
+    state: State<'_, AppState>,
+) -> Result<BurstGapSuggestion, String> {
+    let (db_guard, project_guard) = with_open_project(&state, &slug)?;
+    let conn = db_guard.as_ref().unwrap();
+    let project = project_guard.as_ref().unwrap();
+    repository::suggest_burst_gap(conn, project.id).map_err(|e| e.to_string())
 }
 
+/// Set the burst gap of project `slug` when given, else the global default.
 #[tauri::command]
-pub fn set_burst_gap(secs: u64, state: State<'_, AppState>) -> Result<(), String> {
-    let mut config = manager::read_config(&state.gemkeep_home).map_err(|e| e.to_string())?;
-    config.burst_gap_secs = secs;
-    manager::write_config(&state.gemkeep_home, &config).map_err(|e| e.to_string())
+pub fn set_burst_gap(
+    slug: Option<String>,
+    secs: u64,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    match slug {
+        Some(slug) => {
+            let (db_guard, project_guard) = with_open_project(&state, &slug)?;
+            let conn = db_guard.as_ref().unwrap();
+            let project = project_guard.as_ref().unwrap();
+            let mut overrides =
+                crate::projects::repository::get_settings_overrides(conn, project.id)
+                    .map_err(|e| e.to_string())?;
+            overrides.burst_gap_secs = Some(secs);
+            crate::projects::repository::set_settings_overrides(conn, project.id, &overrides)
+                .map_err(|e| e.to_string())
+        }
+        None => {
+            // Same bounds as a project override: projects inherit this value
+            ProjectSettingsOverrides {
+                burst_gap_secs: Some(secs),
+                ..Default::default()
+            }
+            .validate()?;
+            let mut config =
+                manager::read_config(&state.gemkeep_home).map_err(|e| e.to_string())?;
+            config.burst_gap_secs = secs;
+            manager::write_config(&state.gemkeep_home, &config).map_err(|e| e.to_string())
+        }
+    }
 }
 
 // ── Restack ───────────────────────────────────────────────────────────────────
@@ -499,20 +951,65 @@ pub fn restack(slug: String, state: State<'_, AppState>) -> Result<(), String> {
             .ok_or_else(|| "No open project".to_string())?
     };
 
-    // Read burst gap from config
-    let config = manager::read_config(&state.gemkeep_home).map_err(|e| e.to_string())?;
+    // Do merge-aware restack with DB lock, using the project's stacking strategy
+    let db_guard = state.db.lock().map_err(|_| "lock poisoned".to_string())?;
+    let conn = db_guard
+        .as_ref()
+        .ok_or_else(|| "No DB connection".to_string())?;
+    restack_project(&state, &slug, conn, project_id)
+}
 
-    // Do merge-aware restack with DB lock
-    {
-        let db_guard = state.db.lock().map_err(|_| "lock poisoned".to_string())?;
-        let conn = db_guard
-            .as_ref()
-            .ok_or_else(|| "No DB connection".to_string())?;
-        repository::restack_merge_aware(conn, project_id, config.burst_gap_secs)
-            .map_err(|e| e.to_string())?;
+/// Re-run stacking for a project with its effective settings.
+pub(crate) fn restack_project(
+    state: &AppState,
+    slug: &str,
+    conn: &Connection,
+    project_id: i64,
+) -> Result<(), String> {
+    restack_project_with_clock_shift(state, slug, conn, project_id, |_| Ok(None)).map(|_| ())
+}
+
+/// `restack_project` for the commands that change capture times: `shift` runs
+/// in the restack's transaction and is undone with it. Returns the shift.
+pub(crate) fn restack_project_with_clock_shift(
+    state: &AppState,
+    slug: &str,
+    conn: &Connection,
+    project_id: i64,
+    shift: impl FnOnce(&Connection) -> anyhow::Result<Option<ClockShift>>,
+) -> Result<Option<ClockShift>, String> {
+    let settings = super::projects::effective_settings(conn, project_id, &state.gemkeep_home)?;
+    if settings.stacking_strategy == StackingStrategy::Similarity {
+        // Thumbnails generated since the last import (e.g. resumed) have no hash yet
+        let cache_dir = manager::project_dir(&state.gemkeep_home, slug)
+            .join("cache")
+            .join("thumbnails");
+        phash::hash_cached_thumbnails(conn, project_id, &cache_dir).map_err(|e| e.to_string())?;
     }
+    repository::restack_with_clock_shift(conn, project_id, &settings.stacking_rules(), shift)
+        .map_err(|e| e.to_string())
+}
 
-    Ok(())
+/// What `restack` would do with the given burst gap and strategy, without
+/// changing anything. Other stacking settings come from the project.
+#[tauri::command]
+pub fn preview_restack(
+    slug: String,
+    burst_gap_secs: u64,
+    strategy: StackingStrategy,
+    state: State<'_, AppState>,
+) -> Result<RestackPreview, String> {
+    let (db_guard, project_guard) = with_open_project(&state, &slug)?;
+    let conn = db_guard.as_ref().unwrap();
+    let project = project_guard.as_ref().unwrap();
+
+    let settings = super::projects::effective_settings(conn, project.id, &state.gemkeep_home)?;
+    let rules = StackingRules {
+        strategy,
+        burst_gap_secs,
+        ..settings.stacking_rules()
+    };
+    repository::preview_restack(conn, project.id, &rules).map_err(|e| e.to_string())
 }
 
 // ── Stack listing ─────────────────────────────────────────────────────────────
@@ -561,6 +1058,7 @@ pub fn list_logical_photos(
     slug: String,
     stack_id: i64,
     round_id: Option<i64>,
+    order: Option<PhotoOrder>,
     state: State<'_, AppState>,
 ) -> Result<Vec<LogicalPhotoSummary>, String> {
     let (db_guard, _project_guard) = with_open_project(&state, &slug)?;
@@ -576,13 +1074,18 @@ pub fn list_logical_photos(
         None => repository::query_logical_photos_by_stack(conn, stack_id),
     }
     .map_err(|e| e.to_string())?;
+    if order.unwrap_or_default() == PhotoOrder::Sharpness {
+        repository::sort_by_sharpness(&mut summaries);
+    }
     repository::enrich_with_thumbnails(&mut summaries, &cache_dir);
     Ok(summaries)
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
     use crate::photos::model::IndexingStatus;
+    use crate::scheduler::Scheduler;
 
     /// Guard predicate: should start_indexing be rejected?
     /// Returns true if the system is busy (running OR thumbnails still generating).
@@ -628,4 +1131,61 @@ mod tests {
             "start_indexing guard must reject when thumbnails_running=true"
         );
     }
+
+    #[test]
+    fn test_cancelled_queued_indexing_job_lets_indexing_start_again() {
+        // Cancelling an indexing job still waiting behind another left `running`
+        // set, and every later start_indexing failed until the app restarted.
+        let tmp = tempfile::tempdir().unwrap();
+        let db_path = tmp.path().join("project.db");
+        let conn = open_connection(&db_path).unwrap();
+        run_migrations(&conn).unwrap();
+        let project_id = crate::projects::repository::insert_project(&conn, "Iceland", "iceland")
+            .unwrap()
+            .id;
+        // The interrupted import the queued run would resume
+        jobs::start_job(&conn, project_id, false).unwrap();
+        // What spawn_indexing sets before submitting
+        let status = std::sync::Arc::new(Mutex::new(IndexingStatus {
+            running: true,
+            ..Default::default()
+        }));
+
+        let scheduler = Scheduler::new();
+        let spec = |kind| JobSpec {
+            slug: "iceland".to_string(),
+            kind,
+            priority: JobPriority::Normal,
+            cancel: Default::default(),
+            app_handle: None,
+            on_cancel: None,
+        };
+        let (release, blocked) = std::sync::mpsc::channel::<()>();
+        scheduler.submit(spec(JobKind::Thumbnails), move |_| {
+            let _ = blocked.recv();
+            Ok(())
+        });
+        let queued = scheduler.submit(
+            JobSpec {
+                on_cancel: Some(cancel_queued_indexing(
+                    std::sync::Arc::clone(&status),
+                    db_path.clone(),
+                    project_id,
+                )),
+                ..spec(JobKind::Indexing)
+            },
+            |_| Ok(()),
+        );
+
+        assert!(scheduler.cancel(queued));
+        assert!(
+            !should_reject_new_indexing(&status.lock().unwrap()),
+            "indexing can start again"
+        );
+        assert!(
+            jobs::unfinished_job(&conn, project_id).unwrap().is_none(),
+            "the import job is not resumed on the next open"
+        );
+        release.send(()).unwrap();
+    }
 }
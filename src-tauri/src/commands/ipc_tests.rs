@@ -49,6 +49,7 @@ mod tests {
                 pause_indexing,
                 resume_indexing,
                 restack,
+                preview_restack,
                 merge_stacks,
                 undo_last_merge,
                 list_stack_transactions,
@@ -59,6 +60,8 @@ mod tests {
                 get_photo_detail,
                 get_round_decisions,
                 restore_eliminated_photo,
+                promote_survivors,
+                list_gem_promotions,
             ])
             .build(mock_context(noop_assets()))
             .unwrap()
@@ -579,7 +582,8 @@ mod tests {
             project_id,
             &project_dir,
             vec![photo_dir],
-            3,
+            &crate::projects::model::ProjectSettings::default(),
+            false,
             status,
             cancel,
             pause,
@@ -946,6 +950,14 @@ mod tests {
                 entry["current_status"].is_string(),
                 "current_status must be a string"
             );
+            // Present even for a decision not applied from a suggestion
+            for key in ["suggestion", "suggestion_confidence"] {
+                assert!(
+                    entry.get(key).is_some_and(|v| v.is_null()),
+                    "{} must be present and null",
+                    key
+                );
+            }
         }
     }
 
@@ -1301,6 +1313,57 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_ipc_preview_restack_json_shape() {
+        // Contract test: preview_restack reports the diff and leaves the stacks alone.
+        // TypeScript: previewRestack(slug, burstGapSecs, strategy) => Promise<RestackPreview>
+        let tmp = TempDir::new().unwrap();
+        let home = setup_project_with_photos(&tmp, 3);
+        let app = make_app(home);
+        let wv = make_webview(&app);
+
+        let open_result = tauri::test::get_ipc_response(
+            &wv,
+            invoke_req("open_project", serde_json::json!({ "slug": "test" })),
+        );
+        assert!(open_result.is_ok(), "open_project must succeed");
+        let list_stacks = || -> serde_json::Value {
+            tauri::test::get_ipc_response(
+                &wv,
+                invoke_req("list_stacks", serde_json::json!({ "slug": "test" })),
+            )
+            .unwrap()
+            .deserialize()
+            .unwrap()
+        };
+        let stacks_before = list_stacks();
+
+        let result = tauri::test::get_ipc_response(
+            &wv,
+            invoke_req(
+                "preview_restack",
+                serde_json::json!({ "slug": "test", "burstGapSecs": 3600, "strategy": "burst" }),
+            ),
+        );
+        assert!(result.is_ok(), "preview_restack must succeed: {:?}", result);
+        let preview: serde_json::Value = result.unwrap().deserialize().unwrap();
+        assert_eq!(preview["strategy"], "burst");
+        assert_eq!(preview["burst_gap_secs"], 3600);
+        for field in [
+            "stacks_before",
+            "stacks_after",
+            "stacks_unchanged",
+            "decided_photos_affected",
+        ] {
+            assert!(preview[field].is_u64(), "{} must be a number", field);
+        }
+        for field in ["merges", "splits", "moved_photos"] {
+            assert!(preview[field].is_array(), "{} must be an array", field);
+        }
+
+        assert_eq!(list_stacks(), stacks_before, "preview must not restack");
+    }
+
     #[test]
     fn test_ipc_list_stacks_json_shape_with_data() {
         // Contract test: verify list_stacks returns JSON matching TypeScript StackSummary
@@ -2238,4 +2301,56 @@ mod tests {
             "must return the target round id"
         );
     }
+
+    #[test]
+    fn test_ipc_promote_survivors_json_shape() {
+        // Sprint 11: Contract test — verify promote_survivors returns JSON
+        // matching the TypeScript PromotionResult interface, and that
+        // list_gem_promotions reports provenance for each promoted photo.
+        // Photos without EXIF each get a solo stack, so every photo in the
+        // first stack is a survivor of its untouched Round 1.
+        let (_tmp, _app, wv, stack_id, lp_ids) = setup_ipc_with_photos(3);
+
+        let result = tauri::test::get_ipc_response(
+            &wv,
+            invoke_req(
+                "promote_survivors",
+                serde_json::json!({ "slug": "test", "stackId": stack_id }),
+            ),
+        );
+        assert!(
+            result.is_ok(),
+            "promote_survivors must succeed: {:?}",
+            result
+        );
+        let val: serde_json::Value = result.unwrap().deserialize().unwrap();
+
+        assert!(
+            val["gem_stack_id"].is_number(),
+            "gem_stack_id must be a number"
+        );
+        assert_eq!(val["source_stack_id"], stack_id);
+        assert!(
+            val["source_round_id"].is_number(),
+            "source_round_id must be a number"
+        );
+        assert_eq!(
+            val["promoted"].as_array().map(|a| a.len()),
+            Some(lp_ids.len()),
+            "all survivors must be promoted"
+        );
+        assert!(val["removed"].is_array(), "removed must be an array");
+
+        let list_result = tauri::test::get_ipc_response(
+            &wv,
+            invoke_req("list_gem_promotions", serde_json::json!({ "slug": "test" })),
+        );
+        assert!(list_result.is_ok(), "list_gem_promotions must succeed");
+        let list: serde_json::Value = list_result.unwrap().deserialize().unwrap();
+        let arr = list.as_array().unwrap();
+        assert_eq!(arr.len(), lp_ids.len());
+        assert_eq!(arr[0]["source_stack_id"], stack_id);
+        assert!(arr[0]["source_round_number"].is_number());
+        assert!(arr[0]["promoted_at"].is_string());
+    }
 }
@@ -0,0 +1,18 @@
+use crate::scheduler::JobInfo;
+use crate::state::AppState;
+use tauri::State;
+
+/// Background jobs of project `slug` when given, else of every project; oldest
+/// first. Finished jobs are kept for a while so the UI can show how they ended.
+#[tauri::command]
+pub fn list_jobs(slug: Option<String>, state: State<'_, AppState>) -> Result<Vec<JobInfo>, String> {
+    Ok(state.scheduler.list(slug.as_deref()))
+}
+
+/// Cancel a queued or running job. Returns false if it had already finished.
+#[tauri::command]
+pub fn cancel_job(job_id: u64, state: State<'_, AppState>) -> Result<bool, String> {
+    let cancelled = state.scheduler.cancel(job_id);
+    tracing::info!("cancel_job: job_id={} cancelled={}", job_id, cancelled);
+    Ok(cancelled)
+}
@@ -1,7 +1,9 @@
 pub mod decisions;
+pub mod export;
 pub mod import;
 #[cfg(test)]
 mod ipc_tests;
+pub mod jobs;
 pub mod projects;
 pub mod stacks;
 
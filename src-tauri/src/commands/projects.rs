@@ -1,5 +1,10 @@
+use super::with_open_project;
 use crate::db::{open_connection, run_migrations};
-use crate::projects::{manager, model::Project, repository, slug};
+use crate::import::verify;
+use crate::projects::model::{
+    Project, ProjectSettings, ProjectSettingsOverrides, ProjectSettingsView,
+};
+use crate::projects::{manager, repository, slug};
 use crate::state::AppState;
 use tauri::State;
 
@@ -20,6 +25,16 @@ fn open_project_inner(slug_str: &str, state: &AppState) -> Result<Project, Strin
     config.last_opened_slug = Some(slug_str.to_string());
     manager::write_config(home, &config).map_err(|e| e.to_string())?;
     manager::append_operation_log(home, slug_str, &format!("PROJECT_OPENED slug={}", slug_str));
+    // Follow removable drives that came back under a new mount point. Offline
+    // sources never block opening: cached thumbnails and decisions still work.
+    match verify::reconnect_source_folders(&conn, project.id) {
+        Ok(reconnected) => {
+            for folder in &reconnected {
+                super::import::log_reconnected(home, slug_str, folder);
+            }
+        }
+        Err(e) => tracing::warn!("open_project: reconnecting sources failed: {}", e),
+    }
     // Lock order: db first, then active_project
     let mut db_lock = state
         .db
@@ -119,9 +134,21 @@ pub fn list_projects(state: State<'_, AppState>) -> Result<Vec<Project>, String>
     Ok(projects)
 }
 
+/// Resume an import the previous session left unfinished. Never fails the open.
+fn resume_import(slug_str: &str, state: &AppState) {
+    let Some(app_handle) = state.app_handle() else {
+        return;
+    };
+    if let Err(e) = super::import::resume_interrupted_import(slug_str, state, app_handle) {
+        tracing::warn!("open_project: resuming import failed: {}", e);
+    }
+}
+
 #[tauri::command]
 pub fn open_project(slug: String, state: State<'_, AppState>) -> Result<Project, String> {
-    open_project_inner(&slug, &state)
+    let project = open_project_inner(&slug, &state)?;
+    resume_import(&slug, &state);
+    Ok(project)
 }
 
 #[tauri::command]
@@ -134,7 +161,10 @@ pub fn get_last_project(state: State<'_, AppState>) -> Result<Option<Project>, S
         _ => return Ok(None),
     };
     match open_project_inner(&slug_str, &state) {
-        Ok(p) => Ok(Some(p)),
+        Ok(p) => {
+            resume_import(&slug_str, &state);
+            Ok(Some(p))
+        }
         Err(e) => {
             tracing::warn!("get_last_project failed: {}", e);
             Ok(None)
@@ -173,3 +203,68 @@ pub fn delete_project(slug: String, state: State<'_, AppState>) -> Result<(), St
     }
     Ok(())
 }
+
+// ── Project settings ──────────────────────────────────────────────────────────
+
+/// Settings in effect for a project: its overrides on top of the global config.
+pub(crate) fn effective_settings(
+    conn: &rusqlite::Connection,
+    project_id: i64,
+    home: &std::path::Path,
+) -> Result<ProjectSettings, String> {
+    let config = manager::read_config(home).unwrap_or_default();
+    let overrides =
+        repository::get_settings_overrides(conn, project_id).map_err(|e| e.to_string())?;
+    Ok(overrides.apply(&config.project_defaults()))
+}
+
+fn settings_view(
+    conn: &rusqlite::Connection,
+    project_id: i64,
+    home: &std::path::Path,
+) -> Result<ProjectSettingsView, String> {
+    Ok(ProjectSettingsView {
+        effective: effective_settings(conn, project_id, home)?,
+        overrides: repository::get_settings_overrides(conn, project_id)
+            .map_err(|e| e.to_string())?,
+    })
+}
+
+#[tauri::command]
+pub fn get_project_settings(
+    slug: String,
+    state: State<'_, AppState>,
+) -> Result<ProjectSettingsView, String> {
+    let (db_guard, project_guard) = with_open_project(&state, &slug)?;
+    let conn = db_guard.as_ref().unwrap();
+    let project = project_guard.as_ref().unwrap();
+    settings_view(conn, project.id, &state.gemkeep_home)
+}
+
+/// Replace the project's overrides; `None` fields inherit the global default.
+#[tauri::command]
+pub fn set_project_settings(
+    slug: String,
+    overrides: ProjectSettingsOverrides,
+    state: State<'_, AppState>,
+) -> Result<ProjectSettingsView, String> {
+    let (db_guard, project_guard) = with_open_project(&state, &slug)?;
+    let conn = db_guard.as_ref().unwrap();
+    let project = project_guard.as_ref().unwrap();
+    repository::set_settings_overrides(conn, project.id, &overrides).map_err(|e| e.to_string())?;
+    let view = settings_view(conn, project.id, &state.gemkeep_home)?;
+    manager::append_operation_log(
+        &state.gemkeep_home,
+        &slug,
+        &format!(
+            "PROJECT_SETTINGS_UPDATED burst_gap_secs={} thumbnail_size={} export_mode={} export_filter={} pair_raw_jpeg={} stacking_strategy={}",
+            view.effective.burst_gap_secs,
+            view.effective.thumbnail_size,
+            view.effective.export_mode.as_str(),
+            view.effective.export_filter.as_str(),
+            view.effective.pair_raw_jpeg,
+            view.effective.stacking_strategy.as_str()
+        ),
+    );
+    Ok(view)
+}
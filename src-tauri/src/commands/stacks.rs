@@ -1,5 +1,5 @@
-use crate::photos::model::{MergeResult, StackTransaction};
-use crate::photos::repository;
+use crate::photos::model::{CameraClock, MergeResult, SplitResult, StackTransaction};
+use crate::photos::{clock, history, repository};
 use crate::state::AppState;
 use tauri::State;
 
@@ -31,6 +31,57 @@ pub fn undo_last_merge(slug: String, state: State<'_, AppState>) -> Result<(), S
     repository::undo_last_merge(conn, project.id).map_err(|e| e.to_string())
 }
 
+/// Split logical photos out of a stack into a new stack of their own.
+/// The split is sticky: restack keeps them apart.
+#[tauri::command]
+pub fn split_stack(
+    slug: String,
+    stack_id: i64,
+    logical_photo_ids: Vec<i64>,
+    state: State<'_, AppState>,
+) -> Result<SplitResult, String> {
+    let (db_guard, project_guard) = with_open_project(&state, &slug)?;
+    let conn = db_guard.as_ref().unwrap();
+    let project = project_guard.as_ref().unwrap();
+    repository::split_stack(conn, project.id, stack_id, &logical_photo_ids)
+        .map_err(|e| e.to_string())
+}
+
+/// Undo the most recent split for this project.
+/// Moves the split-out logical photos back and deletes the split stack.
+#[tauri::command]
+pub fn undo_last_split(slug: String, state: State<'_, AppState>) -> Result<(), String> {
+    let (db_guard, project_guard) = with_open_project(&state, &slug)?;
+    let conn = db_guard.as_ref().unwrap();
+    let project = project_guard.as_ref().unwrap();
+    repository::undo_last_split(conn, project.id).map_err(|e| e.to_string())
+}
+
+/// Undo the most recent stack change (merge, split, restack or undo), carrying
+/// decisions along. Returns the undone transaction.
+#[tauri::command]
+pub fn undo_stack_change(
+    slug: String,
+    state: State<'_, AppState>,
+) -> Result<StackTransaction, String> {
+    let (db_guard, project_guard) = with_open_project(&state, &slug)?;
+    let conn = db_guard.as_ref().unwrap();
+    let project = project_guard.as_ref().unwrap();
+    history::undo_stack_transaction(conn, project.id).map_err(|e| e.to_string())
+}
+
+/// Redo the most recently undone stack change. Returns the redone transaction.
+#[tauri::command]
+pub fn redo_stack_change(
+    slug: String,
+    state: State<'_, AppState>,
+) -> Result<StackTransaction, String> {
+    let (db_guard, project_guard) = with_open_project(&state, &slug)?;
+    let conn = db_guard.as_ref().unwrap();
+    let project = project_guard.as_ref().unwrap();
+    history::redo_stack_transaction(conn, project.id).map_err(|e| e.to_string())
+}
+
 /// List all stack transactions for the project, newest first.
 #[tauri::command]
 pub fn list_stack_transactions(
@@ -42,3 +93,75 @@ pub fn list_stack_transactions(
     let project = project_guard.as_ref().unwrap();
     repository::list_stack_transactions(conn, project.id).map_err(|e| e.to_string())
 }
+
+/// Camera bodies in the project with their photo counts and clock offsets.
+#[tauri::command]
+pub fn list_camera_clocks(
+    slug: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<CameraClock>, String> {
+    let (db_guard, project_guard) = with_open_project(&state, &slug)?;
+    let conn = db_guard.as_ref().unwrap();
+    let project = project_guard.as_ref().unwrap();
+    clock::list_camera_clocks(conn, project.id).map_err(|e| e.to_string())
+}
+
+/// Set the clock offset of one camera body, shift its photos' capture times and
+/// restack, as one undoable step. Returns the number of photos shifted.
+#[tauri::command]
+pub fn set_camera_clock_offset(
+    slug: String,
+    camera_model: String,
+    camera_serial: Option<String>,
+    offset_secs: i64,
+    state: State<'_, AppState>,
+) -> Result<usize, String> {
+    let (db_guard, project_guard) = with_open_project(&state, &slug)?;
+    let conn = db_guard.as_ref().unwrap();
+    let project = project_guard.as_ref().unwrap();
+    let shift =
+        super::import::restack_project_with_clock_shift(&state, &slug, conn, project.id, |conn| {
+            clock::set_camera_clock_offset(
+                conn,
+                project.id,
+                &camera_model,
+                camera_serial.as_deref(),
+                offset_secs,
+            )
+        })?;
+    let shifted = shift.map_or(0, |shift| shift.photo_ids.len());
+    tracing::info!(
+        "set_camera_clock_offset: camera={} serial={:?} offset={}s shifted={}",
+        camera_model,
+        camera_serial,
+        offset_secs,
+        shifted
+    );
+    Ok(shifted)
+}
+
+/// Shift the capture times of selected logical photos by a fixed delta and
+/// restack, as one undoable step. Returns the number of photos shifted.
+#[tauri::command]
+pub fn shift_capture_times(
+    slug: String,
+    logical_photo_ids: Vec<i64>,
+    delta_secs: i64,
+    state: State<'_, AppState>,
+) -> Result<usize, String> {
+    let (db_guard, project_guard) = with_open_project(&state, &slug)?;
+    let conn = db_guard.as_ref().unwrap();
+    let project = project_guard.as_ref().unwrap();
+    let shift =
+        super::import::restack_project_with_clock_shift(&state, &slug, conn, project.id, |conn| {
+            clock::shift_capture_times(conn, project.id, &logical_photo_ids, delta_secs)
+        })?;
+    let shifted = shift.map_or(0, |shift| shift.photo_ids.len());
+    tracing::info!(
+        "shift_capture_times: {} logical photos by {}s shifted={}",
+        logical_photo_ids.len(),
+        delta_secs,
+        shifted
+    );
+    Ok(shifted)
+}
@@ -17,11 +17,31 @@ pub fn run_migrations(conn: &rusqlite::Connection) -> anyhow::Result<()> {
             last_opened_at  TEXT
         );
 
+        -- One row per project; NULL inherits the global default
+        CREATE TABLE IF NOT EXISTS project_settings (
+            project_id                  INTEGER PRIMARY KEY REFERENCES projects(id),
+            burst_gap_secs              INTEGER,
+            thumbnail_size              INTEGER,
+            export_mode                 TEXT,
+            export_filter               TEXT,
+            export_preserve_hierarchy   INTEGER,
+            pair_raw_jpeg               INTEGER,
+            stacking_strategy           TEXT,
+            similarity_window_secs      INTEGER,
+            similarity_threshold        INTEGER,
+            -- Comma-separated long edges, e.g. '512,1600'
+            thumbnail_fit_sizes         TEXT,
+            updated_at                  TEXT NOT NULL
+        );
+
         CREATE TABLE IF NOT EXISTS source_folders (
             id          INTEGER PRIMARY KEY,
             project_id  INTEGER NOT NULL REFERENCES projects(id),
             path        TEXT NOT NULL,
-            added_at    TEXT NOT NULL
+            added_at    TEXT NOT NULL,
+            volume_uuid TEXT,
+            volume_label TEXT,
+            relative_path TEXT
         );
 
         CREATE TABLE IF NOT EXISTS stacks (
@@ -37,7 +57,9 @@ pub fn run_migrations(conn: &rusqlite::Connection) -> anyhow::Result<()> {
             project_id              INTEGER NOT NULL REFERENCES projects(id),
             representative_photo_id INTEGER REFERENCES photos(id),
             stack_id                INTEGER REFERENCES stacks(id),
-            current_status          TEXT NOT NULL DEFAULT 'undecided'
+            current_status          TEXT NOT NULL DEFAULT 'undecided',
+            phash                   INTEGER,
+            sharpness               REAL
         );
 
         CREATE TABLE IF NOT EXISTS photos (
@@ -53,7 +75,14 @@ pub fn run_migrations(conn: &rusqlite::Connection) -> anyhow::Result<()> {
             shutter_speed    TEXT,
             iso              INTEGER,
             focal_length     REAL,
-            exposure_comp    REAL
+            exposure_comp    REAL,
+            file_size        INTEGER,
+            fingerprint      TEXT,
+            -- EXIF ImageNumber; orders burst frames sharing a capture time
+            sequence_number  INTEGER,
+            camera_serial    TEXT,
+            -- Seconds already added to the camera's time to get capture_time
+            clock_offset_secs INTEGER NOT NULL DEFAULT 0
         );
 
         CREATE TABLE IF NOT EXISTS rounds (
@@ -72,7 +101,20 @@ pub fn run_migrations(conn: &rusqlite::Connection) -> anyhow::Result<()> {
             logical_photo_id INTEGER NOT NULL REFERENCES logical_photos(id),
             round_id         INTEGER NOT NULL REFERENCES rounds(id),
             action           TEXT NOT NULL,
-            timestamp        TEXT NOT NULL
+            timestamp        TEXT NOT NULL,
+            -- Set when the decision came from applying a quality flag
+            suggestion            TEXT,
+            suggestion_confidence REAL
+        );
+
+        -- Advisory machine suggestions; never decisions until applied
+        CREATE TABLE IF NOT EXISTS quality_flags (
+            id               INTEGER PRIMARY KEY,
+            logical_photo_id INTEGER NOT NULL REFERENCES logical_photos(id),
+            kind             TEXT NOT NULL,
+            confidence       REAL NOT NULL,
+            created_at       TEXT NOT NULL,
+            UNIQUE (logical_photo_id, kind)
         );
 
         CREATE TABLE IF NOT EXISTS merges (
@@ -89,7 +131,8 @@ pub fn run_migrations(conn: &rusqlite::Connection) -> anyhow::Result<()> {
             project_id  INTEGER NOT NULL REFERENCES projects(id),
             action      TEXT NOT NULL,
             details     TEXT NOT NULL,
-            created_at  TEXT NOT NULL
+            created_at  TEXT NOT NULL,
+            state       TEXT NOT NULL DEFAULT 'applied'
         );
 
         CREATE TABLE IF NOT EXISTS manual_merges (
@@ -100,19 +143,112 @@ pub fn run_migrations(conn: &rusqlite::Connection) -> anyhow::Result<()> {
             active      INTEGER NOT NULL DEFAULT 1
         );
 
+        CREATE TABLE IF NOT EXISTS manual_splits (
+            id          INTEGER PRIMARY KEY,
+            project_id  INTEGER NOT NULL REFERENCES projects(id),
+            split_group TEXT NOT NULL,
+            created_at  TEXT NOT NULL,
+            active      INTEGER NOT NULL DEFAULT 1
+        );
+
+        -- Per-camera clock corrections; camera_serial is '' when unrecorded
+        CREATE TABLE IF NOT EXISTS camera_clock_offsets (
+            project_id    INTEGER NOT NULL REFERENCES projects(id),
+            camera_model  TEXT NOT NULL,
+            camera_serial TEXT NOT NULL DEFAULT '',
+            offset_secs   INTEGER NOT NULL,
+            updated_at    TEXT NOT NULL,
+            PRIMARY KEY (project_id, camera_model, camera_serial)
+        );
+
+        -- Capture-time shifts of selected photos, by file content, so a file
+        -- imported again (e.g. found moved by a full rebuild) is shifted the same
+        CREATE TABLE IF NOT EXISTS capture_time_shifts (
+            project_id   INTEGER NOT NULL REFERENCES projects(id),
+            file_size    INTEGER NOT NULL,
+            fingerprint  TEXT NOT NULL,
+            delta_secs   INTEGER NOT NULL,
+            PRIMARY KEY (project_id, file_size, fingerprint)
+        );
+
         CREATE TABLE IF NOT EXISTS round_photos (
             round_id          INTEGER NOT NULL REFERENCES rounds(id),
             logical_photo_id  INTEGER NOT NULL REFERENCES logical_photos(id),
             PRIMARY KEY (round_id, logical_photo_id)
         );
 
+        CREATE TABLE IF NOT EXISTS gem_stacks (
+            id          INTEGER PRIMARY KEY,
+            project_id  INTEGER NOT NULL UNIQUE REFERENCES projects(id),
+            created_at  TEXT NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS gem_promotions (
+            id                INTEGER PRIMARY KEY,
+            gem_stack_id      INTEGER NOT NULL REFERENCES gem_stacks(id),
+            logical_photo_id  INTEGER NOT NULL REFERENCES logical_photos(id),
+            source_stack_id   INTEGER NOT NULL,
+            source_round_id   INTEGER NOT NULL,
+            promoted_at       TEXT NOT NULL,
+            UNIQUE (gem_stack_id, logical_photo_id)
+        );
+
+        CREATE TABLE IF NOT EXISTS tags (
+            id          INTEGER PRIMARY KEY,
+            project_id  INTEGER NOT NULL REFERENCES projects(id),
+            name        TEXT NOT NULL,
+            created_at  TEXT NOT NULL,
+            UNIQUE (project_id, name)
+        );
+
+        CREATE TABLE IF NOT EXISTS logical_photo_tags (
+            logical_photo_id  INTEGER NOT NULL REFERENCES logical_photos(id),
+            tag_id            INTEGER NOT NULL REFERENCES tags(id),
+            PRIMARY KEY (logical_photo_id, tag_id)
+        );
+
+        -- The source content behind each cache id's thumbnail files, so cached
+        -- artifacts follow their photo when logical photo ids change. Not tied to
+        -- logical_photos: a cache id may outlive the logical photo it was made for.
+        CREATE TABLE IF NOT EXISTS thumbnail_artifacts (
+            cache_id          INTEGER PRIMARY KEY,
+            project_id        INTEGER NOT NULL REFERENCES projects(id),
+            artifact_key      TEXT NOT NULL,
+            sharpness         REAL,
+            mean_luma         REAL,
+            highlight_clipped REAL
+        );
+
+        -- One row per import run; completed stages are recorded so an import
+        -- interrupted by quitting the app resumes on next open
+        CREATE TABLE IF NOT EXISTS import_jobs (
+            id               INTEGER PRIMARY KEY,
+            project_id       INTEGER NOT NULL REFERENCES projects(id),
+            state            TEXT NOT NULL DEFAULT 'running',
+            completed_stage  TEXT,
+            -- 1 for a full rebuild, resumed as one
+            full_rebuild     INTEGER NOT NULL DEFAULT 0,
+            started_at       TEXT NOT NULL,
+            updated_at       TEXT NOT NULL
+        );
+
         CREATE INDEX IF NOT EXISTS idx_photos_capture_time ON photos(capture_time);
+        CREATE INDEX IF NOT EXISTS idx_photos_fingerprint  ON photos(file_size, fingerprint);
         CREATE INDEX IF NOT EXISTS idx_logical_stack        ON logical_photos(stack_id);
         CREATE INDEX IF NOT EXISTS idx_logical_project      ON logical_photos(project_id);
         CREATE INDEX IF NOT EXISTS idx_stack_tx_project
             ON stack_transactions(project_id, created_at DESC);
         CREATE INDEX IF NOT EXISTS idx_manual_merges_project
             ON manual_merges(project_id, active);
+        CREATE INDEX IF NOT EXISTS idx_manual_splits_project
+            ON manual_splits(project_id, active);
+        CREATE INDEX IF NOT EXISTS idx_quality_flags_lp    ON quality_flags(logical_photo_id);
+        CREATE INDEX IF NOT EXISTS idx_gem_promotions_source
+            ON gem_promotions(gem_stack_id, source_stack_id);
+        CREATE INDEX IF NOT EXISTS idx_import_jobs_project
+            ON import_jobs(project_id, state);
+        CREATE INDEX IF NOT EXISTS idx_thumbnail_artifacts_key
+            ON thumbnail_artifacts(project_id, artifact_key);
 
         -- Set version = 5. On a fresh DB: insert 0 first, then update.
         -- On an existing v5 DB: INSERT is skipped (row exists), UPDATE is no-op.
@@ -169,6 +305,14 @@ mod tests {
             "decisions",
             "merges",
             "round_photos",
+            "gem_stacks",
+            "gem_promotions",
+            "tags",
+            "logical_photo_tags",
+            "thumbnail_artifacts",
+            "import_jobs",
+            "camera_clock_offsets",
+            "capture_time_shifts",
         ];
         for table in &tables {
             let count: i64 = conn
@@ -275,6 +419,164 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_photos_has_fingerprint_columns() {
+        // Relink support: moved files are matched by size + partial content hash.
+        let conn = in_memory();
+        run_migrations(&conn).unwrap();
+
+        let mut stmt = conn.prepare("PRAGMA table_info(photos)").unwrap();
+        let cols: Vec<String> = stmt
+            .query_map([], |r| r.get(1))
+            .unwrap()
+            .filter_map(|r| r.ok())
+            .collect();
+
+        for col in &["file_size", "fingerprint"] {
+            assert!(
+                cols.contains(&col.to_string()),
+                "photos table must have column '{}', found: {:?}",
+                col,
+                cols
+            );
+        }
+    }
+
+    #[test]
+    fn test_photos_has_sequence_number_column() {
+        // Burst frames sharing a capture time are ordered by the camera's image number.
+        let conn = in_memory();
+        run_migrations(&conn).unwrap();
+
+        let mut stmt = conn.prepare("PRAGMA table_info(photos)").unwrap();
+        let cols: Vec<String> = stmt
+            .query_map([], |r| r.get(1))
+            .unwrap()
+            .filter_map(|r| r.ok())
+            .collect();
+
+        assert!(
+            cols.contains(&"sequence_number".to_string()),
+            "photos table must have column 'sequence_number', found: {:?}",
+            cols
+        );
+    }
+
+    #[test]
+    fn test_photos_has_camera_clock_columns() {
+        // Clock offsets are keyed by camera body and recorded per photo.
+        let conn = in_memory();
+        run_migrations(&conn).unwrap();
+
+        let mut stmt = conn.prepare("PRAGMA table_info(photos)").unwrap();
+        let cols: Vec<String> = stmt
+            .query_map([], |r| r.get(1))
+            .unwrap()
+            .filter_map(|r| r.ok())
+            .collect();
+
+        for col in &["camera_serial", "clock_offset_secs"] {
+            assert!(
+                cols.contains(&col.to_string()),
+                "photos table must have column '{}', found: {:?}",
+                col,
+                cols
+            );
+        }
+    }
+
+    #[test]
+    fn test_logical_photos_has_phash_column() {
+        // Similarity stacking compares perceptual hashes of the cached thumbnails.
+        let conn = in_memory();
+        run_migrations(&conn).unwrap();
+
+        let mut stmt = conn.prepare("PRAGMA table_info(logical_photos)").unwrap();
+        let cols: Vec<String> = stmt
+            .query_map([], |r| r.get(1))
+            .unwrap()
+            .filter_map(|r| r.ok())
+            .collect();
+        assert!(
+            cols.contains(&"phash".to_string()),
+            "logical_photos must have column 'phash', found: {:?}",
+            cols
+        );
+    }
+
+    #[test]
+    fn test_logical_photos_has_sharpness_column() {
+        // Round 1 of a wildlife burst is pre-sorted by focus.
+        let conn = in_memory();
+        run_migrations(&conn).unwrap();
+
+        let mut stmt = conn.prepare("PRAGMA table_info(logical_photos)").unwrap();
+        let cols: Vec<(String, String)> = stmt
+            .query_map([], |r| Ok((r.get(1)?, r.get(2)?)))
+            .unwrap()
+            .filter_map(|r| r.ok())
+            .collect();
+        assert!(
+            cols.iter().any(|(n, t)| n == "sharpness" && t == "REAL"),
+            "logical_photos must have column 'sharpness REAL', found: {:?}",
+            cols
+        );
+    }
+
+    #[test]
+    fn test_quality_flags_table_and_decision_suggestion_columns() {
+        // Suggestions live apart from decisions; applied ones are traceable.
+        let conn = in_memory();
+        run_migrations(&conn).unwrap();
+
+        let count: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='quality_flags'",
+                [],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(count, 1, "quality_flags table must exist after migration");
+
+        let mut stmt = conn.prepare("PRAGMA table_info(decisions)").unwrap();
+        let cols: Vec<String> = stmt
+            .query_map([], |r| r.get(1))
+            .unwrap()
+            .filter_map(|r| r.ok())
+            .collect();
+        for col in ["suggestion", "suggestion_confidence"] {
+            assert!(
+                cols.contains(&col.to_string()),
+                "decisions must have column '{}', found: {:?}",
+                col,
+                cols
+            );
+        }
+    }
+
+    #[test]
+    fn test_source_folders_has_volume_columns() {
+        // Removable drives: folders are re-found by volume UUID/label + relative path.
+        let conn = in_memory();
+        run_migrations(&conn).unwrap();
+
+        let mut stmt = conn.prepare("PRAGMA table_info(source_folders)").unwrap();
+        let cols: Vec<String> = stmt
+            .query_map([], |r| r.get(1))
+            .unwrap()
+            .filter_map(|r| r.ok())
+            .collect();
+
+        for col in &["volume_uuid", "volume_label", "relative_path"] {
+            assert!(
+                cols.contains(&col.to_string()),
+                "source_folders table must have column '{}', found: {:?}",
+                col,
+                cols
+            );
+        }
+    }
+
     #[test]
     fn test_stack_transactions_table_exists() {
         // Sprint 7 §3.1: stack_transactions table records every structural
@@ -295,6 +597,27 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_stack_transactions_has_state_column() {
+        // Undo/redo history: entries are 'applied', 'undone' or 'discarded'.
+        let conn = in_memory();
+        run_migrations(&conn).unwrap();
+
+        let mut stmt = conn
+            .prepare("PRAGMA table_info(stack_transactions)")
+            .unwrap();
+        let cols: Vec<String> = stmt
+            .query_map([], |r| r.get(1))
+            .unwrap()
+            .filter_map(|r| r.ok())
+            .collect();
+        assert!(
+            cols.contains(&"state".to_string()),
+            "stack_transactions must have a state column, found: {:?}",
+            cols
+        );
+    }
+
     #[test]
     fn test_manual_merges_table_exists() {
         // Sprint 7 §3.2: manual_merges table tracks which logical photos
@@ -312,6 +635,84 @@ mod tests {
         assert_eq!(count, 1, "manual_merges table must exist after migration");
     }
 
+    #[test]
+    fn test_project_settings_table_has_typed_columns() {
+        let conn = in_memory();
+        run_migrations(&conn).unwrap();
+
+        let mut stmt = conn.prepare("PRAGMA table_info(project_settings)").unwrap();
+        let cols: Vec<(String, String)> = stmt
+            .query_map([], |r| Ok((r.get(1)?, r.get(2)?)))
+            .unwrap()
+            .filter_map(|r| r.ok())
+            .collect();
+        for (name, ty) in [
+            ("burst_gap_secs", "INTEGER"),
+            ("thumbnail_size", "INTEGER"),
+            ("export_mode", "TEXT"),
+            ("export_filter", "TEXT"),
+            ("export_preserve_hierarchy", "INTEGER"),
+            ("pair_raw_jpeg", "INTEGER"),
+            ("stacking_strategy", "TEXT"),
+            ("similarity_window_secs", "INTEGER"),
+            ("similarity_threshold", "INTEGER"),
+            ("thumbnail_fit_sizes", "TEXT"),
+        ] {
+            assert!(
+                cols.iter().any(|(n, t)| n == name && t == ty),
+                "project_settings must have column {} {}",
+                name,
+                ty
+            );
+        }
+    }
+
+    #[test]
+    fn test_manual_splits_table_exists() {
+        // Photos ejected from a stack stay in their own stack across restacks.
+        let conn = in_memory();
+        run_migrations(&conn).unwrap();
+
+        let count: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='manual_splits'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(count, 1, "manual_splits table must exist after migration");
+    }
+
+    #[test]
+    fn test_gem_promotions_has_provenance_columns() {
+        // Sprint 11: every GemStack member records which stack and round it was
+        // promoted from.
+        let conn = in_memory();
+        run_migrations(&conn).unwrap();
+
+        let mut stmt = conn.prepare("PRAGMA table_info(gem_promotions)").unwrap();
+        let cols: Vec<String> = stmt
+            .query_map([], |r| r.get(1))
+            .unwrap()
+            .filter_map(|r| r.ok())
+            .collect();
+
+        for col in &[
+            "gem_stack_id",
+            "logical_photo_id",
+            "source_stack_id",
+            "source_round_id",
+            "promoted_at",
+        ] {
+            assert!(
+                cols.contains(&col.to_string()),
+                "gem_promotions must have column '{}', found: {:?}",
+                col,
+                cols
+            );
+        }
+    }
+
     #[test]
     fn test_squashed_migrations_decisions_references_logical_photo() {
         // decisions must have logical_photo_id (v3), NOT photo_id (v1).
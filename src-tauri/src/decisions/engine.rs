@@ -1,7 +1,58 @@
 use rusqlite::{params, Connection, OptionalExtension};
 use std::path::Path;
 
-use super::model::{DecisionAction, PhotoDetail, PhotoSnapshot, RoundStatus, RoundSummary};
+use crate::import::histogram;
+
+use super::model::{
+    DecisionAction, PhotoDetail, PhotoSnapshot, QualityFlagKind, RoundStatus, RoundSummary,
+};
+
+/// Effective status of a GemStack round member, over `round_photos rp`: its
+/// latest decision in that round. GemStack curation never writes the
+/// `current_status` cache, which belongs to the photo's own stack.
+const GEM_MEMBER_STATUS: &str = "COALESCE(
+    (SELECT d.action FROM decisions d
+     WHERE d.logical_photo_id = rp.logical_photo_id AND d.round_id = rp.round_id
+     ORDER BY d.id DESC LIMIT 1),
+    'undecided')";
+
+/// Joins `ld`, the latest decision of each `round_photos rp` member in its round,
+/// for the suggestion that decision was applied from.
+const LATEST_ROUND_DECISION_JOIN: &str = "LEFT JOIN decisions ld ON ld.id = (
+    SELECT d.id FROM decisions d
+    WHERE d.logical_photo_id = rp.logical_photo_id AND d.round_id = rp.round_id
+    ORDER BY d.id DESC LIMIT 1)";
+
+/// Read `ld.suggestion, ld.suggestion_confidence` selected at `idx` and `idx + 1`.
+fn read_suggestion(
+    row: &rusqlite::Row<'_>,
+    idx: usize,
+) -> rusqlite::Result<(Option<QualityFlagKind>, Option<f64>)> {
+    let kind: Option<String> = row.get(idx)?;
+    Ok((
+        kind.as_deref().and_then(QualityFlagKind::parse),
+        row.get(idx + 1)?,
+    ))
+}
+
+/// SQL expression for the live status of an open round's member, over
+/// `round_photos rp` joined to `logical_photos lp`.
+pub(crate) fn open_member_status_sql(scope: &str) -> &'static str {
+    if scope == "gem" {
+        GEM_MEMBER_STATUS
+    } else {
+        "lp.current_status"
+    }
+}
+
+/// The scope ('stack' or 'gem') a round belongs to.
+pub(crate) fn round_scope(conn: &Connection, round_id: i64) -> rusqlite::Result<String> {
+    conn.query_row(
+        "SELECT scope FROM rounds WHERE id = ?1",
+        params![round_id],
+        |row| row.get(0),
+    )
+}
 
 /// Find or auto-create an open round for a stack.
 /// Returns (round_id, was_created).
@@ -10,11 +61,22 @@ pub fn find_or_create_round(
     project_id: i64,
     stack_id: i64,
 ) -> rusqlite::Result<(i64, bool)> {
-    // Try to find an existing open round for this stack
+    find_or_create_scoped_round(conn, project_id, "stack", stack_id)
+}
+
+/// Find or auto-create an open round for any scope ('stack' or 'gem').
+/// Returns (round_id, was_created).
+pub(crate) fn find_or_create_scoped_round(
+    conn: &Connection,
+    project_id: i64,
+    scope: &str,
+    scope_id: i64,
+) -> rusqlite::Result<(i64, bool)> {
+    // Try to find an existing open round for this scope
     let existing: Option<i64> = conn
         .query_row(
-            "SELECT id FROM rounds WHERE project_id = ?1 AND scope = 'stack' AND scope_id = ?2 AND state = 'open' LIMIT 1",
-            params![project_id, stack_id],
+            "SELECT id FROM rounds WHERE project_id = ?1 AND scope = ?2 AND scope_id = ?3 AND state = 'open' LIMIT 1",
+            params![project_id, scope, scope_id],
             |row| row.get(0),
         )
         .optional()?;
@@ -27,8 +89,8 @@ pub fn find_or_create_round(
     // Determine next round number from existing rounds
     let max_round_number: Option<i32> = conn
         .query_row(
-            "SELECT MAX(round_number) FROM rounds WHERE project_id = ?1 AND scope = 'stack' AND scope_id = ?2",
-            params![project_id, stack_id],
+            "SELECT MAX(round_number) FROM rounds WHERE project_id = ?1 AND scope = ?2 AND scope_id = ?3",
+            params![project_id, scope, scope_id],
             |row| row.get(0),
         )
         .optional()?
@@ -37,29 +99,71 @@ pub fn find_or_create_round(
 
     let now = chrono::Utc::now().to_rfc3339();
     conn.execute(
-        "INSERT INTO rounds (project_id, scope, scope_id, round_number, state, created_at) VALUES (?1, 'stack', ?2, ?3, 'open', ?4)",
-        params![project_id, stack_id, next_round_number, now],
+        "INSERT INTO rounds (project_id, scope, scope_id, round_number, state, created_at) VALUES (?1, ?2, ?3, ?4, 'open', ?5)",
+        params![project_id, scope, scope_id, next_round_number, now],
     )?;
     let round_id = conn.last_insert_rowid();
 
-    // Populate round_photos with all non-eliminated logical photos in the stack
-    conn.execute(
-        "INSERT INTO round_photos (round_id, logical_photo_id)
-         SELECT ?1, id FROM logical_photos WHERE stack_id = ?2 AND current_status != 'eliminate'",
-        params![round_id, stack_id],
-    )?;
+    // Populate round_photos with all non-eliminated members of the scope:
+    // a stack's logical photos, or the GemStack's promoted photos.
+    if scope == "gem" {
+        conn.execute(
+            "INSERT INTO round_photos (round_id, logical_photo_id)
+             SELECT ?1, gp.logical_photo_id FROM gem_promotions gp
+             JOIN logical_photos lp ON lp.id = gp.logical_photo_id
+             WHERE gp.gem_stack_id = ?2 AND lp.current_status != 'eliminate'",
+            params![round_id, scope_id],
+        )?;
+    } else {
+        conn.execute(
+            "INSERT INTO round_photos (round_id, logical_photo_id)
+             SELECT ?1, id FROM logical_photos WHERE stack_id = ?2 AND current_status != 'eliminate'",
+            params![round_id, scope_id],
+        )?;
+    }
 
     Ok((round_id, true))
 }
 
 /// Record a decision. Append-only: never UPDATE existing decisions.
 /// The latest decision per (logical_photo_id, round_id) is effective.
-/// Also updates logical_photos.current_status as a materialized cache.
+/// Stack-round decisions also update logical_photos.current_status as a
+/// materialized cache; GemStack decisions leave it alone.
 pub fn record_decision(
     conn: &Connection,
     logical_photo_id: i64,
     round_id: i64,
     action: &DecisionAction,
+) -> rusqlite::Result<i64> {
+    insert_decision(conn, logical_photo_id, round_id, action, None)
+}
+
+/// Record a decision made by applying a quality suggestion. Same as
+/// `record_decision`, but the row names the flag and its confidence so
+/// suggestion-driven decisions stay traceable in the decision log.
+pub fn record_suggested_decision(
+    conn: &Connection,
+    logical_photo_id: i64,
+    round_id: i64,
+    action: &DecisionAction,
+    kind: QualityFlagKind,
+    confidence: f64,
+) -> rusqlite::Result<i64> {
+    insert_decision(
+        conn,
+        logical_photo_id,
+        round_id,
+        action,
+        Some((kind, confidence)),
+    )
+}
+
+fn insert_decision(
+    conn: &Connection,
+    logical_photo_id: i64,
+    round_id: i64,
+    action: &DecisionAction,
+    suggestion: Option<(QualityFlagKind, f64)>,
 ) -> rusqlite::Result<i64> {
     // Guard: reject decisions on committed (immutable) rounds
     if is_round_committed(conn, round_id)? {
@@ -71,16 +175,27 @@ pub fn record_decision(
 
     // Append-only: INSERT a new decision row
     conn.execute(
-        "INSERT INTO decisions (logical_photo_id, round_id, action, timestamp) VALUES (?1, ?2, ?3, ?4)",
-        params![logical_photo_id, round_id, action_str, now],
+        "INSERT INTO decisions
+             (logical_photo_id, round_id, action, timestamp, suggestion, suggestion_confidence)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            logical_photo_id,
+            round_id,
+            action_str,
+            now,
+            suggestion.map(|(kind, _)| kind.as_str()),
+            suggestion.map(|(_, confidence)| confidence),
+        ],
     )?;
     let decision_id = conn.last_insert_rowid();
 
     // Update the materialized cache on logical_photos
-    conn.execute(
-        "UPDATE logical_photos SET current_status = ?1 WHERE id = ?2",
-        params![action_str, logical_photo_id],
-    )?;
+    if round_scope(conn, round_id)? == "stack" {
+        conn.execute(
+            "UPDATE logical_photos SET current_status = ?1 WHERE id = ?2",
+            params![action_str, logical_photo_id],
+        )?;
+    }
 
     Ok(decision_id)
 }
@@ -103,36 +218,40 @@ pub fn commit_round(conn: &Connection, round_id: i64) -> rusqlite::Result<()> {
     )?;
 
     // 2. Get round metadata
-    let (project_id, stack_id, round_number): (i64, i64, i32) = conn.query_row(
-        "SELECT project_id, scope_id, round_number FROM rounds WHERE id = ?1",
+    let (project_id, scope, scope_id, round_number): (i64, String, i64, i32) = conn.query_row(
+        "SELECT project_id, scope, scope_id, round_number FROM rounds WHERE id = ?1",
         params![round_id],
-        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
     )?;
 
-    // 3. Reset survivors (non-eliminated) to undecided
-    conn.execute(
-        "UPDATE logical_photos SET current_status = 'undecided'
-         WHERE id IN (SELECT logical_photo_id FROM round_photos WHERE round_id = ?1)
-         AND current_status != 'eliminate'",
-        params![round_id],
-    )?;
+    // 3. Reset survivors (non-eliminated) to undecided. GemStack rounds keep
+    //    their statuses in their own decisions, so the cache is not touched.
+    if scope == "stack" {
+        conn.execute(
+            "UPDATE logical_photos SET current_status = 'undecided'
+             WHERE id IN (SELECT logical_photo_id FROM round_photos WHERE round_id = ?1)
+             AND current_status != 'eliminate'",
+            params![round_id],
+        )?;
+    }
 
-    // 4. Get survivor IDs (those now undecided in this round's photos)
-    let mut stmt = conn.prepare(
+    // 4. Get survivor IDs (this round's photos that were not eliminated)
+    let mut stmt = conn.prepare(&format!(
         "SELECT rp.logical_photo_id FROM round_photos rp
          JOIN logical_photos lp ON rp.logical_photo_id = lp.id
-         WHERE rp.round_id = ?1 AND lp.current_status = 'undecided'",
-    )?;
+         WHERE rp.round_id = ?1 AND {} != 'eliminate'",
+        open_member_status_sql(&scope)
+    ))?;
     let survivor_ids: Vec<i64> = stmt
         .query_map(params![round_id], |row| row.get(0))?
         .filter_map(|r| r.ok())
         .collect();
 
-    // 5. Create next round
+    // 5. Create next round in the same scope
     conn.execute(
         "INSERT INTO rounds (project_id, scope, scope_id, round_number, state, created_at)
-         VALUES (?1, 'stack', ?2, ?3, 'open', ?4)",
-        params![project_id, stack_id, round_number + 1, now],
+         VALUES (?1, ?2, ?3, ?4, 'open', ?5)",
+        params![project_id, scope, scope_id, round_number + 1, now],
     )?;
     let new_round_id = conn.last_insert_rowid();
 
@@ -144,11 +263,11 @@ pub fn commit_round(conn: &Connection, round_id: i64) -> rusqlite::Result<()> {
         )?;
     }
 
-    // 7. If zero survivors, mark the stack as inactive
-    if survivor_ids.is_empty() {
+    // 7. If zero survivors, mark the stack as inactive (the GemStack is never deactivated)
+    if survivor_ids.is_empty() && scope == "stack" {
         conn.execute(
             "UPDATE stacks SET active = 0 WHERE id = ?1",
-            params![stack_id],
+            params![scope_id],
         )?;
     }
 
@@ -248,13 +367,23 @@ pub fn get_round_status(
     project_id: i64,
     stack_id: i64,
 ) -> rusqlite::Result<RoundStatus> {
-    // Find the open round (or most recent) for this stack
+    get_scoped_round_status(conn, project_id, "stack", stack_id)
+}
+
+/// Get round status with decision counts for any scope ('stack' or 'gem').
+pub(crate) fn get_scoped_round_status(
+    conn: &Connection,
+    project_id: i64,
+    scope: &str,
+    scope_id: i64,
+) -> rusqlite::Result<RoundStatus> {
+    // Find the open round (or most recent) for this scope
     let (round_id, round_number, state, committed_at): (i64, i32, String, Option<String>) = conn
         .query_row(
             "SELECT id, round_number, state, committed_at FROM rounds
-             WHERE project_id = ?1 AND scope = 'stack' AND scope_id = ?2
+             WHERE project_id = ?1 AND scope = ?2 AND scope_id = ?3
              ORDER BY id DESC LIMIT 1",
-            params![project_id, stack_id],
+            params![project_id, scope, scope_id],
             |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
         )?;
 
@@ -266,15 +395,16 @@ pub fn get_round_status(
     )?;
 
     // Count kept (join round_photos with logical_photos for current_status)
+    let status = open_member_status_sql(scope);
     let kept: i64 = conn.query_row(
-        "SELECT COUNT(*) FROM round_photos rp JOIN logical_photos lp ON lp.id = rp.logical_photo_id WHERE rp.round_id = ?1 AND lp.current_status = 'keep'",
+        &format!("SELECT COUNT(*) FROM round_photos rp JOIN logical_photos lp ON lp.id = rp.logical_photo_id WHERE rp.round_id = ?1 AND {status} = 'keep'"),
         params![round_id],
         |row| row.get(0),
     )?;
 
     // Count eliminated
     let eliminated: i64 = conn.query_row(
-        "SELECT COUNT(*) FROM round_photos rp JOIN logical_photos lp ON lp.id = rp.logical_photo_id WHERE rp.round_id = ?1 AND lp.current_status = 'eliminate'",
+        &format!("SELECT COUNT(*) FROM round_photos rp JOIN logical_photos lp ON lp.id = rp.logical_photo_id WHERE rp.round_id = ?1 AND {status} = 'eliminate'"),
         params![round_id],
         |row| row.get(0),
     )?;
@@ -303,22 +433,23 @@ pub fn get_round_decisions(
     _stack_id: i64,
     round_id: i64,
 ) -> rusqlite::Result<Vec<super::model::PhotoDecisionStatus>> {
-    let mut stmt = conn.prepare(
+    let mut stmt = conn.prepare(&format!(
         "SELECT lp.id,
-                COALESCE(
-                    (SELECT d.action FROM decisions d
-                     WHERE d.logical_photo_id = lp.id AND d.round_id = ?1
-                     ORDER BY d.id DESC LIMIT 1),
-                    'undecided'
-                ) AS status
+                COALESCE(ld.action, 'undecided') AS status,
+                ld.suggestion, ld.suggestion_confidence
          FROM round_photos rp
          JOIN logical_photos lp ON lp.id = rp.logical_photo_id
+         {}
          WHERE rp.round_id = ?1",
-    )?;
+        LATEST_ROUND_DECISION_JOIN
+    ))?;
     let rows = stmt.query_map(params![round_id], |row| {
+        let (suggestion, suggestion_confidence) = read_suggestion(row, 2)?;
         Ok(super::model::PhotoDecisionStatus {
             logical_photo_id: row.get(0)?,
             current_status: row.get(1)?,
+            suggestion,
+            suggestion_confidence,
         })
     })?;
     rows.collect()
@@ -339,7 +470,8 @@ pub fn get_stack_id_for_photo(
 }
 
 /// Undo the last decision for a logical photo in the current open round.
-/// Recomputes current_status from remaining decisions in the same round.
+/// Recomputes current_status from remaining decisions in the same round
+/// (stack rounds only; GemStack statuses are read from the decisions directly).
 pub fn undo_decision(
     conn: &Connection,
     logical_photo_id: i64,
@@ -367,10 +499,12 @@ pub fn undo_decision(
         .optional()?;
 
     let new_status = remaining_action.as_deref().unwrap_or("undecided");
-    conn.execute(
-        "UPDATE logical_photos SET current_status = ?1 WHERE id = ?2",
-        params![new_status, logical_photo_id],
-    )?;
+    if round_scope(conn, round_id)? == "stack" {
+        conn.execute(
+            "UPDATE logical_photos SET current_status = ?1 WHERE id = ?2",
+            params![new_status, logical_photo_id],
+        )?;
+    }
 
     Ok(())
 }
@@ -382,11 +516,13 @@ pub fn get_photo_detail(
     cache_dir: &Path,
 ) -> rusqlite::Result<PhotoDetail> {
     // Get the logical photo info
-    let (current_status, representative_photo_id): (String, i64) = conn.query_row(
-        "SELECT current_status, representative_photo_id FROM logical_photos WHERE id = ?1",
-        params![logical_photo_id],
-        |row| Ok((row.get(0)?, row.get(1)?)),
-    )?;
+    let (current_status, representative_photo_id, sharpness): (String, i64, Option<f64>) = conn
+        .query_row(
+            "SELECT current_status, representative_photo_id, sharpness
+             FROM logical_photos WHERE id = ?1",
+            params![logical_photo_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )?;
 
     // Get camera params from the representative photo
     struct RepPhoto {
@@ -446,6 +582,9 @@ pub fn get_photo_detail(
         }
     }
 
+    // Offline sources (unplugged drive) fall back to the cached preview/thumbnail
+    let source_online = photos.iter().any(|(path, _)| Path::new(path).exists());
+
     // Build thumbnail path
     let thumbnail_path = {
         let thumb = cache_dir.join(format!("{}.jpg", logical_photo_id));
@@ -470,6 +609,8 @@ pub fn get_photo_detail(
         iso: rep.iso,
         focal_length: rep.focal_length,
         exposure_comp: rep.exposure_comp,
+        sharpness,
+        histogram: histogram::read_histogram(cache_dir, logical_photo_id),
         jpeg_path,
         raw_path,
         preview_path: {
@@ -480,6 +621,7 @@ pub fn get_photo_detail(
                 None
             }
         },
+        source_online,
     })
 }
 
@@ -491,19 +633,29 @@ pub fn list_rounds(
     conn: &Connection,
     project_id: i64,
     stack_id: i64,
+) -> rusqlite::Result<Vec<RoundSummary>> {
+    list_scoped_rounds(conn, project_id, "stack", stack_id)
+}
+
+/// List all rounds for any scope ('stack' or 'gem'). See `list_rounds`.
+pub(crate) fn list_scoped_rounds(
+    conn: &Connection,
+    project_id: i64,
+    scope: &str,
+    scope_id: i64,
 ) -> rusqlite::Result<Vec<RoundSummary>> {
     let mut stmt = conn.prepare(
         "SELECT r.id, r.round_number, r.state, r.committed_at,
                 COUNT(rp.logical_photo_id) as total
          FROM rounds r
          LEFT JOIN round_photos rp ON rp.round_id = r.id
-         WHERE r.project_id = ?1 AND r.scope = 'stack' AND r.scope_id = ?2
+         WHERE r.project_id = ?1 AND r.scope = ?2 AND r.scope_id = ?3
          GROUP BY r.id
          ORDER BY r.round_number",
     )?;
 
     let rows: Vec<(i64, i32, String, Option<String>, i64)> = stmt
-        .query_map(params![project_id, stack_id], |row| {
+        .query_map(params![project_id, scope, scope_id], |row| {
             Ok((
                 row.get(0)?,
                 row.get(1)?,
@@ -541,17 +693,22 @@ pub fn list_rounds(
             (kept, eliminated)
         } else {
             // Open round: derive from logical_photos.current_status
+            let status = open_member_status_sql(scope);
             let kept: i64 = conn.query_row(
-                "SELECT COUNT(*) FROM round_photos rp
-                 JOIN logical_photos lp ON lp.id = rp.logical_photo_id
-                 WHERE rp.round_id = ?1 AND lp.current_status = 'keep'",
+                &format!(
+                    "SELECT COUNT(*) FROM round_photos rp
+                     JOIN logical_photos lp ON lp.id = rp.logical_photo_id
+                     WHERE rp.round_id = ?1 AND {status} = 'keep'"
+                ),
                 params![round_id],
                 |row| row.get(0),
             )?;
             let eliminated: i64 = conn.query_row(
-                "SELECT COUNT(*) FROM round_photos rp
-                 JOIN logical_photos lp ON lp.id = rp.logical_photo_id
-                 WHERE rp.round_id = ?1 AND lp.current_status = 'eliminate'",
+                &format!(
+                    "SELECT COUNT(*) FROM round_photos rp
+                     JOIN logical_photos lp ON lp.id = rp.logical_photo_id
+                     WHERE rp.round_id = ?1 AND {status} = 'eliminate'"
+                ),
                 params![round_id],
                 |row| row.get(0),
             )?;
@@ -580,47 +737,38 @@ pub fn get_round_snapshot(
     conn: &Connection,
     round_id: i64,
 ) -> rusqlite::Result<Vec<PhotoSnapshot>> {
-    let state: String = conn.query_row(
-        "SELECT state FROM rounds WHERE id = ?1",
+    let (state, scope): (String, String) = conn.query_row(
+        "SELECT state, scope FROM rounds WHERE id = ?1",
         params![round_id],
-        |row| row.get(0),
+        |row| Ok((row.get(0)?, row.get(1)?)),
     )?;
 
-    if state == "committed" {
-        // Committed round: derive statuses from decisions table
-        let mut stmt = conn.prepare(
-            "SELECT rp.logical_photo_id,
-                    COALESCE(
-                        (SELECT d.action FROM decisions d
-                         WHERE d.logical_photo_id = rp.logical_photo_id AND d.round_id = ?1
-                         ORDER BY d.id DESC LIMIT 1),
-                        'undecided'
-                    ) AS status
-             FROM round_photos rp WHERE rp.round_id = ?1",
-        )?;
-        let rows = stmt.query_map(params![round_id], |row| {
-            Ok(PhotoSnapshot {
-                logical_photo_id: row.get(0)?,
-                status: row.get(1)?,
-            })
-        })?;
-        rows.collect()
+    // A committed round derives statuses from the decisions table, an open round
+    // from the live member status
+    let status = if state == "committed" {
+        "COALESCE(ld.action, 'undecided')"
     } else {
-        // Open round: derive from logical_photos.current_status
-        let mut stmt = conn.prepare(
-            "SELECT rp.logical_photo_id, lp.current_status as status
-             FROM round_photos rp
-             JOIN logical_photos lp ON lp.id = rp.logical_photo_id
-             WHERE rp.round_id = ?1",
-        )?;
-        let rows = stmt.query_map(params![round_id], |row| {
-            Ok(PhotoSnapshot {
-                logical_photo_id: row.get(0)?,
-                status: row.get(1)?,
-            })
-        })?;
-        rows.collect()
-    }
+        open_member_status_sql(&scope)
+    };
+    let mut stmt = conn.prepare(&format!(
+        "SELECT rp.logical_photo_id, {} as status,
+                ld.suggestion, ld.suggestion_confidence
+         FROM round_photos rp
+         JOIN logical_photos lp ON lp.id = rp.logical_photo_id
+         {}
+         WHERE rp.round_id = ?1",
+        status, LATEST_ROUND_DECISION_JOIN
+    ))?;
+    let rows = stmt.query_map(params![round_id], |row| {
+        let (suggestion, suggestion_confidence) = read_suggestion(row, 2)?;
+        Ok(PhotoSnapshot {
+            logical_photo_id: row.get(0)?,
+            status: row.get(1)?,
+            suggestion,
+            suggestion_confidence,
+        })
+    })?;
+    rows.collect()
 }
 
 /// Restore an eliminated photo into a target round.
@@ -1279,6 +1427,10 @@ mod tests {
             "jpeg_path must be set for a pair"
         );
         assert!(detail.raw_path.is_some(), "raw_path must be set for a pair");
+        assert!(
+            !detail.source_online,
+            "builder paths do not exist on disk, so the source is offline"
+        );
     }
 
     // ── Round-commit: round_photos population ──────────────────────────────
@@ -0,0 +1,550 @@
+//! GemStack — the per-project final-curation stack.
+//!
+//! The GemStack has no logical photos of its own. Its members are survivors
+//! promoted from regular stacks (recorded in `gem_promotions`), and it is
+//! curated with the same round engine using `rounds.scope = 'gem'`.
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use super::engine;
+use super::model::{
+    DecisionAction, GemPromotion, GemStackSummary, PromotionResult, RoundStatus, RoundSummary,
+};
+
+/// Return the GemStack id for a project, creating it on first use.
+pub fn get_or_create_gem_stack(conn: &Connection, project_id: i64) -> rusqlite::Result<i64> {
+    let existing: Option<i64> = conn
+        .query_row(
+            "SELECT id FROM gem_stacks WHERE project_id = ?1",
+            params![project_id],
+            |row| row.get(0),
+        )
+        .optional()?;
+    if let Some(id) = existing {
+        return Ok(id);
+    }
+
+    let now = chrono::Utc::now().to_rfc3339();
+    conn.execute(
+        "INSERT INTO gem_stacks (project_id, created_at) VALUES (?1, ?2)",
+        params![project_id, now],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Find or auto-create the open GemStack round.
+/// Round 1 is populated from all current promotions.
+/// Returns (round_id, was_created).
+pub fn find_or_create_gem_round(
+    conn: &Connection,
+    project_id: i64,
+) -> rusqlite::Result<(i64, bool)> {
+    let gem_stack_id = get_or_create_gem_stack(conn, project_id)?;
+    engine::find_or_create_scoped_round(conn, project_id, "gem", gem_stack_id)
+}
+
+/// Round status for the GemStack (most recent round).
+pub fn get_gem_round_status(conn: &Connection, project_id: i64) -> rusqlite::Result<RoundStatus> {
+    let gem_stack_id = get_or_create_gem_stack(conn, project_id)?;
+    engine::get_scoped_round_status(conn, project_id, "gem", gem_stack_id)
+}
+
+/// List all GemStack rounds with summary counts.
+pub fn list_gem_rounds(conn: &Connection, project_id: i64) -> rusqlite::Result<Vec<RoundSummary>> {
+    let gem_stack_id = get_or_create_gem_stack(conn, project_id)?;
+    engine::list_scoped_rounds(conn, project_id, "gem", gem_stack_id)
+}
+
+/// Record a keep or eliminate decision in the open GemStack round.
+/// The photo must be a member of that round. The decision is GemStack-scoped:
+/// the photo's status in its own stack is unchanged.
+/// Returns (decision_id, round_id, round_was_created).
+pub fn record_gem_decision(
+    conn: &Connection,
+    project_id: i64,
+    logical_photo_id: i64,
+    action: &DecisionAction,
+) -> anyhow::Result<(i64, i64, bool)> {
+    let (round_id, was_created) = find_or_create_gem_round(conn, project_id)?;
+    ensure_gem_round_member(conn, round_id, logical_photo_id)?;
+    let decision_id = engine::record_decision(conn, logical_photo_id, round_id, action)?;
+    Ok((decision_id, round_id, was_created))
+}
+
+/// Undo the last decision for a member of the open GemStack round.
+pub fn undo_gem_decision(
+    conn: &Connection,
+    project_id: i64,
+    logical_photo_id: i64,
+) -> anyhow::Result<()> {
+    let (round_id, _) = find_or_create_gem_round(conn, project_id)?;
+    ensure_gem_round_member(conn, round_id, logical_photo_id)?;
+    engine::undo_decision(conn, logical_photo_id, round_id)?;
+    Ok(())
+}
+
+fn ensure_gem_round_member(
+    conn: &Connection,
+    round_id: i64,
+    logical_photo_id: i64,
+) -> anyhow::Result<()> {
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM round_photos WHERE round_id = ?1 AND logical_photo_id = ?2",
+        params![round_id, logical_photo_id],
+        |row| row.get(0),
+    )?;
+    if count == 0 {
+        return Err(anyhow::anyhow!(
+            "Logical photo {} is not in the open GemStack round",
+            logical_photo_id
+        ));
+    }
+    Ok(())
+}
+
+/// Promote the survivors of a stack's current round to the GemStack.
+///
+/// Survivors are the non-eliminated members of the stack's open round (Round 1 is
+/// auto-created if the stack has none). Re-promotion replaces every earlier
+/// promotion from the same source stack: photos that are no longer survivors
+/// leave the GemStack's open round; committed GemStack rounds are untouched.
+///
+/// Promotion is not a decision: no decisions row is written and
+/// `current_status` is left as-is.
+pub fn promote_survivors(
+    conn: &Connection,
+    project_id: i64,
+    stack_id: i64,
+) -> anyhow::Result<PromotionResult> {
+    use anyhow::anyhow;
+
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM stacks WHERE id = ?1 AND project_id = ?2",
+        params![stack_id, project_id],
+        |row| row.get(0),
+    )?;
+    if count == 0 {
+        return Err(anyhow!(
+            "Stack {} does not exist for project {}",
+            stack_id,
+            project_id
+        ));
+    }
+
+    conn.execute("BEGIN", [])?;
+
+    let result = (|| -> anyhow::Result<PromotionResult> {
+        let gem_stack_id = get_or_create_gem_stack(conn, project_id)?;
+        let (source_round_id, _) = engine::find_or_create_round(conn, project_id, stack_id)?;
+
+        let mut stmt = conn.prepare(
+            "SELECT rp.logical_photo_id FROM round_photos rp
+             JOIN logical_photos lp ON lp.id = rp.logical_photo_id
+             WHERE rp.round_id = ?1 AND lp.current_status != 'eliminate'
+             ORDER BY rp.logical_photo_id",
+        )?;
+        let survivors: Vec<i64> = stmt
+            .query_map(params![source_round_id], |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+
+        // Earlier promotions from this source stack are replaced wholesale
+        let mut stmt = conn.prepare(
+            "SELECT logical_photo_id FROM gem_promotions
+             WHERE gem_stack_id = ?1 AND source_stack_id = ?2
+             ORDER BY logical_photo_id",
+        )?;
+        let previous: Vec<i64> = stmt
+            .query_map(params![gem_stack_id, stack_id], |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+        conn.execute(
+            "DELETE FROM gem_promotions WHERE gem_stack_id = ?1 AND source_stack_id = ?2",
+            params![gem_stack_id, stack_id],
+        )?;
+
+        let open_gem_round: Option<i64> = conn
+            .query_row(
+                "SELECT id FROM rounds
+                 WHERE project_id = ?1 AND scope = 'gem' AND scope_id = ?2 AND state = 'open'
+                 LIMIT 1",
+                params![project_id, gem_stack_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        let removed: Vec<i64> = previous
+            .into_iter()
+            .filter(|lp_id| !survivors.contains(lp_id))
+            .collect();
+        if let Some(round_id) = open_gem_round {
+            for lp_id in &removed {
+                conn.execute(
+                    "DELETE FROM round_photos WHERE round_id = ?1 AND logical_photo_id = ?2",
+                    params![round_id, lp_id],
+                )?;
+            }
+        }
+
+        let now = chrono::Utc::now().to_rfc3339();
+        for lp_id in &survivors {
+            // A photo promoted earlier under another stack id (e.g. before a merge)
+            // is re-pointed at its current source instead of duplicated.
+            conn.execute(
+                "INSERT INTO gem_promotions
+                     (gem_stack_id, logical_photo_id, source_stack_id, source_round_id, promoted_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)
+                 ON CONFLICT (gem_stack_id, logical_photo_id) DO UPDATE SET
+                     source_stack_id = excluded.source_stack_id,
+                     source_round_id = excluded.source_round_id,
+                     promoted_at     = excluded.promoted_at",
+                params![gem_stack_id, lp_id, stack_id, source_round_id, now],
+            )?;
+            if let Some(round_id) = open_gem_round {
+                conn.execute(
+                    "INSERT OR IGNORE INTO round_photos (round_id, logical_photo_id) VALUES (?1, ?2)",
+                    params![round_id, lp_id],
+                )?;
+            }
+        }
+
+        Ok(PromotionResult {
+            gem_stack_id,
+            source_stack_id: stack_id,
+            source_round_id,
+            promoted: survivors,
+            removed,
+        })
+    })();
+
+    match result {
+        Ok(promotion) => {
+            conn.execute("COMMIT", [])?;
+            tracing::info!(
+                stack_id,
+                promoted = promotion.promoted.len(),
+                removed = promotion.removed.len(),
+                "promoted survivors to GemStack"
+            );
+            Ok(promotion)
+        }
+        Err(e) => {
+            let _ = conn.execute("ROLLBACK", []);
+            Err(e)
+        }
+    }
+}
+
+/// List provenance for every GemStack member, ordered by source stack.
+pub fn list_gem_promotions(
+    conn: &Connection,
+    project_id: i64,
+) -> rusqlite::Result<Vec<GemPromotion>> {
+    let mut stmt = conn.prepare(
+        "SELECT gp.logical_photo_id, gp.source_stack_id, gp.source_round_id,
+                r.round_number, gp.promoted_at
+         FROM gem_promotions gp
+         JOIN gem_stacks gs ON gs.id = gp.gem_stack_id
+         LEFT JOIN rounds r ON r.id = gp.source_round_id
+         WHERE gs.project_id = ?1
+         ORDER BY gp.source_stack_id, gp.logical_photo_id",
+    )?;
+    let rows = stmt.query_map(params![project_id], |row| {
+        Ok(GemPromotion {
+            logical_photo_id: row.get(0)?,
+            source_stack_id: row.get(1)?,
+            source_round_id: row.get(2)?,
+            source_round_number: row.get(3)?,
+            promoted_at: row.get(4)?,
+        })
+    })?;
+    rows.collect()
+}
+
+/// GemStack id plus member and promoted-stack counts.
+pub fn get_gem_stack_summary(
+    conn: &Connection,
+    project_id: i64,
+) -> rusqlite::Result<GemStackSummary> {
+    let gem_stack_id = get_or_create_gem_stack(conn, project_id)?;
+    let (photo_count, promoted_stack_count): (i64, i64) = conn.query_row(
+        "SELECT COUNT(*), COUNT(DISTINCT source_stack_id) FROM gem_promotions
+         WHERE gem_stack_id = ?1",
+        params![gem_stack_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+    Ok(GemStackSummary {
+        gem_stack_id,
+        photo_count,
+        promoted_stack_count,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decisions::engine::{commit_round, find_or_create_round, record_decision};
+    use crate::decisions::model::DecisionAction;
+    use crate::import::test_fixtures::TestLibraryBuilder;
+
+    fn gem_round_members(conn: &Connection, round_id: i64) -> Vec<i64> {
+        let mut stmt = conn
+            .prepare(
+                "SELECT logical_photo_id FROM round_photos WHERE round_id = ?1
+                 ORDER BY logical_photo_id",
+            )
+            .unwrap();
+        stmt.query_map(params![round_id], |row| row.get(0))
+            .unwrap()
+            .filter_map(|r| r.ok())
+            .collect()
+    }
+
+    #[test]
+    fn test_gem_stack_is_one_per_project() {
+        // Sprint 11: each project has exactly one GemStack.
+        let project = TestLibraryBuilder::new()
+            .with_stack_layout(&[2])
+            .build_db_only();
+        let first = get_or_create_gem_stack(&project.conn, project.project_id).unwrap();
+        let second = get_or_create_gem_stack(&project.conn, project.project_id).unwrap();
+        assert_eq!(first, second, "GemStack must be reused, not recreated");
+    }
+
+    #[test]
+    fn test_promote_survivors_excludes_eliminated() {
+        // Sprint 11: G promotes all current-round survivors, never eliminated photos.
+        let project = TestLibraryBuilder::new()
+            .with_stack_layout(&[3])
+            .build_db_only();
+        let conn = &project.conn;
+        let stack_id = project.stack_ids[0];
+        let lp_ids = project.lp_ids.clone();
+
+        let (round_id, _) = find_or_create_round(conn, project.project_id, stack_id).unwrap();
+        record_decision(conn, lp_ids[1], round_id, &DecisionAction::Eliminate).unwrap();
+
+        let result = promote_survivors(conn, project.project_id, stack_id).unwrap();
+        assert_eq!(result.promoted, vec![lp_ids[0], lp_ids[2]]);
+        assert_eq!(result.source_round_id, round_id);
+        assert!(result.removed.is_empty());
+    }
+
+    #[test]
+    fn test_re_promotion_replaces_previous_promotions() {
+        // Sprint 11: pressing G again replaces earlier promotions from that stack,
+        // and leaves promotions from other stacks alone.
+        let project = TestLibraryBuilder::new()
+            .with_stack_layout(&[3, 2])
+            .build_db_only();
+        let conn = &project.conn;
+        let (stack_a, lps_a) = project.stacks_with_lps[0].clone();
+        let (stack_b, lps_b) = project.stacks_with_lps[1].clone();
+
+        promote_survivors(conn, project.project_id, stack_a).unwrap();
+        promote_survivors(conn, project.project_id, stack_b).unwrap();
+
+        // Re-enter stack A, cut one photo, commit, promote again
+        let (round_id, _) = find_or_create_round(conn, project.project_id, stack_a).unwrap();
+        record_decision(conn, lps_a[0], round_id, &DecisionAction::Eliminate).unwrap();
+        commit_round(conn, round_id).unwrap();
+        let result = promote_survivors(conn, project.project_id, stack_a).unwrap();
+
+        assert_eq!(result.removed, vec![lps_a[0]]);
+        assert_eq!(result.promoted, vec![lps_a[1], lps_a[2]]);
+
+        let summary = get_gem_stack_summary(conn, project.project_id).unwrap();
+        assert_eq!(summary.photo_count, 2 + lps_b.len() as i64);
+        assert_eq!(summary.promoted_stack_count, 2);
+    }
+
+    #[test]
+    fn test_gem_promotion_records_provenance() {
+        // Sprint 11: every GemStack member links back to its source stack and round.
+        let project = TestLibraryBuilder::new()
+            .with_stack_layout(&[2])
+            .build_db_only();
+        let conn = &project.conn;
+        let stack_id = project.stack_ids[0];
+
+        let (round_1, _) = find_or_create_round(conn, project.project_id, stack_id).unwrap();
+        commit_round(conn, round_1).unwrap();
+        let result = promote_survivors(conn, project.project_id, stack_id).unwrap();
+
+        let promotions = list_gem_promotions(conn, project.project_id).unwrap();
+        assert_eq!(promotions.len(), 2);
+        for p in &promotions {
+            assert_eq!(p.source_stack_id, stack_id);
+            assert_eq!(p.source_round_id, result.source_round_id);
+            assert_eq!(
+                p.source_round_number,
+                Some(2),
+                "survivors come from the open round after the commit"
+            );
+        }
+    }
+
+    #[test]
+    fn test_gem_round_engine_multi_round() {
+        // Sprint 11: the GemStack runs the same round engine — decide, commit,
+        // next round holds survivors only — under scope='gem'.
+        let project = TestLibraryBuilder::new()
+            .with_stack_layout(&[2, 2])
+            .build_db_only();
+        let conn = &project.conn;
+        for &stack_id in &project.stack_ids {
+            promote_survivors(conn, project.project_id, stack_id).unwrap();
+        }
+
+        let (gem_round, created) = find_or_create_gem_round(conn, project.project_id).unwrap();
+        assert!(created);
+        let mut all = project.lp_ids.clone();
+        all.sort();
+        assert_eq!(gem_round_members(conn, gem_round), all);
+
+        record_decision(conn, all[0], gem_round, &DecisionAction::Eliminate).unwrap();
+        record_decision(conn, all[1], gem_round, &DecisionAction::Keep).unwrap();
+        commit_round(conn, gem_round).unwrap();
+
+        let status = get_gem_round_status(conn, project.project_id).unwrap();
+        assert_eq!(status.round_number, 2);
+        assert_eq!(status.state, "open");
+        assert_eq!(status.total_photos, 3);
+
+        let scope: String = conn
+            .query_row(
+                "SELECT scope FROM rounds WHERE id = ?1",
+                params![status.round_id],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(scope, "gem");
+
+        let rounds = list_gem_rounds(conn, project.project_id).unwrap();
+        assert_eq!(rounds.len(), 2);
+        assert_eq!(rounds[0].eliminated, 1);
+        assert_eq!(rounds[0].kept, 1);
+    }
+
+    #[test]
+    fn test_gem_commit_with_no_survivors_keeps_stacks_active() {
+        // Committing an all-eliminated GemStack round must not deactivate the
+        // regular stack whose id happens to equal the GemStack id.
+        let project = TestLibraryBuilder::new()
+            .with_stack_layout(&[1])
+            .build_db_only();
+        let conn = &project.conn;
+        let stack_id = project.stack_ids[0];
+        promote_survivors(conn, project.project_id, stack_id).unwrap();
+
+        let (gem_round, _) = find_or_create_gem_round(conn, project.project_id).unwrap();
+        record_decision(
+            conn,
+            project.lp_ids[0],
+            gem_round,
+            &DecisionAction::Eliminate,
+        )
+        .unwrap();
+        commit_round(conn, gem_round).unwrap();
+
+        let active: i64 = conn
+            .query_row(
+                "SELECT active FROM stacks WHERE id = ?1",
+                params![stack_id],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(active, 1, "GemStack commit must not touch the stacks table");
+    }
+
+    #[test]
+    fn test_promotion_joins_open_gem_round() {
+        // Promoting after GemStack curation has started adds the new survivors to
+        // the open GemStack round; re-promotion removes dropped ones from it.
+        let project = TestLibraryBuilder::new()
+            .with_stack_layout(&[2, 2])
+            .build_db_only();
+        let conn = &project.conn;
+        let (stack_a, lps_a) = project.stacks_with_lps[0].clone();
+        let (stack_b, lps_b) = project.stacks_with_lps[1].clone();
+
+        promote_survivors(conn, project.project_id, stack_a).unwrap();
+        let (gem_round, _) = find_or_create_gem_round(conn, project.project_id).unwrap();
+        assert_eq!(gem_round_members(conn, gem_round), lps_a);
+
+        promote_survivors(conn, project.project_id, stack_b).unwrap();
+        let mut expected: Vec<i64> = lps_a.iter().chain(lps_b.iter()).copied().collect();
+        expected.sort();
+        assert_eq!(gem_round_members(conn, gem_round), expected);
+
+        let (round_b, _) = find_or_create_round(conn, project.project_id, stack_b).unwrap();
+        record_decision(conn, lps_b[1], round_b, &DecisionAction::Eliminate).unwrap();
+        promote_survivors(conn, project.project_id, stack_b).unwrap();
+        assert!(!gem_round_members(conn, gem_round).contains(&lps_b[1]));
+    }
+
+    #[test]
+    fn test_gem_decisions_leave_stack_status_alone() {
+        // A GemStack eliminate is scoped to the GemStack: the photo keeps its
+        // stack status and is still promoted again from its stack.
+        let project = TestLibraryBuilder::new()
+            .with_stack_layout(&[2])
+            .build_db_only();
+        let conn = &project.conn;
+        let pid = project.project_id;
+        let stack_id = project.stack_ids[0];
+        let lps = &project.lp_ids;
+
+        let (stack_round, _) = find_or_create_round(conn, pid, stack_id).unwrap();
+        record_decision(conn, lps[0], stack_round, &DecisionAction::Keep).unwrap();
+        promote_survivors(conn, pid, stack_id).unwrap();
+
+        let (_, gem_round, _) =
+            record_gem_decision(conn, pid, lps[0], &DecisionAction::Eliminate).unwrap();
+        record_gem_decision(conn, pid, lps[1], &DecisionAction::Keep).unwrap();
+        let status = get_gem_round_status(conn, pid).unwrap();
+        assert_eq!((status.kept, status.eliminated), (1, 1));
+
+        commit_round(conn, gem_round).unwrap();
+        let next_round = get_gem_round_status(conn, pid).unwrap().round_id;
+        assert_eq!(gem_round_members(conn, next_round), vec![lps[1]]);
+
+        let stack_status: String = conn
+            .query_row(
+                "SELECT current_status FROM logical_photos WHERE id = ?1",
+                params![lps[0]],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(
+            stack_status, "keep",
+            "GemStack commit must not reset stack keeps"
+        );
+
+        let result = promote_survivors(conn, pid, stack_id).unwrap();
+        assert_eq!(result.promoted, vec![lps[0], lps[1]]);
+    }
+
+    #[test]
+    fn test_gem_decision_rejects_non_members() {
+        let project = TestLibraryBuilder::new()
+            .with_stack_layout(&[1, 1])
+            .build_db_only();
+        let conn = &project.conn;
+        let pid = project.project_id;
+        promote_survivors(conn, pid, project.stack_ids[0]).unwrap();
+
+        let outsider = project.lp_ids[1];
+        assert!(record_gem_decision(conn, pid, outsider, &DecisionAction::Keep).is_err());
+        assert!(undo_gem_decision(conn, pid, outsider).is_err());
+        record_gem_decision(conn, pid, project.lp_ids[0], &DecisionAction::Keep).unwrap();
+        undo_gem_decision(conn, pid, project.lp_ids[0]).unwrap();
+    }
+
+    #[test]
+    fn test_promote_unknown_stack_is_rejected() {
+        let project = TestLibraryBuilder::new()
+            .with_stack_layout(&[1])
+            .build_db_only();
+        let result = promote_survivors(&project.conn, project.project_id, 9999);
+        assert!(result.is_err());
+    }
+}
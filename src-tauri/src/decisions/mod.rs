@@ -1,2 +1,4 @@
 pub mod engine;
+pub mod gem;
 pub mod model;
+pub mod suggestions;
@@ -1,3 +1,5 @@
+use crate::photos::model::PhotoHistogram;
+
 /// The action for a decision.
 #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -62,10 +64,26 @@ pub struct PhotoDetail {
     pub iso: Option<u32>,
     pub focal_length: Option<f64>,  // mm
     pub exposure_comp: Option<f64>, // EV
+    pub sharpness: Option<f64>,     // max tile variance of Laplacian (higher = sharper)
+    // Cached by the thumbnail pass, None until then
+    pub histogram: Option<PhotoHistogram>,
     // File paths for asset protocol display
     pub jpeg_path: Option<String>,    // path to JPEG file (for display)
     pub raw_path: Option<String>,     // path to RAW file (for future toggle)
     pub preview_path: Option<String>, // full-size RAW embedded preview (SingleView fallback)
+    pub source_online: bool,          // false when no source file is reachable (drive unplugged)
+}
+
+/// Which rendering of a logical photo SingleView shows.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PreviewVariant {
+    /// The camera's own JPEG of a RAW+JPEG pair (or a JPEG-only photo).
+    OocJpeg,
+    /// The JPEG preview embedded in the RAW, `{id}_preview.jpg`.
+    EmbeddedPreview,
+    /// The RAW data demosaiced at full resolution, rendered on demand.
+    RawRender,
 }
 
 /// Decision status for a single logical photo within a stack.
@@ -74,6 +92,10 @@ pub struct PhotoDetail {
 pub struct PhotoDecisionStatus {
     pub logical_photo_id: i64,
     pub current_status: String, // "undecided" | "keep" | "eliminate"
+    /// The quality suggestion the photo's decision in this round was applied from.
+    pub suggestion: Option<QualityFlagKind>,
+    /// 0.0–1.0
+    pub suggestion_confidence: Option<f64>,
 }
 
 /// Summary of a round for list_rounds display.
@@ -94,6 +116,10 @@ pub struct RoundSummary {
 pub struct PhotoSnapshot {
     pub logical_photo_id: i64,
     pub status: String, // "undecided" | "keep" | "eliminate"
+    /// The quality suggestion the photo's decision in this round was applied from.
+    pub suggestion: Option<QualityFlagKind>,
+    /// 0.0–1.0
+    pub suggestion_confidence: Option<f64>,
 }
 
 /// Result of restoring an eliminated photo into a round.
@@ -106,3 +132,83 @@ pub struct RestoreResult {
     /// The round the photo was restored into.
     pub round_id: i64,
 }
+
+/// Result of promoting a stack's current-round survivors to the GemStack.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct PromotionResult {
+    pub gem_stack_id: i64,
+    pub source_stack_id: i64,
+    /// The source stack round the survivors were taken from.
+    pub source_round_id: i64,
+    /// Logical photos now in the GemStack from this source stack.
+    pub promoted: Vec<i64>,
+    /// Logical photos from an earlier promotion that are no longer survivors.
+    pub removed: Vec<i64>,
+}
+
+/// Provenance of a single GemStack member.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct GemPromotion {
+    pub logical_photo_id: i64,
+    pub source_stack_id: i64,
+    pub source_round_id: i64,
+    pub source_round_number: Option<i32>, // None if the source round was cleared by re-index
+    pub promoted_at: String,
+}
+
+/// GemStack overview for the StackOverview card and progress header.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct GemStackSummary {
+    pub gem_stack_id: i64,
+    pub photo_count: i64,
+    pub promoted_stack_count: i64,
+}
+
+/// Kind of advisory quality flag the import analysis can raise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QualityFlagKind {
+    LikelyBlurred,
+    Underexposed,
+    ClippedHighlights,
+}
+
+impl QualityFlagKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QualityFlagKind::LikelyBlurred => "likely_blurred",
+            QualityFlagKind::Underexposed => "underexposed",
+            QualityFlagKind::ClippedHighlights => "clipped_highlights",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "likely_blurred" => Some(QualityFlagKind::LikelyBlurred),
+            "underexposed" => Some(QualityFlagKind::Underexposed),
+            "clipped_highlights" => Some(QualityFlagKind::ClippedHighlights),
+            _ => None,
+        }
+    }
+}
+
+/// A machine suggestion about one logical photo. Advisory only: it is never a
+/// decision until the user bulk-applies it.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct QualityFlag {
+    pub logical_photo_id: i64,
+    pub kind: QualityFlagKind,
+    /// 0.0–1.0
+    pub confidence: f64,
+    pub created_at: String,
+}
+
+/// Result of applying quality suggestions to a stack's open round.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SuggestionApplyResult {
+    pub round_id: i64,
+    /// Logical photos eliminated by a suggestion.
+    pub eliminated: Vec<i64>,
+    /// Flagged photos left alone because the user already decided them this round.
+    pub skipped_decided: Vec<i64>,
+}
@@ -0,0 +1,295 @@
+//! Advisory quality flags — machine suggestions such as "likely blurred".
+//!
+//! Flags are written by the import analysis into `quality_flags` and never
+//! touch `decisions` on their own. Applying them is an explicit, bulk action
+//! that records ordinary eliminate decisions tagged with the flag that caused
+//! them, so they can be told apart from the user's own calls.
+
+use rusqlite::{params, Connection};
+
+use super::engine;
+use super::model::{DecisionAction, QualityFlag, QualityFlagKind, SuggestionApplyResult};
+
+/// Replace the flags of one logical photo with a fresh analysis result.
+pub fn replace_quality_flags(
+    conn: &Connection,
+    logical_photo_id: i64,
+    flags: &[(QualityFlagKind, f64)],
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "DELETE FROM quality_flags WHERE logical_photo_id = ?1",
+        params![logical_photo_id],
+    )?;
+    let now = chrono::Utc::now().to_rfc3339();
+    for (kind, confidence) in flags {
+        conn.execute(
+            "INSERT INTO quality_flags (logical_photo_id, kind, confidence, created_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![logical_photo_id, kind.as_str(), confidence, now],
+        )?;
+    }
+    Ok(())
+}
+
+/// All flags on the logical photos of a stack, most confident first.
+pub fn list_quality_flags_for_stack(
+    conn: &Connection,
+    stack_id: i64,
+) -> rusqlite::Result<Vec<QualityFlag>> {
+    let mut stmt = conn.prepare(
+        "SELECT qf.logical_photo_id, qf.kind, qf.confidence, qf.created_at
+         FROM quality_flags qf
+         JOIN logical_photos lp ON lp.id = qf.logical_photo_id
+         WHERE lp.stack_id = ?1
+         ORDER BY qf.confidence DESC, qf.logical_photo_id",
+    )?;
+    let rows = stmt.query_map(params![stack_id], |row| {
+        let kind: String = row.get(1)?;
+        Ok(QualityFlag {
+            logical_photo_id: row.get(0)?,
+            kind: QualityFlagKind::parse(&kind).ok_or_else(|| {
+                rusqlite::Error::FromSqlConversionFailure(
+                    1,
+                    rusqlite::types::Type::Text,
+                    format!("unknown quality flag '{}'", kind).into(),
+                )
+            })?,
+            confidence: row.get(2)?,
+            created_at: row.get(3)?,
+        })
+    })?;
+    rows.collect()
+}
+
+/// Eliminate the photos of a stack's open round that carry one of `kinds` with
+/// at least `min_confidence`. Round 1 is auto-created if the stack has none.
+///
+/// Only undecided photos are touched: anything the user already kept or
+/// eliminated in this round is reported in `skipped_decided` instead.
+pub fn apply_suggestions(
+    conn: &Connection,
+    project_id: i64,
+    stack_id: i64,
+    kinds: &[QualityFlagKind],
+    min_confidence: f64,
+) -> anyhow::Result<SuggestionApplyResult> {
+    let (round_id, _) = engine::find_or_create_round(conn, project_id, stack_id)?;
+
+    // Strongest matching flag per photo in the round, with whether the
+    // photo already has a decision in this round
+    let mut stmt = conn.prepare(
+        "SELECT rp.logical_photo_id, qf.kind, qf.confidence,
+                EXISTS(SELECT 1 FROM decisions d
+                       WHERE d.logical_photo_id = rp.logical_photo_id AND d.round_id = ?1)
+         FROM round_photos rp
+         JOIN quality_flags qf ON qf.logical_photo_id = rp.logical_photo_id
+         WHERE rp.round_id = ?1 AND qf.confidence >= ?2
+         ORDER BY rp.logical_photo_id, qf.confidence DESC",
+    )?;
+    let candidates: Vec<(i64, String, f64, bool)> = stmt
+        .query_map(params![round_id, min_confidence], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+        })?
+        .collect::<rusqlite::Result<_>>()?;
+
+    let mut eliminated: Vec<i64> = Vec::new();
+    let mut skipped_decided: Vec<i64> = Vec::new();
+
+    conn.execute("BEGIN", [])?;
+    let result = (|| -> anyhow::Result<()> {
+        for (lp_id, kind, confidence, decided) in candidates {
+            let Some(kind) = QualityFlagKind::parse(&kind).filter(|k| kinds.contains(k)) else {
+                continue;
+            };
+            // Rows are ordered by photo: the first matching flag is the strongest
+            if eliminated.last() == Some(&lp_id) || skipped_decided.last() == Some(&lp_id) {
+                continue;
+            }
+            if decided {
+                skipped_decided.push(lp_id);
+                continue;
+            }
+            engine::record_suggested_decision(
+                conn,
+                lp_id,
+                round_id,
+                &DecisionAction::Eliminate,
+                kind,
+                confidence,
+            )?;
+            eliminated.push(lp_id);
+        }
+        Ok(())
+    })();
+
+    match result {
+        Ok(()) => {
+            conn.execute("COMMIT", [])?;
+            tracing::info!(
+                stack_id,
+                eliminated = eliminated.len(),
+                skipped = skipped_decided.len(),
+                "applied quality suggestions"
+            );
+            Ok(SuggestionApplyResult {
+                round_id,
+                eliminated,
+                skipped_decided,
+            })
+        }
+        Err(e) => {
+            let _ = conn.execute("ROLLBACK", []);
+            Err(e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decisions::engine::{
+        find_or_create_round, get_round_decisions, get_round_snapshot, record_decision,
+    };
+    use crate::import::test_fixtures::TestLibraryBuilder;
+
+    #[test]
+    fn test_flags_are_not_decisions_until_applied() {
+        let project = TestLibraryBuilder::new()
+            .with_stack_layout(&[3])
+            .build_db_only();
+        let conn = &project.conn;
+        let lps = project.lp_ids();
+        replace_quality_flags(conn, lps[0], &[(QualityFlagKind::LikelyBlurred, 0.9)]).unwrap();
+
+        let decisions: i64 = conn
+            .query_row("SELECT COUNT(*) FROM decisions", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(decisions, 0);
+        let flags = list_quality_flags_for_stack(conn, project.stack_id()).unwrap();
+        assert_eq!(flags.len(), 1);
+        assert_eq!(flags[0].kind, QualityFlagKind::LikelyBlurred);
+
+        // A fresh analysis replaces the old flags
+        replace_quality_flags(conn, lps[0], &[]).unwrap();
+        assert!(list_quality_flags_for_stack(conn, project.stack_id())
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_apply_suggestions_eliminates_undecided_and_records_source() {
+        let project = TestLibraryBuilder::new()
+            .with_stack_layout(&[4])
+            .build_db_only();
+        let conn = &project.conn;
+        let (pid, sid) = (project.project_id, project.stack_id());
+        let lps = project.lp_ids().to_vec();
+        replace_quality_flags(
+            conn,
+            lps[0],
+            &[
+                (QualityFlagKind::Underexposed, 0.6),
+                (QualityFlagKind::LikelyBlurred, 0.9),
+            ],
+        )
+        .unwrap();
+        replace_quality_flags(conn, lps[1], &[(QualityFlagKind::LikelyBlurred, 0.3)]).unwrap();
+        replace_quality_flags(conn, lps[2], &[(QualityFlagKind::LikelyBlurred, 0.8)]).unwrap();
+        replace_quality_flags(conn, lps[3], &[(QualityFlagKind::ClippedHighlights, 1.0)]).unwrap();
+
+        // The user already kept lps[2]: a suggestion must not override that
+        let (round_id, _) = find_or_create_round(conn, pid, sid).unwrap();
+        record_decision(conn, lps[2], round_id, &DecisionAction::Keep).unwrap();
+
+        let result = apply_suggestions(
+            conn,
+            pid,
+            sid,
+            &[
+                QualityFlagKind::LikelyBlurred,
+                QualityFlagKind::Underexposed,
+            ],
+            0.5,
+        )
+        .unwrap();
+        assert_eq!(result.round_id, round_id);
+        assert_eq!(
+            result.eliminated,
+            vec![lps[0]],
+            "lps[1] is below confidence"
+        );
+        assert_eq!(result.skipped_decided, vec![lps[2]]);
+
+        let (suggestion, confidence): (Option<String>, Option<f64>) = conn
+            .query_row(
+                "SELECT suggestion, suggestion_confidence FROM decisions
+                 WHERE logical_photo_id = ?1",
+                params![lps[0]],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(
+            suggestion.as_deref(),
+            Some("likely_blurred"),
+            "strongest flag"
+        );
+        assert_eq!(confidence, Some(0.9));
+
+        let user_suggestion: Option<String> = conn
+            .query_row(
+                "SELECT suggestion FROM decisions WHERE logical_photo_id = ?1",
+                params![lps[2]],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(user_suggestion, None, "user decisions carry no suggestion");
+
+        let status: String = conn
+            .query_row(
+                "SELECT current_status FROM logical_photos WHERE id = ?1",
+                params![lps[3]],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(status, "undecided", "kind not selected: left alone");
+    }
+
+    #[test]
+    fn test_round_queries_return_the_applied_suggestion() {
+        let project = TestLibraryBuilder::new()
+            .with_stack_layout(&[2])
+            .build_db_only();
+        let conn = &project.conn;
+        let (pid, sid) = (project.project_id, project.stack_id());
+        let lps = project.lp_ids().to_vec();
+        replace_quality_flags(conn, lps[0], &[(QualityFlagKind::LikelyBlurred, 0.9)]).unwrap();
+        let round_id = apply_suggestions(conn, pid, sid, &[QualityFlagKind::LikelyBlurred], 0.5)
+            .unwrap()
+            .round_id;
+        record_decision(conn, lps[1], round_id, &DecisionAction::Keep).unwrap();
+
+        let decisions = get_round_decisions(conn, sid, round_id).unwrap();
+        let snapshot = get_round_snapshot(conn, round_id).unwrap();
+        for (lp_id, suggestion, confidence) in [
+            (lps[0], Some(QualityFlagKind::LikelyBlurred), Some(0.9)),
+            (lps[1], None, None),
+        ] {
+            let d = decisions
+                .iter()
+                .find(|d| d.logical_photo_id == lp_id)
+                .unwrap();
+            assert_eq!(
+                (d.suggestion, d.suggestion_confidence),
+                (suggestion, confidence)
+            );
+            let s = snapshot
+                .iter()
+                .find(|s| s.logical_photo_id == lp_id)
+                .unwrap();
+            assert_eq!(
+                (s.suggestion, s.suggestion_confidence),
+                (suggestion, confidence)
+            );
+        }
+    }
+}
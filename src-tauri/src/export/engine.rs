@@ -0,0 +1,593 @@
+use rusqlite::{params, Connection};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use super::model::{ExportItem, ExportMode, ExportOptions, ExportResult};
+use crate::decisions::engine;
+use crate::photos::repository;
+use crate::xmp;
+
+/// Survivors of a single round: members whose effective status is not 'eliminate'.
+/// Committed rounds use the decisions log (historical); open rounds use the live
+/// status of their scope.
+pub fn list_round_survivors(conn: &Connection, round_id: i64) -> rusqlite::Result<Vec<i64>> {
+    let (state, scope): (String, String) = conn.query_row(
+        "SELECT state, scope FROM rounds WHERE id = ?1",
+        params![round_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+
+    let sql = if state == "committed" {
+        "SELECT rp.logical_photo_id FROM round_photos rp
+         WHERE rp.round_id = ?1 AND COALESCE(
+             (SELECT d.action FROM decisions d
+              WHERE d.logical_photo_id = rp.logical_photo_id AND d.round_id = ?1
+              ORDER BY d.id DESC LIMIT 1),
+             'undecided'
+         ) != 'eliminate'
+         ORDER BY rp.logical_photo_id"
+            .to_string()
+    } else {
+        format!(
+            "SELECT rp.logical_photo_id FROM round_photos rp
+             JOIN logical_photos lp ON lp.id = rp.logical_photo_id
+             WHERE rp.round_id = ?1 AND {} != 'eliminate'
+             ORDER BY rp.logical_photo_id",
+            engine::open_member_status_sql(&scope)
+        )
+    };
+    repository::collect_rows(conn, &sql, params![round_id], |row| row.get(0))
+}
+
+/// Survivors of the last committed round of every active stack.
+/// Stacks that were never committed contribute nothing.
+pub fn list_last_committed_survivors(
+    conn: &Connection,
+    project_id: i64,
+) -> rusqlite::Result<Vec<i64>> {
+    let round_ids: Vec<i64> = repository::collect_rows(
+        conn,
+        "SELECT MAX(r.id) FROM rounds r
+         JOIN stacks s ON s.id = r.scope_id
+         WHERE r.project_id = ?1 AND r.scope = 'stack' AND r.state = 'committed'
+           AND s.active = 1
+         GROUP BY r.scope_id
+         ORDER BY r.scope_id",
+        params![project_id],
+        |row| row.get(0),
+    )?;
+
+    let mut seen = HashSet::new();
+    let mut survivors = Vec::new();
+    for round_id in round_ids {
+        for lp_id in list_round_survivors(conn, round_id)? {
+            if seen.insert(lp_id) {
+                survivors.push(lp_id);
+            }
+        }
+    }
+    Ok(survivors)
+}
+
+/// Build the export plan: every file of every survivor that passes the filter,
+/// with a collision-free destination path. Touches nothing on disk.
+///
+/// Both files of a RAW+JPEG pair receive the same suffix so they stay paired
+/// at the destination (IMG_0001_1.CR2 + IMG_0001_1.JPG).
+pub fn plan_export(
+    conn: &Connection,
+    project_id: i64,
+    options: &ExportOptions,
+) -> anyhow::Result<(usize, Vec<ExportItem>)> {
+    use anyhow::anyhow;
+
+    let survivors = match options.round_id {
+        Some(round_id) => {
+            let round_project: i64 = conn
+                .query_row(
+                    "SELECT project_id FROM rounds WHERE id = ?1",
+                    params![round_id],
+                    |row| row.get(0),
+                )
+                .map_err(|_| anyhow!("Round {} does not exist", round_id))?;
+            if round_project != project_id {
+                return Err(anyhow!(
+                    "Round {} does not belong to project {}",
+                    round_id,
+                    project_id
+                ));
+            }
+            list_round_survivors(conn, round_id)?
+        }
+        None => list_last_committed_survivors(conn, project_id)?,
+    };
+
+    let source_roots: Vec<PathBuf> = repository::list_source_folders(conn, project_id)?
+        .into_iter()
+        .map(|f| PathBuf::from(f.path))
+        .collect();
+
+    let mut taken: HashSet<String> = HashSet::new();
+    let mut items = Vec::new();
+
+    for &lp_id in &survivors {
+        let files: Vec<(String, String)> = repository::collect_rows(
+            conn,
+            "SELECT path, format FROM photos WHERE logical_photo_id = ?1 ORDER BY format, path",
+            params![lp_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        let files: Vec<(String, String)> = files
+            .into_iter()
+            .filter(|(_, format)| options.filter.includes(format))
+            .collect();
+        if files.is_empty() {
+            continue;
+        }
+
+        let bases: Vec<PathBuf> = files
+            .iter()
+            .map(|(path, _)| {
+                destination_base(
+                    Path::new(path),
+                    &source_roots,
+                    &options.destination,
+                    options.preserve_hierarchy,
+                )
+            })
+            .collect();
+
+        // Smallest suffix for which no file (or sidecar) of this logical photo collides
+        let mut suffix = 0usize;
+        let targets = loop {
+            let candidates: Vec<PathBuf> = bases.iter().map(|b| with_suffix(b, suffix)).collect();
+            let free = candidates.iter().all(|c| {
+                let sidecar_free = options.sidecars.is_none_or(|n| {
+                    let sc = n.sidecar_path(c);
+                    !sc.exists() && !taken.contains(&collision_key(&sc))
+                });
+                !c.exists() && !taken.contains(&collision_key(c)) && sidecar_free
+            });
+            if free {
+                break candidates;
+            }
+            suffix += 1;
+        };
+
+        for ((source, format), target) in files.into_iter().zip(targets) {
+            taken.insert(collision_key(&target));
+            if let Some(naming) = options.sidecars {
+                taken.insert(collision_key(&naming.sidecar_path(&target)));
+            }
+            items.push(ExportItem {
+                logical_photo_id: lp_id,
+                format,
+                source_path: source,
+                destination_path: target.to_string_lossy().to_string(),
+                renamed: suffix > 0,
+            });
+        }
+    }
+
+    Ok((survivors.len(), items))
+}
+
+/// Plan and (unless dry-run) perform an export. Source files are never modified.
+/// Per-file failures are collected in `errors`; the remaining files still export.
+pub fn run_export(
+    conn: &Connection,
+    project_id: i64,
+    options: &ExportOptions,
+) -> anyhow::Result<ExportResult> {
+    let (logical_photos, items) = plan_export(conn, project_id, options)?;
+
+    let mut files_exported = 0usize;
+    let mut sidecars_written = 0usize;
+    let mut errors = Vec::new();
+
+    if !options.dry_run {
+        let mut written: Vec<(i64, PathBuf)> = Vec::new();
+        for item in &items {
+            match export_file(
+                Path::new(&item.source_path),
+                Path::new(&item.destination_path),
+                options.mode,
+            ) {
+                Ok(()) => {
+                    files_exported += 1;
+                    written.push((item.logical_photo_id, PathBuf::from(&item.destination_path)));
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        "export {} -> {} failed: {}",
+                        item.source_path,
+                        item.destination_path,
+                        e
+                    );
+                    errors.push(format!("{}: {}", item.source_path, e));
+                }
+            }
+        }
+
+        if let Some(naming) = options.sidecars {
+            // Items are grouped per logical photo, so chunk_by yields one group each
+            for group in written.chunk_by(|a, b| a.0 == b.0) {
+                let meta = xmp::writer::load_xmp_metadata(conn, group[0].0)?;
+                let files: Vec<PathBuf> = group.iter().map(|(_, p)| p.clone()).collect();
+                let r = xmp::writer::write_sidecars_for_files(&meta, &files, naming);
+                sidecars_written += r.sidecars_written;
+                errors.extend(r.errors);
+            }
+        }
+    }
+
+    Ok(ExportResult {
+        dry_run: options.dry_run,
+        mode: options.mode,
+        logical_photos,
+        items,
+        files_exported,
+        sidecars_written,
+        errors,
+    })
+}
+
+/// Write one file to `dest` using the requested mode. Never overwrites.
+fn export_file(source: &Path, dest: &Path, mode: ExportMode) -> std::io::Result<()> {
+    if dest.exists() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            format!("{} already exists", dest.display()),
+        ));
+    }
+    if let Some(parent) = dest.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    match mode {
+        ExportMode::Copy => std::fs::copy(source, dest).map(|_| ()),
+        ExportMode::Hardlink => std::fs::hard_link(source, dest),
+        ExportMode::Reflink => reflink_copy::reflink(source, dest),
+    }
+}
+
+/// Destination path before collision handling: the path relative to the
+/// longest matching source folder when preserving hierarchy, else the bare file name.
+fn destination_base(
+    source: &Path,
+    source_roots: &[PathBuf],
+    destination: &Path,
+    preserve_hierarchy: bool,
+) -> PathBuf {
+    let file_name = source.file_name().map(PathBuf::from).unwrap_or_default();
+    if !preserve_hierarchy {
+        return destination.join(file_name);
+    }
+    source_roots
+        .iter()
+        .filter(|root| source.starts_with(root))
+        .max_by_key(|root| root.components().count())
+        .and_then(|root| source.strip_prefix(root).ok())
+        .map(|rel| destination.join(rel))
+        .unwrap_or_else(|| destination.join(file_name))
+}
+
+/// `dir/name.ext` → `dir/name_{n}.ext` (n = 0 leaves the path unchanged).
+fn with_suffix(base: &Path, n: usize) -> PathBuf {
+    if n == 0 {
+        return base.to_path_buf();
+    }
+    let stem = base
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let name = match base.extension() {
+        Some(ext) => format!("{}_{}.{}", stem, n, ext.to_string_lossy()),
+        None => format!("{}_{}", stem, n),
+    };
+    base.with_file_name(name)
+}
+
+/// Case-insensitive key so IMG_1.JPG and img_1.jpg collide on every platform.
+fn collision_key(path: &Path) -> String {
+    path.to_string_lossy().to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decisions::engine::{commit_round, find_or_create_round, record_decision};
+    use crate::decisions::model::DecisionAction;
+    use crate::export::model::ExportFilter;
+    use crate::import::test_fixtures::{
+        Camera, FileType, PhotoSpec, TestLibraryBuilder, TestProject,
+    };
+
+    fn spec(file_type: FileType) -> PhotoSpec {
+        PhotoSpec {
+            camera: Camera::Canon,
+            orientation: 1,
+            file_type,
+            capture_time: Some("2024:01:01 10:00:00".to_string()),
+            camera_params: None,
+        }
+    }
+
+    /// Move the builder's synthetic `/test/...` paths into a real source folder
+    /// (optionally under `subdir`) and write small files there.
+    fn materialize_sources(project: &TestProject, subdir: &str) -> PathBuf {
+        let root = project.dir.path().join("source");
+        let dir = root.join(subdir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let rows: Vec<(i64, String)> =
+            repository::collect_rows(&project.conn, "SELECT id, path FROM photos", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        for (id, path) in rows {
+            let name = Path::new(&path).file_name().unwrap().to_owned();
+            let real = dir.join(name);
+            std::fs::write(&real, format!("bytes of {}", id)).unwrap();
+            project
+                .conn
+                .execute(
+                    "UPDATE photos SET path = ?1 WHERE id = ?2",
+                    params![real.to_string_lossy(), id],
+                )
+                .unwrap();
+        }
+        repository::add_source_folder(&project.conn, project.project_id, &root.to_string_lossy())
+            .unwrap();
+        root
+    }
+
+    fn options(dest: &Path, mode: ExportMode, filter: ExportFilter) -> ExportOptions {
+        ExportOptions {
+            destination: dest.to_path_buf(),
+            mode,
+            filter,
+            round_id: None,
+            preserve_hierarchy: false,
+            dry_run: false,
+            sidecars: None,
+        }
+    }
+
+    /// Commit round 1 of the first stack with the given eliminations.
+    fn commit_first_stack(project: &TestProject, eliminate: &[i64]) -> i64 {
+        let (round_id, _) =
+            find_or_create_round(&project.conn, project.project_id, project.stack_ids[0]).unwrap();
+        for &lp_id in eliminate {
+            record_decision(&project.conn, lp_id, round_id, &DecisionAction::Eliminate).unwrap();
+        }
+        commit_round(&project.conn, round_id).unwrap();
+        round_id
+    }
+
+    #[test]
+    fn test_export_copies_last_committed_survivors_only() {
+        // Sprint 12: export takes the survivors of the last committed round;
+        // eliminated photos are never exported.
+        let project = TestLibraryBuilder::new()
+            .add_photo(spec(FileType::Jpeg))
+            .add_photo(spec(FileType::Jpeg))
+            .add_photo(spec(FileType::Jpeg))
+            .build_db_only();
+        materialize_sources(&project, "");
+        commit_first_stack(&project, &[project.lp_ids[1]]);
+
+        let dest = project.dir.path().join("out");
+        let result = run_export(
+            &project.conn,
+            project.project_id,
+            &options(&dest, ExportMode::Copy, ExportFilter::Both),
+        )
+        .unwrap();
+
+        assert_eq!(result.logical_photos, 2);
+        assert_eq!(result.files_exported, 2);
+        assert!(result.errors.is_empty(), "errors: {:?}", result.errors);
+        let exported: HashSet<i64> = result.items.iter().map(|i| i.logical_photo_id).collect();
+        assert!(!exported.contains(&project.lp_ids[1]));
+        for item in &result.items {
+            assert_eq!(
+                std::fs::read(&item.source_path).unwrap(),
+                std::fs::read(&item.destination_path).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_export_uncommitted_project_exports_nothing() {
+        let project = TestLibraryBuilder::new()
+            .add_photo(spec(FileType::Jpeg))
+            .build_db_only();
+        materialize_sources(&project, "");
+        let dest = project.dir.path().join("out");
+        let result = run_export(
+            &project.conn,
+            project.project_id,
+            &options(&dest, ExportMode::Copy, ExportFilter::Both),
+        )
+        .unwrap();
+        assert!(result.items.is_empty());
+        assert!(!dest.exists());
+    }
+
+    #[test]
+    fn test_export_pair_filters() {
+        // Sprint 12: a pair exports both files by default, or one side only.
+        let project = TestLibraryBuilder::new()
+            .add_photo(spec(FileType::Both))
+            .build_db_only();
+        materialize_sources(&project, "");
+        commit_first_stack(&project, &[]);
+        let dest = project.dir.path().join("out");
+
+        for (filter, expected) in [
+            (ExportFilter::Both, vec!["jpeg", "raw"]),
+            (ExportFilter::Jpeg, vec!["jpeg"]),
+            (ExportFilter::Raw, vec!["raw"]),
+        ] {
+            let mut opts = options(&dest, ExportMode::Copy, filter);
+            opts.dry_run = true;
+            let (_, items) = plan_export(&project.conn, project.project_id, &opts).unwrap();
+            let formats: Vec<&str> = items.iter().map(|i| i.format.as_str()).collect();
+            assert_eq!(formats, expected, "filter {:?}", filter);
+        }
+    }
+
+    #[test]
+    fn test_export_dry_run_writes_nothing() {
+        let project = TestLibraryBuilder::new()
+            .add_photo(spec(FileType::Jpeg))
+            .build_db_only();
+        materialize_sources(&project, "");
+        commit_first_stack(&project, &[]);
+
+        let dest = project.dir.path().join("out");
+        let mut opts = options(&dest, ExportMode::Copy, ExportFilter::Both);
+        opts.dry_run = true;
+        let result = run_export(&project.conn, project.project_id, &opts).unwrap();
+
+        assert!(result.dry_run);
+        assert_eq!(result.items.len(), 1);
+        assert_eq!(result.files_exported, 0);
+        assert!(!dest.exists(), "dry run must not create the destination");
+    }
+
+    #[test]
+    fn test_export_renames_on_collision_keeping_pairs_together() {
+        // An existing file at the destination forces a suffix; both files of the
+        // pair get the same suffix.
+        let project = TestLibraryBuilder::new()
+            .add_photo(spec(FileType::Both))
+            .build_db_only();
+        materialize_sources(&project, "");
+        commit_first_stack(&project, &[]);
+
+        let dest = project.dir.path().join("out");
+        std::fs::create_dir_all(&dest).unwrap();
+        let (_, items) = plan_export(
+            &project.conn,
+            project.project_id,
+            &options(&dest, ExportMode::Copy, ExportFilter::Both),
+        )
+        .unwrap();
+        // Occupy the JPEG's natural name only
+        let jpeg_target = &items.iter().find(|i| i.format == "jpeg").unwrap();
+        std::fs::write(&jpeg_target.destination_path, b"existing").unwrap();
+
+        let result = run_export(
+            &project.conn,
+            project.project_id,
+            &options(&dest, ExportMode::Copy, ExportFilter::Both),
+        )
+        .unwrap();
+
+        assert_eq!(result.files_exported, 2);
+        for item in &result.items {
+            assert!(item.renamed);
+            let stem = Path::new(&item.destination_path)
+                .file_stem()
+                .unwrap()
+                .to_string_lossy()
+                .to_string();
+            assert!(stem.ends_with("_1"), "expected _1 suffix, got {}", stem);
+        }
+        assert_eq!(
+            std::fs::read(&jpeg_target.destination_path).unwrap(),
+            b"existing",
+            "existing files must never be overwritten"
+        );
+    }
+
+    #[test]
+    fn test_export_preserves_hierarchy_relative_to_source_folder() {
+        let project = TestLibraryBuilder::new()
+            .add_photo(spec(FileType::Jpeg))
+            .build_db_only();
+        materialize_sources(&project, "2024/trip");
+        commit_first_stack(&project, &[]);
+
+        let dest = project.dir.path().join("out");
+        let mut opts = options(&dest, ExportMode::Copy, ExportFilter::Both);
+        opts.preserve_hierarchy = true;
+        let result = run_export(&project.conn, project.project_id, &opts).unwrap();
+
+        let target = Path::new(&result.items[0].destination_path);
+        assert_eq!(target.parent().unwrap(), dest.join("2024/trip"));
+        assert!(target.exists());
+    }
+
+    #[test]
+    fn test_export_hardlink_shares_inode() {
+        let project = TestLibraryBuilder::new()
+            .add_photo(spec(FileType::Jpeg))
+            .build_db_only();
+        materialize_sources(&project, "");
+        commit_first_stack(&project, &[]);
+
+        let dest = project.dir.path().join("out");
+        let result = run_export(
+            &project.conn,
+            project.project_id,
+            &options(&dest, ExportMode::Hardlink, ExportFilter::Both),
+        )
+        .unwrap();
+        assert_eq!(result.files_exported, 1);
+
+        // Writing through the link is visible at the source: same file.
+        let item = &result.items[0];
+        std::fs::write(&item.destination_path, b"changed").unwrap();
+        assert_eq!(std::fs::read(&item.source_path).unwrap(), b"changed");
+    }
+
+    #[test]
+    fn test_export_explicit_round_uses_its_survivors() {
+        // A chosen round (e.g. an open one) exports its current non-eliminated members.
+        let project = TestLibraryBuilder::new()
+            .add_photo(spec(FileType::Jpeg))
+            .add_photo(spec(FileType::Jpeg))
+            .build_db_only();
+        materialize_sources(&project, "");
+        let (round_id, _) =
+            find_or_create_round(&project.conn, project.project_id, project.stack_ids[0]).unwrap();
+        record_decision(
+            &project.conn,
+            project.lp_ids[0],
+            round_id,
+            &DecisionAction::Eliminate,
+        )
+        .unwrap();
+
+        let dest = project.dir.path().join("out");
+        let mut opts = options(&dest, ExportMode::Copy, ExportFilter::Both);
+        opts.round_id = Some(round_id);
+        opts.dry_run = true;
+        let (count, items) = plan_export(&project.conn, project.project_id, &opts).unwrap();
+        assert_eq!(count, 1);
+        assert_eq!(items[0].logical_photo_id, project.lp_ids[1]);
+    }
+
+    #[test]
+    fn test_export_writes_sidecars_for_exported_files() {
+        // Sprint 12: exported photos carry an XMP sidecar so Lightroom/darktable
+        // pick up the decisions; a pair with Replace naming shares one sidecar.
+        let project = TestLibraryBuilder::new()
+            .add_photo(spec(FileType::Both))
+            .build_db_only();
+        materialize_sources(&project, "");
+        commit_first_stack(&project, &[]);
+
+        let dest = project.dir.path().join("out");
+        let mut opts = options(&dest, ExportMode::Copy, ExportFilter::Both);
+        opts.sidecars = Some(crate::xmp::model::SidecarNaming::Replace);
+        let result = run_export(&project.conn, project.project_id, &opts).unwrap();
+
+        assert_eq!(result.files_exported, 2);
+        assert_eq!(result.sidecars_written, 1);
+        let sidecar = Path::new(&result.items[0].destination_path).with_extension("xmp");
+        let xml = std::fs::read_to_string(sidecar).unwrap();
+        assert!(xml.contains("gemkeep:RoundReached=\"2\""), "{}", xml);
+    }
+}
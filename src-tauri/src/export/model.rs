@@ -0,0 +1,104 @@
+/// How an exported file is materialised at the destination.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportMode {
+    Copy,
+    Hardlink,
+    Reflink,
+}
+
+impl ExportMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExportMode::Copy => "copy",
+            ExportMode::Hardlink => "hardlink",
+            ExportMode::Reflink => "reflink",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "copy" => Some(ExportMode::Copy),
+            "hardlink" => Some(ExportMode::Hardlink),
+            "reflink" => Some(ExportMode::Reflink),
+            _ => None,
+        }
+    }
+}
+
+/// Which files of a logical photo are exported.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFilter {
+    Jpeg,
+    Raw,
+    Both,
+}
+
+impl ExportFilter {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExportFilter::Jpeg => "jpeg",
+            ExportFilter::Raw => "raw",
+            ExportFilter::Both => "both",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "jpeg" => Some(ExportFilter::Jpeg),
+            "raw" => Some(ExportFilter::Raw),
+            "both" => Some(ExportFilter::Both),
+            _ => None,
+        }
+    }
+
+    /// Whether a photos.format value passes this filter.
+    pub fn includes(&self, format: &str) -> bool {
+        match self {
+            ExportFilter::Jpeg => format == "jpeg",
+            ExportFilter::Raw => format == "raw",
+            ExportFilter::Both => true,
+        }
+    }
+}
+
+/// Options for one export run.
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    pub destination: std::path::PathBuf,
+    pub mode: ExportMode,
+    pub filter: ExportFilter,
+    /// Export survivors of this round; None = last committed round of every active stack.
+    pub round_id: Option<i64>,
+    /// Recreate the folder layout below the matching source folder.
+    pub preserve_hierarchy: bool,
+    /// Plan only: nothing is written.
+    pub dry_run: bool,
+    /// Also write an XMP sidecar for each exported file.
+    pub sidecars: Option<crate::xmp::model::SidecarNaming>,
+}
+
+/// A single planned (or performed) file export.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ExportItem {
+    pub logical_photo_id: i64,
+    pub format: String, // "jpeg" | "raw"
+    pub source_path: String,
+    pub destination_path: String,
+    /// True if a numeric suffix was added to avoid a name collision.
+    pub renamed: bool,
+}
+
+/// Result of an export run (or dry-run plan).
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ExportResult {
+    pub dry_run: bool,
+    pub mode: ExportMode,
+    pub logical_photos: usize,
+    pub items: Vec<ExportItem>,
+    /// Files actually written (always 0 for a dry run).
+    pub files_exported: usize,
+    pub sidecars_written: usize,
+    pub errors: Vec<String>,
+}
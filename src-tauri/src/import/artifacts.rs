@@ -0,0 +1,622 @@
+//! Content identity of cached thumbnail artifacts.
+//!
+//! Cached files are named by the id of the logical photo they were made for
+//! (`{id}.jpg`, `{id}_preview.jpg`, `{id}_fit512.jpg`, `fullres/{id}.jpg`, …), but
+//! logical photo ids change when a project is re-indexed from scratch or a folder
+//! is removed and added back. `thumbnail_artifacts` records, per cache id, the
+//! artifact key of the source the files were made from — its size and content
+//! fingerprint — and the image analysis taken from it, if any.
+//!
+//! Before thumbnails are generated the cache is reconciled with the current
+//! logical photos: files made from a photo's content move to its current id (with
+//! their analysis), and files an id holds for other content are dropped so they
+//! are regenerated instead of shown for the wrong photo. A thumbnail without a
+//! record (a cache made before records existed) is taken to be of the photo that
+//! holds its id, and recorded as such; only a record naming other content drops
+//! files.
+//!
+//! Every id a reconcile moves files to or from is first recorded as pending, a
+//! key no photo has, and gets its final record once its files are in place. A
+//! reconcile interrupted halfway leaves pending ids, whose files the next one
+//! drops: an interruption costs regeneration, never a wrong thumbnail.
+
+use rusqlite::{params, Connection};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::import::fullres;
+use crate::import::quality::{self, ImageAnalysis};
+use crate::photos::repository::collect_rows;
+
+const STAGING_DIR: &str = ".staging";
+
+/// Artifact key of a cache id whose files are being moved or dropped.
+const PENDING_KEY: &str = "";
+
+/// A recorded cache id and the content its files were made from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThumbnailArtifact {
+    pub cache_id: i64,
+    pub artifact_key: String,
+    /// None when the files were recorded without an analysis: the source could
+    /// not be decoded for one, or the files predate the records.
+    pub analysis: Option<ImageAnalysis>,
+}
+
+/// What `reconcile_cache` changed.
+#[derive(Debug, Default, PartialEq)]
+pub struct Reconciled {
+    /// Logical photos whose cached files were moved to them from another id.
+    pub adopted: HashSet<i64>,
+    /// Logical photos whose cached files belonged to other content and were deleted.
+    pub dropped: Vec<i64>,
+}
+
+/// Artifact key of every logical photo whose representative file has a stored
+/// fingerprint, as `(lp_id, "{file_size}-{fingerprint}")`.
+pub fn list_artifact_keys(
+    conn: &Connection,
+    project_id: i64,
+) -> rusqlite::Result<Vec<(i64, String)>> {
+    collect_rows(
+        conn,
+        "SELECT lp.id, p.file_size || '-' || p.fingerprint
+           FROM logical_photos lp
+           JOIN photos p ON p.id = lp.representative_photo_id
+          WHERE lp.project_id = ?1
+            AND p.file_size IS NOT NULL AND p.fingerprint IS NOT NULL
+          ORDER BY lp.id",
+        params![project_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )
+}
+
+/// Every recorded cache id of a project.
+pub fn load_artifacts(
+    conn: &Connection,
+    project_id: i64,
+) -> rusqlite::Result<Vec<ThumbnailArtifact>> {
+    collect_rows(
+        conn,
+        "SELECT cache_id, artifact_key, sharpness, mean_luma, highlight_clipped
+           FROM thumbnail_artifacts WHERE project_id = ?1 ORDER BY cache_id",
+        params![project_id],
+        |row| {
+            let analysis = match (row.get(2)?, row.get(3)?, row.get(4)?) {
+                (Some(sharpness), Some(mean_luma), Some(highlight_clipped)) => {
+                    Some(ImageAnalysis {
+                        sharpness,
+                        mean_luma,
+                        highlight_clipped,
+                    })
+                }
+                _ => None,
+            };
+            Ok(ThumbnailArtifact {
+                cache_id: row.get(0)?,
+                artifact_key: row.get(1)?,
+                analysis,
+            })
+        },
+    )
+}
+
+/// Record the content behind the thumbnails just generated for `lp_ids`, with
+/// the analysis of those that have one. Logical photos without a fingerprint or
+/// without a thumbnail in `cache_dir` are not recorded.
+pub fn record_artifacts(
+    conn: &Connection,
+    project_id: i64,
+    cache_dir: &Path,
+    lp_ids: &[i64],
+    analyses: &[(i64, ImageAnalysis)],
+) -> rusqlite::Result<()> {
+    let keys: HashMap<i64, String> = list_artifact_keys(conn, project_id)?.into_iter().collect();
+    let analyses: HashMap<i64, ImageAnalysis> = analyses.iter().copied().collect();
+    let artifacts: Vec<ThumbnailArtifact> = lp_ids
+        .iter()
+        .filter(|id| cache_dir.join(format!("{}.jpg", id)).exists())
+        .filter_map(|id| {
+            Some(ThumbnailArtifact {
+                cache_id: *id,
+                artifact_key: keys.get(id)?.clone(),
+                analysis: analyses.get(id).copied(),
+            })
+        })
+        .collect();
+    write_records(conn, project_id, &artifacts, &[])
+}
+
+/// Reconcile `cache/thumbnails` and `cache/fullres` with the project's current
+/// logical photos (see the module docs). Ids no logical photo claims keep their
+/// files, so a photo that comes back later still finds them.
+pub fn reconcile_cache(
+    conn: &Connection,
+    project_id: i64,
+    project_dir: &Path,
+) -> rusqlite::Result<Reconciled> {
+    let wanted = list_artifact_keys(conn, project_id)?;
+    let mut records: HashMap<i64, ThumbnailArtifact> = load_artifacts(conn, project_id)?
+        .into_iter()
+        .map(|a| (a.cache_id, a))
+        .collect();
+    let thumb_dir = project_dir.join("cache").join("thumbnails");
+    let fullres_dir = fullres::fullres_dir(project_dir);
+    // Files staged by an interrupted reconcile belong to pending ids
+    for dir in [&thumb_dir, &fullres_dir] {
+        let _ = std::fs::remove_dir_all(dir.join(STAGING_DIR));
+    }
+    let mut thumb_files = index_cache_files(&thumb_dir);
+    let mut fullres_files = index_cache_files(&fullres_dir);
+    let has_thumbnail = |files: &HashMap<i64, Vec<(PathBuf, String)>>, id: i64| {
+        files
+            .get(&id)
+            .is_some_and(|f| f.iter().any(|(_, suffix)| suffix == ".jpg"))
+    };
+
+    let unrecorded: Vec<ThumbnailArtifact> = wanted
+        .iter()
+        .filter(|(lp_id, _)| !records.contains_key(lp_id) && has_thumbnail(&thumb_files, *lp_id))
+        .map(|(lp_id, key)| ThumbnailArtifact {
+            cache_id: *lp_id,
+            artifact_key: key.clone(),
+            analysis: None,
+        })
+        .collect();
+    write_records(conn, project_id, &unrecorded, &[])?;
+    records.extend(unrecorded.into_iter().map(|a| (a.cache_id, a)));
+
+    let current: HashSet<i64> = wanted
+        .iter()
+        .filter(|(lp_id, key)| {
+            records.get(lp_id).is_some_and(|a| &a.artifact_key == key)
+                && has_thumbnail(&thumb_files, *lp_id)
+        })
+        .map(|(lp_id, _)| *lp_id)
+        .collect();
+    if current.len() == wanted.len() {
+        return Ok(Reconciled::default());
+    }
+
+    // Cache ids holding content that no photo holds under its own id
+    let mut donors: HashMap<&str, Vec<i64>> = HashMap::new();
+    for (id, artifact) in &records {
+        if !current.contains(id) && has_thumbnail(&thumb_files, *id) {
+            donors
+                .entry(artifact.artifact_key.as_str())
+                .or_default()
+                .push(*id);
+        }
+    }
+    let mut moves: HashMap<i64, i64> = HashMap::new();
+    let mut replaced: HashSet<i64> = HashSet::new();
+    for (lp_id, key) in &wanted {
+        if current.contains(lp_id) {
+            continue;
+        }
+        if records.get(lp_id).is_some_and(|a| &a.artifact_key != key) {
+            replaced.insert(*lp_id);
+        }
+        if let Some(from) = donors.get_mut(key.as_str()).and_then(|ids| ids.pop()) {
+            moves.insert(*lp_id, from);
+        }
+    }
+    let touched: BTreeSet<i64> = moves
+        .iter()
+        .flat_map(|(&to, &from)| [to, from])
+        .chain(replaced.iter().copied())
+        .collect();
+    if touched.is_empty() {
+        return Ok(Reconciled::default());
+    }
+    let pending: Vec<ThumbnailArtifact> = touched
+        .iter()
+        .map(|id| ThumbnailArtifact {
+            cache_id: *id,
+            artifact_key: PENDING_KEY.to_string(),
+            analysis: None,
+        })
+        .collect();
+    write_records(conn, project_id, &pending, &[])?;
+
+    // Moves may form chains and cycles (5 → 7 while 7 → 5): stage every moved
+    // file first, then move each into place over what the receiving id holds
+    // and drop what it holds beyond that.
+    let mut staged: HashMap<i64, Vec<(PathBuf, PathBuf)>> = HashMap::new();
+    for (&to, &from) in &moves {
+        let files = staged.entry(to).or_default();
+        files.extend(stage_files(&mut thumb_files, &thumb_dir, from, to));
+        files.extend(stage_files(&mut fullres_files, &fullres_dir, from, to));
+    }
+    let mut arrived: HashSet<PathBuf> = HashSet::new();
+    for (staged_path, final_path) in staged.into_values().flatten() {
+        match std::fs::rename(&staged_path, &final_path) {
+            Ok(()) => {
+                arrived.insert(final_path);
+            }
+            Err(e) => tracing::warn!("artifacts: move {:?} failed: {}", final_path, e),
+        }
+    }
+    let _ = std::fs::remove_dir_all(thumb_dir.join(STAGING_DIR));
+    let _ = std::fs::remove_dir_all(fullres_dir.join(STAGING_DIR));
+    let mut dropped = Vec::new();
+    for id in &touched {
+        let mut had_files = false;
+        for files in [&mut thumb_files, &mut fullres_files] {
+            for (path, _) in files.remove(id).unwrap_or_default() {
+                if !arrived.contains(&path) {
+                    had_files |= std::fs::remove_file(&path).is_ok();
+                }
+            }
+        }
+        if had_files {
+            dropped.push(*id);
+        }
+    }
+
+    // Final records: a receiver holds the content of its donor once its
+    // thumbnail arrived; a donor whose files could not be staged keeps them.
+    // Ids still holding a thumbnail of unknown content stay pending.
+    let mut adopted = HashSet::new();
+    let mut upserts = Vec::new();
+    let mut forgotten = Vec::new();
+    for id in &touched {
+        let thumbnail = thumb_dir.join(format!("{}.jpg", id));
+        if let Some(from) = moves.get(id).filter(|_| arrived.contains(&thumbnail)) {
+            adopted.insert(*id);
+            upserts.push(ThumbnailArtifact {
+                cache_id: *id,
+                ..records[from].clone()
+            });
+        } else if !thumbnail.exists() {
+            forgotten.push(*id);
+        } else if !moves.contains_key(id) && !replaced.contains(id) {
+            upserts.push(records[id].clone());
+        }
+    }
+    write_records(conn, project_id, &upserts, &forgotten)?;
+
+    let analyses: Vec<(i64, ImageAnalysis)> = upserts
+        .iter()
+        .filter(|a| adopted.contains(&a.cache_id))
+        .filter_map(|a| Some((a.cache_id, a.analysis?)))
+        .collect();
+    quality::store_image_analyses(conn, &analyses)?;
+
+    tracing::info!(
+        "artifacts: {} thumbnail sets reused, {} dropped",
+        adopted.len(),
+        dropped.len()
+    );
+    Ok(Reconciled { adopted, dropped })
+}
+
+/// Record `upserts` and forget the `forgotten` cache ids, in one transaction.
+fn write_records(
+    conn: &Connection,
+    project_id: i64,
+    upserts: &[ThumbnailArtifact],
+    forgotten: &[i64],
+) -> rusqlite::Result<()> {
+    if upserts.is_empty() && forgotten.is_empty() {
+        return Ok(());
+    }
+    conn.execute("BEGIN", [])?;
+    let result = (|| {
+        for id in forgotten {
+            conn.execute(
+                "DELETE FROM thumbnail_artifacts WHERE project_id = ?1 AND cache_id = ?2",
+                params![project_id, id],
+            )?;
+        }
+        for artifact in upserts {
+            conn.execute(
+                "INSERT OR REPLACE INTO thumbnail_artifacts
+                     (cache_id, project_id, artifact_key, sharpness, mean_luma, highlight_clipped)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    artifact.cache_id,
+                    project_id,
+                    artifact.artifact_key,
+                    artifact.analysis.map(|a| a.sharpness),
+                    artifact.analysis.map(|a| a.mean_luma),
+                    artifact.analysis.map(|a| a.highlight_clipped)
+                ],
+            )?;
+        }
+        Ok(())
+    })();
+    match result {
+        Ok(()) => {
+            conn.execute("COMMIT", [])?;
+            Ok(())
+        }
+        Err(e) => {
+            let _ = conn.execute("ROLLBACK", []);
+            Err(e)
+        }
+    }
+}
+
+/// Cached files in `dir` by the id leading their name (`{id}.jpg`, `{id}_*`),
+/// each with the rest of its name.
+fn index_cache_files(dir: &Path) -> HashMap<i64, Vec<(PathBuf, String)>> {
+    let mut index: HashMap<i64, Vec<(PathBuf, String)>> = HashMap::new();
+    for entry in std::fs::read_dir(dir).into_iter().flatten().flatten() {
+        let Some(name) = entry.file_name().to_str().map(str::to_string) else {
+            continue;
+        };
+        let split = name
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(name.len());
+        let (id, suffix) = name.split_at(split);
+        if !(suffix.starts_with('.') || suffix.starts_with('_')) {
+            continue;
+        }
+        if let Ok(id) = id.parse::<i64>() {
+            index
+                .entry(id)
+                .or_default()
+                .push((entry.path(), suffix.to_string()));
+        }
+    }
+    index
+}
+
+/// Move the files of `from` into the staging dir under the name of `to`.
+/// Returns `(staged, final)` paths of each file moved.
+fn stage_files(
+    files: &mut HashMap<i64, Vec<(PathBuf, String)>>,
+    dir: &Path,
+    from: i64,
+    to: i64,
+) -> Vec<(PathBuf, PathBuf)> {
+    let Some(entries) = files.remove(&from) else {
+        return vec![];
+    };
+    let staging = dir.join(STAGING_DIR);
+    if std::fs::create_dir_all(&staging).is_err() {
+        return vec![];
+    }
+    entries
+        .into_iter()
+        .filter_map(|(path, suffix)| {
+            let name = format!("{}{}", to, suffix);
+            let staged = staging.join(&name);
+            match std::fs::rename(&path, &staged) {
+                Ok(()) => Some((staged, dir.join(name))),
+                Err(e) => {
+                    tracing::warn!("artifacts: stage {:?} failed: {}", path, e);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::test_fixtures::TestLibraryBuilder;
+
+    fn analysis(sharpness: f64) -> ImageAnalysis {
+        ImageAnalysis {
+            sharpness,
+            mean_luma: 120.0,
+            highlight_clipped: 0.0,
+        }
+    }
+
+    fn set_fingerprint(conn: &Connection, lp_id: i64, fingerprint: &str) {
+        conn.execute(
+            "UPDATE photos SET file_size = 100, fingerprint = ?1
+              WHERE id = (SELECT representative_photo_id FROM logical_photos WHERE id = ?2)",
+            params![fingerprint, lp_id],
+        )
+        .unwrap();
+    }
+
+    fn write_set(thumb_dir: &Path, id: i64, content: &str) {
+        std::fs::write(thumb_dir.join(format!("{}.jpg", id)), content).unwrap();
+        std::fs::write(thumb_dir.join(format!("{}_fit512.jpg", id)), content).unwrap();
+    }
+
+    fn read(thumb_dir: &Path, name: &str) -> Option<String> {
+        std::fs::read_to_string(thumb_dir.join(name)).ok()
+    }
+
+    #[test]
+    fn test_reconcile_moves_files_to_the_photos_new_ids() {
+        let project = TestLibraryBuilder::new()
+            .with_stack_layout(&[2])
+            .build_db_only();
+        let conn = &project.conn;
+        let lps = project.lp_ids().to_vec();
+        let dir = tempfile::tempdir().unwrap();
+        let thumb_dir = dir.path().join("cache").join("thumbnails");
+        std::fs::create_dir_all(&thumb_dir).unwrap();
+
+        set_fingerprint(conn, lps[0], "aaaa");
+        set_fingerprint(conn, lps[1], "bbbb");
+        write_set(&thumb_dir, lps[0], "A");
+        write_set(&thumb_dir, lps[1], "B");
+        record_artifacts(
+            conn,
+            project.project_id,
+            &thumb_dir,
+            &lps,
+            &[(lps[0], analysis(300.0)), (lps[1], analysis(40.0))],
+        )
+        .unwrap();
+
+        // Re-indexing swapped the ids of the two photos
+        set_fingerprint(conn, lps[0], "bbbb");
+        set_fingerprint(conn, lps[1], "aaaa");
+        let result = reconcile_cache(conn, project.project_id, dir.path()).unwrap();
+
+        assert_eq!(result.adopted, HashSet::from([lps[0], lps[1]]));
+        assert_eq!(
+            read(&thumb_dir, &format!("{}.jpg", lps[0])).as_deref(),
+            Some("B")
+        );
+        assert_eq!(
+            read(&thumb_dir, &format!("{}_fit512.jpg", lps[1])).as_deref(),
+            Some("A")
+        );
+        assert!(!thumb_dir.join(STAGING_DIR).exists());
+        let sharpness: f64 = conn
+            .query_row(
+                "SELECT sharpness FROM logical_photos WHERE id = ?1",
+                [lps[1]],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(sharpness, 300.0, "analysis follows the content");
+
+        let again = reconcile_cache(conn, project.project_id, dir.path()).unwrap();
+        assert_eq!(again, Reconciled::default(), "reconciled cache is stable");
+    }
+
+    #[test]
+    fn test_reconcile_drops_files_made_for_other_content() {
+        let project = TestLibraryBuilder::new()
+            .with_stack_layout(&[1])
+            .build_db_only();
+        let conn = &project.conn;
+        let lp = project.lp_ids()[0];
+        let dir = tempfile::tempdir().unwrap();
+        let thumb_dir = dir.path().join("cache").join("thumbnails");
+        std::fs::create_dir_all(&thumb_dir).unwrap();
+
+        set_fingerprint(conn, lp, "aaaa");
+        write_set(&thumb_dir, lp, "A");
+        record_artifacts(
+            conn,
+            project.project_id,
+            &thumb_dir,
+            &[lp],
+            &[(lp, analysis(300.0))],
+        )
+        .unwrap();
+
+        // The id now belongs to a different photo
+        set_fingerprint(conn, lp, "cccc");
+        let result = reconcile_cache(conn, project.project_id, dir.path()).unwrap();
+
+        assert!(result.adopted.is_empty());
+        assert_eq!(result.dropped, vec![lp]);
+        assert!(!thumb_dir.join(format!("{}.jpg", lp)).exists());
+        assert!(load_artifacts(conn, project.project_id).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_reconcile_adopts_a_cache_without_records() {
+        // A cache made before artifacts were recorded must survive the first
+        // reconcile, and be recorded for the photos holding its ids.
+        let project = TestLibraryBuilder::new()
+            .with_stack_layout(&[2])
+            .build_db_only();
+        let conn = &project.conn;
+        let lps = project.lp_ids().to_vec();
+        let dir = tempfile::tempdir().unwrap();
+        let thumb_dir = dir.path().join("cache").join("thumbnails");
+        std::fs::create_dir_all(&thumb_dir).unwrap();
+
+        set_fingerprint(conn, lps[0], "aaaa");
+        set_fingerprint(conn, lps[1], "bbbb");
+        write_set(&thumb_dir, lps[0], "A");
+        write_set(&thumb_dir, lps[1], "B");
+        let result = reconcile_cache(conn, project.project_id, dir.path()).unwrap();
+
+        assert_eq!(result, Reconciled::default());
+        assert_eq!(
+            read(&thumb_dir, &format!("{}.jpg", lps[0])).as_deref(),
+            Some("A")
+        );
+        assert_eq!(
+            read(&thumb_dir, &format!("{}_fit512.jpg", lps[1])).as_deref(),
+            Some("B")
+        );
+        let keys: Vec<(i64, String, Option<ImageAnalysis>)> =
+            load_artifacts(conn, project.project_id)
+                .unwrap()
+                .into_iter()
+                .map(|a| (a.cache_id, a.artifact_key, a.analysis))
+                .collect();
+        assert_eq!(
+            keys,
+            vec![
+                (lps[0], "100-aaaa".to_string(), None),
+                (lps[1], "100-bbbb".to_string(), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_thumbnail_without_analysis_is_recorded_and_kept() {
+        // A photo whose thumbnail was written but whose source could not be
+        // decoded for analysis must not be regenerated on every reconcile.
+        let project = TestLibraryBuilder::new()
+            .with_stack_layout(&[1])
+            .build_db_only();
+        let conn = &project.conn;
+        let lp = project.lp_ids()[0];
+        let dir = tempfile::tempdir().unwrap();
+        let thumb_dir = dir.path().join("cache").join("thumbnails");
+        std::fs::create_dir_all(&thumb_dir).unwrap();
+
+        set_fingerprint(conn, lp, "aaaa");
+        write_set(&thumb_dir, lp, "A");
+        record_artifacts(conn, project.project_id, &thumb_dir, &[lp], &[]).unwrap();
+        let records = load_artifacts(conn, project.project_id).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].analysis, None);
+
+        let result = reconcile_cache(conn, project.project_id, dir.path()).unwrap();
+        assert_eq!(result, Reconciled::default());
+        assert!(thumb_dir.join(format!("{}.jpg", lp)).exists());
+    }
+
+    #[test]
+    fn test_interrupted_reconcile_drops_pending_files() {
+        // An id left pending holds files of unknown content: they are dropped,
+        // and files left in the staging dir are cleared.
+        let project = TestLibraryBuilder::new()
+            .with_stack_layout(&[1])
+            .build_db_only();
+        let conn = &project.conn;
+        let lp = project.lp_ids()[0];
+        let dir = tempfile::tempdir().unwrap();
+        let thumb_dir = dir.path().join("cache").join("thumbnails");
+        std::fs::create_dir_all(thumb_dir.join(STAGING_DIR)).unwrap();
+        std::fs::write(thumb_dir.join(STAGING_DIR).join("9.jpg"), "X").unwrap();
+
+        set_fingerprint(conn, lp, "aaaa");
+        write_set(&thumb_dir, lp, "B");
+        let pending = ThumbnailArtifact {
+            cache_id: lp,
+            artifact_key: PENDING_KEY.to_string(),
+            analysis: None,
+        };
+        write_records(conn, project.project_id, &[pending], &[]).unwrap();
+        let result = reconcile_cache(conn, project.project_id, dir.path()).unwrap();
+
+        assert_eq!(result.dropped, vec![lp]);
+        assert!(!thumb_dir.join(format!("{}.jpg", lp)).exists());
+        assert!(!thumb_dir.join(STAGING_DIR).exists());
+        assert!(load_artifacts(conn, project.project_id).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_index_ignores_files_not_named_by_id() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["12.jpg", "12_preview.jpg", "manifest.json", "12abc.jpg"] {
+            std::fs::write(dir.path().join(name), b"").unwrap();
+        }
+        let index = index_cache_files(dir.path());
+        assert_eq!(index.len(), 1);
+        let mut suffixes: Vec<&str> = index[&12].iter().map(|(_, s)| s.as_str()).collect();
+        suffixes.sort();
+        assert_eq!(suffixes, vec![".jpg", "_preview.jpg"]);
+    }
+}
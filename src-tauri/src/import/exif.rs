@@ -32,6 +32,10 @@ pub struct ExifData {
     pub iso: Option<u32>,              // e.g. 400
     pub focal_length: Option<f64>,     // mm, e.g. 85.0
     pub exposure_comp: Option<f64>,    // EV, e.g. +0.7
+    /// Camera image/sequence number; orders burst frames sharing a timestamp
+    pub sequence_number: Option<u32>,
+    /// Body serial number; tells apart two bodies of the same model
+    pub camera_serial: Option<String>,
 }
 
 /// Format a shutter speed value (in seconds) as a human-readable string.
@@ -78,7 +82,13 @@ fn extract_jpeg_exif_inner(path: &Path) -> ExifData {
         }
     };
 
-    let capture_time = read_datetime_original(&exif);
+    let capture_time = read_datetime_original(&exif).map(|t| {
+        refine_capture_time(
+            t,
+            read_ascii_tag(&exif, exif::Tag::SubSecTimeOriginal).as_deref(),
+            read_ascii_tag(&exif, exif::Tag::OffsetTimeOriginal).as_deref(),
+        )
+    });
 
     let camera_model = {
         let make = read_ascii_tag(&exif, exif::Tag::Make).unwrap_or_default();
@@ -96,6 +106,8 @@ fn extract_jpeg_exif_inner(path: &Path) -> ExifData {
     let iso = read_iso(&exif);
     let focal_length = read_rational_tag(&exif, exif::Tag::FocalLength);
     let exposure_comp = read_srational_tag(&exif, exif::Tag::ExposureBiasValue);
+    let sequence_number = read_image_number(&exif);
+    let camera_serial = read_ascii_tag(&exif, exif::Tag::BodySerialNumber);
 
     ExifData {
         capture_time,
@@ -107,6 +119,8 @@ fn extract_jpeg_exif_inner(path: &Path) -> ExifData {
         iso,
         focal_length,
         exposure_comp,
+        sequence_number,
+        camera_serial,
     }
 }
 
@@ -135,6 +149,60 @@ pub fn parse_exif_datetime(s: &str) -> Option<chrono::DateTime<chrono::Utc>> {
     Some(chrono::Utc.from_utc_datetime(&ndt))
 }
 
+/// Add a SubSecTime value to a whole-second EXIF time. The value holds the
+/// fractional digits: "5" is 0.5 s, "045" is 0.045 s. Only milliseconds are kept,
+/// so every stored time has the same precision.
+pub fn with_subsec(
+    time: chrono::DateTime<chrono::Utc>,
+    subsec: &str,
+) -> chrono::DateTime<chrono::Utc> {
+    let digits: String = subsec
+        .trim()
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .take(3)
+        .collect();
+    if digits.is_empty() {
+        return time;
+    }
+    let millis: i64 = format!("{:0<3}", digits).parse().unwrap_or(0);
+    time + chrono::Duration::milliseconds(millis)
+}
+
+/// Turn an EXIF local time (parsed as if it were UTC) into true UTC using an
+/// OffsetTime value such as "+02:00". An unreadable offset leaves the time as
+/// is, like a file that records none.
+pub fn with_utc_offset(
+    time: chrono::DateTime<chrono::Utc>,
+    offset: &str,
+) -> chrono::DateTime<chrono::Utc> {
+    let offset = offset.trim();
+    let sign = match offset.chars().next() {
+        Some('+') => 1,
+        Some('-') => -1,
+        _ => return time,
+    };
+    let Some((hours, minutes)) = offset[1..].split_once(':') else {
+        return time;
+    };
+    match (hours.parse::<i64>(), minutes.parse::<i64>()) {
+        (Ok(h), Ok(m)) if h <= 14 && m < 60 => {
+            time - chrono::Duration::minutes(sign * (h * 60 + m))
+        }
+        _ => time,
+    }
+}
+
+/// DateTimeOriginal refined by its SubSecTimeOriginal and OffsetTimeOriginal tags.
+fn refine_capture_time(
+    time: chrono::DateTime<chrono::Utc>,
+    subsec: Option<&str>,
+    offset: Option<&str>,
+) -> chrono::DateTime<chrono::Utc> {
+    let time = subsec.map_or(time, |subsec| with_subsec(time, subsec));
+    offset.map_or(time, |offset| with_utc_offset(time, offset))
+}
+
 fn read_ascii_tag(exif: &exif::Exif, tag: exif::Tag) -> Option<String> {
     let field = exif.get_field(tag, exif::In::PRIMARY)?;
     match &field.value {
@@ -199,6 +267,17 @@ fn read_iso(exif: &exif::Exif) -> Option<u32> {
     }
 }
 
+/// EXIF ImageNumber (0x9211), which kamadak-exif has no named tag for.
+fn read_image_number(exif: &exif::Exif) -> Option<u32> {
+    let tag = exif::Tag(exif::Context::Exif, 0x9211);
+    let field = exif.get_field(tag, exif::In::PRIMARY)?;
+    match &field.value {
+        exif::Value::Short(v) => v.first().map(|&v| v as u32),
+        exif::Value::Long(v) => v.first().copied(),
+        _ => None,
+    }
+}
+
 /// Extract EXIF metadata from a RAW file using rawler (pure Rust).
 /// Never panics; returns all-None on any error.
 pub fn extract_raw_exif(path: &Path) -> ExifData {
@@ -241,7 +320,14 @@ fn extract_raw_exif_inner(path: &Path) -> ExifData {
         .exif
         .date_time_original
         .as_deref()
-        .and_then(parse_exif_datetime);
+        .and_then(parse_exif_datetime)
+        .map(|t| {
+            refine_capture_time(
+                t,
+                metadata.exif.sub_sec_time_original.as_deref(),
+                metadata.exif.offset_time_original.as_deref(),
+            )
+        });
 
     let camera_model = format_camera_model(&metadata.make, &metadata.model);
 
@@ -280,6 +366,14 @@ fn extract_raw_exif_inner(path: &Path) -> ExifData {
         .exposure_bias
         .filter(|r| r.d != 0)
         .map(|r| r.n as f64 / r.d as f64);
+    // rawler exposes the EXIF ImageNumber; maker-note shot counters are not decoded
+    let sequence_number = metadata.exif.image_number;
+    let camera_serial = metadata
+        .exif
+        .serial_number
+        .as_deref()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty());
 
     ExifData {
         capture_time,
@@ -291,6 +385,8 @@ fn extract_raw_exif_inner(path: &Path) -> ExifData {
         iso,
         focal_length,
         exposure_comp,
+        sequence_number,
+        camera_serial,
     }
 }
 
@@ -375,6 +471,39 @@ mod tests {
         assert!(parse_exif_datetime("short").is_none());
     }
 
+    #[test]
+    fn test_with_subsec_reads_fractional_digits_as_milliseconds() {
+        let t = parse_exif_datetime("2024:03:15 12:30:45").unwrap();
+        let millis = |subsec: &str| (with_subsec(t, subsec) - t).num_milliseconds();
+        assert_eq!(millis("5"), 500);
+        assert_eq!(millis("05"), 50);
+        assert_eq!(millis("045"), 45);
+        assert_eq!(millis("123456"), 123, "sub-millisecond digits are dropped");
+        assert_eq!(millis(" 25 "), 250);
+        assert_eq!(millis(""), 0);
+        assert_eq!(millis("abc"), 0);
+        assert_eq!(
+            with_subsec(t, "5").to_rfc3339(),
+            "2024-03-15T12:30:45.500+00:00"
+        );
+    }
+
+    #[test]
+    fn test_with_utc_offset_converts_local_time_to_utc() {
+        let t = parse_exif_datetime("2024:06:01 14:00:00").unwrap();
+        assert_eq!(
+            with_utc_offset(t, "+02:00").to_rfc3339(),
+            "2024-06-01T12:00:00+00:00"
+        );
+        assert_eq!(
+            with_utc_offset(t, "-05:30").to_rfc3339(),
+            "2024-06-01T19:30:00+00:00"
+        );
+        assert_eq!(with_utc_offset(t, "+00:00"), t);
+        assert_eq!(with_utc_offset(t, "   :  "), t, "blank offset");
+        assert_eq!(with_utc_offset(t, "+25:00"), t);
+    }
+
     #[test]
     fn test_exif_jpeg_synthetic_no_exif_returns_none() {
         // WHY: image crate does not embed EXIF when creating an ImageBuffer.
@@ -0,0 +1,97 @@
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Bytes hashed from each end of the file.
+const CHUNK: u64 = 64 * 1024;
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// A cheap content identity: file size plus a hash of the first and last 64 KiB.
+///
+/// Header and tail cover the EXIF block and the end of the image data, which is
+/// enough to tell photos apart without reading whole RAW files. The hash is
+/// FNV-1a so stored values stay stable across builds.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Fingerprint {
+    pub size: u64,
+    pub hash: String,
+}
+
+pub fn compute_fingerprint(path: &Path) -> std::io::Result<Fingerprint> {
+    let mut file = std::fs::File::open(path)?;
+    let size = file.metadata()?.len();
+
+    let mut hash = FNV_OFFSET;
+    let mut feed = |bytes: &[u8]| {
+        for b in bytes {
+            hash ^= *b as u64;
+            hash = hash.wrapping_mul(FNV_PRIME);
+        }
+    };
+
+    let mut buf = Vec::with_capacity(CHUNK as usize);
+    (&mut file).take(CHUNK).read_to_end(&mut buf)?;
+    feed(&buf);
+
+    // Tail (skipped when the head already covered the whole file)
+    if size > CHUNK {
+        buf.clear();
+        file.seek(SeekFrom::Start(size.saturating_sub(CHUNK).max(CHUNK)))?;
+        file.take(CHUNK).read_to_end(&mut buf)?;
+        feed(&buf);
+    }
+
+    Ok(Fingerprint {
+        size,
+        hash: format!("{:016x}", hash),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint_follows_content_not_path() {
+        let tmp = tempfile::tempdir().unwrap();
+        let a = tmp.path().join("a.jpg");
+        let b = tmp.path().join("moved").join("renamed.jpg");
+        std::fs::create_dir_all(b.parent().unwrap()).unwrap();
+        std::fs::write(&a, b"same bytes").unwrap();
+        std::fs::write(&b, b"same bytes").unwrap();
+
+        let fa = compute_fingerprint(&a).unwrap();
+        assert_eq!(fa, compute_fingerprint(&b).unwrap());
+        assert_eq!(fa.size, 10);
+
+        std::fs::write(&b, b"same bytez").unwrap();
+        assert_ne!(fa, compute_fingerprint(&b).unwrap());
+    }
+
+    #[test]
+    fn test_fingerprint_covers_tail_of_large_files() {
+        // Two files with identical 64 KiB headers must still differ by their tail
+        let tmp = tempfile::tempdir().unwrap();
+        let mut data = vec![7u8; 200 * 1024];
+        let a = tmp.path().join("a.cr2");
+        std::fs::write(&a, &data).unwrap();
+        *data.last_mut().unwrap() = 8;
+        let b = tmp.path().join("b.cr2");
+        std::fs::write(&b, &data).unwrap();
+
+        assert_ne!(
+            compute_fingerprint(&a).unwrap().hash,
+            compute_fingerprint(&b).unwrap().hash
+        );
+    }
+
+    #[test]
+    fn test_fingerprint_is_stable() {
+        // Persisted fingerprints must not change between builds
+        let tmp = tempfile::tempdir().unwrap();
+        let a = tmp.path().join("a.jpg");
+        std::fs::write(&a, b"").unwrap();
+        assert_eq!(compute_fingerprint(&a).unwrap().hash, "cbf29ce484222325");
+    }
+}
@@ -0,0 +1,215 @@
+//! Full-resolution RAW renders — the top tier of the preview cache.
+//!
+//! Tiers, smallest first: the grid thumbnail `{id}.jpg`, the embedded RAW preview
+//! `{id}_preview.jpg` (both in `cache/thumbnails`), and a demosaiced render of the
+//! RAW data itself in `cache/fullres/{id}.jpg`. Renders are made on demand only;
+//! the tier is bounded in size and evicts the least recently viewed files.
+
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::import::thumbnails;
+
+/// Size bound of the `cache/fullres` tier per project. A 24 MP render is ~8 MB.
+pub const FULLRES_CACHE_MAX_BYTES: u64 = 2 * 1024 * 1024 * 1024;
+
+const RENDER_JPEG_QUALITY: u8 = 92;
+
+/// `cache/fullres` of a project.
+pub fn fullres_dir(project_dir: &Path) -> PathBuf {
+    project_dir.join("cache").join("fullres")
+}
+
+/// `{id}.jpg` in the fullres tier.
+pub fn fullres_path(fullres_dir: &Path, logical_photo_id: i64) -> PathBuf {
+    fullres_dir.join(format!("{}.jpg", logical_photo_id))
+}
+
+/// Demosaic a RAW file to an 8-bit sRGB image with EXIF orientation applied.
+/// None if rawler cannot decode the file.
+pub fn render_raw(source_path: &Path, orientation: Option<u16>) -> Option<image::DynamicImage> {
+    // rawler can panic on malformed files (see exif::extract_raw_exif)
+    match std::panic::catch_unwind(|| render_raw_inner(source_path)) {
+        Ok(img) => img.map(|img| thumbnails::apply_orientation_to_image(img, orientation)),
+        Err(_) => {
+            tracing::warn!("panic in render_raw for {:?}", source_path);
+            None
+        }
+    }
+}
+
+fn render_raw_inner(source_path: &Path) -> Option<image::DynamicImage> {
+    let raw = match rawler::decode_file(source_path) {
+        Ok(raw) => raw,
+        Err(e) => {
+            tracing::debug!("rawler: cannot decode {:?}: {:?}", source_path, e);
+            return None;
+        }
+    };
+    let developed = match rawler::imgop::develop::RawDevelop::default().develop_intermediate(&raw) {
+        Ok(developed) => developed,
+        Err(e) => {
+            tracing::debug!("rawler: cannot develop {:?}: {:?}", source_path, e);
+            return None;
+        }
+    };
+    let img = developed.to_dynamic_image()?;
+    Some(image::DynamicImage::ImageRgb8(img.to_rgb8()))
+}
+
+/// The cached render of a RAW, rendering it on a miss. Every call counts as a
+/// view for eviction; after a fresh render the tier is trimmed to `max_bytes`.
+pub fn load_or_render_raw(
+    fullres_dir: &Path,
+    logical_photo_id: i64,
+    source_path: &Path,
+    orientation: Option<u16>,
+    max_bytes: u64,
+) -> Option<PathBuf> {
+    let out_path = fullres_path(fullres_dir, logical_photo_id);
+    if out_path.exists() {
+        touch(&out_path);
+        return Some(out_path);
+    }
+
+    let img = render_raw(source_path, orientation)?;
+    std::fs::create_dir_all(fullres_dir).ok()?;
+    // Write under a temporary name so a concurrent request never sees a partial file
+    let tmp_path = out_path.with_extension("jpg.tmp");
+    if let Err(e) = write_jpeg(&img, &tmp_path)
+        .and_then(|_| std::fs::rename(&tmp_path, &out_path).map_err(image::ImageError::IoError))
+    {
+        tracing::warn!("fullres: save failed for {:?}: {}", out_path, e);
+        let _ = std::fs::remove_file(&tmp_path);
+        return None;
+    }
+    tracing::debug!(
+        "fullres render saved: {:?} ({}×{})",
+        out_path,
+        img.width(),
+        img.height()
+    );
+
+    evict_to_size(fullres_dir, max_bytes, &out_path);
+    Some(out_path)
+}
+
+fn write_jpeg(img: &image::DynamicImage, path: &Path) -> image::ImageResult<()> {
+    let file = std::fs::File::create(path)?;
+    let encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(
+        std::io::BufWriter::new(file),
+        RENDER_JPEG_QUALITY,
+    );
+    img.to_rgb8().write_with_encoder(encoder)
+}
+
+/// Mark a cached file as just viewed: the modification time is the LRU clock.
+fn touch(path: &Path) {
+    if let Ok(file) = std::fs::File::options().append(true).open(path) {
+        let _ = file.set_modified(SystemTime::now());
+    }
+}
+
+/// Delete the least recently viewed renders until the tier fits in `max_bytes`.
+/// `keep` (the render just produced) is never evicted. Returns the number removed.
+pub fn evict_to_size(fullres_dir: &Path, max_bytes: u64, keep: &Path) -> usize {
+    let Ok(entries) = std::fs::read_dir(fullres_dir) else {
+        return 0;
+    };
+    let mut files: Vec<(SystemTime, u64, PathBuf)> = entries
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let meta = e.metadata().ok()?;
+            if !meta.is_file() {
+                return None;
+            }
+            Some((meta.modified().ok()?, meta.len(), e.path()))
+        })
+        .collect();
+    let mut total: u64 = files.iter().map(|(_, len, _)| len).sum();
+    if total <= max_bytes {
+        return 0;
+    }
+
+    files.sort_by_key(|(modified, _, _)| *modified);
+    let mut removed = 0;
+    for (_, len, path) in files {
+        if total <= max_bytes {
+            break;
+        }
+        if path == keep {
+            continue;
+        }
+        match std::fs::remove_file(&path) {
+            Ok(()) => {
+                total -= len;
+                removed += 1;
+            }
+            Err(e) => tracing::warn!("fullres: evict {:?} failed: {}", path, e),
+        }
+    }
+    tracing::debug!("fullres: evicted {} renders", removed);
+    removed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn write_file(dir: &Path, name: &str, len: usize, age_secs: u64) -> PathBuf {
+        let path = dir.join(name);
+        std::fs::write(&path, vec![0u8; len]).unwrap();
+        let file = std::fs::File::options().append(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() - Duration::from_secs(age_secs))
+            .unwrap();
+        path
+    }
+
+    #[test]
+    fn test_evict_removes_least_recently_viewed_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let oldest = write_file(dir.path(), "1.jpg", 100, 300);
+        let middle = write_file(dir.path(), "2.jpg", 100, 200);
+        let newest = write_file(dir.path(), "3.jpg", 100, 100);
+
+        assert_eq!(evict_to_size(dir.path(), 300, &newest), 0, "within bound");
+        assert_eq!(evict_to_size(dir.path(), 200, &newest), 1);
+        assert!(!oldest.exists());
+        assert!(middle.exists() && newest.exists());
+    }
+
+    #[test]
+    fn test_evict_never_removes_the_kept_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = write_file(dir.path(), "1.jpg", 100, 300);
+        let kept = write_file(dir.path(), "2.jpg", 500, 400);
+
+        evict_to_size(dir.path(), 200, &kept);
+        assert!(kept.exists(), "the render being returned survives");
+        assert!(!old.exists());
+    }
+
+    #[test]
+    fn test_cached_render_is_served_and_touched() {
+        let dir = tempfile::tempdir().unwrap();
+        let cached = write_file(dir.path(), "7.jpg", 10, 3600);
+        let before = std::fs::metadata(&cached).unwrap().modified().unwrap();
+
+        // The source does not exist: a hit must not need it
+        let path = load_or_render_raw(dir.path(), 7, Path::new("/nonexistent.CR3"), None, 1024);
+        assert_eq!(path, Some(cached.clone()));
+        let after = std::fs::metadata(&cached).unwrap().modified().unwrap();
+        assert!(after > before, "a view refreshes the LRU clock");
+    }
+
+    #[test]
+    fn test_undecodable_raw_renders_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let bogus = write_file(dir.path(), "bogus.NEF", 64, 0);
+        let out = dir.path().join("fullres");
+
+        assert!(load_or_render_raw(&out, 1, &bogus, None, 1024).is_none());
+        assert!(!fullres_path(&out, 1).exists());
+    }
+}
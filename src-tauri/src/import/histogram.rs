@@ -0,0 +1,119 @@
+use std::path::{Path, PathBuf};
+
+use crate::import::quality::{self, HIGHLIGHT_CLIP};
+use crate::photos::model::{PhotoFormat, PhotoHistogram};
+
+/// Pixels at or below this luminance count as clipped shadows.
+const SHADOW_CLIP: u8 = 5;
+
+/// Luminance and per-channel histograms plus clipping percentages.
+pub fn compute_histogram(img: &image::DynamicImage) -> PhotoHistogram {
+    let rgb = img.to_rgb8();
+    let mut luminance = vec![0u32; 256];
+    let mut red = vec![0u32; 256];
+    let mut green = vec![0u32; 256];
+    let mut blue = vec![0u32; 256];
+    let (mut highlights, mut shadows) = (0u64, 0u64);
+    for px in rgb.pixels() {
+        let [r, g, b] = px.0;
+        // Rec. 709 weights, as used by `DynamicImage::to_luma8`
+        let luma = ((2126 * u32::from(r) + 7152 * u32::from(g) + 722 * u32::from(b)) / 10000) as u8;
+        luminance[luma as usize] += 1;
+        red[r as usize] += 1;
+        green[g as usize] += 1;
+        blue[b as usize] += 1;
+        if luma >= HIGHLIGHT_CLIP {
+            highlights += 1;
+        } else if luma <= SHADOW_CLIP {
+            shadows += 1;
+        }
+    }
+    let total = (rgb.width() as u64 * rgb.height() as u64).max(1) as f64;
+    PhotoHistogram {
+        luminance,
+        red,
+        green,
+        blue,
+        highlight_clipping_pct: highlights as f64 * 100.0 / total,
+        shadow_clipping_pct: shadows as f64 * 100.0 / total,
+    }
+}
+
+/// `{id}_histogram.json` in the thumbnail cache dir.
+pub fn histogram_path(cache_dir: &Path, logical_photo_id: i64) -> PathBuf {
+    cache_dir.join(format!("{}_histogram.json", logical_photo_id))
+}
+
+/// Cache a histogram next to the thumbnail. Failures are logged, not fatal.
+pub fn write_histogram(cache_dir: &Path, logical_photo_id: i64, histogram: &PhotoHistogram) {
+    let path = histogram_path(cache_dir, logical_photo_id);
+    let result = serde_json::to_vec(histogram)
+        .map_err(std::io::Error::from)
+        .and_then(|json| std::fs::write(&path, json));
+    if let Err(e) = result {
+        tracing::warn!("histogram: save failed for {:?}: {}", path, e);
+    }
+}
+
+/// The cached histogram, or None if missing or unreadable.
+pub fn read_histogram(cache_dir: &Path, logical_photo_id: i64) -> Option<PhotoHistogram> {
+    let json = std::fs::read(histogram_path(cache_dir, logical_photo_id)).ok()?;
+    serde_json::from_slice(&json).ok()
+}
+
+/// The cached histogram; computed from the source and cached on a miss
+/// (e.g. thumbnails made before histograms existed).
+pub fn load_or_compute_histogram(
+    cache_dir: &Path,
+    logical_photo_id: i64,
+    source_path: &Path,
+    format: &PhotoFormat,
+) -> Option<PhotoHistogram> {
+    if let Some(histogram) = read_histogram(cache_dir, logical_photo_id) {
+        return Some(histogram);
+    }
+    let histogram = compute_histogram(&quality::decode_preview(source_path, format)?);
+    write_histogram(cache_dir, logical_photo_id, &histogram);
+    Some(histogram)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_counts_channels_and_clipping() {
+        // Left half pure white, right half pure red
+        let img = image::RgbImage::from_fn(10, 10, |x, _| {
+            if x < 5 {
+                image::Rgb([255, 255, 255])
+            } else {
+                image::Rgb([255, 0, 0])
+            }
+        });
+        let h = compute_histogram(&image::DynamicImage::ImageRgb8(img));
+
+        assert_eq!(h.luminance.len(), 256);
+        assert_eq!(h.red[255], 100);
+        assert_eq!(h.green[255], 50);
+        assert_eq!(h.green[0], 50);
+        assert_eq!(h.luminance[255], 50);
+        assert_eq!(h.luminance[54], 50, "pure red is dark in Rec. 709 luma");
+        assert_eq!(h.highlight_clipping_pct, 50.0);
+        assert_eq!(h.shadow_clipping_pct, 0.0);
+    }
+
+    #[test]
+    fn test_histogram_cache_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(read_histogram(dir.path(), 7).is_none());
+
+        let black = image::DynamicImage::ImageRgb8(image::RgbImage::new(4, 4));
+        let h = compute_histogram(&black);
+        assert_eq!(h.shadow_clipping_pct, 100.0);
+        write_histogram(dir.path(), 7, &h);
+
+        assert!(dir.path().join("7_histogram.json").exists());
+        assert_eq!(read_histogram(dir.path(), 7), Some(h));
+    }
+}
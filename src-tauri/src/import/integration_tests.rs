@@ -1,7 +1,7 @@
 /// Integration tests for the import pipeline.
 /// These tests use an in-memory SQLite DB and a temp directory to simulate real imports.
 use crate::db::run_migrations;
-use crate::import::pipeline;
+use crate::import::{jobs, pipeline};
 use crate::photos::model::IndexingStatus;
 use crate::photos::repository;
 use rusqlite::Connection;
@@ -42,7 +42,7 @@ fn make_counter() -> Arc<AtomicUsize> {
 }
 
 /// Test harness that wraps pipeline setup boilerplate.
-/// Reduces the 10-arg `run_pipeline()` call + 5-line setup to a single `run()`.
+/// Reduces the 11-arg `run_pipeline()` call + 5-line setup to a single `run()`.
 struct PipelineHarness {
     conn: Connection,
     tmp: TempDir,
@@ -79,6 +79,14 @@ impl PipelineHarness {
         self.tmp.path().join("cache").join("thumbnails")
     }
 
+    /// The project's settings with `burst_gap_secs`, as `start_indexing` resolves them.
+    fn settings(&self, burst_gap_secs: u64) -> crate::projects::model::ProjectSettings {
+        crate::projects::model::ProjectSettings {
+            burst_gap_secs,
+            ..pipeline::pipeline_settings(&self.conn, self.project_id)
+        }
+    }
+
     /// Run the pipeline with default burst_gap=3.
     fn run(&self, folders: Vec<std::path::PathBuf>) -> crate::photos::model::ImportStats {
         self.run_with_gap(folders, 3)
@@ -95,7 +103,29 @@ impl PipelineHarness {
             self.project_id,
             self.tmp.path(),
             folders,
-            burst_gap_secs,
+            &self.settings(burst_gap_secs),
+            false,
+            self.status.clone(),
+            self.cancel.clone(),
+            self.pause.clone(),
+            None,
+            self.counter.clone(),
+        )
+    }
+
+    /// Run the pipeline in incremental mode (what `start_indexing` uses).
+    fn run_incremental(
+        &self,
+        folders: Vec<std::path::PathBuf>,
+        burst_gap_secs: u64,
+    ) -> crate::photos::model::ImportStats {
+        pipeline::run_pipeline(
+            &self.conn,
+            self.project_id,
+            self.tmp.path(),
+            folders,
+            &self.settings(burst_gap_secs),
+            true,
             self.status.clone(),
             self.cancel.clone(),
             self.pause.clone(),
@@ -247,18 +277,10 @@ fn write_jpeg_with_timestamp(path: &std::path::Path, datetime_original: &str) {
     std::fs::write(path, &jpeg).unwrap();
 }
 
-/// Count .jpg thumbnail files in a cache directory.
+/// Count grid thumbnail files ({id}.jpg) in a cache directory.
 fn count_cached_thumbnails(cache_dir: &std::path::Path) -> usize {
-    std::fs::read_dir(cache_dir)
-        .expect("cache_dir must exist")
-        .filter_map(|e| e.ok())
-        .filter(|e| {
-            e.file_name()
-                .to_str()
-                .map(|s| s.ends_with(".jpg"))
-                .unwrap_or(false)
-        })
-        .count()
+    assert!(cache_dir.is_dir(), "cache_dir must exist");
+    get_existing_thumbnail_ids(cache_dir).len()
 }
 
 /// Collect LP IDs from thumbnail filenames ({id}.jpg) in a cache directory.
@@ -438,6 +460,217 @@ fn test_pipeline_cancel() {
     );
 }
 
+/// State and last completed stage of the project's newest import job.
+fn latest_job(h: &PipelineHarness) -> (String, Option<String>) {
+    h.conn
+        .query_row(
+            "SELECT state, completed_stage FROM import_jobs
+              WHERE project_id = ?1 ORDER BY id DESC LIMIT 1",
+            [h.project_id],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )
+        .unwrap()
+}
+
+#[test]
+fn test_pipeline_records_job_through_every_stage() {
+    let h = PipelineHarness::new();
+    let folder = h.create_folder("photos");
+    write_valid_jpeg(&folder.join("img_001.jpg"));
+
+    h.run(vec![folder.clone()]);
+    assert_eq!(
+        latest_job(&h),
+        ("completed".to_string(), Some("thumbnails".to_string()))
+    );
+
+    h.run_incremental(vec![folder], 3);
+    assert_eq!(
+        latest_job(&h),
+        ("completed".to_string(), Some("thumbnails".to_string()))
+    );
+}
+
+#[test]
+fn test_pipeline_cancel_marks_job_cancelled() {
+    let h = PipelineHarness::new();
+    let folder = h.create_folder("photos");
+    write_minimal_jpeg(&folder.join("img_001.jpg"));
+
+    h.cancel.store(true, Ordering::SeqCst);
+    h.run(vec![folder]);
+
+    let (state, stage) = latest_job(&h);
+    assert_eq!(state, "cancelled");
+    assert_eq!(stage, None, "cancelled before the scan finished");
+}
+
+#[test]
+fn test_pipeline_failing_to_clear_stacks_marks_job_failed() {
+    // A full run that cannot clear the old stacks stops before writing; its job
+    // must not be reported completed.
+    let h = PipelineHarness::new();
+    let folder = h.create_folder("photos");
+    write_valid_jpeg(&folder.join("img_001.jpg"));
+    h.conn
+        .execute_batch("DROP TABLE logical_photo_tags")
+        .unwrap();
+
+    let stats = h.run(vec![folder]);
+
+    assert!(stats.failed);
+    assert!(!stats.cancelled);
+    assert_eq!(
+        latest_job(&h),
+        ("failed".to_string(), Some("stack".to_string()))
+    );
+}
+
+#[test]
+fn test_full_rebuild_interrupted_after_clear_keeps_photos_and_resumes_as_rebuild() {
+    // A full rebuild that stopped after clearing the old logical photos used to
+    // leave their photos linked to nothing, and the incremental resume never saw
+    // them again: they dropped out of the project.
+    let h = PipelineHarness::new();
+    let folder = h.create_folder("photos");
+    write_valid_jpeg(&folder.join("img_001.jpg"));
+    write_valid_jpeg(&folder.join("img_002.jpg"));
+    h.run(vec![folder.clone()]);
+    // The stack of each photo, through its logical photo
+    let linked = |h: &PipelineHarness| -> Vec<i64> {
+        let mut stmt = h
+            .conn
+            .prepare(
+                "SELECT lp.stack_id FROM photos p
+                 LEFT JOIN logical_photos lp ON lp.id = p.logical_photo_id
+                 ORDER BY p.id",
+            )
+            .unwrap();
+        stmt.query_map([], |r| r.get::<_, Option<i64>>(0))
+            .unwrap()
+            .map(|r| {
+                r.unwrap()
+                    .expect("every photo belongs to a stacked logical photo")
+            })
+            .collect()
+    };
+    let before = linked(&h);
+    assert_eq!(before.len(), 2);
+
+    // Fail the rebuild after the clear, where it first writes a logical photo
+    h.conn
+        .execute_batch(
+            "CREATE TEMP TRIGGER interrupt_rebuild BEFORE INSERT ON logical_photos
+             BEGIN SELECT RAISE(ABORT, 'interrupted'); END;",
+        )
+        .unwrap();
+    let stats = h.run(vec![folder.clone()]);
+    assert!(stats.failed);
+    assert_eq!(linked(&h), before, "the cleared library is restored");
+    assert_eq!(
+        repository::list_photo_paths_for_project(&h.conn, h.project_id)
+            .unwrap()
+            .len(),
+        2
+    );
+
+    // A rebuild killed mid-run leaves its job running; it resumes as a rebuild
+    h.conn
+        .execute_batch("DROP TRIGGER interrupt_rebuild")
+        .unwrap();
+    jobs::start_job(&h.conn, h.project_id, true).unwrap();
+    let job = jobs::unfinished_job(&h.conn, h.project_id)
+        .unwrap()
+        .unwrap();
+    assert!(job.full_rebuild);
+
+    let stats = h.run(vec![folder]);
+    assert!(!stats.failed);
+    let after = linked(&h);
+    assert_eq!(after.len(), 2);
+    assert!(
+        after.iter().all(|stack_id| !before.contains(stack_id)),
+        "the stacks were rebuilt"
+    );
+}
+
+#[test]
+fn test_full_rebuild_shifts_a_moved_file_like_its_selection_shift() {
+    // A full rebuild reads a moved file's EXIF again; its selection shift used to
+    // live only on the old photos row and was lost.
+    let h = PipelineHarness::new();
+    let a = h.create_folder("a");
+    write_valid_jpeg_with_timestamp(&a.join("img_001.jpg"), "2024:06:01 10:00:00");
+    h.run(vec![a.clone()]);
+    let time_of = |path: &std::path::Path| -> (chrono::DateTime<chrono::FixedOffset>, i64) {
+        let (time, offset): (String, i64) = h
+            .conn
+            .query_row(
+                "SELECT capture_time, clock_offset_secs FROM photos WHERE path = ?1",
+                [path.to_string_lossy()],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .unwrap();
+        (chrono::DateTime::parse_from_rfc3339(&time).unwrap(), offset)
+    };
+    let (original, _) = time_of(&a.join("img_001.jpg"));
+    let lp_id: i64 = h
+        .conn
+        .query_row("SELECT logical_photo_id FROM photos", [], |r| r.get(0))
+        .unwrap();
+    crate::photos::clock::shift_capture_times(&h.conn, h.project_id, &[lp_id], 90).unwrap();
+
+    let b = h.create_folder("b");
+    std::fs::rename(a.join("img_001.jpg"), b.join("img_001.jpg")).unwrap();
+    h.run(vec![b.clone()]);
+
+    assert_eq!(
+        time_of(&b.join("img_001.jpg")),
+        (original + chrono::Duration::seconds(90), 90)
+    );
+}
+
+#[test]
+fn test_thumbnail_pool_holds_while_paused() {
+    // BUG-07: pause used to be checked only in the EXIF loop, so the thumbnail
+    // pool kept running while the UI showed "paused".
+    let h = PipelineHarness::new();
+    let folder = h.create_folder("photos");
+    let path = folder.join("img_001.jpg");
+    write_valid_jpeg(&path);
+    let targets = vec![(1, path, crate::photos::model::PhotoFormat::Jpeg, None)];
+    let pyramid = crate::import::pyramid::ThumbnailPyramid {
+        grid_size: 256,
+        fit_sizes: vec![],
+    };
+
+    h.pause.store(true, Ordering::SeqCst);
+    let cancel = Arc::clone(&h.cancel);
+    let canceller = std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(300));
+        cancel.store(true, Ordering::SeqCst);
+    });
+    let analyses = pipeline::run_thumbnail_pool(
+        &targets,
+        &h.cache_dir(),
+        &pyramid,
+        1,
+        &h.cancel,
+        &h.pause,
+        &h.counter,
+        None,
+    );
+    canceller.join().unwrap();
+
+    assert!(analyses.is_empty());
+    assert_eq!(
+        h.counter.load(Ordering::Relaxed),
+        0,
+        "nothing ran while paused"
+    );
+    assert!(!h.cache_dir().join("1.jpg").exists());
+}
+
 #[test]
 fn test_pipeline_partial_errors() {
     // WHY: Verifies that an invalid file in the batch does not abort the pipeline.
@@ -1666,3 +1899,326 @@ fn test_restack_creates_rounds_for_new_stacks() {
         round_photo_count
     );
 }
+
+#[test]
+fn test_pipeline_seeds_decisions_from_xmp_sidecars() {
+    // Sprint 12: a rejected sidecar (Lightroom naming) and a picked darktable
+    // sidecar are honoured on import; keywords become tags.
+    let h = PipelineHarness::new();
+    let folder = h.create_folder("photos");
+    write_minimal_jpeg(&folder.join("img_001.jpg"));
+    write_minimal_jpeg(&folder.join("img_002.jpg"));
+    write_minimal_jpeg(&folder.join("img_003.jpg"));
+    std::fs::write(
+        folder.join("img_001.xmp"),
+        r#"<x:xmpmeta xmlns:x="adobe:ns:meta/"><rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+<rdf:Description rdf:about="" xmlns:xmp="http://ns.adobe.com/xap/1.0/" xmp:Rating="-1"/>
+</rdf:RDF></x:xmpmeta>"#,
+    )
+    .unwrap();
+    std::fs::write(
+        folder.join("img_002.jpg.xmp"),
+        r#"<x:xmpmeta xmlns:x="adobe:ns:meta/"><rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+<rdf:Description rdf:about="" xmlns:xmpDM="http://ns.adobe.com/xmp/1.0/DynamicMedia/"
+  xmlns:dc="http://purl.org/dc/elements/1.1/" xmpDM:pick="1">
+<dc:subject><rdf:Bag><rdf:li>family</rdf:li></rdf:Bag></dc:subject>
+</rdf:Description></rdf:RDF></x:xmpmeta>"#,
+    )
+    .unwrap();
+
+    let stats = h.run(vec![folder.clone()]);
+    assert_eq!(
+        stats.logical_photos, 3,
+        "sidecars must not be imported as photos"
+    );
+    assert_eq!(stats.sidecar_decisions, 2);
+    assert_eq!(stats.errors, 0);
+
+    let status_of = |name: &str| -> (i64, String) {
+        h.conn
+            .query_row(
+                "SELECT lp.id, lp.current_status FROM photos p
+                 JOIN logical_photos lp ON lp.id = p.logical_photo_id
+                 WHERE p.path = ?1",
+                [folder.join(name).to_string_lossy()],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap()
+    };
+    assert_eq!(status_of("img_001.jpg").1, "eliminate");
+    let (picked_lp, picked_status) = status_of("img_002.jpg");
+    assert_eq!(picked_status, "keep");
+    assert_eq!(status_of("img_003.jpg").1, "undecided");
+    assert_eq!(
+        repository::list_tags_for_logical_photo(&h.conn, picked_lp).unwrap(),
+        vec!["family"]
+    );
+}
+
+#[test]
+fn test_incremental_reindex_preserves_ids_rounds_and_merges() {
+    // WHY: adding a card dump to an in-progress project must not throw away the
+    // logical photo ids that decisions, rounds and manual merges reference.
+    use crate::decisions::engine;
+    use crate::decisions::model::DecisionAction;
+
+    let h = PipelineHarness::new();
+    let day1 = h.create_folder("day1");
+    write_jpeg_with_timestamp(&day1.join("a_1.jpg"), "2024:03:15 10:00:00");
+    write_jpeg_with_timestamp(&day1.join("a_2.jpg"), "2024:03:15 10:00:01");
+    write_jpeg_with_timestamp(&day1.join("b_1.jpg"), "2024:03:15 11:00:00");
+    write_jpeg_with_timestamp(&day1.join("c_1.jpg"), "2024:03:15 12:00:00");
+    h.run_incremental(vec![day1.clone()], 3);
+
+    let stacks = repository::list_stacks_summary(&h.conn, h.project_id).unwrap();
+    assert_eq!(stacks.len(), 3);
+    let burst_stack = stacks[0].stack_id;
+    let old_lp_ids = get_lp_ids_for_project(&h.conn, h.project_id);
+
+    // Curate: eliminate one photo of the burst and commit round 1
+    let burst_lps = get_lp_ids_for_stack(&h.conn, burst_stack);
+    let (round1, _) = engine::find_or_create_round(&h.conn, h.project_id, burst_stack).unwrap();
+    engine::record_decision(&h.conn, burst_lps[0], round1, &DecisionAction::Eliminate).unwrap();
+    engine::commit_round(&h.conn, round1).unwrap();
+
+    // Manually merge the two solo stacks
+    let merged = repository::merge_stacks(
+        &h.conn,
+        h.project_id,
+        &[stacks[1].stack_id, stacks[2].stack_id],
+    )
+    .unwrap();
+
+    // Second card: one shot continues the burst, one is a new scene
+    let day2 = h.create_folder("day2");
+    write_jpeg_with_timestamp(&day2.join("a_3.jpg"), "2024:03:15 10:00:03");
+    write_jpeg_with_timestamp(&day2.join("d_1.jpg"), "2024:03:16 09:00:00");
+    let stats = h.run_incremental(vec![day1, day2], 3);
+
+    assert_eq!(stats.imported, 2);
+    assert_eq!(stats.skipped_existing, 4);
+    assert_eq!(stats.errors, 0, "errors: {:?}", stats.error_log);
+    assert_eq!(stats.logical_photos, 6, "totals cover the whole project");
+
+    let all_lp_ids = get_lp_ids_for_project(&h.conn, h.project_id);
+    for id in &old_lp_ids {
+        assert!(all_lp_ids.contains(id), "existing lp {} must survive", id);
+    }
+
+    // The burst kept its history and the new shot joined the open round 2
+    let rounds = engine::list_rounds(&h.conn, h.project_id, burst_stack).unwrap();
+    assert_eq!(rounds.len(), 2, "committed round 1 + open round 2");
+    let status: String = h
+        .conn
+        .query_row(
+            "SELECT current_status FROM logical_photos WHERE id = ?1",
+            [burst_lps[0]],
+            |r| r.get(0),
+        )
+        .unwrap();
+    assert_eq!(status, "eliminate", "decision must survive re-index");
+    let (open_round, _) = engine::find_or_create_round(&h.conn, h.project_id, burst_stack).unwrap();
+    let open_members: i64 = h
+        .conn
+        .query_row(
+            "SELECT COUNT(*) FROM round_photos WHERE round_id = ?1",
+            [open_round],
+            |r| r.get(0),
+        )
+        .unwrap();
+    assert_eq!(open_members, 2, "1 survivor + the new burst shot");
+
+    // Manual merge is kept; the new scene got its own stack with round 1
+    let stacks_after = repository::list_stacks_summary(&h.conn, h.project_id).unwrap();
+    assert!(stacks_after
+        .iter()
+        .any(|s| s.stack_id == merged.merged_stack_id));
+    assert_eq!(stacks_after.len(), 3, "burst + merged + new scene");
+    let new_stack = stacks_after.last().unwrap().stack_id;
+    assert_eq!(get_lp_ids_for_stack(&h.conn, new_stack).len(), 1);
+    assert_eq!(
+        engine::list_rounds(&h.conn, h.project_id, new_stack)
+            .unwrap()
+            .len(),
+        1
+    );
+
+    // Only the new logical photos were queued for thumbnails
+    assert_eq!(h.status.lock().unwrap().thumbnails_total, 2);
+}
+
+#[test]
+fn test_incremental_reindex_completes_pair_across_imports() {
+    // WHY: RAW files copied after their JPEGs must join the existing logical
+    // photo, not appear as a separate stack.
+    let h = PipelineHarness::new();
+    let folder = h.create_folder("paired");
+    write_minimal_jpeg(&folder.join("IMG_0001.jpg"));
+    h.run_incremental(vec![folder.clone()], 2);
+    let lp_ids = get_lp_ids_for_project(&h.conn, h.project_id);
+
+    std::fs::write(folder.join("IMG_0001.CR2"), b"fake raw").unwrap();
+    let stats = h.run_incremental(vec![folder], 2);
+
+    assert_eq!(stats.imported, 1);
+    assert_eq!(stats.pairs_detected, 1);
+    assert_eq!(get_lp_ids_for_project(&h.conn, h.project_id), lp_ids);
+    let stacks = repository::list_stacks_summary(&h.conn, h.project_id).unwrap();
+    assert_eq!(stacks.len(), 1);
+    assert!(stacks[0].has_raw && stacks[0].has_jpeg);
+}
+
+#[test]
+fn test_incremental_reindex_jpeg_becomes_representative_of_completed_pair() {
+    // WHY: a JPEG arriving after its RAW completes the pair, and like a full
+    // run the JPEG represents it; the thumbnail is regenerated from it.
+    let h = PipelineHarness::new();
+    let folder = h.create_folder("paired");
+    std::fs::write(folder.join("IMG_0002.CR2"), b"fake raw").unwrap();
+    h.run_incremental(vec![folder.clone()], 2);
+    let lp_ids = get_lp_ids_for_project(&h.conn, h.project_id);
+    assert_eq!(lp_ids.len(), 1);
+
+    write_valid_jpeg(&folder.join("IMG_0002.jpg"));
+    let stats = h.run_incremental(vec![folder], 2);
+
+    assert_eq!(stats.pairs_detected, 1);
+    assert_eq!(get_lp_ids_for_project(&h.conn, h.project_id), lp_ids);
+    let representative: String = h
+        .conn
+        .query_row(
+            "SELECT p.path FROM logical_photos lp
+             JOIN photos p ON p.id = lp.representative_photo_id
+             WHERE lp.id = ?1",
+            [lp_ids[0]],
+            |row| row.get(0),
+        )
+        .unwrap();
+    assert!(
+        representative.ends_with("IMG_0002.jpg"),
+        "{}",
+        representative
+    );
+    assert_eq!(h.status.lock().unwrap().thumbnails_total, 1);
+    assert!(get_existing_thumbnail_ids(&h.cache_dir()).contains(&lp_ids[0]));
+}
+
+#[test]
+fn test_incremental_reindex_relinks_moved_files() {
+    // WHY: reorganising a card dump inside a source folder must not re-import the
+    // moved files as new photos (which would orphan their decisions).
+    let h = PipelineHarness::new();
+    let folder = h.create_folder("dump");
+    write_valid_jpeg(&folder.join("img_001.jpg"));
+    std::fs::write(
+        folder.join("img_002.jpg"),
+        b"\xFF\xD8 different bytes \xFF\xD9",
+    )
+    .unwrap();
+    h.run_incremental(vec![folder.clone()], 3);
+    let lp_ids = get_lp_ids_for_project(&h.conn, h.project_id);
+
+    let sorted = folder.join("sorted");
+    std::fs::create_dir_all(&sorted).unwrap();
+    std::fs::rename(folder.join("img_001.jpg"), sorted.join("best.jpg")).unwrap();
+
+    let stats = h.run_incremental(vec![folder], 3);
+    assert_eq!(stats.relinked, 1);
+    assert_eq!(stats.imported, 0, "a moved file is not a new photo");
+    assert_eq!(get_lp_ids_for_project(&h.conn, h.project_id), lp_ids);
+    let relinked: i64 = h
+        .conn
+        .query_row(
+            "SELECT COUNT(*) FROM photos WHERE path = ?1",
+            [sorted.join("best.jpg").to_string_lossy()],
+            |r| r.get(0),
+        )
+        .unwrap();
+    assert_eq!(relinked, 1);
+}
+
+#[test]
+fn test_incremental_reindex_skips_offline_folder_and_keeps_its_photos() {
+    // WHY: re-indexing with an external drive unplugged must neither fail nor drop
+    // the photos (and decisions) that live on it.
+    let h = PipelineHarness::new();
+    let local = h.create_folder("local");
+    let external = h.create_folder("external");
+    write_valid_jpeg(&local.join("img_001.jpg"));
+    write_jpeg_with_timestamp(&external.join("img_101.jpg"), "2024:05:01 12:00:00");
+    h.run_incremental(vec![local.clone(), external.clone()], 3);
+    let lp_ids = get_lp_ids_for_project(&h.conn, h.project_id);
+    assert_eq!(lp_ids.len(), 2);
+
+    let unplugged = h.tmp.path().join("unplugged");
+    std::fs::rename(&external, &unplugged).unwrap();
+    write_jpeg_with_timestamp(&local.join("img_002.jpg"), "2024:06:01 12:00:00");
+
+    let stats = h.run_incremental(vec![local, external], 3);
+    assert_eq!(stats.offline_folders, 1);
+    assert_eq!(stats.errors, 0, "an offline folder is not an error");
+    assert_eq!(stats.imported, 1);
+    let after = get_lp_ids_for_project(&h.conn, h.project_id);
+    assert_eq!(after.len(), 3);
+    assert!(lp_ids.iter().all(|id| after.contains(id)));
+}
+
+#[test]
+fn test_pipeline_logs_import_as_history_barrier() {
+    // WHY: undoing a merge made before an import would move photos into stacks
+    // the import has since rebuilt or extended.
+    let h = PipelineHarness::new();
+    let folder = h.create_folder("photos");
+    write_jpeg_with_timestamp(&folder.join("a.jpg"), "2024:05:01 12:00:00");
+    write_jpeg_with_timestamp(&folder.join("b.jpg"), "2024:05:01 14:00:00");
+    h.run_incremental(vec![folder.clone()], 3);
+
+    let stacks = repository::list_stacks_summary(&h.conn, h.project_id).unwrap();
+    let ids: Vec<i64> = stacks.iter().map(|s| s.stack_id).collect();
+    repository::merge_stacks(&h.conn, h.project_id, &ids).unwrap();
+    write_jpeg_with_timestamp(&folder.join("c.jpg"), "2024:05:02 09:00:00");
+    h.run_incremental(vec![folder], 3);
+
+    let txs = repository::list_stack_transactions(&h.conn, h.project_id).unwrap();
+    assert_eq!(
+        txs.iter().filter(|t| t.action == "import").count(),
+        2,
+        "each run that imports photos is logged"
+    );
+    let err = crate::photos::history::undo_stack_transaction(&h.conn, h.project_id)
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("import"), "got: {}", err);
+}
+
+#[test]
+fn test_pairing_disabled_keeps_raw_and_jpeg_separate() {
+    // WHY: the project's pairing rule must reach the pipeline; with pairing off a
+    // RAW and its JPEG are culled as two photos.
+    use crate::projects::model::ProjectSettingsOverrides;
+
+    let h = PipelineHarness::new();
+    crate::projects::repository::set_settings_overrides(
+        &h.conn,
+        h.project_id,
+        &ProjectSettingsOverrides {
+            pair_raw_jpeg: Some(false),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    let folder = h.create_folder("unpaired");
+    write_minimal_jpeg(&folder.join("IMG_0001.jpg"));
+    std::fs::write(folder.join("IMG_0001.CR2"), b"fake raw").unwrap();
+
+    let stats = h.run(vec![folder.clone()]);
+    assert_eq!(stats.pairs_detected, 0);
+    assert_eq!(get_lp_ids_for_project(&h.conn, h.project_id).len(), 2);
+
+    // A RAW copied in later does not complete a single either
+    std::fs::write(folder.join("IMG_0002.CR2"), b"fake raw 2").unwrap();
+    write_minimal_jpeg(&folder.join("IMG_0002.jpg"));
+    let stats = h.run_incremental(vec![folder], 3);
+    assert_eq!(stats.pairs_detected, 0);
+    assert_eq!(get_lp_ids_for_project(&h.conn, h.project_id).len(), 4);
+}
@@ -0,0 +1,258 @@
+//! Persisted import jobs.
+//!
+//! Each import run is a row in `import_jobs` recording the last stage it
+//! completed and whether it is running, paused, cancelled, failed or completed. A job
+//! left running or paused when the app exits is resumed the next time the
+//! project is opened: from the thumbnail stage if its photos were persisted,
+//! otherwise by running the (idempotent) pipeline again in the same mode, since
+//! the earlier stages only hold their results in memory.
+
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+/// Pipeline stages, in the order they run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportStage {
+    /// Walk the source folders.
+    Scan,
+    /// Read EXIF and fingerprints of new files.
+    Exif,
+    /// Combine RAW+JPEG pairs into logical groups.
+    Pair,
+    /// Assign groups to stacks.
+    Stack,
+    /// Write photos, logical photos, stacks and rounds.
+    Persist,
+    /// Generate thumbnails and image analyses.
+    Thumbnails,
+}
+
+impl ImportStage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ImportStage::Scan => "scan",
+            ImportStage::Exif => "exif",
+            ImportStage::Pair => "pair",
+            ImportStage::Stack => "stack",
+            ImportStage::Persist => "persist",
+            ImportStage::Thumbnails => "thumbnails",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "scan" => Some(ImportStage::Scan),
+            "exif" => Some(ImportStage::Exif),
+            "pair" => Some(ImportStage::Pair),
+            "stack" => Some(ImportStage::Stack),
+            "persist" => Some(ImportStage::Persist),
+            "thumbnails" => Some(ImportStage::Thumbnails),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobState {
+    Running,
+    Paused,
+    Cancelled,
+    /// Stopped by an error a stage could not recover from.
+    Failed,
+    Completed,
+}
+
+impl JobState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobState::Running => "running",
+            JobState::Paused => "paused",
+            JobState::Cancelled => "cancelled",
+            JobState::Failed => "failed",
+            JobState::Completed => "completed",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "running" => Some(JobState::Running),
+            "paused" => Some(JobState::Paused),
+            "cancelled" => Some(JobState::Cancelled),
+            "failed" => Some(JobState::Failed),
+            "completed" => Some(JobState::Completed),
+            _ => None,
+        }
+    }
+
+    /// Running and paused jobs are unfinished; after a restart they are resumed.
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            JobState::Cancelled | JobState::Failed | JobState::Completed
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImportJob {
+    pub id: i64,
+    pub project_id: i64,
+    pub state: JobState,
+    /// Last stage that ran to completion; None before the scan finished.
+    pub completed_stage: Option<ImportStage>,
+    /// The run rebuilds stacks and logical photos rather than adding new files.
+    pub full_rebuild: bool,
+    pub started_at: String,
+    pub updated_at: String,
+}
+
+impl ImportJob {
+    /// True once the photos are in the DB and only thumbnails remain.
+    pub fn is_persisted(&self) -> bool {
+        self.completed_stage >= Some(ImportStage::Persist)
+    }
+}
+
+/// Start a job for a new pipeline run. Unfinished earlier jobs of the project
+/// are superseded and marked cancelled.
+pub fn start_job(conn: &Connection, project_id: i64, full_rebuild: bool) -> rusqlite::Result<i64> {
+    let now = chrono::Utc::now().to_rfc3339();
+    conn.execute(
+        "UPDATE import_jobs SET state = 'cancelled', updated_at = ?2
+          WHERE project_id = ?1 AND state IN ('running', 'paused')",
+        params![project_id, now],
+    )?;
+    conn.execute(
+        "INSERT INTO import_jobs (project_id, state, full_rebuild, started_at, updated_at)
+         VALUES (?1, 'running', ?2, ?3, ?3)",
+        params![project_id, full_rebuild, now],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Record that `stage` ran to completion.
+pub fn complete_stage(conn: &Connection, job_id: i64, stage: ImportStage) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE import_jobs SET completed_stage = ?2, updated_at = ?3 WHERE id = ?1",
+        params![job_id, stage.as_str(), chrono::Utc::now().to_rfc3339()],
+    )?;
+    Ok(())
+}
+
+pub fn set_job_state(conn: &Connection, job_id: i64, state: JobState) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE import_jobs SET state = ?2, updated_at = ?3 WHERE id = ?1",
+        params![job_id, state.as_str(), chrono::Utc::now().to_rfc3339()],
+    )?;
+    Ok(())
+}
+
+/// The project's running or paused job, if any.
+pub fn unfinished_job(conn: &Connection, project_id: i64) -> rusqlite::Result<Option<ImportJob>> {
+    use rusqlite::OptionalExtension;
+    conn.query_row(
+        "SELECT id, project_id, state, completed_stage, full_rebuild, started_at, updated_at
+           FROM import_jobs
+          WHERE project_id = ?1 AND state IN ('running', 'paused')
+          ORDER BY id DESC LIMIT 1",
+        params![project_id],
+        |row| {
+            let state: String = row.get(2)?;
+            let stage: Option<String> = row.get(3)?;
+            Ok(ImportJob {
+                id: row.get(0)?,
+                project_id: row.get(1)?,
+                state: JobState::parse(&state).unwrap_or(JobState::Running),
+                completed_stage: stage.as_deref().and_then(ImportStage::parse),
+                full_rebuild: row.get(4)?,
+                started_at: row.get(5)?,
+                updated_at: row.get(6)?,
+            })
+        },
+    )
+    .optional()
+}
+
+/// Set the state of the project's unfinished job. No-op without one.
+pub fn set_unfinished_job_state(
+    conn: &Connection,
+    project_id: i64,
+    state: JobState,
+) -> rusqlite::Result<()> {
+    if let Some(job) = unfinished_job(conn, project_id)? {
+        set_job_state(conn, job.id, state)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::run_migrations;
+    use crate::projects::repository::insert_project;
+
+    fn setup() -> (Connection, i64) {
+        let conn = Connection::open_in_memory().unwrap();
+        run_migrations(&conn).unwrap();
+        let project = insert_project(&conn, "Iceland", "iceland").unwrap();
+        (conn, project.id)
+    }
+
+    #[test]
+    fn test_job_records_stages_until_finished() {
+        let (conn, project_id) = setup();
+        let job_id = start_job(&conn, project_id, false).unwrap();
+        complete_stage(&conn, job_id, ImportStage::Scan).unwrap();
+        complete_stage(&conn, job_id, ImportStage::Persist).unwrap();
+
+        let job = unfinished_job(&conn, project_id).unwrap().unwrap();
+        assert_eq!(job.state, JobState::Running);
+        assert_eq!(job.completed_stage, Some(ImportStage::Persist));
+        assert!(job.is_persisted());
+
+        set_job_state(&conn, job_id, JobState::Completed).unwrap();
+        assert!(unfinished_job(&conn, project_id).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_paused_job_stays_unfinished() {
+        let (conn, project_id) = setup();
+        start_job(&conn, project_id, false).unwrap();
+        set_unfinished_job_state(&conn, project_id, JobState::Paused).unwrap();
+
+        let job = unfinished_job(&conn, project_id).unwrap().unwrap();
+        assert_eq!(job.state, JobState::Paused);
+        assert!(!job.is_persisted(), "no stage completed yet");
+    }
+
+    #[test]
+    fn test_new_job_supersedes_unfinished_one() {
+        let (conn, project_id) = setup();
+        let first = start_job(&conn, project_id, false).unwrap();
+        let second = start_job(&conn, project_id, false).unwrap();
+
+        assert_eq!(
+            unfinished_job(&conn, project_id).unwrap().unwrap().id,
+            second
+        );
+        let state: String = conn
+            .query_row(
+                "SELECT state FROM import_jobs WHERE id = ?1",
+                [first],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(state, "cancelled");
+    }
+
+    #[test]
+    fn test_job_records_full_rebuild() {
+        let (conn, project_id) = setup();
+        start_job(&conn, project_id, true).unwrap();
+
+        let job = unfinished_job(&conn, project_id).unwrap().unwrap();
+        assert!(job.full_rebuild);
+    }
+}
@@ -1,13 +1,23 @@
+pub mod artifacts;
 pub mod exif;
+pub mod fingerprint;
+pub mod fullres;
+pub mod histogram;
 #[cfg(test)]
 pub mod integration_tests;
+pub mod jobs;
 #[cfg(test)]
 pub mod metadata_tests;
 #[cfg(test)]
 pub mod orientation_tests;
 pub mod pairs;
+pub mod phash;
 pub mod pipeline;
+pub mod prefetch;
+pub mod pyramid;
+pub mod quality;
 pub mod scanner;
+pub mod sharpness;
 pub mod stacks;
 #[cfg(test)]
 pub mod stacks_tests;
@@ -19,3 +29,5 @@ pub mod test_fixtures_validation;
 pub mod thumbnail_composition_tests;
 pub mod thumbnails;
 pub mod util;
+pub mod verify;
+pub mod volumes;
@@ -24,6 +24,11 @@ impl LogicalGroup {
     pub fn capture_time(&self) -> Option<chrono::DateTime<chrono::Utc>> {
         self.representative().capture_time
     }
+
+    /// The camera sequence number, ordering burst frames with equal capture times.
+    pub fn sequence_number(&self) -> Option<u32> {
+        self.representative().sequence_number
+    }
 }
 
 /// Group scanned files into logical groups.
@@ -49,6 +54,11 @@ pub fn detect_pairs(files: Vec<ScannedFile>) -> Vec<LogicalGroup> {
     result
 }
 
+/// Every file as its own logical group, for projects with RAW+JPEG pairing off.
+pub fn detect_singles(files: Vec<ScannedFile>) -> Vec<LogicalGroup> {
+    files.into_iter().map(single_group).collect()
+}
+
 /// Dispatch a bucket of same-(dir, base_name) files into LogicalGroup(s).
 fn resolve_group(
     dir: &std::path::Path,
@@ -139,6 +149,10 @@ mod tests {
                 .unwrap()
                 .to_lowercase(),
             dir: PathBuf::from(dir),
+            fingerprint: None,
+            sequence_number: None,
+            camera_serial: None,
+            clock_offset_secs: 0,
         }
     }
 
@@ -226,4 +240,15 @@ mod tests {
         assert_eq!(groups.len(), 2);
         assert!(groups.iter().all(|g| !g.is_pair));
     }
+
+    #[test]
+    fn test_detect_singles_never_pairs() {
+        let files = vec![
+            make_file("/photos", "IMG_001.jpg", PhotoFormat::Jpeg),
+            make_file("/photos", "IMG_001.CR2", PhotoFormat::Raw),
+        ];
+        let groups = detect_singles(files);
+        assert_eq!(groups.len(), 2);
+        assert!(groups.iter().all(|g| !g.is_pair));
+    }
 }
@@ -0,0 +1,115 @@
+use rayon::prelude::*;
+use rusqlite::Connection;
+use std::path::Path;
+
+use crate::photos::repository;
+
+/// 64-bit difference hash (dHash): shrink to 9×8 grayscale and set one bit per
+/// pixel that is darker than its right-hand neighbour. Robust to scaling,
+/// exposure shifts and recompression; small framing changes flip few bits.
+pub fn dhash(img: &image::DynamicImage) -> u64 {
+    let small = img
+        .resize_exact(9, 8, image::imageops::FilterType::Triangle)
+        .to_luma8();
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            let left = small.get_pixel(x, y)[0];
+            let right = small.get_pixel(x + 1, y)[0];
+            hash = (hash << 1) | u64::from(left < right);
+        }
+    }
+    hash
+}
+
+/// dHash of an image file, or None if it cannot be decoded.
+pub fn dhash_file(path: &Path) -> Option<u64> {
+    image::open(path).ok().map(|img| dhash(&img))
+}
+
+/// Number of differing bits between two hashes (0 = identical, 64 = inverse).
+pub fn distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+/// Hash the cached thumbnail of every logical photo that has none yet.
+/// Logical photos whose thumbnail is not cached are skipped. Returns how many
+/// hashes were stored.
+pub fn hash_cached_thumbnails(
+    conn: &Connection,
+    project_id: i64,
+    cache_dir: &Path,
+) -> rusqlite::Result<usize> {
+    let missing = repository::list_logical_photos_without_phash(conn, project_id)?;
+    let hashes: Vec<(i64, u64)> = missing
+        .par_iter()
+        .filter_map(|&lp_id| {
+            dhash_file(&cache_dir.join(format!("{}.jpg", lp_id))).map(|h| (lp_id, h))
+        })
+        .collect();
+    for &(lp_id, hash) in &hashes {
+        repository::set_logical_photo_phash(conn, lp_id, hash)?;
+    }
+    Ok(hashes.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::test_fixtures::TestLibraryBuilder;
+
+    /// Horizontal gradient, optionally mirrored, with a brightness offset.
+    fn gradient(mirrored: bool, offset: u8) -> image::DynamicImage {
+        let img = image::GrayImage::from_fn(256, 256, |x, _| {
+            let v = (if mirrored { 255 - x } else { x }) as u8;
+            image::Luma([v.saturating_add(offset)])
+        });
+        image::DynamicImage::ImageLuma8(img)
+    }
+
+    #[test]
+    fn test_dhash_tolerates_exposure_but_separates_different_scenes() {
+        let base = dhash(&gradient(false, 0));
+        let brighter = dhash(&gradient(false, 20));
+        let mirrored = dhash(&gradient(true, 0));
+        assert!(
+            distance(base, brighter) <= 4,
+            "same scene, brighter: {}",
+            distance(base, brighter)
+        );
+        assert!(
+            distance(base, mirrored) >= 32,
+            "different scene: {}",
+            distance(base, mirrored)
+        );
+    }
+
+    #[test]
+    fn test_hash_cached_thumbnails_fills_missing_hashes_only() {
+        let project = TestLibraryBuilder::new()
+            .with_stack_layout(&[2])
+            .build_db_only();
+        let conn = &project.conn;
+        let cache_dir = project.dir.path().join("thumbs");
+        std::fs::create_dir_all(&cache_dir).unwrap();
+        // Only the first logical photo has a cached thumbnail
+        gradient(false, 0)
+            .to_rgb8()
+            .save(cache_dir.join(format!("{}.jpg", project.lp_ids[0])))
+            .unwrap();
+
+        assert_eq!(
+            hash_cached_thumbnails(conn, project.project_id, &cache_dir).unwrap(),
+            1
+        );
+        let hashes = repository::load_logical_photo_phashes(conn, project.project_id).unwrap();
+        assert!(hashes.contains_key(&project.lp_ids[0]));
+        assert!(!hashes.contains_key(&project.lp_ids[1]));
+
+        // Already hashed: nothing left to do
+        assert_eq!(
+            hash_cached_thumbnails(conn, project.project_id, &cache_dir).unwrap(),
+            0
+        );
+    }
+}
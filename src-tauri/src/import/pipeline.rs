@@ -1,8 +1,16 @@
+use crate::import::jobs::{self, ImportStage, JobState};
 use crate::import::pairs::LogicalGroup;
-use crate::import::{exif, pairs, scanner, stacks, thumbnails};
+use crate::import::pyramid::{self, ThumbnailPyramid};
+use crate::import::quality::{self, ImageAnalysis};
+use crate::import::{
+    artifacts, exif, fingerprint, histogram, pairs, phash, scanner, sharpness, stacks, thumbnails,
+};
 use crate::photos::model::{ImportStats, IndexingStatus, PhotoFormat, ScannedFile};
-use crate::photos::repository;
 use crate::photos::repository::init_round_for_stack;
+use crate::photos::{clock, repository};
+use crate::projects;
+use crate::projects::model::ProjectSettings;
+use crate::xmp;
 use rayon::prelude::*;
 use rusqlite::Connection;
 use serde::Serialize;
@@ -20,16 +28,24 @@ pub struct ThumbnailReadyPayload {
 /// Run a rayon thread pool to generate thumbnails in parallel.
 ///
 /// Shared by the full import pipeline (step 8) and `resume_thumbnails`.
-/// Each successfully generated thumbnail increments `done_counter` and,
-/// if `app_handle` is `Some`, emits a `thumbnail-ready` event.
+/// While `pause` is set the workers hold before their next photo; `cancel`
+/// skips the rest. Each successfully generated thumbnail increments
+/// `done_counter` and, if `app_handle` is `Some`, emits a `thumbnail-ready` event.
+/// The source is decoded once and that image feeds the grid thumbnail, the fit
+/// variants of `pyramid`, the cached exposure histogram and the image analysis.
+/// Returns the analysis `(lp_id, analysis)` of every thumbnailed photo whose
+/// source could be decoded; the caller stores them.
+#[allow(clippy::too_many_arguments)]
 pub fn run_thumbnail_pool(
     targets: &[(i64, PathBuf, PhotoFormat, Option<u16>)],
     cache_dir: &Path,
+    pyramid: &ThumbnailPyramid,
     num_threads: usize,
     cancel: &AtomicBool,
+    pause: &AtomicBool,
     done_counter: &AtomicUsize,
     app_handle: Option<&tauri::AppHandle>,
-) {
+) -> Vec<(i64, ImageAnalysis)> {
     let pool = rayon::ThreadPoolBuilder::new()
         .num_threads(num_threads)
         .build()
@@ -42,11 +58,11 @@ pub fn run_thumbnail_pool(
     pool.install(|| {
         targets
             .par_iter()
-            .for_each(|(lp_id, path, format, orientation)| {
-                if !cancel.load(Ordering::SeqCst)
-                    && thumbnails::generate_thumbnail(path, format, *lp_id, cache_dir, *orientation)
-                        .is_some()
-                {
+            .filter_map(|(lp_id, path, format, orientation)| {
+                if wait_if_paused(pause, cancel) {
+                    return None;
+                }
+                let thumbnail_ready = || {
                     done_counter.fetch_add(1, Ordering::Relaxed);
                     if let Some(handle) = app_handle {
                         use tauri::Emitter;
@@ -57,9 +73,72 @@ pub fn run_thumbnail_pool(
                             },
                         );
                     }
-                }
-            });
-    });
+                };
+                let min_edge = pyramid.max_fit_size().max(sharpness::WORKING_SIZE);
+                let Some(img) = thumbnails::decode_source_preview(path, format, min_edge) else {
+                    // The thumbnail generator has fallbacks of its own (embedded
+                    // EXIF thumbnail); such a photo just goes without analysis
+                    if thumbnails::generate_thumbnail_sized(
+                        path,
+                        format,
+                        *lp_id,
+                        cache_dir,
+                        *orientation,
+                        pyramid.grid_size,
+                    )
+                    .is_some()
+                    {
+                        thumbnail_ready();
+                    }
+                    return None;
+                };
+                thumbnails::generate_thumbnail_from_source_preview(
+                    &img,
+                    format,
+                    *lp_id,
+                    cache_dir,
+                    *orientation,
+                    pyramid.grid_size,
+                )?;
+                thumbnail_ready();
+                pyramid::write_fit_variants(&img, cache_dir, *lp_id, *orientation, pyramid);
+                let img = sharpness::to_working_size(img);
+                histogram::write_histogram(cache_dir, *lp_id, &histogram::compute_histogram(&img));
+                Some((*lp_id, quality::analyze(&img)))
+            })
+            .collect()
+    })
+}
+
+/// Image analyses for logical photos that already have cached thumbnails but no
+/// sharpness score (thumbnailed before scoring existed, or reused from the cache),
+/// computed from the largest cached fit variant instead of decoding the source
+/// again. Photos without a fit variant are left for their next thumbnail pass.
+pub fn backfill_image_analyses(
+    conn: &Connection,
+    project_id: i64,
+    cache_dir: &Path,
+    pyramid: &ThumbnailPyramid,
+    cancel: &AtomicBool,
+) -> Vec<(i64, ImageAnalysis)> {
+    let unscored = match repository::list_unscored_lp_ids(conn, project_id) {
+        Ok(ids) => ids,
+        Err(e) => {
+            tracing::warn!("pipeline: list unscored photos: {}", e);
+            return vec![];
+        }
+    };
+    let edge = pyramid.max_fit_size();
+    unscored
+        .par_iter()
+        .filter_map(|lp_id| {
+            if edge == 0 || cancel.load(Ordering::SeqCst) {
+                return None;
+            }
+            let img = image::open(pyramid::fit_path(cache_dir, *lp_id, edge)).ok()?;
+            Some((*lp_id, quality::analyze(&img)))
+        })
+        .collect()
 }
 
 /// Static configuration for a pipeline run (source folders, cache, burst settings).
@@ -68,6 +147,12 @@ pub struct PipelineConfig {
     pub project_dir: PathBuf,
     pub folder_paths: Vec<PathBuf>,
     pub burst_gap_secs: u64,
+    /// Project pairing rule: combine a JPEG and a RAW with the same base name.
+    pub pair_raw_jpeg: bool,
+    /// Thumbnail sizes the project generates.
+    pub pyramid: ThumbnailPyramid,
+    /// Keep existing stacks, logical photos and rounds; only add the new files.
+    pub incremental: bool,
 }
 
 /// Runtime controls shared with the background thread (cancel/pause signals, status, counters).
@@ -77,12 +162,50 @@ pub struct PipelineControls {
     pub pause: Arc<AtomicBool>,
     pub app_handle: Option<tauri::AppHandle>,
     pub thumbnails_done_counter: Arc<AtomicUsize>,
+    /// The `import_jobs` row stages are recorded on; None if it could not be created.
+    pub job_id: Option<i64>,
+}
+
+/// Block while `pause` is set. Returns true if `cancel` is set, including when
+/// it was set during the pause.
+pub fn wait_if_paused(pause: &AtomicBool, cancel: &AtomicBool) -> bool {
+    while pause.load(Ordering::SeqCst) && !cancel.load(Ordering::SeqCst) {
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
+    cancel.load(Ordering::SeqCst)
+}
+
+/// Stage boundary: wait out a pause, then report a cancel in the status.
+/// Returns true if the run must stop.
+fn checkpoint(controls: &PipelineControls, stats: &mut ImportStats) -> bool {
+    if wait_if_paused(&controls.pause, &controls.cancel) {
+        update_status(&controls.status, |s| s.cancelled = true);
+        stats.cancelled = true;
+        return true;
+    }
+    false
+}
+
+fn record_stage(conn: &Connection, controls: &PipelineControls, stage: ImportStage) {
+    if let Some(job_id) = controls.job_id {
+        if let Err(e) = jobs::complete_stage(conn, job_id, stage) {
+            tracing::warn!("pipeline: record stage {}: {}", stage.as_str(), e);
+        }
+    }
 }
 
 /// Run the full import pipeline. Designed to be called from a background thread.
 ///
 /// The pipeline is idempotent: running it twice on the same folder produces no duplicates.
-/// Existing photos are skipped; stacks and logical_photos are rebuilt from scratch each run.
+/// Existing photos are skipped. A full run (`incremental = false`) rebuilds stacks and
+/// logical_photos from scratch; an incremental run keeps them (and all round history)
+/// and only slots the new files into existing or new stacks.
+///
+/// Every run is recorded as an import job (see `jobs`); pausing holds it at the
+/// next file, folder or thumbnail in any stage.
+///
+/// `settings`: the project's effective settings (burst gap, pairing rule and
+/// thumbnail sizes), resolved by the caller.
 ///
 /// `app_handle`: pass `Some(handle)` from a Tauri command to emit `thumbnail-ready` events;
 /// pass `None` in tests where no Tauri runtime is available.
@@ -92,7 +215,8 @@ pub fn run_pipeline(
     project_id: i64,
     project_dir: &Path,
     folder_paths: Vec<PathBuf>,
-    burst_gap_secs: u64,
+    settings: &ProjectSettings,
+    incremental: bool,
     status: Arc<Mutex<IndexingStatus>>,
     cancel: Arc<AtomicBool>,
     pause: Arc<AtomicBool>,
@@ -103,7 +227,24 @@ pub fn run_pipeline(
         project_id,
         project_dir: project_dir.to_path_buf(),
         folder_paths,
-        burst_gap_secs,
+        burst_gap_secs: settings.burst_gap_secs,
+        pair_raw_jpeg: settings.pair_raw_jpeg,
+        pyramid: ThumbnailPyramid::from_settings(settings),
+        incremental,
+    };
+    let job_id = match jobs::start_job(conn, project_id, !incremental) {
+        // A run resumed paused after a restart stays paused in the DB too
+        Ok(id) if pause.load(Ordering::SeqCst) => {
+            if let Err(e) = jobs::set_job_state(conn, id, JobState::Paused) {
+                tracing::warn!("pipeline: cannot record paused import job: {}", e);
+            }
+            Some(id)
+        }
+        Ok(id) => Some(id),
+        Err(e) => {
+            tracing::warn!("pipeline: cannot record import job: {}", e);
+            None
+        }
     };
     let controls = PipelineControls {
         status,
@@ -111,8 +252,35 @@ pub fn run_pipeline(
         pause,
         app_handle,
         thumbnails_done_counter,
+        job_id,
     };
-    run_pipeline_inner(conn, &config, &controls)
+    let stats = run_pipeline_inner(conn, &config, &controls);
+    if let Some(job_id) = job_id {
+        let state = if stats.cancelled {
+            JobState::Cancelled
+        } else if stats.failed {
+            JobState::Failed
+        } else {
+            JobState::Completed
+        };
+        if let Err(e) = jobs::set_job_state(conn, job_id, state) {
+            tracing::warn!("pipeline: finish import job: {}", e);
+        }
+    }
+    stats
+}
+
+/// The project's settings for pairing and thumbnails, for thumbnail passes run
+/// outside an import. Neither has a global default to inherit, so the project's
+/// values apply on top of the defaults.
+pub(crate) fn pipeline_settings(conn: &Connection, project_id: i64) -> ProjectSettings {
+    match projects::repository::get_settings_overrides(conn, project_id) {
+        Ok(overrides) => overrides.apply(&ProjectSettings::default()),
+        Err(e) => {
+            tracing::warn!("pipeline: cannot load project settings: {}", e);
+            ProjectSettings::default()
+        }
+    }
 }
 
 /// Internal pipeline implementation that uses structured config/controls.
@@ -122,7 +290,6 @@ fn run_pipeline_inner(
     controls: &PipelineControls,
 ) -> ImportStats {
     let mut stats = ImportStats::default();
-    let cache_dir = config.project_dir.join("cache").join("thumbnails");
 
     // ── STEP 1: Scan all folders ──────────────────────────────────────────────
     tracing::info!(
@@ -134,13 +301,18 @@ fn run_pipeline_inner(
     let mut scanned_paths: Vec<scanner::ScannedPath> = Vec::new();
 
     for folder in &config.folder_paths {
-        if controls.cancel.load(Ordering::SeqCst) {
+        if checkpoint(controls, &mut stats) {
             tracing::info!("pipeline: cancelled during scan");
-            update_status(&controls.status, |s| s.cancelled = true);
-            stats.cancelled = true;
             return stats;
         }
 
+        // Offline (unplugged) folders keep their photos; they are just not rescanned
+        if !folder.is_dir() {
+            tracing::info!("pipeline: source folder offline, skipped: {:?}", folder);
+            stats.offline_folders += 1;
+            continue;
+        }
+
         let (paths, errors) = scanner::scan_directory(folder);
         stats.total_files_scanned += paths.len();
         for e in errors {
@@ -153,6 +325,7 @@ fn run_pipeline_inner(
         "pipeline: scan complete — {} files found",
         stats.total_files_scanned
     );
+    record_stage(conn, controls, ImportStage::Scan);
 
     // ── STEP 2: Check existing photos (idempotency) ───────────────────────────
     // Load already-imported paths for this project.
@@ -172,29 +345,27 @@ fn run_pipeline_inner(
     });
 
     // ── STEP 3: Extract EXIF + build ScannedFile list ─────────────────────────
-    if controls.cancel.load(Ordering::SeqCst) {
-        update_status(&controls.status, |s| s.cancelled = true);
-        stats.cancelled = true;
+    if checkpoint(controls, &mut stats) {
         return stats;
     }
 
+    // Cameras whose clocks the user corrected: their new photos are shifted too
+    let clock_offsets =
+        clock::load_camera_clock_offsets(conn, config.project_id).unwrap_or_else(|e| {
+            tracing::warn!("pipeline: cannot load camera clock offsets: {}", e);
+            Default::default()
+        });
+    // Photos shifted as a selection: the same files are shifted again
+    let capture_shifts =
+        clock::load_capture_time_shifts(conn, config.project_id).unwrap_or_else(|e| {
+            tracing::warn!("pipeline: cannot load capture time shifts: {}", e);
+            Default::default()
+        });
+
     let mut new_files: Vec<ScannedFile> = Vec::new();
 
     for sp in scanned_paths {
-        if controls.cancel.load(Ordering::SeqCst) {
-            update_status(&controls.status, |s| s.cancelled = true);
-            stats.cancelled = true;
-            return stats;
-        }
-        while controls.pause.load(Ordering::SeqCst) {
-            std::thread::sleep(std::time::Duration::from_millis(100));
-            if controls.cancel.load(Ordering::SeqCst) {
-                break;
-            }
-        }
-        if controls.cancel.load(Ordering::SeqCst) {
-            update_status(&controls.status, |s| s.cancelled = true);
-            stats.cancelled = true;
+        if checkpoint(controls, &mut stats) {
             return stats;
         }
 
@@ -206,6 +377,13 @@ fn run_pipeline_inner(
         }
 
         let exif_data = exif::extract_exif(&sp.path, &sp.format);
+        let fingerprint = match fingerprint::compute_fingerprint(&sp.path) {
+            Ok(f) => Some(f),
+            Err(e) => {
+                tracing::warn!("pipeline: fingerprint {:?}: {}", sp.path, e);
+                None
+            }
+        };
 
         let base_name = sp
             .path
@@ -219,10 +397,26 @@ fn run_pipeline_inner(
             .map(|p| p.to_path_buf())
             .unwrap_or_default();
 
+        // Untimed photos have nothing to shift, so they record no offset either
+        let clock_offset_secs = match exif_data.capture_time {
+            Some(_) => {
+                clock::offset_for(
+                    &clock_offsets,
+                    exif_data.camera_model.as_deref(),
+                    exif_data.camera_serial.as_deref(),
+                ) + fingerprint
+                    .as_ref()
+                    .map_or(0, |fp| clock::shift_for(&capture_shifts, fp))
+            }
+            None => 0,
+        };
+
         new_files.push(ScannedFile {
             path: sp.path,
             format: sp.format,
-            capture_time: exif_data.capture_time,
+            capture_time: exif_data
+                .capture_time
+                .map(|t| t + chrono::Duration::seconds(clock_offset_secs)),
             camera_model: exif_data.camera_model,
             lens: exif_data.lens,
             orientation: exif_data.orientation,
@@ -233,6 +427,10 @@ fn run_pipeline_inner(
             exposure_comp: exif_data.exposure_comp,
             base_name,
             dir,
+            fingerprint,
+            sequence_number: exif_data.sequence_number,
+            camera_serial: exif_data.camera_serial,
+            clock_offset_secs,
         });
 
         update_status(&controls.status, |s| s.processed += 1);
@@ -243,20 +441,39 @@ fn run_pipeline_inner(
         new_files.len(),
         stats.skipped_existing
     );
+    record_stage(conn, controls, ImportStage::Exif);
 
-    if controls.cancel.load(Ordering::SeqCst) {
-        update_status(&controls.status, |s| s.cancelled = true);
-        stats.cancelled = true;
+    if checkpoint(controls, &mut stats) {
         return stats;
     }
 
+    if config.incremental {
+        // ── STEPS 4–7 (incremental): slot new files into the existing library ─
+        // Pairing, stacking and persisting interleave per file here
+        let Some(lp_thumb_targets) = append_new_files(
+            conn,
+            config,
+            controls,
+            new_files,
+            &existing_paths,
+            &mut stats,
+        ) else {
+            return stats;
+        };
+        return finish_pipeline(conn, config, controls, stats, lp_thumb_targets);
+    }
+
     // ── STEP 4: Load existing files from DB for re-stacking ───────────────────
     // Load existing photos so they can be re-incorporated into pairs/stacks.
     let existing_scanned = repository::load_existing_scanned_files(conn, config.project_id);
     let all_files: Vec<ScannedFile> = existing_scanned.into_iter().chain(new_files).collect();
 
     // ── STEP 5: Pair detection ────────────────────────────────────────────────
-    let groups = pairs::detect_pairs(all_files);
+    let groups = if config.pair_raw_jpeg {
+        pairs::detect_pairs(all_files)
+    } else {
+        pairs::detect_singles(all_files)
+    };
     let pairs_count = groups.iter().filter(|g| g.is_pair).count();
     stats.pairs_detected = pairs_count;
     tracing::info!(
@@ -264,10 +481,9 @@ fn run_pipeline_inner(
         groups.len(),
         pairs_count
     );
+    record_stage(conn, controls, ImportStage::Pair);
 
-    if controls.cancel.load(Ordering::SeqCst) {
-        update_status(&controls.status, |s| s.cancelled = true);
-        stats.cancelled = true;
+    if checkpoint(controls, &mut stats) {
         return stats;
     }
 
@@ -289,50 +505,23 @@ fn run_pipeline_inner(
         stats.stacks_generated,
         stats.logical_photos
     );
+    record_stage(conn, controls, ImportStage::Stack);
 
     // ── STEP 7: DB writes ─────────────────────────────────────────────────────
-    if controls.cancel.load(Ordering::SeqCst) {
-        update_status(&controls.status, |s| s.cancelled = true);
-        stats.cancelled = true;
-        return stats;
-    }
-
-    // Clear old stacks/logical_photos so we can rebuild cleanly.
-    if let Err(e) = repository::clear_stacks_and_logical_photos(conn, config.project_id) {
-        let msg = format!("pipeline: failed to clear stacks: {}", e);
-        tracing::warn!("{}", msg);
-        log_error(&mut stats, msg);
+    if checkpoint(controls, &mut stats) {
         return stats;
     }
 
-    // Map from stack_index → DB stack id
-    let mut stack_id_map: Vec<Option<i64>> = vec![None; max_stack_idx.max(1)];
-
-    // Pre-create all stack rows
-    for idx in 0..max_stack_idx {
-        match repository::insert_stack(conn, config.project_id) {
-            Ok(id) => {
-                if idx < stack_id_map.len() {
-                    stack_id_map[idx] = Some(id);
-                }
-            }
-            Err(e) => {
-                let msg = format!("pipeline: insert stack {}: {}", idx, e);
-                tracing::warn!("{}", msg);
-                log_error(&mut stats, msg);
-            }
-        }
-    }
-
-    // Persist logical photos and link scanned files via shared function.
-    let lp_thumb_targets = persist_groups_to_db(
+    let Some(lp_thumb_targets) = rebuild_library(
         conn,
-        &assigned,
-        &stack_id_map,
         config.project_id,
-        Some(&existing_paths),
+        &assigned,
+        max_stack_idx,
+        &existing_paths,
         &mut stats,
-    );
+    ) else {
+        return stats;
+    };
 
     tracing::info!(
         "pipeline: DB writes complete — imported={} skipped_existing={} errors={}",
@@ -340,16 +529,162 @@ fn run_pipeline_inner(
         stats.skipped_existing,
         stats.errors
     );
+    record_stage(conn, controls, ImportStage::Persist);
 
-    // Create round 1 for each stack so the decision engine is ready immediately.
-    for stack_id in stack_id_map.iter().flatten() {
-        if let Err(e) = init_round_for_stack(conn, config.project_id, *stack_id) {
-            let msg = format!("pipeline: create round for stack {}: {}", stack_id, e);
+    finish_pipeline(conn, config, controls, stats, lp_thumb_targets)
+}
+
+/// STEP 7 of a full run: clear the old stacks and logical photos and write the
+/// new ones with their first rounds, in one transaction. Cleared photos belong
+/// to no logical photo until they are written again, so a rebuild killed
+/// midway or failing for some group would drop them from the project: it
+/// commits only once every group is written, and otherwise keeps the previous
+/// library and fails the run. Returns the thumbnail targets.
+fn rebuild_library(
+    conn: &Connection,
+    project_id: i64,
+    assigned: &[(LogicalGroup, usize)],
+    stack_count: usize,
+    existing_paths: &std::collections::HashSet<String>,
+    stats: &mut ImportStats,
+) -> Option<Vec<(i64, PathBuf, PhotoFormat, Option<u16>)>> {
+    if let Err(e) = conn.execute("BEGIN", []) {
+        log_error(stats, format!("pipeline: begin rebuild: {}", e));
+        stats.failed = true;
+        return None;
+    }
+
+    let errors_before = stats.errors;
+    let mut lp_thumb_targets = Vec::new();
+    match repository::clear_stacks_and_logical_photos(conn, project_id) {
+        Err(e) => {
+            let msg = format!("pipeline: failed to clear stacks: {}", e);
+            tracing::warn!("{}", msg);
+            log_error(stats, msg);
+        }
+        Ok(()) => {
+            // Map from stack_index → DB stack id
+            let mut stack_id_map: Vec<Option<i64>> = vec![None; stack_count.max(1)];
+
+            // Pre-create all stack rows
+            for (idx, slot) in stack_id_map.iter_mut().enumerate().take(stack_count) {
+                match repository::insert_stack(conn, project_id) {
+                    Ok(id) => *slot = Some(id),
+                    Err(e) => {
+                        let msg = format!("pipeline: insert stack {}: {}", idx, e);
+                        tracing::warn!("{}", msg);
+                        log_error(stats, msg);
+                    }
+                }
+            }
+
+            // Persist logical photos and link scanned files via shared function.
+            lp_thumb_targets = persist_groups_to_db(
+                conn,
+                assigned,
+                &stack_id_map,
+                project_id,
+                Some(existing_paths),
+                stats,
+            );
+
+            // Create round 1 for each stack so the decision engine is ready immediately.
+            for stack_id in stack_id_map.iter().flatten() {
+                if let Err(e) = init_round_for_stack(conn, project_id, *stack_id) {
+                    let msg = format!("pipeline: create round for stack {}: {}", stack_id, e);
+                    tracing::warn!("{}", msg);
+                    log_error(stats, msg);
+                }
+            }
+        }
+    }
+
+    if stats.errors > errors_before {
+        let _ = conn.execute("ROLLBACK", []);
+        tracing::warn!(
+            "pipeline: rebuild rolled back after {} error(s); the previous library is kept",
+            stats.errors - errors_before
+        );
+        stats.failed = true;
+        return None;
+    }
+    if let Err(e) = conn.execute("COMMIT", []) {
+        let _ = conn.execute("ROLLBACK", []);
+        log_error(stats, format!("pipeline: commit rebuild: {}", e));
+        stats.failed = true;
+        return None;
+    }
+    Some(lp_thumb_targets)
+}
+
+/// Shared tail of full and incremental runs: seed decisions from sidecars, publish
+/// the stats, then generate thumbnails for `lp_thumb_targets` only (STEP 8).
+fn finish_pipeline(
+    conn: &Connection,
+    config: &PipelineConfig,
+    controls: &PipelineControls,
+    mut stats: ImportStats,
+    mut lp_thumb_targets: Vec<(i64, PathBuf, PhotoFormat, Option<u16>)>,
+) -> ImportStats {
+    let cache_dir = config.project_dir.join("cache").join("thumbnails");
+
+    // Seed round 1 from existing XMP sidecars (culled in-camera or in another tool).
+    let lp_ids: Vec<i64> = lp_thumb_targets.iter().map(|(id, ..)| *id).collect();
+    match xmp::reader::import_sidecars_for_logical_photos(conn, config.project_id, &lp_ids) {
+        Ok(result) => {
+            stats.sidecar_decisions = result.decisions_seeded;
+            if result.sidecars_read > 0 {
+                tracing::info!(
+                    "pipeline: {} sidecar(s) read — {} decisions, {} tags seeded",
+                    result.sidecars_read,
+                    result.decisions_seeded,
+                    result.tags_imported
+                );
+            }
+            for e in result.errors {
+                log_error(&mut stats, format!("xmp: {}", e));
+            }
+        }
+        Err(e) => {
+            let msg = format!("pipeline: xmp sidecar import: {}", e);
             tracing::warn!("{}", msg);
             log_error(&mut stats, msg);
         }
     }
 
+    // Imports are a barrier in the stack history: a full run rebuilds every logical
+    // photo, and new photos sit in stacks earlier entries know nothing about.
+    if stats.imported > 0 || !config.incremental {
+        let details = serde_json::json!({
+            "incremental": config.incremental,
+            "imported": stats.imported,
+            "relinked": stats.relinked,
+        });
+        if let Err(e) =
+            repository::log_stack_transaction(conn, config.project_id, "import", &details)
+        {
+            let msg = format!("pipeline: log import transaction: {}", e);
+            tracing::warn!("{}", msg);
+            log_error(&mut stats, msg);
+        }
+    }
+
+    // Thumbnails made from the same content under an earlier id are moved over
+    // instead of regenerated
+    match artifacts::reconcile_cache(conn, config.project_id, &config.project_dir) {
+        Ok(reconciled) => lp_thumb_targets.retain(|(id, ..)| !reconciled.adopted.contains(id)),
+        Err(e) => tracing::warn!("pipeline: reconcile thumbnail cache: {}", e),
+    }
+
+    // A cache built by another generator version or with other sizes is
+    // regenerated in full, not just for the new photos
+    if !pyramid::is_current(&cache_dir, &config.pyramid) {
+        match find_missing_thumbnail_targets(conn, config.project_id, &cache_dir, &config.pyramid) {
+            Ok((stale, _)) => lp_thumb_targets = stale,
+            Err(e) => tracing::warn!("pipeline: list stale thumbnails: {}", e),
+        }
+    }
+
     // After STEP 7 (DB writes complete — stacks ready to display):
     update_status(&controls.status, |s| {
         s.running = false; // Frontend can show grid now
@@ -364,12 +699,8 @@ fn run_pipeline_inner(
     controls.thumbnails_done_counter.store(0, Ordering::SeqCst);
 
     // ── STEP 8: Thumbnail generation (non-blocking from UI perspective) ───────
-    if controls.cancel.load(Ordering::SeqCst) {
-        update_status(&controls.status, |s| {
-            s.cancelled = true;
-            s.thumbnails_running = false;
-        });
-        stats.cancelled = true;
+    if checkpoint(controls, &mut stats) {
+        update_status(&controls.status, |s| s.thumbnails_running = false);
         return stats;
     }
 
@@ -380,14 +711,49 @@ fn run_pipeline_inner(
         strategy.num_threads,
         strategy.use_exif_fast_path
     );
-    run_thumbnail_pool(
+    let analyses = run_thumbnail_pool(
         &lp_thumb_targets,
         &cache_dir,
+        &config.pyramid,
         strategy.num_threads,
         &controls.cancel,
+        &controls.pause,
         &controls.thumbnails_done_counter,
         controls.app_handle.as_ref(),
     );
+    if let Err(e) = quality::store_image_analyses(conn, &analyses) {
+        tracing::warn!("pipeline: store image analyses: {}", e);
+    }
+    let thumbnailed: Vec<i64> = lp_thumb_targets.iter().map(|(id, ..)| *id).collect();
+    if let Err(e) =
+        artifacts::record_artifacts(conn, config.project_id, &cache_dir, &thumbnailed, &analyses)
+    {
+        tracing::warn!("pipeline: record thumbnail artifacts: {}", e);
+    }
+    let backfilled = backfill_image_analyses(
+        conn,
+        config.project_id,
+        &cache_dir,
+        &config.pyramid,
+        &controls.cancel,
+    );
+    if let Err(e) = quality::store_image_analyses(conn, &backfilled) {
+        tracing::warn!("pipeline: store backfilled image analyses: {}", e);
+    }
+    if controls.cancel.load(Ordering::SeqCst) {
+        stats.cancelled = true;
+    } else {
+        if let Err(e) = pyramid::write_manifest(&cache_dir, &config.pyramid) {
+            tracing::warn!("pipeline: write thumbnail manifest: {}", e);
+        }
+        record_stage(conn, controls, ImportStage::Thumbnails);
+    }
+
+    // Perceptual hashes of the fresh thumbnails feed similarity restacks
+    match phash::hash_cached_thumbnails(conn, config.project_id, &cache_dir) {
+        Ok(n) => tracing::info!("pipeline: {} perceptual hash(es) stored", n),
+        Err(e) => tracing::warn!("pipeline: perceptual hashing: {}", e),
+    }
 
     // Mark thumbnails done
     update_status(&controls.status, |s| {
@@ -396,6 +762,266 @@ fn run_pipeline_inner(
     stats
 }
 
+/// Incremental STEPS 4–7: add `new_files` without touching existing logical photos,
+/// stacks or rounds.
+///
+/// A new file whose fingerprint matches a photo that vanished from its path is a
+/// move: the photo row is relinked and nothing is imported. A new file whose
+/// RAW/JPEG partner is already a single-file logical photo joins that photo.
+/// Remaining groups slot into the active stack whose capture span they fall within
+/// (± burst gap), so manual merges are kept; the rest are burst-grouped into new
+/// stacks. New logical photos join the open round of an existing stack; new stacks
+/// get round 1. Returns thumbnail targets for the new logical photos (and for pairs
+/// whose representative changed), or None if the run was cancelled before any
+/// stack was written.
+fn append_new_files(
+    conn: &Connection,
+    config: &PipelineConfig,
+    controls: &PipelineControls,
+    new_files: Vec<ScannedFile>,
+    existing_paths: &std::collections::HashSet<String>,
+    stats: &mut ImportStats,
+) -> Option<Vec<(i64, PathBuf, PhotoFormat, Option<u16>)>> {
+    let gap = chrono::Duration::seconds(config.burst_gap_secs as i64);
+
+    // ── STEP 4a: Relink moved files instead of importing them again ───────────
+    let mut moved_away: std::collections::HashMap<fingerprint::Fingerprint, i64> =
+        repository::list_photo_files_for_project(conn, config.project_id)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|row| !Path::new(&row.path).exists())
+            .filter_map(|row| {
+                let fp = fingerprint::Fingerprint {
+                    size: row.file_size?,
+                    hash: row.fingerprint?,
+                };
+                Some((fp, row.photo_id))
+            })
+            .collect();
+    let mut not_moved: Vec<ScannedFile> = Vec::new();
+    for file in new_files {
+        if checkpoint(controls, stats) {
+            return None;
+        }
+        let Some(photo_id) = file.fingerprint.as_ref().and_then(|f| moved_away.remove(f)) else {
+            not_moved.push(file);
+            continue;
+        };
+        match repository::update_photo_path(conn, photo_id, &file.path.to_string_lossy()) {
+            Ok(()) => stats.relinked += 1,
+            Err(e) => {
+                log_error(stats, format!("incremental: relink {:?}: {}", file.path, e));
+                not_moved.push(file);
+            }
+        }
+    }
+
+    // ── STEP 4b: Complete existing single-file logical photos into pairs ──────
+    let singles = repository::list_single_file_logical_photos(conn, config.project_id)
+        .unwrap_or_else(|e| {
+            tracing::warn!("pipeline: cannot load single-file logical photos: {}", e);
+            vec![]
+        });
+    let pair_key = |path: &Path| {
+        let base = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .map(|s| s.to_lowercase())
+            .unwrap_or_default();
+        (path.parent().map(|p| p.to_path_buf()), base)
+    };
+    let mut single_by_key: std::collections::HashMap<_, (i64, PhotoFormat)> = singles
+        .into_iter()
+        .map(|(lp_id, path, format)| (pair_key(Path::new(&path)), (lp_id, format)))
+        .collect();
+
+    let mut unpaired: Vec<ScannedFile> = Vec::new();
+    let mut completed_pairs: Vec<(i64, PathBuf, PhotoFormat, Option<u16>)> = Vec::new();
+    for file in not_moved {
+        if checkpoint(controls, stats) {
+            return None;
+        }
+        let key = pair_key(&file.path);
+        let partner = single_by_key
+            .get(&key)
+            .filter(|(_, format)| config.pair_raw_jpeg && *format != file.format)
+            .map(|(lp_id, _)| *lp_id);
+        match partner {
+            Some(lp_id) => match insert_scanned_file(conn, &file) {
+                Ok(photo_id) => {
+                    if let Err(e) = repository::set_logical_photo_id(conn, photo_id, lp_id) {
+                        log_error(stats, format!("incremental: link {:?}: {}", file.path, e));
+                        continue;
+                    }
+                    single_by_key.remove(&key);
+                    stats.imported += 1;
+                    stats.pairs_detected += 1;
+                    // The JPEG is the representative of a pair, as in a full run;
+                    // its thumbnail replaces the one made from the RAW
+                    if file.format == PhotoFormat::Jpeg {
+                        if let Err(e) =
+                            repository::set_representative_photo_id(conn, lp_id, photo_id)
+                        {
+                            log_error(
+                                stats,
+                                format!("incremental: representative {:?}: {}", file.path, e),
+                            );
+                            continue;
+                        }
+                        completed_pairs.push((
+                            lp_id,
+                            file.path.clone(),
+                            file.format.clone(),
+                            file.orientation,
+                        ));
+                    }
+                }
+                Err(e) => log_error(stats, format!("incremental: insert {:?}: {}", file.path, e)),
+            },
+            None => unpaired.push(file),
+        }
+    }
+
+    // ── STEP 5: Pair detection among the new files ────────────────────────────
+    let groups = if config.pair_raw_jpeg {
+        pairs::detect_pairs(unpaired)
+    } else {
+        pairs::detect_singles(unpaired)
+    };
+    stats.pairs_detected += groups.iter().filter(|g| g.is_pair).count();
+    record_stage(conn, controls, ImportStage::Pair);
+
+    // Last stop before stacks are written: from here the run completes
+    if checkpoint(controls, stats) {
+        return None;
+    }
+
+    // ── STEP 6: Slot into existing stacks, burst-group the rest ───────────────
+    let mut spans: Vec<(
+        i64,
+        chrono::DateTime<chrono::Utc>,
+        chrono::DateTime<chrono::Utc>,
+    )> = repository::list_active_stack_time_spans(conn, config.project_id)
+        .unwrap_or_else(|e| {
+            tracing::warn!("pipeline: cannot load stack spans: {}", e);
+            vec![]
+        })
+        .into_iter()
+        .filter_map(|(id, min, max)| {
+            let parse = |s: Option<String>| {
+                chrono::DateTime::parse_from_rfc3339(&s?)
+                    .ok()
+                    .map(|dt| dt.with_timezone(&chrono::Utc))
+            };
+            Some((id, parse(min)?, parse(max)?))
+        })
+        .collect();
+
+    let mut groups = groups;
+    groups.sort_by_key(|g| (g.capture_time(), g.sequence_number()));
+
+    // Target stacks: existing ids first, new stacks appended as burst indices are known
+    let mut stack_id_map: Vec<Option<i64>> = Vec::new();
+    let mut assigned: Vec<(LogicalGroup, usize)> = Vec::new();
+    let mut fresh: Vec<LogicalGroup> = Vec::new();
+    for group in groups {
+        let slot = group.capture_time().and_then(|t| {
+            spans
+                .iter_mut()
+                .find(|(_, min, max)| t >= *min - gap && t <= *max + gap)
+        });
+        match slot {
+            Some((stack_id, min, max)) => {
+                // Widen the span so a burst continuing past the edge stays together
+                let t = group.capture_time().unwrap_or(*min);
+                *min = (*min).min(t);
+                *max = (*max).max(t);
+                let idx = match stack_id_map.iter().position(|id| *id == Some(*stack_id)) {
+                    Some(idx) => idx,
+                    None => {
+                        stack_id_map.push(Some(*stack_id));
+                        stack_id_map.len() - 1
+                    }
+                };
+                assigned.push((group, idx));
+            }
+            None => fresh.push(group),
+        }
+    }
+    let existing_stack_count = stack_id_map.len();
+
+    let fresh_assigned = stacks::assign_stacks_by_burst(fresh, config.burst_gap_secs);
+    let fresh_stacks = fresh_assigned
+        .iter()
+        .map(|(_, i)| *i + 1)
+        .max()
+        .unwrap_or(0);
+    record_stage(conn, controls, ImportStage::Stack);
+    for idx in 0..fresh_stacks {
+        match repository::insert_stack(conn, config.project_id) {
+            Ok(id) => stack_id_map.push(Some(id)),
+            Err(e) => {
+                log_error(stats, format!("incremental: insert stack {}: {}", idx, e));
+                stack_id_map.push(None);
+            }
+        }
+    }
+    assigned.extend(
+        fresh_assigned
+            .into_iter()
+            .map(|(g, i)| (g, existing_stack_count + i)),
+    );
+
+    // ── STEP 7: DB writes ─────────────────────────────────────────────────────
+    let mut lp_thumb_targets = persist_groups_to_db(
+        conn,
+        &assigned,
+        &stack_id_map,
+        config.project_id,
+        Some(existing_paths),
+        stats,
+    );
+
+    // New logical photos in existing stacks join that stack's open round;
+    // new stacks get round 1 (which picks up all their members).
+    for (idx, stack_id) in stack_id_map.iter().enumerate() {
+        let Some(stack_id) = stack_id else { continue };
+        let result = if idx < existing_stack_count {
+            repository::add_new_logical_photos_to_open_round(conn, config.project_id, *stack_id)
+        } else {
+            init_round_for_stack(conn, config.project_id, *stack_id)
+        };
+        if let Err(e) = result {
+            let msg = format!("incremental: round for stack {}: {}", stack_id, e);
+            tracing::warn!("{}", msg);
+            log_error(stats, msg);
+        }
+    }
+
+    record_stage(conn, controls, ImportStage::Persist);
+
+    // Report project totals, as a full run does
+    if let Ok(summaries) = repository::list_stacks_summary(conn, config.project_id) {
+        stats.stacks_generated = summaries.len();
+        stats.logical_photos = summaries
+            .iter()
+            .map(|s| s.logical_photo_count as usize)
+            .sum();
+    }
+
+    tracing::info!(
+        "pipeline: incremental — imported={} relinked={} new_logical_photos={} new_stacks={} errors={}",
+        stats.imported,
+        stats.relinked,
+        lp_thumb_targets.len(),
+        fresh_stacks,
+        stats.errors
+    );
+
+    lp_thumb_targets.extend(completed_pairs);
+    Some(lp_thumb_targets)
+}
+
 /// Persist assigned logical groups to the database: insert logical_photo rows,
 /// link scanned files, and handle RAW+JPEG pairs.
 ///
@@ -529,7 +1155,7 @@ fn persist_groups_to_db(
 fn insert_scanned_file(conn: &Connection, file: &ScannedFile) -> rusqlite::Result<i64> {
     let path = file.path.to_string_lossy();
     let capture_time_rfc = file.capture_time.as_ref().map(|t| t.to_rfc3339());
-    repository::insert_photo(
+    let photo_id = repository::insert_photo(
         conn,
         &path,
         file.format.as_str(),
@@ -542,7 +1168,22 @@ fn insert_scanned_file(conn: &Connection, file: &ScannedFile) -> rusqlite::Resul
         file.iso,
         file.focal_length,
         file.exposure_comp,
-    )
+    )?;
+    if let Some(fp) = &file.fingerprint {
+        repository::set_photo_fingerprint(conn, photo_id, fp.size, &fp.hash)?;
+    }
+    if let Some(sequence_number) = file.sequence_number {
+        repository::set_photo_sequence_number(conn, photo_id, sequence_number)?;
+    }
+    if file.camera_serial.is_some() || file.clock_offset_secs != 0 {
+        repository::set_photo_camera_clock(
+            conn,
+            photo_id,
+            file.camera_serial.as_deref(),
+            file.clock_offset_secs,
+        )?;
+    }
+    Ok(photo_id)
 }
 
 /// Record an error in stats (increments counter + appends to log if < 100 entries).
@@ -562,24 +1203,31 @@ fn update_status<F: FnOnce(&mut IndexingStatus)>(status: &Arc<Mutex<IndexingStat
 /// Find logical photo targets that are missing thumbnails on disk.
 ///
 /// Returns `(missing_targets, total_lp_count)` where `missing_targets` contains
-/// `(lp_id, source_path, PhotoFormat, orientation)` for each LP without a cached thumbnail.
+/// `(lp_id, source_path, PhotoFormat, orientation)` for each LP without a cached
+/// grid thumbnail or fit variant. If the cache manifest does not match
+/// `pyramid`, every LP is returned.
 #[allow(clippy::type_complexity)]
 pub fn find_missing_thumbnail_targets(
     conn: &Connection,
     project_id: i64,
     cache_dir: &Path,
+    pyramid: &ThumbnailPyramid,
 ) -> Result<(Vec<(i64, PathBuf, PhotoFormat, Option<u16>)>, usize), String> {
     // Get all lp_ids for this project (one per logical photo, not one per stack)
     let all_lp_ids =
         repository::list_all_lp_ids_for_project(conn, project_id).map_err(|e| e.to_string())?;
     let total_lp_count = all_lp_ids.len();
 
+    let stale = !pyramid::is_current(cache_dir, pyramid);
+
     // Find which thumbnails already exist on disk
     let existing = crate::import::util::cached_thumbnail_ids(cache_dir);
 
     let missing_ids: Vec<i64> = all_lp_ids
         .into_iter()
-        .filter(|id| !existing.contains(id))
+        .filter(|id| {
+            stale || !existing.contains(id) || !pyramid::has_fit_variants(cache_dir, *id, pyramid)
+        })
         .collect();
 
     if missing_ids.is_empty() {
@@ -629,19 +1277,25 @@ pub fn restack_from_existing_photos(
         .map_err(|e| format!("restack: clear stacks: {}", e))?;
 
     // Parse capture times and apply burst grouping via shared algorithm.
-    let items: Vec<(i64, Option<chrono::DateTime<chrono::Utc>>)> = lp_rows
+    let items: Vec<(i64, Option<chrono::DateTime<chrono::Utc>>, Option<u32>)> = lp_rows
         .iter()
-        .map(|(lp_id, capture_time_str)| {
+        .map(|(lp_id, capture_time_str, sequence_number)| {
             let dt = capture_time_str.as_ref().and_then(|ct_str| {
                 chrono::DateTime::parse_from_rfc3339(ct_str)
                     .ok()
                     .map(|dt| dt.with_timezone(&chrono::Utc))
             });
-            (*lp_id, dt)
+            (*lp_id, dt, *sequence_number)
         })
         .collect();
 
-    let assignments = stacks::burst_group(items, burst_gap_secs, |(id, _)| *id, |(_, dt)| *dt);
+    let assignments = stacks::burst_group(
+        items,
+        burst_gap_secs,
+        |(id, _, _)| *id,
+        |(_, dt, _)| *dt,
+        |(_, _, seq)| *seq,
+    );
 
     let max_stack_idx = assignments
         .iter()
@@ -719,7 +1373,94 @@ pub fn restack_from_existing_photos(
 
 #[cfg(test)]
 mod tests {
-    use super::ThumbnailReadyPayload;
+    use super::*;
+    use crate::import::stacks_tests::make_group;
+    use crate::import::test_fixtures::TestLibraryBuilder;
+
+    #[test]
+    fn test_append_new_files_stops_at_cancel_before_writing() {
+        // A cancelled incremental run must leave the library as it was: no new
+        // photos, stacks or logical photos.
+        let project = TestLibraryBuilder::new()
+            .with_stack_layout(&[1])
+            .build_db_only();
+        let conn = &project.conn;
+        let config = PipelineConfig {
+            project_id: project.project_id,
+            project_dir: project.dir.path().to_path_buf(),
+            folder_paths: vec![],
+            burst_gap_secs: 3,
+            pair_raw_jpeg: true,
+            pyramid: ThumbnailPyramid::from_settings(&ProjectSettings::default()),
+            incremental: true,
+        };
+        let controls = PipelineControls {
+            status: Arc::new(Mutex::new(IndexingStatus::default())),
+            cancel: Arc::new(AtomicBool::new(true)),
+            pause: Arc::new(AtomicBool::new(true)),
+            app_handle: None,
+            thumbnails_done_counter: Arc::new(AtomicUsize::new(0)),
+            job_id: None,
+        };
+        let file = make_group(None).jpeg.unwrap();
+        let count = |table: &str| -> i64 {
+            conn.query_row(&format!("SELECT COUNT(*) FROM {table}"), [], |row| {
+                row.get(0)
+            })
+            .unwrap()
+        };
+        let before = (count("photos"), count("stacks"), count("logical_photos"));
+
+        let mut stats = ImportStats::default();
+        let targets = append_new_files(
+            conn,
+            &config,
+            &controls,
+            vec![file],
+            &Default::default(),
+            &mut stats,
+        );
+
+        assert!(targets.is_none());
+        assert!(stats.cancelled);
+        assert_eq!(
+            (count("photos"), count("stacks"), count("logical_photos")),
+            before
+        );
+    }
+
+    #[test]
+    fn test_backfill_scores_unscored_photos_from_their_cached_fit_variant() {
+        // Photos whose thumbnails came from the cache are never decoded by the
+        // thumbnail pool; the backfill must score them from the fit variant.
+        let project = TestLibraryBuilder::new()
+            .with_stack_layout(&[2])
+            .build_db_only();
+        let cache_dir = project.dir.path().join("cache");
+        std::fs::create_dir_all(&cache_dir).unwrap();
+        let pyramid = ThumbnailPyramid::from_settings(&ProjectSettings::default());
+        let edge = pyramid.max_fit_size();
+        let (cached, uncached) = (project.lp_ids[0], project.lp_ids[1]);
+        image::DynamicImage::new_rgb8(edge, edge * 2 / 3)
+            .save(pyramid::fit_path(&cache_dir, cached, edge))
+            .unwrap();
+
+        let analyses = backfill_image_analyses(
+            &project.conn,
+            project.project_id,
+            &cache_dir,
+            &pyramid,
+            &AtomicBool::new(false),
+        );
+        assert_eq!(
+            analyses.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+            vec![cached]
+        );
+
+        quality::store_image_analyses(&project.conn, &analyses).unwrap();
+        let unscored = repository::list_unscored_lp_ids(&project.conn, project.project_id).unwrap();
+        assert_eq!(unscored, vec![uncached]);
+    }
 
     #[test]
     fn test_thumbnail_ready_payload_serializes_correctly() {
@@ -0,0 +1,216 @@
+//! SingleView preview prefetch.
+//!
+//! When the user focuses a photo, the display images of its neighbours in the
+//! round are warmed on a small pool of their own, so navigation never waits on
+//! disk and prefetch never competes with the import thumbnail pool. A newer
+//! request supersedes older ones: each job carries the generation it was queued
+//! under and is dropped once the project's generation has moved on.
+
+use rusqlite::Connection;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+
+use crate::import::thumbnails;
+use crate::photos::model::PhotoFormat;
+use crate::photos::repository;
+
+/// Photos warmed on each side of the focused one.
+pub const PREFETCH_RADIUS: usize = 5;
+
+/// Kept small on purpose: prefetch is background work and must leave the CPU
+/// and disk to the UI and to a running import.
+const PREFETCH_THREADS: usize = 2;
+
+/// Payload emitted on the `preview-ready` event after each preview is warmed.
+#[derive(Serialize, Clone)]
+pub struct PreviewReadyPayload {
+    pub logical_photo_id: i64,
+    pub path: String,
+}
+
+/// The files that can back one photo's SingleView image.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrefetchTarget {
+    pub logical_photo_id: i64,
+    pub jpeg_path: Option<PathBuf>,
+    /// RAW path and EXIF orientation, for the embedded preview.
+    pub raw: Option<(PathBuf, Option<u16>)>,
+}
+
+/// The focused photo followed by its neighbours, nearest first, alternating
+/// next/previous: `[focus, +1, -1, +2, -2, …]`. Empty if `focus` is not in `ids`.
+pub fn neighbour_order(ids: &[i64], focus: i64, radius: usize) -> Vec<i64> {
+    let Some(pos) = ids.iter().position(|&id| id == focus) else {
+        return vec![];
+    };
+    let mut order = vec![focus];
+    for step in 1..=radius {
+        if let Some(&next) = ids.get(pos + step) {
+            order.push(next);
+        }
+        if let Some(prev) = pos.checked_sub(step).map(|i| ids[i]) {
+            order.push(prev);
+        }
+    }
+    order
+}
+
+/// Prefetch targets around `focus` in a round, in `neighbour_order`.
+pub fn prefetch_targets(
+    conn: &Connection,
+    round_id: i64,
+    focus: i64,
+    radius: usize,
+) -> rusqlite::Result<Vec<PrefetchTarget>> {
+    let ids: Vec<i64> = repository::query_logical_photos_by_round(conn, round_id)?
+        .iter()
+        .map(|p| p.logical_photo_id)
+        .collect();
+    neighbour_order(&ids, focus, radius)
+        .into_iter()
+        .map(|lp_id| {
+            Ok(PrefetchTarget {
+                logical_photo_id: lp_id,
+                jpeg_path: repository::find_photo_file(conn, lp_id, &PhotoFormat::Jpeg)?
+                    .map(|(path, _)| PathBuf::from(path)),
+                raw: repository::find_photo_file(conn, lp_id, &PhotoFormat::Raw)?
+                    .map(|(path, orientation)| (PathBuf::from(path), orientation)),
+            })
+        })
+        .collect()
+}
+
+/// Warm one photo's SingleView image: its JPEG, or else the RAW's embedded
+/// preview (extracted if missing). Returns the warmed file.
+pub fn warm_preview(target: &PrefetchTarget, cache_dir: &Path) -> Option<PathBuf> {
+    let path = match (&target.jpeg_path, &target.raw) {
+        (Some(jpeg), _) => jpeg.clone(),
+        (None, Some((raw, orientation))) => {
+            thumbnails::ensure_raw_preview(raw, target.logical_photo_id, cache_dir, *orientation)?
+        }
+        (None, None) => return None,
+    };
+    // Reading the whole file pulls it into the OS page cache, so the webview's
+    // request for it is served from memory
+    std::fs::read(&path).ok()?;
+    Some(path)
+}
+
+fn pool() -> &'static rayon::ThreadPool {
+    static POOL: OnceLock<rayon::ThreadPool> = OnceLock::new();
+    POOL.get_or_init(|| {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(PREFETCH_THREADS)
+            .thread_name(|i| format!("preview-prefetch-{}", i))
+            .build()
+            .unwrap_or_else(|_| {
+                rayon::ThreadPoolBuilder::new()
+                    .num_threads(1)
+                    .build()
+                    .unwrap()
+            })
+    })
+}
+
+/// Run one queued job unless a newer prefetch has superseded it.
+fn run_job(
+    target: &PrefetchTarget,
+    cache_dir: &Path,
+    generation: u64,
+    current: &AtomicU64,
+) -> Option<PathBuf> {
+    if current.load(Ordering::SeqCst) != generation {
+        return None;
+    }
+    warm_preview(target, cache_dir)
+}
+
+/// Queue `targets` on the prefetch pool in order under `generation`. Each warmed
+/// preview emits a `preview-ready` event if `app_handle` is `Some`.
+pub fn spawn_prefetch(
+    targets: Vec<PrefetchTarget>,
+    cache_dir: PathBuf,
+    generation: u64,
+    current: Arc<AtomicU64>,
+    app_handle: Option<tauri::AppHandle>,
+) {
+    let cache_dir = Arc::new(cache_dir);
+    for target in targets {
+        let cache_dir = Arc::clone(&cache_dir);
+        let current = Arc::clone(&current);
+        let app_handle = app_handle.clone();
+        // FIFO keeps the nearest photos first
+        pool().spawn_fifo(move || {
+            let Some(path) = run_job(&target, &cache_dir, generation, &current) else {
+                return;
+            };
+            if let Some(handle) = app_handle {
+                use tauri::Emitter;
+                let _ = handle.emit(
+                    "preview-ready",
+                    PreviewReadyPayload {
+                        logical_photo_id: target.logical_photo_id,
+                        path: path.to_string_lossy().to_string(),
+                    },
+                );
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decisions::engine::find_or_create_round;
+    use crate::import::test_fixtures::TestLibraryBuilder;
+
+    #[test]
+    fn test_neighbour_order_alternates_nearest_first() {
+        let ids = [10, 11, 12, 13, 14, 15];
+        assert_eq!(neighbour_order(&ids, 12, 2), vec![12, 13, 11, 14, 10]);
+        assert_eq!(
+            neighbour_order(&ids, 10, 2),
+            vec![10, 11, 12],
+            "clamped at start"
+        );
+        assert_eq!(neighbour_order(&ids, 15, 1), vec![15, 14], "clamped at end");
+        assert!(
+            neighbour_order(&ids, 99, 2).is_empty(),
+            "focus not in round"
+        );
+    }
+
+    #[test]
+    fn test_prefetch_targets_follow_round_order() {
+        let project = TestLibraryBuilder::new()
+            .with_stack_layout(&[5])
+            .build_db_only();
+        let conn = &project.conn;
+        let lps = project.lp_ids().to_vec();
+        let (round_id, _) =
+            find_or_create_round(conn, project.project_id, project.stack_id()).unwrap();
+
+        let targets = prefetch_targets(conn, round_id, lps[2], 1).unwrap();
+        let ids: Vec<i64> = targets.iter().map(|t| t.logical_photo_id).collect();
+        assert_eq!(ids, vec![lps[2], lps[3], lps[1]]);
+        assert!(targets.iter().all(|t| t.jpeg_path.is_some()));
+    }
+
+    #[test]
+    fn test_superseded_job_does_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let jpeg = dir.path().join("IMG_0001.jpg");
+        std::fs::write(&jpeg, b"jpeg bytes").unwrap();
+        let target = PrefetchTarget {
+            logical_photo_id: 1,
+            jpeg_path: Some(jpeg.clone()),
+            raw: None,
+        };
+        let current = AtomicU64::new(2);
+
+        assert_eq!(run_job(&target, dir.path(), 1, &current), None);
+        assert_eq!(run_job(&target, dir.path(), 2, &current), Some(jpeg));
+    }
+}
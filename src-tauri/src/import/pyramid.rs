@@ -0,0 +1,227 @@
+//! Thumbnail pyramid: the square grid thumbnail `{id}.jpg` plus aspect-preserving
+//! fit variants `{id}_fit{edge}.jpg` for comparison and single views.
+//!
+//! `manifest.json` in the thumbnail cache records the generator version and the
+//! sizes the cache was built with. When either no longer matches, every logical
+//! photo is regenerated in place; ids, decisions and other cache tiers are kept.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::import::thumbnails;
+use crate::projects::model::ProjectSettings;
+
+/// Bump when thumbnail output changes (crop, filter, quality) so existing caches
+/// are regenerated.
+pub const GENERATOR_VERSION: u32 = 1;
+
+const MANIFEST_FILE: &str = "manifest.json";
+const FIT_JPEG_QUALITY: u8 = 85;
+
+/// The thumbnail sizes a project generates.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ThumbnailPyramid {
+    /// Edge of the square, cropped-to-fill grid thumbnail.
+    pub grid_size: u32,
+    /// Long edges of the fit variants, largest first, without duplicates.
+    pub fit_sizes: Vec<u32>,
+}
+
+impl ThumbnailPyramid {
+    pub fn from_settings(settings: &ProjectSettings) -> Self {
+        let mut fit_sizes = settings.thumbnail_fit_sizes.clone();
+        fit_sizes.sort_unstable_by(|a, b| b.cmp(a));
+        fit_sizes.dedup();
+        Self {
+            grid_size: settings.thumbnail_size,
+            fit_sizes,
+        }
+    }
+
+    /// Long edge a source must be decoded at to cover every fit variant.
+    pub fn max_fit_size(&self) -> u32 {
+        self.fit_sizes.first().copied().unwrap_or(0)
+    }
+}
+
+/// Contents of `manifest.json`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CacheManifest {
+    pub generator_version: u32,
+    #[serde(flatten)]
+    pub pyramid: ThumbnailPyramid,
+}
+
+impl CacheManifest {
+    pub fn current(pyramid: &ThumbnailPyramid) -> Self {
+        Self {
+            generator_version: GENERATOR_VERSION,
+            pyramid: pyramid.clone(),
+        }
+    }
+}
+
+/// `{id}_fit{edge}.jpg` in the thumbnail cache.
+pub fn fit_path(cache_dir: &Path, logical_photo_id: i64, edge: u32) -> PathBuf {
+    cache_dir.join(format!("{}_fit{}.jpg", logical_photo_id, edge))
+}
+
+/// The manifest the cache was built with. None if missing or unreadable.
+pub fn read_manifest(cache_dir: &Path) -> Option<CacheManifest> {
+    let bytes = std::fs::read(cache_dir.join(MANIFEST_FILE)).ok()?;
+    serde_json::from_slice(&bytes).ok()
+}
+
+/// True if the cache was built by this generator with `pyramid`'s sizes.
+/// A cache without a manifest predates versioning and is stale.
+pub fn is_current(cache_dir: &Path, pyramid: &ThumbnailPyramid) -> bool {
+    read_manifest(cache_dir) == Some(CacheManifest::current(pyramid))
+}
+
+/// Record that the cache now matches `pyramid`, and delete fit variants of
+/// sizes it no longer generates. Call only after a complete generation pass.
+pub fn write_manifest(cache_dir: &Path, pyramid: &ThumbnailPyramid) -> std::io::Result<()> {
+    let json = serde_json::to_vec_pretty(&CacheManifest::current(pyramid))
+        .map_err(std::io::Error::other)?;
+    std::fs::write(cache_dir.join(MANIFEST_FILE), json)?;
+    let removed = remove_unused_fit_variants(cache_dir, pyramid);
+    if removed > 0 {
+        tracing::debug!("pyramid: removed {} unused fit thumbnails", removed);
+    }
+    Ok(())
+}
+
+/// True if every fit variant of a logical photo is on disk.
+pub fn has_fit_variants(
+    cache_dir: &Path,
+    logical_photo_id: i64,
+    pyramid: &ThumbnailPyramid,
+) -> bool {
+    pyramid
+        .fit_sizes
+        .iter()
+        .all(|&edge| fit_path(cache_dir, logical_photo_id, edge).exists())
+}
+
+/// Write the fit variants of a decoded source (orientation not yet applied).
+/// Sizes are produced largest first, each from the previous one, and never
+/// upscaled. Returns the number written.
+pub fn write_fit_variants(
+    img: &image::DynamicImage,
+    cache_dir: &Path,
+    logical_photo_id: i64,
+    orientation: Option<u16>,
+    pyramid: &ThumbnailPyramid,
+) -> usize {
+    let mut current = img.clone();
+    let mut written = 0;
+    for &edge in &pyramid.fit_sizes {
+        if current.width().max(current.height()) > edge {
+            current = current.resize(edge, edge, image::imageops::FilterType::CatmullRom);
+        }
+        let oriented = thumbnails::apply_orientation_to_image(current.clone(), orientation);
+        let out_path = fit_path(cache_dir, logical_photo_id, edge);
+        match write_jpeg(&oriented, &out_path) {
+            Ok(()) => written += 1,
+            Err(e) => tracing::warn!("pyramid: save failed for {:?}: {}", out_path, e),
+        }
+    }
+    written
+}
+
+fn write_jpeg(img: &image::DynamicImage, path: &Path) -> image::ImageResult<()> {
+    let file = std::fs::File::create(path)?;
+    let encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(
+        std::io::BufWriter::new(file),
+        FIT_JPEG_QUALITY,
+    );
+    img.to_rgb8().write_with_encoder(encoder)
+}
+
+/// Delete `{id}_fit{edge}.jpg` files whose edge `pyramid` no longer generates.
+fn remove_unused_fit_variants(cache_dir: &Path, pyramid: &ThumbnailPyramid) -> usize {
+    let Ok(entries) = std::fs::read_dir(cache_dir) else {
+        return 0;
+    };
+    let mut removed = 0;
+    for entry in entries.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        let edge = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .and_then(|s| s.split_once("_fit"))
+            .and_then(|(_, edge)| edge.parse::<u32>().ok());
+        if let Some(edge) = edge {
+            if !pyramid.fit_sizes.contains(&edge) && std::fs::remove_file(&path).is_ok() {
+                removed += 1;
+            }
+        }
+    }
+    removed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pyramid(grid_size: u32, fit_sizes: &[u32]) -> ThumbnailPyramid {
+        ThumbnailPyramid {
+            grid_size,
+            fit_sizes: fit_sizes.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_from_settings_orders_fit_sizes_largest_first() {
+        let settings = ProjectSettings {
+            thumbnail_fit_sizes: vec![512, 1600, 512],
+            ..ProjectSettings::default()
+        };
+        assert_eq!(
+            ThumbnailPyramid::from_settings(&settings),
+            pyramid(256, &[1600, 512])
+        );
+    }
+
+    #[test]
+    fn test_manifest_goes_stale_when_sizes_change() {
+        let dir = tempfile::tempdir().unwrap();
+        let built = pyramid(256, &[1600, 512]);
+        assert!(!is_current(dir.path(), &built), "no manifest yet");
+
+        write_manifest(dir.path(), &built).unwrap();
+        assert!(is_current(dir.path(), &built));
+        assert!(!is_current(dir.path(), &pyramid(320, &[1600, 512])));
+        assert!(!is_current(dir.path(), &pyramid(256, &[1600])));
+    }
+
+    #[test]
+    fn test_fit_variants_preserve_aspect_and_never_upscale() {
+        let dir = tempfile::tempdir().unwrap();
+        let img = image::DynamicImage::ImageRgb8(image::RgbImage::new(1200, 800));
+        let sizes = pyramid(256, &[1600, 600]);
+
+        assert_eq!(write_fit_variants(&img, dir.path(), 7, None, &sizes), 2);
+        assert!(has_fit_variants(dir.path(), 7, &sizes));
+        let large = image::open(fit_path(dir.path(), 7, 1600)).unwrap();
+        assert_eq!((large.width(), large.height()), (1200, 800), "not upscaled");
+        let small = image::open(fit_path(dir.path(), 7, 600)).unwrap();
+        assert_eq!((small.width(), small.height()), (600, 400));
+    }
+
+    #[test]
+    fn test_write_manifest_removes_dropped_fit_sizes() {
+        let dir = tempfile::tempdir().unwrap();
+        let img = image::DynamicImage::ImageRgb8(image::RgbImage::new(64, 48));
+        write_fit_variants(&img, dir.path(), 3, None, &pyramid(256, &[1600, 512]));
+        std::fs::write(dir.path().join("3.jpg"), b"grid").unwrap();
+
+        write_manifest(dir.path(), &pyramid(256, &[512])).unwrap();
+        assert!(!fit_path(dir.path(), 3, 1600).exists());
+        assert!(fit_path(dir.path(), 3, 512).exists());
+        assert!(
+            dir.path().join("3.jpg").exists(),
+            "grid thumbnail untouched"
+        );
+    }
+}
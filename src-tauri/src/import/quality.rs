@@ -0,0 +1,156 @@
+use rusqlite::Connection;
+use std::path::Path;
+
+use crate::decisions::model::QualityFlagKind;
+use crate::decisions::suggestions;
+use crate::import::{sharpness, thumbnails};
+use crate::photos::model::PhotoFormat;
+use crate::photos::repository;
+
+/// Below this sharpness score a frame is likely blurred.
+const BLUR_THRESHOLD: f64 = 100.0;
+/// Below this mean luminance (0–255) a frame is likely underexposed.
+const DARK_MEAN_LUMA: f64 = 60.0;
+/// Pixels at or above this value count as clipped highlights.
+pub(crate) const HIGHLIGHT_CLIP: u8 = 250;
+/// Clipped highlights are flagged from this share of the frame, with full
+/// confidence at HIGHLIGHT_FULL_CONFIDENCE.
+const HIGHLIGHT_MIN_SHARE: f64 = 0.01;
+const HIGHLIGHT_FULL_CONFIDENCE: f64 = 0.1;
+
+/// What the thumbnail pass measures on a logical photo's preview.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImageAnalysis {
+    /// See `sharpness::sharpness_score`.
+    pub sharpness: f64,
+    /// Mean luminance, 0–255.
+    pub mean_luma: f64,
+    /// Share of pixels (0–1) at or above HIGHLIGHT_CLIP.
+    pub highlight_clipped: f64,
+}
+
+/// Measure a decoded preview.
+pub fn analyze(img: &image::DynamicImage) -> ImageAnalysis {
+    let gray = sharpness::working_luma(img);
+    let total = (gray.width() as u64 * gray.height() as u64).max(1) as f64;
+    let (mut sum, mut clipped) = (0u64, 0u64);
+    for px in gray.pixels() {
+        sum += u64::from(px[0]);
+        if px[0] >= HIGHLIGHT_CLIP {
+            clipped += 1;
+        }
+    }
+    ImageAnalysis {
+        sharpness: sharpness::luma_sharpness(&gray),
+        mean_luma: sum as f64 / total,
+        highlight_clipped: clipped as f64 / total,
+    }
+}
+
+/// Decode the preview the analyses run on: the JPEG at the coarsest DCT scale
+/// covering the working size, or the largest JPEG preview embedded in a RAW,
+/// downscaled to the working size. None if neither can be decoded.
+pub fn decode_preview(source_path: &Path, format: &PhotoFormat) -> Option<image::DynamicImage> {
+    let img = thumbnails::decode_source_preview(source_path, format, sharpness::WORKING_SIZE)?;
+    Some(sharpness::to_working_size(img))
+}
+
+/// Advisory flags for an analysis, each with a confidence in (0, 1].
+pub fn suggest_flags(analysis: &ImageAnalysis) -> Vec<(QualityFlagKind, f64)> {
+    let mut flags = Vec::new();
+    if analysis.sharpness < BLUR_THRESHOLD {
+        flags.push((
+            QualityFlagKind::LikelyBlurred,
+            1.0 - analysis.sharpness / BLUR_THRESHOLD,
+        ));
+    }
+    if analysis.mean_luma < DARK_MEAN_LUMA {
+        flags.push((
+            QualityFlagKind::Underexposed,
+            1.0 - analysis.mean_luma / DARK_MEAN_LUMA,
+        ));
+    }
+    if analysis.highlight_clipped >= HIGHLIGHT_MIN_SHARE {
+        flags.push((
+            QualityFlagKind::ClippedHighlights,
+            (analysis.highlight_clipped / HIGHLIGHT_FULL_CONFIDENCE).min(1.0),
+        ));
+    }
+    flags.retain(|(_, confidence)| *confidence > 0.0);
+    flags
+}
+
+/// Persist the thumbnail pass's analyses: sharpness scores and a fresh set of
+/// advisory flags per logical photo.
+pub fn store_image_analyses(
+    conn: &Connection,
+    analyses: &[(i64, ImageAnalysis)],
+) -> rusqlite::Result<()> {
+    let scores: Vec<(i64, f64)> = analyses
+        .iter()
+        .map(|(lp_id, a)| (*lp_id, a.sharpness))
+        .collect();
+    repository::set_sharpness_scores(conn, &scores)?;
+    for (lp_id, analysis) in analyses {
+        suggestions::replace_quality_flags(conn, *lp_id, &suggest_flags(analysis))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn analysis(sharpness: f64, mean_luma: f64, highlight_clipped: f64) -> ImageAnalysis {
+        ImageAnalysis {
+            sharpness,
+            mean_luma,
+            highlight_clipped,
+        }
+    }
+
+    fn kinds(a: &ImageAnalysis) -> Vec<QualityFlagKind> {
+        suggest_flags(a).into_iter().map(|(k, _)| k).collect()
+    }
+
+    #[test]
+    fn test_well_exposed_sharp_frame_gets_no_flags() {
+        assert!(suggest_flags(&analysis(800.0, 120.0, 0.001)).is_empty());
+    }
+
+    #[test]
+    fn test_each_defect_raises_its_flag() {
+        assert_eq!(
+            kinds(&analysis(10.0, 120.0, 0.0)),
+            vec![QualityFlagKind::LikelyBlurred]
+        );
+        assert_eq!(
+            kinds(&analysis(800.0, 15.0, 0.0)),
+            vec![QualityFlagKind::Underexposed]
+        );
+        assert_eq!(
+            kinds(&analysis(800.0, 200.0, 0.3)),
+            vec![QualityFlagKind::ClippedHighlights]
+        );
+    }
+
+    #[test]
+    fn test_confidence_grows_with_severity() {
+        let slightly = suggest_flags(&analysis(80.0, 120.0, 0.0))[0].1;
+        let badly = suggest_flags(&analysis(5.0, 120.0, 0.0))[0].1;
+        assert!(badly > slightly && badly <= 1.0);
+    }
+
+    #[test]
+    fn test_analyze_measures_exposure() {
+        let white = image::DynamicImage::ImageLuma8(image::GrayImage::from_pixel(
+            64,
+            64,
+            image::Luma([255]),
+        ));
+        let a = analyze(&white);
+        assert_eq!(a.mean_luma, 255.0);
+        assert_eq!(a.highlight_clipped, 1.0);
+        assert_eq!(a.sharpness, 0.0);
+    }
+}
@@ -0,0 +1,126 @@
+/// Images are scored at this long side so scores compare across cameras and
+/// preview sizes (a 6000px JPEG and a 1620px RAW preview land on the same scale).
+pub(crate) const WORKING_SIZE: u32 = 1024;
+
+/// The image is split into TILES×TILES tiles; the sharpest tile is the score.
+/// A bird in focus against a smooth sky is sharp even if most of the frame is not.
+const TILES: u32 = 8;
+
+/// Local sharpness: variance of the Laplacian per tile, reporting the maximum.
+/// Higher is sharper; 0 for a flat or fully defocused frame.
+pub fn sharpness_score(img: &image::DynamicImage) -> f64 {
+    luma_sharpness(&working_luma(img))
+}
+
+/// Grayscale copy of `img`, downscaled to at most WORKING_SIZE on the long side.
+pub(crate) fn working_luma(img: &image::DynamicImage) -> image::GrayImage {
+    if img.width().max(img.height()) > WORKING_SIZE {
+        to_working_size(img.clone()).to_luma8()
+    } else {
+        img.to_luma8()
+    }
+}
+
+/// Downscale `img` to at most WORKING_SIZE on the long side, so every analysis
+/// of one decoded preview shares a single resize.
+pub(crate) fn to_working_size(img: image::DynamicImage) -> image::DynamicImage {
+    if img.width().max(img.height()) > WORKING_SIZE {
+        img.resize(
+            WORKING_SIZE,
+            WORKING_SIZE,
+            image::imageops::FilterType::Triangle,
+        )
+    } else {
+        img
+    }
+}
+
+/// `sharpness_score` of an image already reduced by `working_luma`.
+pub(crate) fn luma_sharpness(gray: &image::GrayImage) -> f64 {
+    let (width, height) = gray.dimensions();
+    if width < 3 || height < 3 {
+        return 0.0;
+    }
+
+    let px = |x: u32, y: u32| f64::from(gray.get_pixel(x, y)[0]);
+    let tile_w = (width / TILES).max(3);
+    let tile_h = (height / TILES).max(3);
+    let mut best = 0.0f64;
+    for ty in (0..height).step_by(tile_h as usize) {
+        for tx in (0..width).step_by(tile_w as usize) {
+            // Laplacian needs one neighbour on each side: skip the image border
+            let x0 = tx.max(1);
+            let y0 = ty.max(1);
+            let x1 = (tx + tile_w).min(width - 1);
+            let y1 = (ty + tile_h).min(height - 1);
+            let (mut sum, mut sum_sq, mut n) = (0.0f64, 0.0f64, 0u32);
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    let lap =
+                        4.0 * px(x, y) - px(x - 1, y) - px(x + 1, y) - px(x, y - 1) - px(x, y + 1);
+                    sum += lap;
+                    sum_sq += lap * lap;
+                    n += 1;
+                }
+            }
+            if n > 0 {
+                let mean = sum / f64::from(n);
+                best = best.max(sum_sq / f64::from(n) - mean * mean);
+            }
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Black/white checkerboard of `cell`-pixel squares, Gaussian-blurred `blur` times.
+    fn checkerboard(cell: u32, blur: u32) -> image::DynamicImage {
+        let img = image::GrayImage::from_fn(512, 512, |x, y| {
+            image::Luma([if (x / cell + y / cell) % 2 == 0 {
+                0
+            } else {
+                255
+            }])
+        });
+        let mut img = image::DynamicImage::ImageLuma8(img);
+        for _ in 0..blur {
+            img = img.blur(2.0);
+        }
+        img
+    }
+
+    #[test]
+    fn test_sharp_frame_scores_higher_than_blurred_frame() {
+        let sharp = sharpness_score(&checkerboard(8, 0));
+        let soft = sharpness_score(&checkerboard(8, 2));
+        assert!(sharp > soft * 4.0, "sharp={} soft={}", sharp, soft);
+    }
+
+    #[test]
+    fn test_flat_frame_scores_zero() {
+        let flat = image::DynamicImage::ImageLuma8(image::GrayImage::from_pixel(
+            300,
+            200,
+            image::Luma([128]),
+        ));
+        assert_eq!(sharpness_score(&flat), 0.0);
+    }
+
+    #[test]
+    fn test_score_uses_sharpest_tile() {
+        // A small sharp subject on a flat background scores like a fully sharp frame.
+        let mut img = image::GrayImage::from_pixel(512, 512, image::Luma([128]));
+        let board = checkerboard(8, 0).to_luma8();
+        for y in 64..128 {
+            for x in 64..128 {
+                img.put_pixel(x, y, *board.get_pixel(x, y));
+            }
+        }
+        let subject = sharpness_score(&image::DynamicImage::ImageLuma8(img));
+        let full = sharpness_score(&checkerboard(8, 0));
+        assert!(subject > full * 0.5, "subject={} full={}", subject, full);
+    }
+}
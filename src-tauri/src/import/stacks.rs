@@ -1,43 +1,72 @@
 use crate::import::pairs::LogicalGroup;
+use crate::import::phash;
+use crate::photos::model::{BurstGapSuggestion, GapBucket, StackingStrategy};
+use crate::projects::model::BURST_GAP_RANGE;
+
+/// Parameters for grouping free (not manually grouped) logical photos into stacks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StackingRules {
+    pub strategy: StackingStrategy,
+    pub burst_gap_secs: u64,
+    /// Similarity: photos up to this far apart may share a stack when they look alike.
+    pub similarity_window_secs: u64,
+    /// Similarity: maximum dHash distance (out of 64 bits) for two photos to look alike.
+    pub similarity_threshold: u32,
+}
+
+impl StackingRules {
+    /// Pure time-gap stacking.
+    pub fn burst(burst_gap_secs: u64) -> Self {
+        Self {
+            strategy: StackingStrategy::Burst,
+            burst_gap_secs,
+            similarity_window_secs: burst_gap_secs,
+            similarity_threshold: 0,
+        }
+    }
+}
 
 /// Generic burst grouping algorithm: separate items into timed/untimed,
-/// sort timed by time, group consecutive items whose gap is ≤ burst_gap_secs
-/// into the same stack, and give each untimed item its own solo stack.
+/// sort timed by time (then camera sequence number, for burst frames sharing a
+/// timestamp), group consecutive items whose gap is ≤ burst_gap_secs into the
+/// same stack, and give each untimed item its own solo stack.
 ///
-/// Returns `(key, stack_index)` pairs where stack_index is 0-based.
+/// Returns `(key, stack_index)` pairs where stack_index is 0-based, timed items
+/// in shooting order.
 ///
 /// - `items`: the collection to group
 /// - `burst_gap_secs`: maximum gap (inclusive) for items to share a stack
 /// - `key_fn`: extracts the key (e.g. `LogicalGroup` or `i64`) from each item
 /// - `time_fn`: extracts the optional capture time from each item
+/// - `sequence_fn`: extracts the optional camera image/sequence number
 pub fn burst_group<T, K>(
     items: Vec<T>,
     burst_gap_secs: u64,
     key_fn: impl Fn(&T) -> K,
     time_fn: impl Fn(&T) -> Option<chrono::DateTime<chrono::Utc>>,
+    sequence_fn: impl Fn(&T) -> Option<u32>,
 ) -> Vec<(K, usize)> {
-    let mut with_time: Vec<(K, chrono::DateTime<chrono::Utc>)> = Vec::new();
+    let mut with_time: Vec<(K, chrono::DateTime<chrono::Utc>, Option<u32>)> = Vec::new();
     let mut without_time: Vec<K> = Vec::new();
 
     for item in &items {
         let key = key_fn(item);
         if let Some(t) = time_fn(item) {
-            with_time.push((key, t));
+            with_time.push((key, t, sequence_fn(item)));
         } else {
             without_time.push(key);
         }
     }
 
-    with_time.sort_by_key(|(_, t)| *t);
+    with_time.sort_by_key(|(_, t, seq)| (*t, seq.is_none(), *seq));
 
     let mut result: Vec<(K, usize)> = Vec::new();
     let mut stack_index: usize = 0;
     let mut last_time: Option<chrono::DateTime<chrono::Utc>> = None;
 
-    for (key, t) in with_time {
+    for (key, t, _) in with_time {
         if let Some(prev) = last_time {
-            let gap = (t - prev).num_seconds().unsigned_abs();
-            if gap > burst_gap_secs {
+            if !within_secs(prev, t, burst_gap_secs) {
                 stack_index += 1;
             }
         }
@@ -54,6 +83,170 @@ pub fn burst_group<T, K>(
     result
 }
 
+/// Time + similarity grouping: like `burst_group`, but when two consecutive items
+/// both have a perceptual hash they share a stack iff they are at most
+/// `similarity_window_secs` apart AND within `similarity_threshold` bits. A scene
+/// shot over minutes stays together; different subjects a second apart split.
+/// Items without a hash fall back to the burst-gap rule. Items are ordered as
+/// in `burst_group`.
+///
+/// Returns `(key, stack_index)` pairs where stack_index is 0-based.
+pub fn similarity_group<T, K>(
+    items: Vec<T>,
+    rules: &StackingRules,
+    key_fn: impl Fn(&T) -> K,
+    time_fn: impl Fn(&T) -> Option<chrono::DateTime<chrono::Utc>>,
+    sequence_fn: impl Fn(&T) -> Option<u32>,
+    hash_fn: impl Fn(&T) -> Option<u64>,
+) -> Vec<(K, usize)> {
+    type Timed<K> = (K, chrono::DateTime<chrono::Utc>, Option<u32>, Option<u64>);
+    let mut with_time: Vec<Timed<K>> = Vec::new();
+    let mut without_time: Vec<K> = Vec::new();
+
+    for item in &items {
+        let key = key_fn(item);
+        if let Some(t) = time_fn(item) {
+            with_time.push((key, t, sequence_fn(item), hash_fn(item)));
+        } else {
+            without_time.push(key);
+        }
+    }
+
+    with_time.sort_by_key(|(_, t, seq, _)| (*t, seq.is_none(), *seq));
+
+    let mut result: Vec<(K, usize)> = Vec::new();
+    let mut stack_index: usize = 0;
+    let mut last: Option<(chrono::DateTime<chrono::Utc>, Option<u64>)> = None;
+
+    for (key, t, _, hash) in with_time {
+        if let Some((prev_time, prev_hash)) = last {
+            let same_stack = match (prev_hash, hash) {
+                (Some(a), Some(b)) => {
+                    within_secs(prev_time, t, rules.similarity_window_secs)
+                        && phash::distance(a, b) <= rules.similarity_threshold
+                }
+                _ => within_secs(prev_time, t, rules.burst_gap_secs),
+            };
+            if !same_stack {
+                stack_index += 1;
+            }
+        }
+        last = Some((t, hash));
+        result.push((key, stack_index));
+    }
+
+    // Each untimed item gets a solo stack
+    for key in without_time {
+        stack_index += 1;
+        result.push((key, stack_index));
+    }
+
+    result
+}
+
+/// Whether two capture times are at most `max_secs` apart. Compared exactly:
+/// times carry milliseconds, and a 3.5s gap is over a 3s burst gap.
+fn within_secs(
+    a: chrono::DateTime<chrono::Utc>,
+    b: chrono::DateTime<chrono::Utc>,
+    max_secs: u64,
+) -> bool {
+    (b - a).abs() <= chrono::Duration::seconds(max_secs as i64)
+}
+
+/// Seconds between two capture times, rounded up: the shortest burst gap that
+/// joins them.
+fn gap_secs(a: chrono::DateTime<chrono::Utc>, b: chrono::DateTime<chrono::Utc>) -> u64 {
+    let gap = (b - a).abs();
+    let secs = gap.num_seconds();
+    let secs = if gap > chrono::Duration::seconds(secs) {
+        secs + 1
+    } else {
+        secs
+    };
+    secs.unsigned_abs()
+}
+
+/// Lower bounds of the gap histogram buckets, in seconds.
+const GAP_BUCKET_BOUNDS: [u64; 14] = [0, 1, 2, 3, 5, 10, 20, 30, 60, 120, 300, 600, 1800, 3600];
+
+/// The longest burst gap worth suggesting: shots further apart are separate scenes.
+const MAX_SUGGESTED_GAP_SECS: u64 = 60;
+
+/// How many times longer the gap after the break must be than the one before it.
+const MIN_BREAK_RATIO: f64 = 2.0;
+
+/// Fewer gaps than this say nothing about how a shoot was paced.
+const MIN_GAPS_FOR_SUGGESTION: usize = 4;
+
+/// Suggest a burst gap from the capture times of `(camera, time)` shots.
+///
+/// Gaps are measured between consecutive shots of the same camera, so two
+/// bodies shooting side by side do not look like one fast burst, and rounded
+/// up to whole seconds like the burst gap that would join them. The suggestion
+/// sits at the natural break in the distribution: the largest jump (on a log
+/// scale) between neighbouring distinct gaps, taking the gap just below it so
+/// `burst_group` joins everything up to the break and splits beyond it.
+pub fn suggest_burst_gap<C: Ord>(
+    shots: impl IntoIterator<Item = (C, chrono::DateTime<chrono::Utc>)>,
+) -> BurstGapSuggestion {
+    let mut by_camera: std::collections::BTreeMap<C, Vec<chrono::DateTime<chrono::Utc>>> =
+        std::collections::BTreeMap::new();
+    for (camera, t) in shots {
+        by_camera.entry(camera).or_default().push(t);
+    }
+
+    let mut gaps: Vec<u64> = Vec::new();
+    for times in by_camera.values_mut() {
+        times.sort();
+        gaps.extend(times.windows(2).map(|pair| gap_secs(pair[0], pair[1])));
+    }
+    gaps.sort_unstable();
+
+    let histogram = GAP_BUCKET_BOUNDS
+        .iter()
+        .enumerate()
+        .map(|(i, &min_secs)| {
+            let max_secs = GAP_BUCKET_BOUNDS.get(i + 1).copied();
+            GapBucket {
+                min_secs,
+                max_secs,
+                count: gaps
+                    .iter()
+                    .filter(|&&gap| gap >= min_secs && max_secs.is_none_or(|max| gap < max))
+                    .count(),
+            }
+        })
+        .collect();
+
+    let mut suggested_gap_secs = None;
+    if gaps.len() >= MIN_GAPS_FOR_SUGGESTION {
+        let mut distinct = gaps.clone();
+        distinct.dedup();
+        let mut best_ratio: Option<f64> = None;
+        for pair in distinct.windows(2) {
+            let (below, above) = (pair[0], pair[1]);
+            if below > MAX_SUGGESTED_GAP_SECS {
+                break;
+            }
+            let gap = below.max(*BURST_GAP_RANGE.start());
+            // +1 so a run of same-second shots still has a finite ratio
+            let ratio = (above + 1) as f64 / (below + 1) as f64;
+            if above > gap && ratio >= MIN_BREAK_RATIO && best_ratio.is_none_or(|r| ratio > r) {
+                best_ratio = Some(ratio);
+                suggested_gap_secs = Some(gap);
+            }
+        }
+    }
+
+    BurstGapSuggestion {
+        suggested_gap_secs,
+        gaps: gaps.len(),
+        cameras: by_camera.len(),
+        histogram,
+    }
+}
+
 /// Assign logical groups to stacks based on burst detection.
 ///
 /// Groups with a capture_time are sorted; consecutive groups whose gap is
@@ -65,7 +258,13 @@ pub fn assign_stacks_clean(
     groups: Vec<LogicalGroup>,
     burst_gap_secs: u64,
 ) -> Vec<(LogicalGroup, usize)> {
-    burst_group(groups, burst_gap_secs, |g| g.clone(), |g| g.capture_time())
+    burst_group(
+        groups,
+        burst_gap_secs,
+        |g| g.clone(),
+        |g| g.capture_time(),
+        |g| g.sequence_number(),
+    )
 }
 
 /// Primary public API for burst-based stack assignment.
@@ -217,4 +416,176 @@ mod tests {
             indices
         );
     }
+
+    #[test]
+    fn test_burst_group_orders_same_timestamp_frames_by_sequence_number() {
+        // A 20 fps burst: frames share whole seconds; the camera numbers them
+        let t = base_time();
+        let items = vec![
+            (1, t + Duration::milliseconds(50), Some(103)),
+            (2, t, Some(102)),
+            (3, t, Some(101)),
+            (4, t, None),
+            (5, t + Duration::milliseconds(50), Some(104)),
+        ];
+        let assigned = burst_group(items, 3, |i| i.0, |i| Some(i.1), |i| i.2);
+        let order: Vec<i32> = assigned.iter().map(|(k, _)| *k).collect();
+        assert_eq!(order, vec![3, 2, 4, 1, 5]);
+        assert!(assigned.iter().all(|(_, i)| *i == 0));
+    }
+
+    #[test]
+    fn test_burst_group_compares_subsecond_gaps_exactly() {
+        // A 3.5s gap used to truncate to 3s and join a 3s burst
+        let t = base_time();
+        let items = vec![
+            (1, t),
+            (2, t + Duration::milliseconds(3000)),
+            (3, t + Duration::milliseconds(6500)),
+        ];
+        let assigned = burst_group(items, 3, |i| i.0, |i| Some(i.1), |_| None);
+        assert_eq!(assigned, vec![(1, 0), (2, 0), (3, 1)]);
+    }
+
+    #[test]
+    fn test_similarity_group_compares_subsecond_gaps_exactly() {
+        let t = base_time();
+        let items = vec![
+            (1, t, None),
+            (2, t + Duration::milliseconds(3500), None),
+            (3, t + Duration::milliseconds(4000), Some(0u64)),
+            (4, t + Duration::milliseconds(124_500), Some(0u64)),
+        ];
+        let rules = StackingRules {
+            strategy: StackingStrategy::Similarity,
+            burst_gap_secs: 3,
+            similarity_window_secs: 120,
+            similarity_threshold: 10,
+        };
+        let assigned = similarity_group(items, &rules, |i| i.0, |i| Some(i.1), |_| None, |i| i.2);
+        assert_eq!(assigned, vec![(1, 0), (2, 1), (3, 1), (4, 2)]);
+    }
+
+    #[test]
+    fn test_suggest_burst_gap_rounds_subsecond_gaps_up() {
+        // Bursts 1.5s apart need a 2s burst gap to stay together
+        let t = base_time();
+        let offsets_ms = [0, 1500, 3000, 4500, 60_000, 61_500, 63_000];
+        let shots = offsets_ms
+            .iter()
+            .map(|&ms| ("A", t + Duration::milliseconds(ms)));
+
+        let suggestion = suggest_burst_gap(shots);
+
+        assert_eq!(suggestion.suggested_gap_secs, Some(2));
+        let suggested = suggestion.suggested_gap_secs.unwrap();
+        let assigned = burst_group(
+            offsets_ms.to_vec(),
+            suggested,
+            |&ms| ms,
+            |&ms| Some(t + Duration::milliseconds(ms)),
+            |_| None,
+        );
+        assert_eq!(assigned.iter().filter(|(_, i)| *i == 0).count(), 4);
+    }
+
+    #[test]
+    fn test_suggest_burst_gap_finds_break_between_bursts() {
+        // Three bursts of shots 1-2s apart, 40s and 90s between bursts
+        let t = base_time();
+        let offsets = [0, 1, 3, 4, 44, 45, 47, 137, 138, 139];
+        let shots = offsets.iter().map(|&s| ("A", t + Duration::seconds(s)));
+
+        let suggestion = suggest_burst_gap(shots);
+
+        assert_eq!(suggestion.suggested_gap_secs, Some(2));
+        assert_eq!(suggestion.gaps, 9);
+        assert_eq!(suggestion.cameras, 1);
+        let count_from = |min: u64| {
+            suggestion
+                .histogram
+                .iter()
+                .find(|b| b.min_secs == min)
+                .unwrap()
+                .count
+        };
+        assert_eq!(count_from(1), 5, "1s gaps");
+        assert_eq!(count_from(2), 2, "2s gaps");
+        assert_eq!(count_from(30), 1, "the 40s gap");
+        assert_eq!(count_from(60), 1, "the 90s gap");
+        assert_eq!(suggestion.histogram.last().unwrap().max_secs, None);
+    }
+
+    #[test]
+    fn test_suggest_burst_gap_measures_each_camera_separately() {
+        // Two bodies shooting 20s apart each, interleaved 1s apart: merged they
+        // would look like 1s bursts, per camera every gap is 20s.
+        let t = base_time();
+        let shots = (0..6).flat_map(|i| {
+            [
+                ("A", t + Duration::seconds(i * 20)),
+                ("B", t + Duration::seconds(i * 20 + 1)),
+            ]
+        });
+
+        let suggestion = suggest_burst_gap(shots);
+
+        assert_eq!(suggestion.cameras, 2);
+        assert_eq!(suggestion.gaps, 10);
+        assert_eq!(
+            suggestion.suggested_gap_secs, None,
+            "no break: every gap is 20s"
+        );
+    }
+
+    #[test]
+    fn test_suggest_burst_gap_needs_enough_shots() {
+        let t = base_time();
+        let shots = [0, 1, 30].map(|s| ("A", t + Duration::seconds(s)));
+
+        let suggestion = suggest_burst_gap(shots);
+
+        assert_eq!(suggestion.gaps, 2);
+        assert_eq!(suggestion.suggested_gap_secs, None);
+    }
+
+    #[test]
+    fn test_similarity_group_joins_slow_scene_and_splits_quick_subject_change() {
+        // Scene A shot over 60s (gaps of 30s > burst gap), then subject B one second later.
+        let t = base_time();
+        let scene_a = 0x0f0f_0f0f_0f0f_0f0fu64;
+        let subject_b = !scene_a;
+        let items = vec![
+            (1, t, Some(scene_a)),
+            (2, t + Duration::seconds(30), Some(scene_a ^ 0b11)),
+            (3, t + Duration::seconds(60), Some(scene_a)),
+            (4, t + Duration::seconds(61), Some(subject_b)),
+        ];
+        let rules = StackingRules {
+            strategy: StackingStrategy::Similarity,
+            burst_gap_secs: 3,
+            similarity_window_secs: 120,
+            similarity_threshold: 10,
+        };
+        let assigned = similarity_group(items, &rules, |i| i.0, |i| Some(i.1), |_| None, |i| i.2);
+        assert_eq!(assigned, vec![(1, 0), (2, 0), (3, 0), (4, 1)]);
+    }
+
+    #[test]
+    fn test_similarity_group_falls_back_to_burst_gap_without_hashes() {
+        let t = base_time();
+        let items = vec![
+            (1, t, Some(0u64)),
+            (2, t + Duration::seconds(2), None),
+            (3, t + Duration::seconds(30), Some(0u64)),
+        ];
+        let rules = StackingRules {
+            strategy: StackingStrategy::Similarity,
+            burst_gap_secs: 3,
+            similarity_window_secs: 120,
+            similarity_threshold: 10,
+        };
+        let assigned = similarity_group(items, &rules, |i| i.0, |i| Some(i.1), |_| None, |i| i.2);
+        assert_eq!(assigned, vec![(1, 0), (2, 0), (3, 1)]);
+    }
 }
@@ -19,6 +19,10 @@ pub fn make_group(capture_time: Option<chrono::DateTime<chrono::Utc>>) -> Logica
         exposure_comp: None,
         base_name: "photo".to_string(),
         dir: PathBuf::from("/tmp"),
+        fingerprint: None,
+        sequence_number: None,
+        camera_serial: None,
+        clock_offset_secs: 0,
     };
     LogicalGroup {
         jpeg: Some(sf),
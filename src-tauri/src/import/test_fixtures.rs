@@ -1563,6 +1563,8 @@ mod tests {
             shutter_speed: Some("1/320".to_string()),
             exposure_comp: Some(0.0),
             lens: None,
+            sequence_number: None,
+            camera_serial: None,
         };
         let expected = Expected {
             make: Some("Canon".to_string()),
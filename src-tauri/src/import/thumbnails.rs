@@ -1,6 +1,9 @@
 use crate::photos::model::PhotoFormat;
 use std::path::{Path, PathBuf};
 
+/// Edge of the square grid thumbnail unless a project sets `thumbnail_size`.
+pub const DEFAULT_THUMBNAIL_SIZE: u32 = 256;
+
 /// Generate a 256×256 JPEG thumbnail and save to cache dir.
 /// Returns path to saved thumbnail, or None on any failure (non-fatal).
 pub fn generate_thumbnail(
@@ -9,6 +12,26 @@ pub fn generate_thumbnail(
     logical_photo_id: i64,
     cache_dir: &Path,
     orientation: Option<u16>,
+) -> Option<PathBuf> {
+    generate_thumbnail_sized(
+        source_path,
+        format,
+        logical_photo_id,
+        cache_dir,
+        orientation,
+        DEFAULT_THUMBNAIL_SIZE,
+    )
+}
+
+/// Generate a `size`×`size` JPEG grid thumbnail as `{id}.jpg` in the cache dir.
+/// Returns path to saved thumbnail, or None on any failure (non-fatal).
+pub fn generate_thumbnail_sized(
+    source_path: &Path,
+    format: &PhotoFormat,
+    logical_photo_id: i64,
+    cache_dir: &Path,
+    orientation: Option<u16>,
+    size: u32,
 ) -> Option<PathBuf> {
     match std::panic::catch_unwind(|| {
         generate_thumbnail_inner(
@@ -17,6 +40,7 @@ pub fn generate_thumbnail(
             logical_photo_id,
             cache_dir,
             orientation,
+            size,
         )
     }) {
         Ok(result) => result,
@@ -37,12 +61,13 @@ fn generate_thumbnail_inner(
     logical_photo_id: i64,
     cache_dir: &Path,
     orientation: Option<u16>,
+    size: u32,
 ) -> Option<PathBuf> {
     let out_path = cache_dir.join(format!("{}.jpg", logical_photo_id));
 
     match format {
-        PhotoFormat::Jpeg => generate_jpeg_thumbnail(source_path, &out_path, orientation),
-        PhotoFormat::Raw => generate_raw_thumbnail(source_path, &out_path, orientation),
+        PhotoFormat::Jpeg => generate_jpeg_thumbnail(source_path, &out_path, orientation, size),
+        PhotoFormat::Raw => generate_raw_thumbnail(source_path, &out_path, orientation, size),
     }
 }
 
@@ -112,7 +137,7 @@ fn apply_orientation(img: image::DynamicImage, orientation: Option<u16>) -> imag
     }
 }
 
-/// Resize a decoded image to exactly 256×256 (crop to fill), apply orientation, save.
+/// Resize a decoded image to exactly `size`×`size` (crop to fill), apply orientation, save.
 ///
 /// Uses `resize_to_fill` with Lanczos3 so the output always fills the container
 /// without letterboxing. The grid's `object-cover` CSS then has a same-resolution
@@ -121,8 +146,9 @@ pub fn generate_thumbnail_from_image(
     img: image::DynamicImage,
     out_path: &Path,
     orientation: Option<u16>,
+    size: u32,
 ) -> Option<PathBuf> {
-    let resized = img.resize_to_fill(256, 256, image::imageops::FilterType::Lanczos3);
+    let resized = img.resize_to_fill(size, size, image::imageops::FilterType::Lanczos3);
     let thumbnail = apply_orientation(resized, orientation);
 
     ensure_parent_dir(out_path)?;
@@ -139,12 +165,31 @@ pub fn generate_thumbnail_from_image(
     }
 }
 
-/// Decode `jpeg_bytes`, check minimum size, resize to 256×256, apply orientation, save.
+/// Write the grid thumbnail `{id}.jpg` from a source already decoded by
+/// `decode_source_preview`, so later passes over the same photo can share the
+/// decode. A RAW also gets its `{id}_preview.jpg`, as from `generate_thumbnail`.
+pub(crate) fn generate_thumbnail_from_source_preview(
+    img: &image::DynamicImage,
+    format: &PhotoFormat,
+    logical_photo_id: i64,
+    cache_dir: &Path,
+    orientation: Option<u16>,
+    size: u32,
+) -> Option<PathBuf> {
+    let out_path = cache_dir.join(format!("{}.jpg", logical_photo_id));
+    if *format == PhotoFormat::Raw {
+        save_raw_preview(img, &out_path, orientation);
+    }
+    generate_thumbnail_from_image(img.clone(), &out_path, orientation, size)
+}
+
+/// Decode `jpeg_bytes`, resize to `size`×`size`, apply orientation, save.
 #[cfg_attr(not(test), allow(dead_code))]
 fn generate_thumbnail_from_bytes(
     jpeg_bytes: &[u8],
     out_path: &Path,
     orientation: Option<u16>,
+    size: u32,
 ) -> Option<PathBuf> {
     let img = match image::load_from_memory(jpeg_bytes) {
         Ok(i) => i,
@@ -157,7 +202,7 @@ fn generate_thumbnail_from_bytes(
             return None;
         }
     };
-    generate_thumbnail_from_image(img, out_path, orientation)
+    generate_thumbnail_from_image(img, out_path, orientation, size)
 }
 
 /// Return true if two images share the same aspect ratio (within 5% tolerance).
@@ -182,20 +227,20 @@ fn generate_jpeg_thumbnail(
     source_path: &Path,
     out_path: &Path,
     orientation: Option<u16>,
+    size: u32,
 ) -> Option<PathBuf> {
     // Fast path: EXIF IFD1 embedded thumbnail.
-    // Reject if short side < 200px (blurry upscales) or if the aspect ratio
-    // differs from the main image (indicates a crop, not a downscale).
+    // Reject if short side < 200px at the default size, proportionally more for
+    // larger grids (blurry upscales), or if the aspect ratio differs from the
+    // main image (indicates a crop, not a downscale).
     if let Some(bytes) = extract_exif_embedded_thumbnail(source_path) {
         if let Ok(embedded_img) = image::load_from_memory(&bytes) {
             let short_side = embedded_img.width().min(embedded_img.height());
-            if short_side >= 200 {
+            if short_side >= 200 * size / DEFAULT_THUMBNAIL_SIZE {
                 // Read main image dimensions via turbojpeg header (cheap, no decode)
-                let main_dims = std::fs::read(source_path).ok().and_then(|buf| {
-                    let mut d = turbojpeg::Decompressor::new().ok()?;
-                    let h = d.read_header(&buf).ok()?;
-                    Some((h.width as u32, h.height as u32))
-                });
+                let main_dims = std::fs::read(source_path)
+                    .ok()
+                    .and_then(|buf| jpeg_dimensions(&buf));
 
                 let ratio_ok = main_dims
                     .map(|(mw, mh)| {
@@ -205,7 +250,7 @@ fn generate_jpeg_thumbnail(
 
                 if ratio_ok {
                     if let Some(result) =
-                        generate_thumbnail_from_image(embedded_img, out_path, orientation)
+                        generate_thumbnail_from_image(embedded_img, out_path, orientation, size)
                     {
                         tracing::debug!(
                             "thumbnail: embedded EXIF path (short={}px) for {:?}",
@@ -236,7 +281,7 @@ fn generate_jpeg_thumbnail(
     }
 
     // Primary fallback: turbojpeg DCT 1/8 downscale (~50x faster than full decode)
-    if let Some(result) = generate_jpeg_thumbnail_turbo(source_path, out_path, orientation) {
+    if let Some(result) = generate_jpeg_thumbnail_turbo(source_path, out_path, orientation, size) {
         return Some(result);
     }
 
@@ -249,16 +294,17 @@ fn generate_jpeg_thumbnail(
             return None;
         }
     };
-    generate_thumbnail_from_image(img, out_path, orientation)
+    generate_thumbnail_from_image(img, out_path, orientation, size)
 }
 
 fn generate_raw_thumbnail(
     source_path: &Path,
     out_path: &Path,
     orientation: Option<u16>,
+    size: u32,
 ) -> Option<PathBuf> {
     // rsraw extracts a large embedded JPEG preview (typically 1620×1080).
-    // Resize to 256×256 to keep cache files small and grid loads fast.
+    // Resize to the grid size to keep cache files small and grid loads fast.
     let jpeg_bytes = extract_raw_embedded_jpeg(source_path)?;
     let img = image::load_from_memory(&jpeg_bytes).ok()?;
 
@@ -266,7 +312,7 @@ fn generate_raw_thumbnail(
     // The preview uses the embedded JPEG at its native resolution with orientation applied.
     save_raw_preview(&img, out_path, orientation);
 
-    generate_thumbnail_from_image(img, out_path, orientation)
+    generate_thumbnail_from_image(img, out_path, orientation, size)
 }
 
 /// Save the full-size RAW embedded preview as `{id}_preview.jpg` alongside the thumbnail.
@@ -292,6 +338,24 @@ fn save_raw_preview(
     }
 }
 
+/// The `{id}_preview.jpg` of a RAW, extracting it if an earlier thumbnail pass
+/// did not (legacy imports, JPEG-represented pairs). None if the RAW has no preview.
+pub(crate) fn ensure_raw_preview(
+    source_path: &Path,
+    logical_photo_id: i64,
+    cache_dir: &Path,
+    orientation: Option<u16>,
+) -> Option<PathBuf> {
+    let thumb_path = cache_dir.join(format!("{}.jpg", logical_photo_id));
+    let preview_path = raw_preview_path(&thumb_path);
+    if !preview_path.exists() {
+        let jpeg_bytes = extract_raw_embedded_jpeg(source_path)?;
+        let img = image::load_from_memory(&jpeg_bytes).ok()?;
+        save_raw_preview(&img, &thumb_path, orientation);
+    }
+    preview_path.exists().then_some(preview_path)
+}
+
 /// Derive the preview path from a thumbnail path: `{id}.jpg` → `{id}_preview.jpg`
 pub fn raw_preview_path(thumbnail_path: &Path) -> PathBuf {
     let stem = thumbnail_path
@@ -329,13 +393,15 @@ pub fn thumbnail_strategy(photo_count: usize) -> ThumbnailStrategy {
     }
 }
 
-/// Generate JPEG thumbnail using turbojpeg DCT 1/8 downscaling.
-/// Decodes at 1/8 resolution (e.g. 6000x4000 -> 750x500), then resizes to 256x256.
-/// ~50x faster than full decode and uses ~63x less memory.
+/// Generate JPEG thumbnail using turbojpeg DCT downscaling.
+/// Decodes at the coarsest scale that still covers `size` (1/8 for a 256px grid:
+/// 6000x4000 -> 750x500), then resizes to `size`×`size`.
+/// At 1/8, ~50x faster than full decode and uses ~63x less memory.
 pub fn generate_jpeg_thumbnail_turbo(
     source_path: &Path,
     out_path: &Path,
     orientation: Option<u16>,
+    size: u32,
 ) -> Option<PathBuf> {
     let jpeg_bytes = match std::fs::read(source_path) {
         Ok(b) => b,
@@ -345,6 +411,67 @@ pub fn generate_jpeg_thumbnail_turbo(
         }
     };
 
+    let scaling = jpeg_dimensions(&jpeg_bytes)
+        .map(|(w, h)| scaling_for(w.min(h), size))
+        .unwrap_or(turbojpeg::ScalingFactor::ONE_EIGHTH);
+    let dyn_img = match decode_jpeg_turbo(&jpeg_bytes, scaling) {
+        Some(img) => img,
+        None => {
+            tracing::debug!("turbo: decode failed for {:?}", source_path);
+            return None;
+        }
+    };
+    generate_thumbnail_from_image(dyn_img, out_path, orientation, size)
+}
+
+/// Width and height from a JPEG header (cheap, no decode).
+pub(crate) fn jpeg_dimensions(jpeg_bytes: &[u8]) -> Option<(u32, u32)> {
+    let mut d = turbojpeg::Decompressor::new().ok()?;
+    let h = d.read_header(jpeg_bytes).ok()?;
+    Some((h.width as u32, h.height as u32))
+}
+
+/// Coarsest DCT scaling factor that keeps `dimension` at or above `min`.
+pub(crate) fn scaling_for(dimension: u32, min: u32) -> turbojpeg::ScalingFactor {
+    [
+        turbojpeg::ScalingFactor::ONE_EIGHTH,
+        turbojpeg::ScalingFactor::ONE_QUARTER,
+        turbojpeg::ScalingFactor::ONE_HALF,
+    ]
+    .into_iter()
+    .find(|f| f.scale(dimension as usize) >= min as usize)
+    .unwrap_or(turbojpeg::ScalingFactor::ONE)
+}
+
+/// A source decoded for further downscaling, covering `min_long_edge` where the
+/// source allows: a JPEG via turbojpeg at the coarsest sufficient scale, a RAW via
+/// its largest embedded JPEG preview. Orientation is not applied.
+pub(crate) fn decode_source_preview(
+    source_path: &Path,
+    format: &PhotoFormat,
+    min_long_edge: u32,
+) -> Option<image::DynamicImage> {
+    match format {
+        PhotoFormat::Jpeg => {
+            let bytes = std::fs::read(source_path).ok()?;
+            let scaling = jpeg_dimensions(&bytes)
+                .map(|(w, h)| scaling_for(w.max(h), min_long_edge))
+                .unwrap_or(turbojpeg::ScalingFactor::ONE);
+            decode_jpeg_turbo(&bytes, scaling).or_else(|| image::load_from_memory(&bytes).ok())
+        }
+        PhotoFormat::Raw => {
+            let bytes = extract_raw_embedded_jpeg(source_path)?;
+            image::load_from_memory(&bytes).ok()
+        }
+    }
+}
+
+/// Decode a JPEG with turbojpeg at a DCT scaling factor (1/8 decodes ~50x faster
+/// than full size). Shared by the thumbnail and sharpness passes.
+pub(crate) fn decode_jpeg_turbo(
+    jpeg_bytes: &[u8],
+    scaling: turbojpeg::ScalingFactor,
+) -> Option<image::DynamicImage> {
     let mut decompressor = match turbojpeg::Decompressor::new() {
         Ok(d) => d,
         Err(e) => {
@@ -353,15 +480,14 @@ pub fn generate_jpeg_thumbnail_turbo(
         }
     };
 
-    let header = match decompressor.read_header(&jpeg_bytes) {
+    let header = match decompressor.read_header(jpeg_bytes) {
         Ok(h) => h,
         Err(e) => {
-            tracing::debug!("turbo: read header failed for {:?}: {}", source_path, e);
+            tracing::debug!("turbo: read header failed: {}", e);
             return None;
         }
     };
 
-    let scaling = turbojpeg::ScalingFactor::ONE_EIGHTH;
     if let Err(e) = decompressor.set_scaling_factor(scaling) {
         tracing::debug!("turbo: set scaling failed: {}", e);
         return None;
@@ -377,8 +503,8 @@ pub fn generate_jpeg_thumbnail_turbo(
         format: turbojpeg::PixelFormat::RGB,
     };
 
-    if let Err(e) = decompressor.decompress(&jpeg_bytes, turbo_image.as_deref_mut()) {
-        tracing::debug!("turbo: decompress failed for {:?}: {}", source_path, e);
+    if let Err(e) = decompressor.decompress(jpeg_bytes, turbo_image.as_deref_mut()) {
+        tracing::debug!("turbo: decompress failed: {}", e);
         return None;
     }
 
@@ -389,16 +515,16 @@ pub fn generate_jpeg_thumbnail_turbo(
     ) {
         Some(img) => img,
         None => {
-            tracing::debug!("turbo: RgbImage::from_raw failed for {:?}", source_path);
+            tracing::debug!("turbo: RgbImage::from_raw failed");
             return None;
         }
     };
 
-    let dyn_img = image::DynamicImage::ImageRgb8(rgb_img);
-    generate_thumbnail_from_image(dyn_img, out_path, orientation)
+    Some(image::DynamicImage::ImageRgb8(rgb_img))
 }
 
-fn extract_raw_embedded_jpeg(source_path: &Path) -> Option<Vec<u8>> {
+/// Largest JPEG preview embedded in a RAW file.
+pub(crate) fn extract_raw_embedded_jpeg(source_path: &Path) -> Option<Vec<u8>> {
     use rsraw::RawImage;
     let buf = std::fs::read(source_path).ok()?;
     let mut raw = RawImage::open(&buf).ok()?;
@@ -687,7 +813,7 @@ mod tests {
         let out_dir = TempDir::new().unwrap();
         let out_path = out_dir.path().join("raw_test.jpg");
 
-        let result = generate_thumbnail_from_image(img, &out_path, None);
+        let result = generate_thumbnail_from_image(img, &out_path, None, DEFAULT_THUMBNAIL_SIZE);
 
         assert!(result.is_some());
         let output = image::open(&out_path).unwrap();
@@ -904,6 +1030,19 @@ mod tests {
 
     // ── Sprint 7 Part B: Thumbnail DCT optimization tests (RED) ─────────────
 
+    #[test]
+    fn test_scaling_for_picks_coarsest_factor_covering_size() {
+        use turbojpeg::ScalingFactor;
+        assert_eq!(scaling_for(4000, 256), ScalingFactor::ONE_EIGHTH);
+        assert_eq!(scaling_for(4000, 512), ScalingFactor::ONE_QUARTER);
+        assert_eq!(scaling_for(4000, 1600), ScalingFactor::ONE_HALF);
+        assert_eq!(
+            scaling_for(1000, 1600),
+            ScalingFactor::ONE,
+            "never below size"
+        );
+    }
+
     #[test]
     fn test_turbo_thumbnail_produces_256x256_jpeg() {
         // RED: generate_jpeg_thumbnail_turbo doesn't exist yet
@@ -911,7 +1050,12 @@ mod tests {
         let cache_dir = TempDir::new().unwrap();
         let out_path = cache_dir.path().join("turbo_test.jpg");
 
-        let result = super::generate_jpeg_thumbnail_turbo(src.path(), &out_path, None);
+        let result = super::generate_jpeg_thumbnail_turbo(
+            src.path(),
+            &out_path,
+            None,
+            DEFAULT_THUMBNAIL_SIZE,
+        );
 
         assert!(result.is_some(), "turbo path must produce a thumbnail");
         let img = image::open(&out_path).expect("output must be readable");
@@ -925,7 +1069,7 @@ mod tests {
         let cache_dir = TempDir::new().unwrap();
         let out_path = cache_dir.path().join("turbo_valid.jpg");
 
-        super::generate_jpeg_thumbnail_turbo(src.path(), &out_path, None);
+        super::generate_jpeg_thumbnail_turbo(src.path(), &out_path, None, DEFAULT_THUMBNAIL_SIZE);
 
         let bytes = std::fs::read(&out_path).expect("thumbnail file must exist");
         assert_eq!(
@@ -941,7 +1085,12 @@ mod tests {
         let cache_dir = TempDir::new().unwrap();
         let out_path = cache_dir.path().join("turbo_orient.jpg");
 
-        let result = super::generate_jpeg_thumbnail_turbo(src.path(), &out_path, Some(6));
+        let result = super::generate_jpeg_thumbnail_turbo(
+            src.path(),
+            &out_path,
+            Some(6),
+            DEFAULT_THUMBNAIL_SIZE,
+        );
 
         assert!(result.is_some());
         let img = image::open(&out_path).unwrap();
@@ -954,7 +1103,8 @@ mod tests {
         let out_path = cache_dir.path().join("turbo_missing.jpg");
         let missing = Path::new("/tmp/definitely_does_not_exist_gemkeep_turbo.jpg");
 
-        let result = super::generate_jpeg_thumbnail_turbo(missing, &out_path, None);
+        let result =
+            super::generate_jpeg_thumbnail_turbo(missing, &out_path, None, DEFAULT_THUMBNAIL_SIZE);
 
         assert!(result.is_none(), "must return None for missing source");
     }
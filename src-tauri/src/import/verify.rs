@@ -0,0 +1,464 @@
+use crate::import::fingerprint::{compute_fingerprint, Fingerprint};
+use crate::import::scanner;
+use crate::import::volumes::{self, VolumeInfo, VolumeLocation};
+use crate::photos::model::{
+    PhotoFileRow, ReconnectedFolder, RelinkResult, SourceFileIssue, SourceVerification,
+};
+use crate::photos::repository;
+use rusqlite::Connection;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+fn issue(row: &PhotoFileRow, found_at: Option<&Path>) -> SourceFileIssue {
+    SourceFileIssue {
+        photo_id: row.photo_id,
+        logical_photo_id: row.logical_photo_id,
+        path: row.path.clone(),
+        found_at: found_at.map(|p| p.to_string_lossy().to_string()),
+    }
+}
+
+/// Check every photo file of the project against the disk.
+///
+/// Files still at their path are compared with their stored fingerprint (photos
+/// imported before fingerprinting get one stored now). Missing files are looked
+/// up by fingerprint among the untracked files under `search_roots`; a match is
+/// reported as moved, anything else as missing. Files under offline source
+/// folders are counted but not checked. Nothing but fingerprints is written.
+pub fn verify_sources(
+    conn: &Connection,
+    project_id: i64,
+    search_roots: &[PathBuf],
+) -> rusqlite::Result<SourceVerification> {
+    let rows = repository::list_photo_files_for_project(conn, project_id)?;
+    let offline_roots: Vec<PathBuf> = repository::list_source_folders(conn, project_id)?
+        .into_iter()
+        .filter(|f| !f.online)
+        .map(|f| PathBuf::from(f.path))
+        .collect();
+    let mut result = SourceVerification {
+        checked: rows.len(),
+        ..Default::default()
+    };
+
+    let mut missing: Vec<&PhotoFileRow> = Vec::new();
+    for row in &rows {
+        let path = Path::new(&row.path);
+        if offline_roots.iter().any(|r| path.starts_with(r)) {
+            result.offline += 1;
+            continue;
+        }
+        if !path.exists() {
+            missing.push(row);
+            continue;
+        }
+        let current = match compute_fingerprint(path) {
+            Ok(f) => f,
+            Err(e) => {
+                tracing::warn!("verify: cannot read {}: {}", row.path, e);
+                missing.push(row);
+                continue;
+            }
+        };
+        match (row.file_size, &row.fingerprint) {
+            (Some(size), Some(hash)) if size == current.size && *hash == current.hash => {
+                result.ok += 1
+            }
+            (_, Some(_)) => result.changed.push(issue(row, None)),
+            (_, None) => {
+                repository::set_photo_fingerprint(conn, row.photo_id, current.size, &current.hash)?;
+                result.fingerprints_added += 1;
+                result.ok += 1;
+            }
+        }
+    }
+
+    if missing.is_empty() {
+        return Ok(result);
+    }
+
+    // Missing files we can recognise again, by content identity
+    let mut wanted: HashMap<Fingerprint, Vec<&PhotoFileRow>> = HashMap::new();
+    for row in &missing {
+        if let (Some(size), Some(hash)) = (row.file_size, &row.fingerprint) {
+            let key = Fingerprint {
+                size,
+                hash: hash.clone(),
+            };
+            wanted.entry(key).or_default().push(row);
+        }
+    }
+    let wanted_sizes: HashSet<u64> = wanted.keys().map(|f| f.size).collect();
+    let tracked: HashSet<&str> = rows.iter().map(|r| r.path.as_str()).collect();
+
+    let mut found: HashMap<i64, PathBuf> = HashMap::new();
+    for root in search_roots {
+        let (paths, _errors) = scanner::scan_directory(root);
+        for sp in paths {
+            if tracked.contains(sp.path.to_string_lossy().as_ref()) {
+                continue;
+            }
+            // Only hash candidates whose size matches a missing file
+            let size = std::fs::metadata(&sp.path).map(|m| m.len()).unwrap_or(0);
+            if !wanted_sizes.contains(&size) {
+                continue;
+            }
+            let Ok(fp) = compute_fingerprint(&sp.path) else {
+                continue;
+            };
+            if let Some(candidates) = wanted.get_mut(&fp) {
+                if let Some(row) = candidates.pop() {
+                    found.insert(row.photo_id, sp.path);
+                }
+            }
+        }
+    }
+
+    for row in missing {
+        match found.get(&row.photo_id) {
+            Some(new_path) => result.moved.push(issue(row, Some(new_path))),
+            None => result.missing.push(issue(row, None)),
+        }
+    }
+    Ok(result)
+}
+
+/// Relink moved files: verify, then point each moved photo at its new path in one
+/// transaction. Search roots holding relinked files that are not yet source
+/// folders are attached, so later re-indexes see them as known files.
+pub fn relink_moved_files(
+    conn: &Connection,
+    project_id: i64,
+    search_roots: &[PathBuf],
+) -> anyhow::Result<RelinkResult> {
+    let verification = verify_sources(conn, project_id, search_roots)?;
+
+    conn.execute("BEGIN", [])?;
+    let result = (|| -> anyhow::Result<RelinkResult> {
+        let mut result = RelinkResult {
+            still_missing: verification.missing.len(),
+            ..Default::default()
+        };
+        for moved in &verification.moved {
+            let Some(new_path) = &moved.found_at else {
+                continue;
+            };
+            repository::update_photo_path(conn, moved.photo_id, new_path)?;
+            result.relinked += 1;
+
+            let root = search_roots
+                .iter()
+                .find(|r| Path::new(new_path).starts_with(r))
+                .map(|r| r.to_string_lossy().to_string());
+            if let Some(root) = root {
+                if !result.folders_added.contains(&root) && !is_covered(conn, project_id, &root)? {
+                    let folder_id = repository::add_source_folder(conn, project_id, &root)?;
+                    record_volume(conn, folder_id, Path::new(&root))?;
+                    result.folders_added.push(root);
+                }
+            }
+        }
+        Ok(result)
+    })();
+
+    match result {
+        Ok(r) => {
+            conn.execute("COMMIT", [])?;
+            Ok(r)
+        }
+        Err(e) => {
+            let _ = conn.execute("ROLLBACK", []);
+            Err(e)
+        }
+    }
+}
+
+/// True if `folder` is an attached source folder or lies inside one.
+fn is_covered(conn: &Connection, project_id: i64, folder: &str) -> rusqlite::Result<bool> {
+    Ok(repository::list_source_folders(conn, project_id)?
+        .iter()
+        .any(|f| Path::new(folder).starts_with(&f.path)))
+}
+
+/// Remember which removable volume `path` lives on, so the folder can be found
+/// again when the volume comes back under another mount point.
+pub fn record_volume(conn: &Connection, folder_id: i64, path: &Path) -> rusqlite::Result<()> {
+    if let Some(loc) = volumes::locate(path) {
+        repository::set_source_folder_volume(
+            conn,
+            folder_id,
+            loc.uuid.as_deref(),
+            loc.label.as_deref(),
+            &loc.relative_path.to_string_lossy(),
+        )?;
+    }
+    Ok(())
+}
+
+/// Find offline source folders on the currently mounted volumes and move them
+/// (with their photos' paths) to where their volume is mounted now. Online
+/// folders without volume info get it recorded.
+pub fn reconnect_source_folders(
+    conn: &Connection,
+    project_id: i64,
+) -> rusqlite::Result<Vec<ReconnectedFolder>> {
+    reconnect_source_folders_in(conn, project_id, &volumes::list_mounted_volumes())
+}
+
+pub fn reconnect_source_folders_in(
+    conn: &Connection,
+    project_id: i64,
+    mounted: &[VolumeInfo],
+) -> rusqlite::Result<Vec<ReconnectedFolder>> {
+    let mut reconnected = Vec::new();
+    for folder in repository::list_source_folders(conn, project_id)? {
+        if folder.online {
+            if folder.relative_path.is_none() {
+                if let Some(loc) = volumes::locate_in(Path::new(&folder.path), mounted) {
+                    repository::set_source_folder_volume(
+                        conn,
+                        folder.id,
+                        loc.uuid.as_deref(),
+                        loc.label.as_deref(),
+                        &loc.relative_path.to_string_lossy(),
+                    )?;
+                }
+            }
+            continue;
+        }
+
+        let Some(relative_path) = folder.relative_path else {
+            continue;
+        };
+        let location = VolumeLocation {
+            uuid: folder.volume_uuid,
+            label: folder.volume_label,
+            relative_path: PathBuf::from(relative_path),
+        };
+        let Some(new_path) = volumes::resolve_in(&location, mounted).filter(|p| p.is_dir()) else {
+            continue;
+        };
+        let new_path = new_path.to_string_lossy().to_string();
+        if new_path == folder.path {
+            continue;
+        }
+
+        conn.execute("BEGIN", [])?;
+        let moved = repository::relocate_source_folder(
+            conn,
+            project_id,
+            folder.id,
+            &folder.path,
+            &new_path,
+        );
+        match moved {
+            Ok(photos_updated) => {
+                conn.execute("COMMIT", [])?;
+                tracing::info!(
+                    "reconnected source folder {} -> {} ({} photos)",
+                    folder.path,
+                    new_path,
+                    photos_updated
+                );
+                reconnected.push(ReconnectedFolder {
+                    folder_id: folder.id,
+                    old_path: folder.path,
+                    new_path,
+                    photos_updated,
+                });
+            }
+            Err(e) => {
+                let _ = conn.execute("ROLLBACK", []);
+                return Err(e);
+            }
+        }
+    }
+    Ok(reconnected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::test_fixtures::TestLibraryBuilder;
+    use rusqlite::params;
+
+    /// Give every photo of the project a real file under `dir` and store its fingerprint.
+    fn materialize(project: &crate::import::test_fixtures::TestProject, dir: &Path) -> Vec<i64> {
+        std::fs::create_dir_all(dir).unwrap();
+        let rows =
+            repository::list_photo_files_for_project(&project.conn, project.project_id).unwrap();
+        for (i, row) in rows.iter().enumerate() {
+            let path = dir.join(format!("IMG_{:04}.JPG", i));
+            std::fs::write(&path, format!("photo {}", i)).unwrap();
+            let fp = compute_fingerprint(&path).unwrap();
+            project
+                .conn
+                .execute(
+                    "UPDATE photos SET path = ?1 WHERE id = ?2",
+                    params![path.to_string_lossy(), row.photo_id],
+                )
+                .unwrap();
+            repository::set_photo_fingerprint(&project.conn, row.photo_id, fp.size, &fp.hash)
+                .unwrap();
+        }
+        rows.iter().map(|r| r.photo_id).collect()
+    }
+
+    fn path_of(conn: &Connection, photo_id: i64) -> String {
+        conn.query_row("SELECT path FROM photos WHERE id = ?1", [photo_id], |r| {
+            r.get(0)
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_verify_reports_missing_moved_and_changed() {
+        let project = TestLibraryBuilder::new()
+            .with_stack_layout(&[4])
+            .build_db_only();
+        let card = project.dir.path().join("card");
+        let ids = materialize(&project, &card);
+
+        // 0: untouched, 1: moved + renamed, 2: deleted, 3: edited in place
+        let sorted = project.dir.path().join("sorted");
+        std::fs::create_dir_all(&sorted).unwrap();
+        std::fs::rename(path_of(&project.conn, ids[1]), sorted.join("venice.jpg")).unwrap();
+        std::fs::remove_file(path_of(&project.conn, ids[2])).unwrap();
+        std::fs::write(path_of(&project.conn, ids[3]), "edited").unwrap();
+
+        let v = verify_sources(&project.conn, project.project_id, &[sorted.clone()]).unwrap();
+        assert_eq!(v.checked, 4);
+        assert_eq!(v.ok, 1);
+        assert_eq!(v.moved.len(), 1);
+        assert_eq!(v.moved[0].photo_id, ids[1]);
+        assert_eq!(
+            v.moved[0].found_at.as_deref(),
+            Some(sorted.join("venice.jpg").to_string_lossy().as_ref())
+        );
+        assert_eq!(v.missing.len(), 1);
+        assert_eq!(v.missing[0].photo_id, ids[2]);
+        assert_eq!(v.changed.len(), 1);
+        assert_eq!(v.changed[0].photo_id, ids[3]);
+
+        // Verification alone never rewrites paths
+        assert!(path_of(&project.conn, ids[1]).starts_with(card.to_string_lossy().as_ref()));
+    }
+
+    #[test]
+    fn test_verify_backfills_missing_fingerprints() {
+        let project = TestLibraryBuilder::new()
+            .with_stack_layout(&[2])
+            .build_db_only();
+        materialize(&project, &project.dir.path().join("card"));
+        project
+            .conn
+            .execute("UPDATE photos SET file_size = NULL, fingerprint = NULL", [])
+            .unwrap();
+
+        let v = verify_sources(&project.conn, project.project_id, &[]).unwrap();
+        assert_eq!(v.ok, 2);
+        assert_eq!(v.fingerprints_added, 2);
+        let stored: i64 = project
+            .conn
+            .query_row(
+                "SELECT COUNT(*) FROM photos WHERE fingerprint IS NOT NULL",
+                [],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(stored, 2);
+    }
+
+    #[test]
+    fn test_relink_updates_paths_and_keeps_decisions() {
+        use crate::decisions::engine;
+        use crate::decisions::model::DecisionAction;
+
+        let project = TestLibraryBuilder::new()
+            .with_stack_layout(&[2])
+            .build_db_only();
+        let conn = &project.conn;
+        let ids = materialize(&project, &project.dir.path().join("card"));
+        let (round_id, _) =
+            engine::find_or_create_round(conn, project.project_id, project.stack_ids[0]).unwrap();
+        engine::record_decision(conn, project.lp_ids[0], round_id, &DecisionAction::Keep).unwrap();
+
+        // The whole card dump moves to another drive
+        let drive = project.dir.path().join("drive");
+        std::fs::rename(project.dir.path().join("card"), &drive).unwrap();
+
+        let result = relink_moved_files(conn, project.project_id, &[drive.clone()]).unwrap();
+        assert_eq!(result.relinked, 2);
+        assert_eq!(result.still_missing, 0);
+        assert_eq!(
+            result.folders_added,
+            vec![drive.to_string_lossy().to_string()]
+        );
+        for id in &ids {
+            assert!(path_of(conn, *id).starts_with(drive.to_string_lossy().as_ref()));
+        }
+
+        let status: String = conn
+            .query_row(
+                "SELECT current_status FROM logical_photos WHERE id = ?1",
+                [project.lp_ids[0]],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(status, "keep");
+
+        // Relinking again is a no-op
+        let again = relink_moved_files(conn, project.project_id, &[drive]).unwrap();
+        assert_eq!(again.relinked, 0);
+        assert!(again.folders_added.is_empty());
+    }
+
+    #[test]
+    fn test_reconnect_moves_offline_folder_to_new_mount_point() {
+        let project = TestLibraryBuilder::new()
+            .with_stack_layout(&[2])
+            .build_db_only();
+        let conn = &project.conn;
+        let old_mount = project.dir.path().join("media").join("SSD");
+        let folder = old_mount.join("2024");
+        let ids = materialize(&project, &folder);
+        conn.execute("DELETE FROM source_folders", []).unwrap();
+        let folder_id =
+            repository::add_source_folder(conn, project.project_id, &folder.to_string_lossy())
+                .unwrap();
+
+        let volume = |mount: &Path| VolumeInfo {
+            mount_point: mount.to_path_buf(),
+            uuid: Some("ab12".to_string()),
+            label: Some("SSD".to_string()),
+        };
+        // First open while plugged in records the volume
+        let none =
+            reconnect_source_folders_in(conn, project.project_id, &[volume(&old_mount)]).unwrap();
+        assert!(none.is_empty());
+
+        // Unplugged: offline, and verification does not call its files missing
+        let new_mount = project.dir.path().join("run").join("SSD1");
+        std::fs::create_dir_all(new_mount.parent().unwrap()).unwrap();
+        std::fs::rename(&old_mount, &new_mount).unwrap();
+        let folders = repository::list_source_folders(conn, project.project_id).unwrap();
+        assert!(!folders[0].online);
+        assert_eq!(folders[0].relative_path.as_deref(), Some("2024"));
+        let v = verify_sources(conn, project.project_id, &[]).unwrap();
+        assert_eq!(v.offline, 2);
+        assert!(v.missing.is_empty());
+
+        // Plugged back in elsewhere
+        let result =
+            reconnect_source_folders_in(conn, project.project_id, &[volume(&new_mount)]).unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].folder_id, folder_id);
+        assert_eq!(result[0].photos_updated, 2);
+        let new_folder = new_mount.join("2024");
+        for id in &ids {
+            assert!(path_of(conn, *id).starts_with(new_folder.to_string_lossy().as_ref()));
+        }
+        assert!(repository::list_source_folders(conn, project.project_id).unwrap()[0].online);
+        assert_eq!(verify_sources(conn, project.project_id, &[]).unwrap().ok, 2);
+    }
+}
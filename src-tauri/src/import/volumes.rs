@@ -0,0 +1,243 @@
+use std::path::{Path, PathBuf};
+
+/// A mounted filesystem that source folders can live on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VolumeInfo {
+    pub mount_point: PathBuf,
+    /// Filesystem UUID (Linux `/dev/disk/by-uuid`); stable across mount points.
+    pub uuid: Option<String>,
+    /// Volume label (Linux `/dev/disk/by-label`, macOS `/Volumes/<label>`).
+    pub label: Option<String>,
+}
+
+/// Where a source folder lives: its volume identity and the path below the mount point.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VolumeLocation {
+    pub uuid: Option<String>,
+    pub label: Option<String>,
+    pub relative_path: PathBuf,
+}
+
+/// The volume holding `path`: the mounted volume with the longest matching mount point.
+/// Returns None for the root filesystem, which cannot be unplugged.
+pub fn locate_in(path: &Path, volumes: &[VolumeInfo]) -> Option<VolumeLocation> {
+    let volume = volumes
+        .iter()
+        .filter(|v| v.mount_point != Path::new("/") && path.starts_with(&v.mount_point))
+        .max_by_key(|v| v.mount_point.components().count())?;
+    if volume.uuid.is_none() && volume.label.is_none() {
+        return None;
+    }
+    Some(VolumeLocation {
+        uuid: volume.uuid.clone(),
+        label: volume.label.clone(),
+        relative_path: path.strip_prefix(&volume.mount_point).ok()?.to_path_buf(),
+    })
+}
+
+/// Where a volume location is reachable now, if its volume is mounted.
+/// UUID wins when known; the label is the fallback (macOS, or filesystems without one).
+pub fn resolve_in(location: &VolumeLocation, volumes: &[VolumeInfo]) -> Option<PathBuf> {
+    let volume = match &location.uuid {
+        Some(uuid) => volumes.iter().find(|v| v.uuid.as_ref() == Some(uuid)),
+        None => volumes
+            .iter()
+            .find(|v| v.label.is_some() && v.label == location.label),
+    }?;
+    Some(volume.mount_point.join(&location.relative_path))
+}
+
+/// Volume location of `path` on this machine.
+pub fn locate(path: &Path) -> Option<VolumeLocation> {
+    locate_in(path, &list_mounted_volumes())
+}
+
+/// Current path of a volume location on this machine.
+pub fn resolve(location: &VolumeLocation) -> Option<PathBuf> {
+    resolve_in(location, &list_mounted_volumes())
+}
+
+/// All mounted, identifiable volumes.
+#[cfg(target_os = "linux")]
+pub fn list_mounted_volumes() -> Vec<VolumeInfo> {
+    let mounts = std::fs::read_to_string("/proc/self/mounts").unwrap_or_default();
+    let uuids = device_names("/dev/disk/by-uuid");
+    let labels = device_names("/dev/disk/by-label");
+    parse_mounts(&mounts)
+        .into_iter()
+        .map(|(device, mount_point)| {
+            let device = std::fs::canonicalize(&device).unwrap_or(device);
+            let find = |names: &[(PathBuf, String)]| {
+                names
+                    .iter()
+                    .find(|(d, _)| *d == device)
+                    .map(|(_, n)| n.clone())
+            };
+            VolumeInfo {
+                mount_point,
+                uuid: find(&uuids),
+                label: find(&labels),
+            }
+        })
+        .collect()
+}
+
+/// All mounted, identifiable volumes.
+#[cfg(target_os = "macos")]
+pub fn list_mounted_volumes() -> Vec<VolumeInfo> {
+    std::fs::read_dir("/Volumes")
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok())
+        // The boot volume appears as a symlink to "/"
+        .filter(|e| e.file_type().map(|t| t.is_dir()).unwrap_or(false))
+        .map(|e| VolumeInfo {
+            mount_point: e.path(),
+            uuid: None,
+            label: e.file_name().to_str().map(str::to_string),
+        })
+        .collect()
+}
+
+/// All mounted, identifiable volumes (not detected on this platform).
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn list_mounted_volumes() -> Vec<VolumeInfo> {
+    Vec::new()
+}
+
+/// (device, mount point) for each block-device line of a `/proc/self/mounts` table.
+/// Fields are space-separated with spaces inside paths escaped as octal (`\040`).
+pub fn parse_mounts(table: &str) -> Vec<(PathBuf, PathBuf)> {
+    table
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split(' ');
+            let device = fields.next()?;
+            let mount_point = fields.next()?;
+            device.starts_with("/dev/").then(|| {
+                (
+                    PathBuf::from(unescape_octal(device)),
+                    PathBuf::from(unescape_octal(mount_point)),
+                )
+            })
+        })
+        .collect()
+}
+
+fn unescape_octal(s: &str) -> String {
+    unescape(s, b"\\", 3, 8)
+}
+
+/// udev escapes unsafe characters in by-label names as `\xHH`.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn unescape_hex(s: &str) -> String {
+    unescape(s, b"\\x", 2, 16)
+}
+
+/// Replace `marker` + `digits` digits in `radix` with the byte they encode.
+fn unescape(s: &str, marker: &[u8], digits: usize, radix: u32) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i..].starts_with(marker) {
+            let start = i + marker.len();
+            let value = bytes
+                .get(start..start + digits)
+                .and_then(|d| std::str::from_utf8(d).ok())
+                .and_then(|d| u8::from_str_radix(d, radix).ok());
+            if let Some(v) = value {
+                out.push(v);
+                i = start + digits;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// (canonical device, name) for the symlinks in a `/dev/disk/by-*` directory.
+#[cfg(target_os = "linux")]
+fn device_names(dir: &str) -> Vec<(PathBuf, String)> {
+    std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let device = std::fs::canonicalize(e.path()).ok()?;
+            let name = unescape_hex(e.file_name().to_str()?);
+            Some((device, name))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn volume(mount: &str, uuid: Option<&str>, label: Option<&str>) -> VolumeInfo {
+        VolumeInfo {
+            mount_point: PathBuf::from(mount),
+            uuid: uuid.map(str::to_string),
+            label: label.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_parse_mounts_keeps_block_devices_and_unescapes_spaces() {
+        let table = "proc /proc proc rw 0 0\n\
+                     /dev/nvme0n1p2 / ext4 rw 0 0\n\
+                     /dev/sdb1 /media/me/Photo\\040SSD exfat rw 0 0\n";
+        assert_eq!(
+            parse_mounts(table),
+            vec![
+                (PathBuf::from("/dev/nvme0n1p2"), PathBuf::from("/")),
+                (
+                    PathBuf::from("/dev/sdb1"),
+                    PathBuf::from("/media/me/Photo SSD")
+                ),
+            ]
+        );
+        assert_eq!(unescape_hex("Photo\\x20SSD"), "Photo SSD");
+    }
+
+    #[test]
+    fn test_locate_uses_deepest_mount_and_skips_root() {
+        let volumes = vec![
+            volume("/", Some("root-uuid"), None),
+            volume("/media/me/SSD", Some("ab12"), Some("SSD")),
+        ];
+        let loc = locate_in(Path::new("/media/me/SSD/2024/venice"), &volumes).unwrap();
+        assert_eq!(loc.uuid.as_deref(), Some("ab12"));
+        assert_eq!(loc.relative_path, PathBuf::from("2024/venice"));
+        assert_eq!(locate_in(Path::new("/home/me/Pictures"), &volumes), None);
+    }
+
+    #[test]
+    fn test_resolve_follows_volume_to_new_mount_point() {
+        let loc = VolumeLocation {
+            uuid: Some("ab12".to_string()),
+            label: Some("SSD".to_string()),
+            relative_path: PathBuf::from("2024/venice"),
+        };
+        let remounted = vec![volume("/run/media/me/SSD1", Some("ab12"), Some("SSD"))];
+        assert_eq!(
+            resolve_in(&loc, &remounted),
+            Some(PathBuf::from("/run/media/me/SSD1/2024/venice"))
+        );
+
+        // A different drive with the same label is not ours when the UUID is known
+        let other = vec![volume("/media/me/SSD", Some("ffff"), Some("SSD"))];
+        assert_eq!(resolve_in(&loc, &other), None);
+
+        // Label-only volumes (macOS) match by label
+        let label_only = VolumeLocation { uuid: None, ..loc };
+        let mac = vec![volume("/Volumes/SSD", None, Some("SSD"))];
+        assert_eq!(
+            resolve_in(&label_only, &mac),
+            Some(PathBuf::from("/Volumes/SSD/2024/venice"))
+        );
+    }
+}
@@ -2,10 +2,13 @@ pub mod asset_scope_tests;
 pub mod commands;
 pub mod db;
 pub mod decisions;
+pub mod export;
 pub mod import;
 pub mod photos;
 pub mod projects;
+pub mod scheduler;
 pub mod state;
+pub mod xmp;
 
 use projects::manager;
 use state::AppState;
@@ -26,6 +29,11 @@ pub fn run() {
     std::fs::create_dir_all(home.join("projects")).expect("cannot create gemkeep home");
     tauri::Builder::default()
         .manage(AppState::new(home))
+        .setup(|app| {
+            use tauri::Manager;
+            app.state::<AppState>().set_app_handle(app.handle().clone());
+            Ok(())
+        })
         .plugin(tauri_plugin_dialog::init())
         .invoke_handler(tauri::generate_handler![
             toggle_devtools,
@@ -35,9 +43,14 @@ pub fn run() {
             commands::projects::open_project,
             commands::projects::get_last_project,
             commands::projects::delete_project,
+            commands::projects::get_project_settings,
+            commands::projects::set_project_settings,
             commands::import::add_source_folder,
             commands::import::remove_source_folder,
             commands::import::list_source_folders,
+            commands::import::verify_sources,
+            commands::import::relink_sources,
+            commands::import::reconnect_sources,
             commands::import::start_indexing,
             commands::import::cancel_indexing,
             commands::import::pause_indexing,
@@ -48,21 +61,51 @@ pub fn run() {
             commands::import::resume_thumbnails,
             commands::import::get_burst_gap,
             commands::import::set_burst_gap,
+            commands::import::suggest_burst_gap,
             commands::import::restack,
+            commands::import::preview_restack,
             commands::import::expand_source_scopes,
             commands::stacks::merge_stacks,
             commands::stacks::undo_last_merge,
+            commands::stacks::split_stack,
+            commands::stacks::undo_last_split,
+            commands::stacks::undo_stack_change,
+            commands::stacks::redo_stack_change,
             commands::stacks::list_stack_transactions,
+            commands::stacks::list_camera_clocks,
+            commands::stacks::set_camera_clock_offset,
+            commands::stacks::shift_capture_times,
             commands::decisions::make_decision,
             commands::decisions::undo_decision,
             commands::decisions::get_round_status,
             commands::decisions::get_stack_progress_batch,
             commands::decisions::commit_round,
             commands::decisions::get_photo_detail,
+            commands::decisions::get_photo_histogram,
+            commands::decisions::get_fullres_preview,
+            commands::decisions::prefetch_previews,
+            commands::decisions::cancel_prefetch,
             commands::decisions::get_round_decisions,
             commands::decisions::list_rounds,
             commands::decisions::get_round_snapshot,
             commands::decisions::restore_eliminated_photo,
+            commands::decisions::add_photo_tag,
+            commands::decisions::remove_photo_tag,
+            commands::decisions::list_photo_tags,
+            commands::decisions::promote_survivors,
+            commands::decisions::get_gem_stack,
+            commands::decisions::list_gem_promotions,
+            commands::decisions::make_gem_decision,
+            commands::decisions::undo_gem_decision,
+            commands::decisions::commit_gem_round,
+            commands::decisions::get_gem_round_status,
+            commands::decisions::list_gem_rounds,
+            commands::decisions::list_quality_flags,
+            commands::decisions::apply_quality_suggestions,
+            commands::export::export_survivors,
+            commands::export::write_xmp_sidecars,
+            commands::jobs::list_jobs,
+            commands::jobs::cancel_job,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,557 @@
+use anyhow::anyhow;
+use rusqlite::{params, Connection};
+use std::collections::{BTreeSet, HashMap};
+
+use crate::import::fingerprint::Fingerprint;
+use crate::photos::model::{CameraClock, CameraOffsetChange, ClockShift};
+use crate::photos::repository::collect_rows;
+
+/// Camera clock offsets of a project, keyed by (camera_model, camera_serial);
+/// the serial is "" for cameras that do not record one.
+pub type ClockOffsets = HashMap<(String, String), i64>;
+
+pub fn load_camera_clock_offsets(
+    conn: &Connection,
+    project_id: i64,
+) -> rusqlite::Result<ClockOffsets> {
+    let rows: Vec<(String, String, i64)> = collect_rows(
+        conn,
+        "SELECT camera_model, camera_serial, offset_secs FROM camera_clock_offsets WHERE project_id = ?1",
+        params![project_id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    )?;
+    Ok(rows
+        .into_iter()
+        .map(|(model, serial, offset)| ((model, serial), offset))
+        .collect())
+}
+
+/// The offset to add to a photo's camera time. Photos without a camera model
+/// cannot be told apart by body and are never shifted.
+pub fn offset_for(
+    offsets: &ClockOffsets,
+    camera_model: Option<&str>,
+    camera_serial: Option<&str>,
+) -> i64 {
+    camera_model
+        .and_then(|model| {
+            offsets
+                .get(&(model.to_string(), camera_serial.unwrap_or("").to_string()))
+                .copied()
+        })
+        .unwrap_or(0)
+}
+
+/// Camera bodies of a project with their photo counts and clock offsets,
+/// ordered by model then serial.
+pub fn list_camera_clocks(
+    conn: &Connection,
+    project_id: i64,
+) -> rusqlite::Result<Vec<CameraClock>> {
+    collect_rows(
+        conn,
+        "SELECT p.camera_model, COALESCE(p.camera_serial, '') AS serial, COUNT(*),
+                COALESCE(o.offset_secs, 0)
+         FROM photos p
+         JOIN logical_photos lp ON lp.id = p.logical_photo_id
+         LEFT JOIN camera_clock_offsets o
+             ON o.project_id = lp.project_id
+            AND o.camera_model = p.camera_model
+            AND o.camera_serial = COALESCE(p.camera_serial, '')
+         WHERE lp.project_id = ?1 AND p.camera_model IS NOT NULL
+         GROUP BY p.camera_model, serial
+         ORDER BY p.camera_model, serial",
+        params![project_id],
+        |row| {
+            let serial: String = row.get(1)?;
+            let photo_count: i64 = row.get(2)?;
+            Ok(CameraClock {
+                camera_model: row.get(0)?,
+                camera_serial: (!serial.is_empty()).then_some(serial),
+                photo_count: photo_count as usize,
+                offset_secs: row.get(3)?,
+            })
+        },
+    )
+}
+
+/// Capture-time shifts of selected photos, keyed by file `(size, fingerprint)`.
+pub type CaptureShifts = HashMap<(u64, String), i64>;
+
+pub fn load_capture_time_shifts(
+    conn: &Connection,
+    project_id: i64,
+) -> rusqlite::Result<CaptureShifts> {
+    let rows: Vec<(i64, String, i64)> = collect_rows(
+        conn,
+        "SELECT file_size, fingerprint, delta_secs FROM capture_time_shifts
+         WHERE project_id = ?1",
+        params![project_id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    )?;
+    Ok(rows
+        .into_iter()
+        .map(|(size, hash, delta)| ((size as u64, hash), delta))
+        .collect())
+}
+
+/// The selection shift recorded for a file's content; 0 if none.
+pub fn shift_for(shifts: &CaptureShifts, fingerprint: &Fingerprint) -> i64 {
+    shifts
+        .get(&(fingerprint.size, fingerprint.hash.clone()))
+        .copied()
+        .unwrap_or(0)
+}
+
+/// Set the clock offset of one camera body and shift the capture times of its
+/// photos by the change. Several writes: run it in the transaction of the
+/// restack it calls for (`repository::restack_with_clock_shift`). None if the
+/// offset is unchanged.
+pub fn set_camera_clock_offset(
+    conn: &Connection,
+    project_id: i64,
+    camera_model: &str,
+    camera_serial: Option<&str>,
+    offset_secs: i64,
+) -> anyhow::Result<Option<ClockShift>> {
+    let serial = camera_serial.unwrap_or("");
+    let previous = offset_for(
+        &load_camera_clock_offsets(conn, project_id)?,
+        Some(camera_model),
+        Some(serial),
+    );
+    if offset_secs == previous {
+        return Ok(None);
+    }
+
+    write_camera_offset(conn, project_id, camera_model, serial, offset_secs)?;
+    let photo_ids: Vec<i64> = collect_rows(
+        conn,
+        "SELECT p.id FROM photos p
+         JOIN logical_photos lp ON lp.id = p.logical_photo_id
+         WHERE lp.project_id = ?1 AND p.camera_model = ?2
+           AND COALESCE(p.camera_serial, '') = ?3",
+        params![project_id, camera_model, serial],
+        |row| row.get(0),
+    )?;
+    let delta_secs = offset_secs - previous;
+    Ok(Some(ClockShift {
+        photo_ids: shift_photos(conn, &photo_ids, delta_secs)?,
+        delta_secs,
+        camera: Some(CameraOffsetChange {
+            camera_model: camera_model.to_string(),
+            camera_serial: serial.to_string(),
+            before_secs: previous,
+            after_secs: offset_secs,
+        }),
+    }))
+}
+
+/// Shift the capture times of the given logical photos (all their files) by
+/// `delta_secs`, for a selection the camera offsets do not cover. Like
+/// `set_camera_clock_offset`, run it in the restack's transaction. None if no
+/// photo had a capture time to shift.
+pub fn shift_capture_times(
+    conn: &Connection,
+    project_id: i64,
+    logical_photo_ids: &[i64],
+    delta_secs: i64,
+) -> anyhow::Result<Option<ClockShift>> {
+    // A logical photo listed twice is still shifted once
+    let lp_ids: BTreeSet<i64> = logical_photo_ids.iter().copied().collect();
+    let mut photo_ids: Vec<i64> = Vec::new();
+    for lp_id in lp_ids {
+        let ids: Vec<i64> = collect_rows(
+            conn,
+            "SELECT p.id FROM photos p
+             JOIN logical_photos lp ON lp.id = p.logical_photo_id
+             WHERE lp.project_id = ?1 AND lp.id = ?2",
+            params![project_id, lp_id],
+            |row| row.get(0),
+        )?;
+        if ids.is_empty() {
+            return Err(anyhow!("Logical photo {} is not in this project", lp_id));
+        }
+        photo_ids.extend(ids);
+    }
+    let shifted = shift_photos(conn, &photo_ids, delta_secs)?;
+    record_selection_shift(conn, project_id, &shifted, delta_secs)?;
+    Ok((!shifted.is_empty()).then_some(ClockShift {
+        photo_ids: shifted,
+        delta_secs,
+        camera: None,
+    }))
+}
+
+/// Undo (`undo = true`) or redo a recorded shift: move its photos back or
+/// forward by its delta and restore the camera offset on the same side.
+pub(crate) fn replay_clock_shift(
+    conn: &Connection,
+    project_id: i64,
+    shift: &ClockShift,
+    undo: bool,
+) -> anyhow::Result<()> {
+    let delta_secs = if undo {
+        -shift.delta_secs
+    } else {
+        shift.delta_secs
+    };
+    shift_photos(conn, &shift.photo_ids, delta_secs)?;
+    if shift.camera.is_none() {
+        record_selection_shift(conn, project_id, &shift.photo_ids, delta_secs)?;
+    }
+    if let Some(camera) = &shift.camera {
+        let offset_secs = if undo {
+            camera.before_secs
+        } else {
+            camera.after_secs
+        };
+        write_camera_offset(
+            conn,
+            project_id,
+            &camera.camera_model,
+            &camera.camera_serial,
+            offset_secs,
+        )?;
+    }
+    Ok(())
+}
+
+fn write_camera_offset(
+    conn: &Connection,
+    project_id: i64,
+    camera_model: &str,
+    camera_serial: &str,
+    offset_secs: i64,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO camera_clock_offsets (project_id, camera_model, camera_serial, offset_secs, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT (project_id, camera_model, camera_serial)
+         DO UPDATE SET offset_secs = excluded.offset_secs, updated_at = excluded.updated_at",
+        params![
+            project_id,
+            camera_model,
+            camera_serial,
+            offset_secs,
+            chrono::Utc::now().to_rfc3339()
+        ],
+    )?;
+    Ok(())
+}
+
+/// Add `delta_secs` to the recorded shift of each photo's file, which the
+/// pipeline applies when it reads the file again. Files without a fingerprint
+/// cannot be recognised and are not recorded.
+fn record_selection_shift(
+    conn: &Connection,
+    project_id: i64,
+    photo_ids: &[i64],
+    delta_secs: i64,
+) -> rusqlite::Result<()> {
+    for &photo_id in photo_ids {
+        conn.execute(
+            "INSERT INTO capture_time_shifts (project_id, file_size, fingerprint, delta_secs)
+             SELECT ?1, file_size, fingerprint, ?2 FROM photos
+              WHERE id = ?3 AND file_size IS NOT NULL AND fingerprint IS NOT NULL
+             ON CONFLICT (project_id, file_size, fingerprint)
+             DO UPDATE SET delta_secs = delta_secs + excluded.delta_secs",
+            params![project_id, delta_secs, photo_id],
+        )?;
+    }
+    conn.execute(
+        "DELETE FROM capture_time_shifts WHERE project_id = ?1 AND delta_secs = 0",
+        params![project_id],
+    )?;
+    Ok(())
+}
+
+/// Move capture times by `delta_secs` and add it to each photo's recorded
+/// offset. Untimed photos have nothing to shift and are skipped. Returns the
+/// photos shifted.
+fn shift_photos(conn: &Connection, photo_ids: &[i64], delta_secs: i64) -> anyhow::Result<Vec<i64>> {
+    use rusqlite::OptionalExtension;
+
+    if delta_secs == 0 {
+        return Ok(vec![]);
+    }
+    let mut shifted = Vec::new();
+    for &photo_id in photo_ids {
+        let capture_time: Option<String> = conn
+            .query_row(
+                "SELECT capture_time FROM photos WHERE id = ?1",
+                params![photo_id],
+                |row| row.get(0),
+            )
+            .optional()?
+            .flatten();
+        let Some(time) = capture_time
+            .as_deref()
+            .and_then(|ct| chrono::DateTime::parse_from_rfc3339(ct).ok())
+        else {
+            continue;
+        };
+        let time = time.with_timezone(&chrono::Utc) + chrono::Duration::seconds(delta_secs);
+        conn.execute(
+            "UPDATE photos SET capture_time = ?1, clock_offset_secs = clock_offset_secs + ?2
+             WHERE id = ?3",
+            params![time.to_rfc3339(), delta_secs, photo_id],
+        )?;
+        shifted.push(photo_id);
+    }
+    Ok(shifted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::stacks::StackingRules;
+    use crate::import::test_fixtures::TestLibraryBuilder;
+    use crate::photos::history;
+    use crate::photos::repository::{restack_merge_aware, restack_with_clock_shift};
+
+    /// Give each logical photo's file a camera body and an RFC 3339 capture time.
+    fn set_camera(conn: &Connection, lp_id: i64, model: &str, serial: Option<&str>, time: &str) {
+        conn.execute(
+            "UPDATE photos SET camera_model = ?1, camera_serial = ?2, capture_time = ?3
+             WHERE logical_photo_id = ?4",
+            params![model, serial, time, lp_id],
+        )
+        .unwrap();
+    }
+
+    fn capture_time_of(conn: &Connection, lp_id: i64) -> (String, i64) {
+        conn.query_row(
+            "SELECT capture_time, clock_offset_secs FROM photos WHERE logical_photo_id = ?1",
+            params![lp_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .unwrap()
+    }
+
+    fn stack_of(conn: &Connection, lp_id: i64) -> i64 {
+        conn.query_row(
+            "SELECT stack_id FROM logical_photos WHERE id = ?1",
+            params![lp_id],
+            |row| row.get(0),
+        )
+        .unwrap()
+    }
+
+    /// Two bodies at one wedding: body B's clock runs an hour ahead of body A.
+    fn two_body_project() -> crate::import::test_fixtures::TestProject {
+        let project = TestLibraryBuilder::new()
+            .with_stack_layout(&[2, 2])
+            .build_db_only();
+        let conn = &project.conn;
+        let lps = &project.lp_ids;
+        set_camera(
+            conn,
+            lps[0],
+            "X-T5",
+            Some("A1"),
+            "2024-06-01T10:00:00+00:00",
+        );
+        set_camera(
+            conn,
+            lps[1],
+            "X-T5",
+            Some("A1"),
+            "2024-06-01T10:00:02+00:00",
+        );
+        set_camera(
+            conn,
+            lps[2],
+            "X-T5",
+            Some("B2"),
+            "2024-06-01T11:00:01+00:00",
+        );
+        set_camera(
+            conn,
+            lps[3],
+            "X-T5",
+            Some("B2"),
+            "2024-06-01T11:00:03+00:00",
+        );
+        project
+    }
+
+    #[test]
+    fn test_set_camera_clock_offset_shifts_only_that_body() {
+        let project = two_body_project();
+        let conn = &project.conn;
+        let lps = &project.lp_ids;
+
+        let shift = set_camera_clock_offset(conn, project.project_id, "X-T5", Some("B2"), -3600)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(shift.photo_ids.len(), 2);
+        assert_eq!(
+            capture_time_of(conn, lps[2]),
+            ("2024-06-01T10:00:01+00:00".to_string(), -3600)
+        );
+        assert_eq!(
+            capture_time_of(conn, lps[0]),
+            ("2024-06-01T10:00:00+00:00".to_string(), 0),
+            "body A is untouched"
+        );
+
+        let clocks = list_camera_clocks(conn, project.project_id).unwrap();
+        assert_eq!(clocks.len(), 2);
+        assert_eq!(clocks[0].camera_serial.as_deref(), Some("A1"));
+        assert_eq!(clocks[0].offset_secs, 0);
+        assert_eq!(clocks[1].camera_serial.as_deref(), Some("B2"));
+        assert_eq!(clocks[1].offset_secs, -3600);
+        assert_eq!(clocks[1].photo_count, 2);
+    }
+
+    #[test]
+    fn test_changing_camera_clock_offset_shifts_by_the_difference() {
+        let project = two_body_project();
+        let conn = &project.conn;
+        let pid = project.project_id;
+
+        set_camera_clock_offset(conn, pid, "X-T5", Some("B2"), -3000).unwrap();
+        set_camera_clock_offset(conn, pid, "X-T5", Some("B2"), -3600).unwrap();
+
+        assert_eq!(
+            capture_time_of(conn, project.lp_ids[3]),
+            ("2024-06-01T10:00:03+00:00".to_string(), -3600)
+        );
+        let offsets = load_camera_clock_offsets(conn, pid).unwrap();
+        assert_eq!(offset_for(&offsets, Some("X-T5"), Some("B2")), -3600);
+        assert_eq!(offset_for(&offsets, Some("X-T5"), Some("A1")), 0);
+        assert_eq!(offset_for(&offsets, None, Some("B2")), 0);
+    }
+
+    #[test]
+    fn test_corrected_bodies_interleave_into_one_burst() {
+        let project = two_body_project();
+        let conn = &project.conn;
+        let pid = project.project_id;
+        let lps = &project.lp_ids;
+
+        restack_merge_aware(conn, pid, 5).unwrap();
+        assert_ne!(stack_of(conn, lps[0]), stack_of(conn, lps[2]));
+
+        set_camera_clock_offset(conn, pid, "X-T5", Some("B2"), -3600).unwrap();
+        restack_merge_aware(conn, pid, 5).unwrap();
+
+        let stack = stack_of(conn, lps[0]);
+        assert!(lps.iter().all(|&lp| stack_of(conn, lp) == stack));
+    }
+
+    #[test]
+    fn test_shift_capture_times_moves_selection_and_skips_untimed() {
+        let project = two_body_project();
+        let conn = &project.conn;
+        let lps = &project.lp_ids;
+        conn.execute(
+            "UPDATE photos SET capture_time = NULL WHERE logical_photo_id = ?1",
+            params![lps[1]],
+        )
+        .unwrap();
+
+        let shift = shift_capture_times(conn, project.project_id, &lps[..2], 90)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            shift.photo_ids.len(),
+            1,
+            "the untimed photo has nothing to shift"
+        );
+        assert_eq!(
+            capture_time_of(conn, lps[0]),
+            ("2024-06-01T10:01:30+00:00".to_string(), 90)
+        );
+        assert!(shift_capture_times(conn, project.project_id, &[999_999], 90).is_err());
+    }
+
+    #[test]
+    fn test_shift_capture_times_shifts_a_repeated_photo_once() {
+        let project = two_body_project();
+        let conn = &project.conn;
+        let lps = &project.lp_ids;
+
+        let shift = shift_capture_times(conn, project.project_id, &[lps[0], lps[0]], 90)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(shift.photo_ids.len(), 1);
+        assert_eq!(
+            capture_time_of(conn, lps[0]),
+            ("2024-06-01T10:01:30+00:00".to_string(), 90)
+        );
+    }
+
+    #[test]
+    fn test_undoing_a_clock_correction_restores_times_offset_and_stacks() {
+        let project = two_body_project();
+        let conn = &project.conn;
+        let pid = project.project_id;
+        let lps = &project.lp_ids;
+        let rules = StackingRules::burst(5);
+        restack_merge_aware(conn, pid, 5).unwrap();
+        let stacks_before: Vec<i64> = lps.iter().map(|&lp| stack_of(conn, lp)).collect();
+
+        let shift = restack_with_clock_shift(conn, pid, &rules, |conn| {
+            set_camera_clock_offset(conn, pid, "X-T5", Some("B2"), -3600)
+        })
+        .unwrap()
+        .unwrap();
+        assert_eq!(shift.photo_ids.len(), 2);
+        assert_eq!(stack_of(conn, lps[0]), stack_of(conn, lps[2]));
+
+        history::undo_stack_transaction(conn, pid).unwrap();
+        assert_eq!(
+            capture_time_of(conn, lps[2]),
+            ("2024-06-01T11:00:01+00:00".to_string(), 0)
+        );
+        let offsets = load_camera_clock_offsets(conn, pid).unwrap();
+        assert_eq!(offset_for(&offsets, Some("X-T5"), Some("B2")), 0);
+        let stacks_after: Vec<i64> = lps.iter().map(|&lp| stack_of(conn, lp)).collect();
+        assert_eq!(stacks_after, stacks_before);
+
+        history::redo_stack_transaction(conn, pid).unwrap();
+        assert_eq!(
+            capture_time_of(conn, lps[2]),
+            ("2024-06-01T10:00:01+00:00".to_string(), -3600)
+        );
+        let offsets = load_camera_clock_offsets(conn, pid).unwrap();
+        assert_eq!(offset_for(&offsets, Some("X-T5"), Some("B2")), -3600);
+    }
+
+    #[test]
+    fn test_selection_shift_is_recorded_by_file_and_undone_with_it() {
+        let project = two_body_project();
+        let conn = &project.conn;
+        let pid = project.project_id;
+        let lps = &project.lp_ids;
+        conn.execute(
+            "UPDATE photos SET file_size = 1000 + logical_photo_id, fingerprint = 'fp' || logical_photo_id",
+            [],
+        )
+        .unwrap();
+        let fingerprint = |lp_id: i64| Fingerprint {
+            size: 1000 + lp_id as u64,
+            hash: format!("fp{}", lp_id),
+        };
+
+        restack_with_clock_shift(conn, pid, &StackingRules::burst(5), |conn| {
+            shift_capture_times(conn, pid, &lps[..1], 90)
+        })
+        .unwrap();
+        let shifts = load_capture_time_shifts(conn, pid).unwrap();
+        assert_eq!(shift_for(&shifts, &fingerprint(lps[0])), 90);
+        assert_eq!(shift_for(&shifts, &fingerprint(lps[1])), 0);
+
+        history::undo_stack_transaction(conn, pid).unwrap();
+        assert!(load_capture_time_shifts(conn, pid).unwrap().is_empty());
+        assert_eq!(
+            capture_time_of(conn, lps[0]),
+            ("2024-06-01T10:00:00+00:00".to_string(), 0)
+        );
+    }
+}
@@ -0,0 +1,468 @@
+use anyhow::anyhow;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::BTreeSet;
+
+use crate::decisions::engine;
+use crate::decisions::model::DecisionAction;
+use crate::photos::clock;
+use crate::photos::model::{StackChange, StackTransaction};
+use crate::photos::repository::init_round_for_stack;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Direction {
+    Undo,
+    Redo,
+}
+
+fn load_transaction(
+    conn: &Connection,
+    sql: &str,
+    project_id: i64,
+) -> rusqlite::Result<Option<StackTransaction>> {
+    conn.query_row(sql, params![project_id], |row| {
+        Ok(StackTransaction {
+            id: row.get(0)?,
+            project_id: row.get(1)?,
+            action: row.get(2)?,
+            details: row.get(3)?,
+            created_at: row.get(4)?,
+            state: row.get(5)?,
+        })
+    })
+    .optional()
+}
+
+/// The replayable change recorded in a transaction's details.
+fn change_of(tx: &StackTransaction) -> anyhow::Result<StackChange> {
+    if tx.action == "import" {
+        return Err(anyhow!(
+            "Cannot undo past an import: it rebuilt the logical photos the earlier history refers to"
+        ));
+    }
+    let details: serde_json::Value = serde_json::from_str(&tx.details)?;
+    match details.get("change") {
+        Some(change) => Ok(serde_json::from_value(change.clone())?),
+        None => Err(anyhow!(
+            "Transaction {} ({}) predates the undo history and cannot be replayed",
+            tx.id,
+            tx.action
+        )),
+    }
+}
+
+/// Undo the most recent applied stack transaction (merge, split, restack,
+/// undo_merge, undo_split): move its logical photos back to their previous
+/// stacks and switch stacks and manual groups back, along with any capture-time
+/// shift the restack was run for. Returns the undone entry.
+pub fn undo_stack_transaction(
+    conn: &Connection,
+    project_id: i64,
+) -> anyhow::Result<StackTransaction> {
+    let tx = load_transaction(
+        conn,
+        "SELECT id, project_id, action, details, created_at, state FROM stack_transactions
+         WHERE project_id = ?1 AND state = 'applied' ORDER BY id DESC LIMIT 1",
+        project_id,
+    )?
+    .ok_or_else(|| anyhow!("Nothing to undo"))?;
+    replay(conn, project_id, tx, Direction::Undo)
+}
+
+/// Redo the most recently undone stack transaction. Returns the redone entry.
+pub fn redo_stack_transaction(
+    conn: &Connection,
+    project_id: i64,
+) -> anyhow::Result<StackTransaction> {
+    // Undone entries always sit above every applied one: the oldest is the last undone
+    let tx = load_transaction(
+        conn,
+        "SELECT id, project_id, action, details, created_at, state FROM stack_transactions
+         WHERE project_id = ?1 AND state = 'undone' ORDER BY id ASC LIMIT 1",
+        project_id,
+    )?
+    .ok_or_else(|| anyhow!("Nothing to redo"))?;
+    replay(conn, project_id, tx, Direction::Redo)
+}
+
+fn replay(
+    conn: &Connection,
+    project_id: i64,
+    mut tx: StackTransaction,
+    direction: Direction,
+) -> anyhow::Result<StackTransaction> {
+    let change = change_of(&tx)?;
+    let (from, to, activate, deactivate) = match direction {
+        Direction::Undo => (
+            &change.after,
+            &change.before,
+            &change.stacks_deactivated,
+            &change.stacks_activated,
+        ),
+        Direction::Redo => (
+            &change.before,
+            &change.after,
+            &change.stacks_activated,
+            &change.stacks_deactivated,
+        ),
+    };
+
+    // 1. Guard: the logical photos must still be exactly where this entry left them
+    for (lp_id, to_stack) in to {
+        let Some(expected) = from.get(lp_id) else {
+            return Err(anyhow!(
+                "Transaction {} has no recorded stack for logical photo {}",
+                tx.id,
+                lp_id
+            ));
+        };
+        let current: Option<Option<i64>> = conn
+            .query_row(
+                "SELECT stack_id FROM logical_photos WHERE id = ?1 AND project_id = ?2",
+                params![lp_id, project_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if current != Some(Some(*expected)) {
+            return Err(anyhow!(
+                "Cannot replay transaction {}: logical photo {} has changed since (expected in stack {}, not moving to {})",
+                tx.id,
+                lp_id,
+                expected,
+                to_stack
+            ));
+        }
+    }
+
+    // 2. Guard: multi-round culling cannot be replayed onto other stacks
+    let touched: BTreeSet<i64> = from
+        .values()
+        .chain(to.values())
+        .chain(activate)
+        .chain(deactivate)
+        .copied()
+        .collect();
+    for &stack_id in &touched {
+        let max_round: Option<i32> = conn.query_row(
+            "SELECT MAX(round_number) FROM rounds WHERE scope = 'stack' AND scope_id = ?1",
+            params![stack_id],
+            |row| row.get(0),
+        )?;
+        if matches!(max_round, Some(rn) if rn > 1) {
+            return Err(anyhow!(
+                "Cannot replay transaction {}: stack {} has progressed beyond round 1",
+                tx.id,
+                stack_id
+            ));
+        }
+    }
+
+    // 3. BEGIN TRANSACTION
+    conn.execute("BEGIN", [])?;
+
+    let result = (|| -> anyhow::Result<()> {
+        for &stack_id in activate {
+            conn.execute(
+                "UPDATE stacks SET active = 1 WHERE id = ?1",
+                params![stack_id],
+            )?;
+        }
+        for &stack_id in deactivate {
+            conn.execute(
+                "UPDATE stacks SET active = 0 WHERE id = ?1",
+                params![stack_id],
+            )?;
+        }
+        for (lp_id, stack_id) in to {
+            conn.execute(
+                "UPDATE logical_photos SET stack_id = ?1 WHERE id = ?2",
+                params![stack_id, lp_id],
+            )?;
+        }
+        for group in &change.manual_groups {
+            let active = match direction {
+                Direction::Undo => !group.active,
+                Direction::Redo => group.active,
+            };
+            conn.execute(
+                &format!(
+                    "UPDATE {} SET active = ?1 WHERE id = ?2",
+                    group.kind.table()
+                ),
+                params![active as i64, group.id],
+            )?;
+        }
+        if let Some(shift) = &change.clock_shift {
+            clock::replay_clock_shift(conn, project_id, shift, direction == Direction::Undo)?;
+        }
+
+        // 4. Each destination stack's open round covers its members again
+        let destinations: BTreeSet<i64> = to.values().copied().collect();
+        for &stack_id in &destinations {
+            let round_id = init_round_for_stack(conn, project_id, stack_id)?;
+            if engine::is_round_committed(conn, round_id)? {
+                continue;
+            }
+            conn.execute(
+                "DELETE FROM round_photos WHERE round_id = ?1 AND logical_photo_id NOT IN
+                 (SELECT id FROM logical_photos WHERE stack_id = ?2)",
+                params![round_id, stack_id],
+            )?;
+            conn.execute(
+                "INSERT OR IGNORE INTO round_photos (round_id, logical_photo_id)
+                 SELECT ?1, id FROM logical_photos WHERE stack_id = ?2",
+                params![round_id, stack_id],
+            )?;
+        }
+
+        // 5. Carry decisions along with the moved logical photos
+        for (lp_id, to_stack) in to {
+            carry_decision(conn, project_id, *lp_id, from[lp_id], *to_stack)?;
+        }
+
+        let state = match direction {
+            Direction::Undo => "undone",
+            Direction::Redo => "applied",
+        };
+        conn.execute(
+            "UPDATE stack_transactions SET state = ?1 WHERE id = ?2",
+            params![state, tx.id],
+        )?;
+        tx.state = state.to_string();
+        Ok(())
+    })();
+
+    match result {
+        Ok(()) => {
+            conn.execute("COMMIT", [])?;
+            Ok(tx)
+        }
+        Err(e) => {
+            let _ = conn.execute("ROLLBACK", []);
+            Err(e)
+        }
+    }
+}
+
+/// Give a logical photo that moved from `from_stack` to `to_stack` the most recent
+/// decision it received in either stack. A decision made in the stack it left is
+/// recorded again in the destination's open round; with no decision it is undecided.
+fn carry_decision(
+    conn: &Connection,
+    project_id: i64,
+    lp_id: i64,
+    from_stack: i64,
+    to_stack: i64,
+) -> anyhow::Result<()> {
+    let latest: Option<(i64, String)> = conn
+        .query_row(
+            "SELECT d.round_id, d.action FROM decisions d
+             JOIN rounds r ON r.id = d.round_id
+             WHERE d.logical_photo_id = ?1 AND r.scope = 'stack' AND r.scope_id IN (?2, ?3)
+             ORDER BY d.id DESC LIMIT 1",
+            params![lp_id, from_stack, to_stack],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+    let round_id = init_round_for_stack(conn, project_id, to_stack)?;
+
+    match latest {
+        Some((decided_in, action)) if decided_in != round_id => {
+            let action = match action.as_str() {
+                "keep" => DecisionAction::Keep,
+                "eliminate" => DecisionAction::Eliminate,
+                other => return Err(anyhow!("Unknown decision action '{}'", other)),
+            };
+            engine::record_decision(conn, lp_id, round_id, &action)?;
+        }
+        Some((_, action)) => {
+            conn.execute(
+                "UPDATE logical_photos SET current_status = ?1 WHERE id = ?2",
+                params![action, lp_id],
+            )?;
+        }
+        None => {
+            conn.execute(
+                "UPDATE logical_photos SET current_status = 'undecided' WHERE id = ?1",
+                params![lp_id],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::test_fixtures::TestLibraryBuilder;
+    use crate::photos::repository::{
+        log_stack_transaction, merge_stacks, restack_merge_aware, split_stack,
+    };
+
+    fn stack_of(conn: &Connection, lp_id: i64) -> i64 {
+        conn.query_row(
+            "SELECT stack_id FROM logical_photos WHERE id = ?1",
+            params![lp_id],
+            |row| row.get(0),
+        )
+        .unwrap()
+    }
+
+    fn status_of(conn: &Connection, lp_id: i64) -> String {
+        conn.query_row(
+            "SELECT current_status FROM logical_photos WHERE id = ?1",
+            params![lp_id],
+            |row| row.get(0),
+        )
+        .unwrap()
+    }
+
+    fn is_active(conn: &Connection, stack_id: i64) -> bool {
+        conn.query_row(
+            "SELECT active FROM stacks WHERE id = ?1",
+            params![stack_id],
+            |row| row.get::<_, i64>(0),
+        )
+        .unwrap()
+            == 1
+    }
+
+    #[test]
+    fn test_undo_redo_merge_carries_decisions() {
+        // undo_last_merge refuses once decisions exist; the history replay keeps them.
+        let project = TestLibraryBuilder::new()
+            .with_stack_layout(&[2, 2])
+            .build_db_only();
+        let conn = &project.conn;
+        let pid = project.project_id;
+        let lps = &project.lp_ids;
+
+        let merged = merge_stacks(conn, pid, &project.stack_ids).unwrap();
+        let (round_id, _) =
+            engine::find_or_create_round(conn, pid, merged.merged_stack_id).unwrap();
+        engine::record_decision(conn, lps[0], round_id, &DecisionAction::Keep).unwrap();
+        engine::record_decision(conn, lps[3], round_id, &DecisionAction::Eliminate).unwrap();
+
+        let undone = undo_stack_transaction(conn, pid).unwrap();
+        assert_eq!(undone.action, "merge");
+        assert_eq!(undone.state, "undone");
+        assert!(!is_active(conn, merged.merged_stack_id));
+        assert_eq!(stack_of(conn, lps[0]), project.stack_ids[0]);
+        assert_eq!(stack_of(conn, lps[3]), project.stack_ids[1]);
+        assert_eq!(status_of(conn, lps[0]), "keep");
+        assert_eq!(status_of(conn, lps[3]), "eliminate");
+        assert_eq!(status_of(conn, lps[1]), "undecided");
+
+        // The carried decision lives in the restored stack's own round
+        let (restored_round, _) =
+            engine::find_or_create_round(conn, pid, project.stack_ids[0]).unwrap();
+        let decisions =
+            engine::get_round_decisions(conn, project.stack_ids[0], restored_round).unwrap();
+        assert!(decisions
+            .iter()
+            .any(|d| d.logical_photo_id == lps[0] && d.current_status == "keep"));
+
+        let redone = redo_stack_transaction(conn, pid).unwrap();
+        assert_eq!(redone.id, undone.id);
+        assert_eq!(redone.state, "applied");
+        for lp in lps {
+            assert_eq!(stack_of(conn, *lp), merged.merged_stack_id);
+        }
+        assert_eq!(status_of(conn, lps[0]), "keep");
+        assert!(
+            redo_stack_transaction(conn, pid).is_err(),
+            "nothing left to redo"
+        );
+    }
+
+    #[test]
+    fn test_multi_level_undo_redo_and_new_change_discards_redo() {
+        let project = TestLibraryBuilder::new()
+            .with_stack_layout(&[3, 2])
+            .build_db_only();
+        let conn = &project.conn;
+        let pid = project.project_id;
+        let lps = &project.lp_ids;
+        let original: Vec<i64> = lps.iter().map(|lp| stack_of(conn, *lp)).collect();
+
+        let split = split_stack(conn, pid, project.stack_ids[0], &[lps[2]]).unwrap();
+        let merged = merge_stacks(conn, pid, &[split.new_stack_id, project.stack_ids[1]]).unwrap();
+
+        assert_eq!(undo_stack_transaction(conn, pid).unwrap().action, "merge");
+        assert_eq!(undo_stack_transaction(conn, pid).unwrap().action, "split");
+        let restored: Vec<i64> = lps.iter().map(|lp| stack_of(conn, *lp)).collect();
+        assert_eq!(restored, original);
+        assert!(!is_active(conn, split.new_stack_id));
+        let active_manual: i64 = conn
+            .query_row(
+                "SELECT (SELECT COUNT(*) FROM manual_merges WHERE active = 1)
+                      + (SELECT COUNT(*) FROM manual_splits WHERE active = 1)",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(
+            active_manual, 0,
+            "restack must not see undone manual groups"
+        );
+
+        assert_eq!(redo_stack_transaction(conn, pid).unwrap().action, "split");
+        assert_eq!(redo_stack_transaction(conn, pid).unwrap().action, "merge");
+        assert_eq!(stack_of(conn, lps[2]), merged.merged_stack_id);
+        assert_eq!(stack_of(conn, lps[0]), project.stack_ids[0]);
+
+        // Undo once, then make a new change: the undone merge can no longer be redone
+        undo_stack_transaction(conn, pid).unwrap();
+        split_stack(conn, pid, project.stack_ids[0], &[lps[0]]).unwrap();
+        assert!(redo_stack_transaction(conn, pid).is_err());
+    }
+
+    #[test]
+    fn test_undo_restack_restores_stacks_and_decisions() {
+        let project = TestLibraryBuilder::new()
+            .with_stack_layout(&[2, 2])
+            .build_db_only();
+        let conn = &project.conn;
+        let pid = project.project_id;
+        let lps = &project.lp_ids;
+        let (round_id, _) = engine::find_or_create_round(conn, pid, project.stack_ids[1]).unwrap();
+        engine::record_decision(conn, lps[2], round_id, &DecisionAction::Keep).unwrap();
+
+        restack_merge_aware(conn, pid, 3600).unwrap();
+        assert_ne!(stack_of(conn, lps[2]), project.stack_ids[1]);
+        assert_eq!(status_of(conn, lps[2]), "keep", "restack carries decisions");
+
+        assert_eq!(undo_stack_transaction(conn, pid).unwrap().action, "restack");
+        for (i, lp) in lps.iter().enumerate() {
+            assert_eq!(stack_of(conn, *lp), project.stack_ids[i / 2]);
+        }
+        assert!(is_active(conn, project.stack_ids[0]));
+        assert_eq!(
+            status_of(conn, lps[2]),
+            "keep",
+            "old round's decision is back"
+        );
+    }
+
+    #[test]
+    fn test_undo_refuses_diverged_history_and_imports() {
+        let project = TestLibraryBuilder::new()
+            .with_stack_layout(&[2, 2])
+            .build_db_only();
+        let conn = &project.conn;
+        let pid = project.project_id;
+
+        merge_stacks(conn, pid, &project.stack_ids).unwrap();
+        // Moved behind the history's back
+        conn.execute(
+            "UPDATE logical_photos SET stack_id = ?1 WHERE id = ?2",
+            params![project.stack_ids[0], project.lp_ids[3]],
+        )
+        .unwrap();
+        let err = undo_stack_transaction(conn, pid).unwrap_err().to_string();
+        assert!(err.contains("has changed since"), "got: {}", err);
+
+        log_stack_transaction(conn, pid, "import", &serde_json::json!({"imported": 4})).unwrap();
+        let err = undo_stack_transaction(conn, pid).unwrap_err().to_string();
+        assert!(err.contains("import"), "got: {}", err);
+    }
+}
@@ -1,2 +1,4 @@
+pub mod clock;
+pub mod history;
 pub mod model;
 pub mod repository;
@@ -16,6 +16,34 @@ impl PhotoFormat {
     }
 }
 
+/// How restack groups logical photos that are not in a manual group.
+#[derive(Debug, Clone, Copy, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StackingStrategy {
+    /// Capture-time gaps only.
+    #[default]
+    Burst,
+    /// Capture-time proximity combined with perceptual-hash similarity.
+    Similarity,
+}
+
+impl StackingStrategy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StackingStrategy::Burst => "burst",
+            StackingStrategy::Similarity => "similarity",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "burst" => Some(StackingStrategy::Burst),
+            "similarity" => Some(StackingStrategy::Similarity),
+            _ => None,
+        }
+    }
+}
+
 /// Intermediate struct used during pipeline (not stored directly in DB)
 #[derive(Debug, Clone)]
 pub struct ScannedFile {
@@ -35,6 +63,14 @@ pub struct ScannedFile {
     pub base_name: String,
     /// parent directory
     pub dir: PathBuf,
+    /// content identity (size + partial hash); None if the file could not be read
+    pub fingerprint: Option<crate::import::fingerprint::Fingerprint>,
+    /// camera image/sequence number, orders burst frames with equal capture times
+    pub sequence_number: Option<u32>,
+    /// body serial number, with camera_model the key of a camera clock offset
+    pub camera_serial: Option<String>,
+    /// seconds added to the camera's time to get `capture_time`
+    pub clock_offset_secs: i64,
 }
 
 #[derive(Debug, Default, Clone, serde::Serialize, serde::Deserialize)]
@@ -47,10 +83,18 @@ pub struct ImportStats {
     pub pairs_detected: usize,
     pub stacks_generated: usize,
     pub logical_photos: usize,
+    /// round-1 decisions seeded from existing XMP sidecars
+    pub sidecar_decisions: usize,
+    /// moved files matched to their existing photo by fingerprint (incremental runs)
+    pub relinked: usize,
+    /// source folders skipped because they are offline
+    pub offline_folders: usize,
     /// capped at 100 entries
     pub error_log: Vec<String>,
     /// true if the run was cancelled before completion
     pub cancelled: bool,
+    /// true if a stage failed and the run stopped before completion
+    pub failed: bool,
 }
 
 #[derive(Debug, Default, Clone, serde::Serialize, serde::Deserialize)]
@@ -95,12 +139,103 @@ pub struct LogicalPhotoSummary {
     pub shutter_speed: Option<String>,
     pub iso: Option<u32>,
     pub focal_length: Option<f64>,
+    /// Local sharpness (max tile variance of Laplacian); None until the thumbnail pass scores it
+    pub sharpness: Option<f64>,
+}
+
+/// Exposure data of a logical photo's preview, cached as `{id}_histogram.json`
+/// next to the thumbnail.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PhotoHistogram {
+    /// 256 bins each: pixel counts over the preview at analysis size (≤1024px)
+    pub luminance: Vec<u32>,
+    pub red: Vec<u32>,
+    pub green: Vec<u32>,
+    pub blue: Vec<u32>,
+    /// Percent (0–100) of pixels whose luminance is blown out
+    pub highlight_clipping_pct: f64,
+    /// Percent (0–100) of pixels whose luminance is crushed to black
+    pub shadow_clipping_pct: f64,
+}
+
+/// Order of the photos returned by `list_logical_photos`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PhotoOrder {
+    /// Oldest first (the default).
+    #[default]
+    CaptureTime,
+    /// Sharpest first; unscored photos last.
+    Sharpness,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct SourceFolderRow {
     pub id: i64,
     pub path: String,
+    /// Identity of the removable volume holding the folder (None on the system disk)
+    pub volume_uuid: Option<String>,
+    pub volume_label: Option<String>,
+    /// Folder path below the volume's mount point
+    pub relative_path: Option<String>,
+    /// false while the folder is unreachable (drive unplugged)
+    pub online: bool,
+}
+
+/// A source folder found again on its volume under a different mount point.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ReconnectedFolder {
+    pub folder_id: i64,
+    pub old_path: String,
+    pub new_path: String,
+    /// Photo paths rewritten to the new mount point
+    pub photos_updated: usize,
+}
+
+/// A stored photo file with its content identity, for source verification.
+#[derive(Debug, Clone)]
+pub struct PhotoFileRow {
+    pub photo_id: i64,
+    pub logical_photo_id: Option<i64>,
+    pub path: String,
+    pub file_size: Option<u64>,
+    pub fingerprint: Option<String>,
+}
+
+/// A photo file that is not where (or what) the project expects.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SourceFileIssue {
+    pub photo_id: i64,
+    pub logical_photo_id: Option<i64>,
+    pub path: String,
+    /// Where the file was found by fingerprint (moved files only)
+    pub found_at: Option<String>,
+}
+
+/// Result of checking every photo file of a project against the disk.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct SourceVerification {
+    pub checked: usize,
+    pub ok: usize,
+    /// Gone, and no file with the same fingerprint was found
+    pub missing: Vec<SourceFileIssue>,
+    /// Gone from its path but found elsewhere with the same fingerprint
+    pub moved: Vec<SourceFileIssue>,
+    /// Still at its path but the content no longer matches
+    pub changed: Vec<SourceFileIssue>,
+    /// Photos imported before fingerprinting that got one during this check
+    pub fingerprints_added: usize,
+    /// Photos under offline source folders, not checked
+    pub offline: usize,
+}
+
+/// Result of relinking moved files.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct RelinkResult {
+    pub relinked: usize,
+    pub still_missing: usize,
+    /// Search roots attached as new source folders because relinked files live there
+    pub folders_added: Vec<String>,
 }
 
 /// Result of a merge operation.
@@ -116,12 +251,168 @@ pub struct MergeResult {
     pub transaction_id: i64,
 }
 
+/// Result of a split operation.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SplitResult {
+    /// The id of the new stack holding the ejected logical photos
+    pub new_stack_id: i64,
+    /// Number of logical photos moved out of the source stack
+    pub logical_photos_moved: usize,
+    /// The stack the logical photos were taken from
+    pub source_stack_id: i64,
+    /// Transaction log entry id
+    pub transaction_id: i64,
+}
+
 /// A record from the stack_transactions table.
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct StackTransaction {
     pub id: i64,
     pub project_id: i64,
-    pub action: String,     // "merge" | "split" | "restack" | "import"
-    pub details: String,    // JSON string
+    pub action: String, // "merge" | "split" | "restack" | "import" | "undo_merge" | "undo_split"
+    pub details: String, // JSON string
     pub created_at: String, // ISO-8601
+    pub state: String,  // "applied" | "undone" | "discarded" (redo branch dropped)
+}
+
+/// Which manual grouping table a history entry toggles.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ManualGroupKind {
+    Merge,
+    Split,
+}
+
+impl ManualGroupKind {
+    pub fn table(&self) -> &'static str {
+        match self {
+            ManualGroupKind::Merge => "manual_merges",
+            ManualGroupKind::Split => "manual_splits",
+        }
+    }
+}
+
+/// A manual merge/split group whose `active` flag a transaction set.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ManualGroupToggle {
+    pub kind: ManualGroupKind,
+    pub id: i64,
+    /// The flag after the transaction
+    pub active: bool,
+}
+
+/// The replayable effect of a stack transaction, stored under `"change"` in its
+/// details: stack assignments before and after, and what was switched on and off.
+/// Undo applies `before`, redo applies `after`.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct StackChange {
+    /// logical_photo_id → stack_id
+    pub before: std::collections::BTreeMap<i64, i64>,
+    pub after: std::collections::BTreeMap<i64, i64>,
+    pub stacks_activated: Vec<i64>,
+    pub stacks_deactivated: Vec<i64>,
+    pub manual_groups: Vec<ManualGroupToggle>,
+    /// Capture times corrected in the same transaction (restacks only)
+    #[serde(default)]
+    pub clock_shift: Option<ClockShift>,
+}
+
+/// A capture-time correction, replayed with the restack it triggered: undo moves
+/// the photos back by `delta_secs`, redo moves them again.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ClockShift {
+    /// Photos whose capture times moved (untimed photos are never in here)
+    pub photo_ids: Vec<i64>,
+    pub delta_secs: i64,
+    /// The camera body whose clock offset was set, if the shift came from one
+    pub camera: Option<CameraOffsetChange>,
+}
+
+/// A camera body's clock offset before and after a `ClockShift`.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct CameraOffsetChange {
+    pub camera_model: String,
+    /// "" for cameras that do not record one
+    pub camera_serial: String,
+    pub before_secs: i64,
+    pub after_secs: i64,
+}
+
+/// Where a stack left by a restack came from, stored under `"mapping"` in the
+/// restack transaction's details.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct RestackedStack {
+    pub stack_id: i64,
+    /// Stacks its logical photos were in before the restack
+    pub from_stacks: Vec<i64>,
+    /// The stack was left exactly as it was, rounds and decisions included
+    pub history_kept: bool,
+    /// Keep decisions carried into its fresh round
+    pub carried_keeps: usize,
+    /// Eliminated logical photos kept out of its fresh round
+    pub carried_eliminations: usize,
+}
+
+/// Current stacks a restack would combine into one.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct RestackMerge {
+    pub from_stacks: Vec<i64>,
+    pub photo_count: usize,
+}
+
+/// A current stack a restack would break up.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct RestackSplit {
+    pub stack_id: i64,
+    pub into_stacks: usize,
+}
+
+/// A camera body seen in a project and the correction applied to its clock.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct CameraClock {
+    pub camera_model: String,
+    /// `None` when the camera does not record its serial number
+    pub camera_serial: Option<String>,
+    pub photo_count: usize,
+    /// Seconds added to the camera's time; 0 when never corrected
+    pub offset_secs: i64,
+}
+
+/// Histogram bucket of inter-shot gaps: `min_secs <= gap < max_secs`; the
+/// last bucket has no upper bound.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct GapBucket {
+    pub min_secs: u64,
+    pub max_secs: Option<u64>,
+    pub count: usize,
+}
+
+/// A burst gap derived from how far apart a project's shots are.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct BurstGapSuggestion {
+    /// `None` when the gaps show no clear break between bursts
+    pub suggested_gap_secs: Option<u64>,
+    /// Gaps measured, each between consecutive shots of one camera
+    pub gaps: usize,
+    pub cameras: usize,
+    pub histogram: Vec<GapBucket>,
+}
+
+/// What a restack would change, worked out without writing anything.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct RestackPreview {
+    pub strategy: StackingStrategy,
+    pub burst_gap_secs: u64,
+    /// Active stacks now
+    pub stacks_before: usize,
+    /// Stacks after the restack, unchanged ones included
+    pub stacks_after: usize,
+    /// Stacks whose members stay together as they are, history and all
+    pub stacks_unchanged: usize,
+    pub merges: Vec<RestackMerge>,
+    pub splits: Vec<RestackSplit>,
+    /// Logical photos that would land in a new stack, sorted
+    pub moved_photos: Vec<i64>,
+    /// Moved logical photos with a keep or eliminate decision to carry over
+    pub decided_photos_affected: usize,
 }
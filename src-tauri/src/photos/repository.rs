@@ -1,5 +1,7 @@
+use crate::import::stacks::{self, StackingRules};
 use crate::photos::model::{
-    LogicalPhotoSummary, PhotoFormat, ScannedFile, SourceFolderRow, StackSummary,
+    BurstGapSuggestion, LogicalPhotoSummary, PhotoFileRow, PhotoFormat, ScannedFile,
+    SourceFolderRow, StackSummary, StackingStrategy,
 };
 use rusqlite::{params, Connection};
 use std::path::PathBuf;
@@ -8,7 +10,7 @@ use std::path::PathBuf;
 
 /// Execute a prepared statement, collect all rows with `f`, and return a Vec.
 /// Factored out to avoid the repetitive `prepare → query_map → collect` boilerplate.
-fn collect_rows<T, F>(
+pub(crate) fn collect_rows<T, F>(
     conn: &Connection,
     sql: &str,
     params: impl rusqlite::Params,
@@ -86,6 +88,19 @@ pub fn set_logical_photo_id(
     Ok(())
 }
 
+/// Point a logical photo at a different representative file.
+pub fn set_representative_photo_id(
+    conn: &Connection,
+    logical_photo_id: i64,
+    photo_id: i64,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE logical_photos SET representative_photo_id = ?1 WHERE id = ?2",
+        params![photo_id, logical_photo_id],
+    )?;
+    Ok(())
+}
+
 /// Insert a new stack row. Returns the new stack id.
 pub fn insert_stack(conn: &Connection, project_id: i64) -> rusqlite::Result<i64> {
     let now = chrono::Utc::now().to_rfc3339();
@@ -113,7 +128,7 @@ pub fn insert_logical_photo(
 
 /// Delete all stacks and logical_photos for this project (for idempotent re-indexing).
 /// Photos rows are kept (they represent files on disk) but their logical_photo_id is cleared.
-/// Cascade order: decisions → rounds → photos.logical_photo_id → logical_photos → stacks.
+/// Cascade order: decisions → tags → quality_flags → gem_promotions → rounds → photos.logical_photo_id → logical_photos → stacks.
 pub fn clear_stacks_and_logical_photos(conn: &Connection, project_id: i64) -> rusqlite::Result<()> {
     // 1. Delete decisions that reference logical_photos in this project.
     conn.execute(
@@ -122,6 +137,27 @@ pub fn clear_stacks_and_logical_photos(conn: &Connection, project_id: i64) -> ru
          )",
         params![project_id],
     )?;
+    // 1a. Delete tag assignments (tags themselves are project-level and kept).
+    conn.execute(
+        "DELETE FROM logical_photo_tags WHERE logical_photo_id IN (
+             SELECT id FROM logical_photos WHERE project_id = ?1
+         )",
+        params![project_id],
+    )?;
+    // 1b. Delete advisory quality flags (recomputed by the thumbnail pass).
+    conn.execute(
+        "DELETE FROM quality_flags WHERE logical_photo_id IN (
+             SELECT id FROM logical_photos WHERE project_id = ?1
+         )",
+        params![project_id],
+    )?;
+    // 1c. Delete GemStack promotions (the GemStack itself is kept).
+    conn.execute(
+        "DELETE FROM gem_promotions WHERE gem_stack_id IN (
+             SELECT id FROM gem_stacks WHERE project_id = ?1
+         )",
+        params![project_id],
+    )?;
     // 2. Delete rounds for this project (decisions are already gone).
     conn.execute(
         "DELETE FROM rounds WHERE project_id = ?1",
@@ -203,13 +239,14 @@ pub fn query_logical_photos_by_stack(
             rep.aperture                                        AS aperture,
             rep.shutter_speed                                   AS shutter_speed,
             rep.iso                                             AS iso,
-            rep.focal_length                                    AS focal_length
+            rep.focal_length                                    AS focal_length,
+            lp.sharpness                                        AS sharpness
          FROM logical_photos lp
          LEFT JOIN photos rep ON rep.id = lp.representative_photo_id
          LEFT JOIN photos p   ON p.logical_photo_id = lp.id
          WHERE lp.stack_id = ?1 AND lp.current_status != 'eliminate'
          GROUP BY lp.id
-         ORDER BY rep.capture_time ASC NULLS LAST, lp.id ASC",
+         ORDER BY rep.capture_time ASC NULLS LAST, rep.sequence_number ASC NULLS LAST, lp.id ASC",
         params![stack_id],
         |row| {
             let has_raw: i64 = row.get(4)?;
@@ -226,6 +263,7 @@ pub fn query_logical_photos_by_stack(
                 shutter_speed: row.get(7)?,
                 iso: row.get(8)?,
                 focal_length: row.get(9)?,
+                sharpness: row.get(10)?,
             })
         },
     )
@@ -247,6 +285,16 @@ pub fn enrich_with_thumbnails(summaries: &mut [LogicalPhotoSummary], cache_dir:
     }
 }
 
+/// Reorder summaries sharpest first; unscored photos keep their order at the end.
+pub fn sort_by_sharpness(summaries: &mut [LogicalPhotoSummary]) {
+    summaries.sort_by(|a, b| match (a.sharpness, b.sharpness) {
+        (Some(x), Some(y)) => y.total_cmp(&x),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => std::cmp::Ordering::Equal,
+    });
+}
+
 /// Return summaries of logical photos belonging to a specific round.
 /// Only photos linked via `round_photos` are included (i.e., survivors of previous rounds).
 pub fn query_logical_photos_by_round(
@@ -275,14 +323,15 @@ pub fn query_logical_photos_by_round(
             rep.aperture                                        AS aperture,
             rep.shutter_speed                                   AS shutter_speed,
             rep.iso                                             AS iso,
-            rep.focal_length                                    AS focal_length
+            rep.focal_length                                    AS focal_length,
+            lp.sharpness                                        AS sharpness
          FROM round_photos rp
          JOIN logical_photos lp ON lp.id = rp.logical_photo_id
          LEFT JOIN photos rep ON rep.id = lp.representative_photo_id
          LEFT JOIN photos p   ON p.logical_photo_id = lp.id
          WHERE rp.round_id = ?1
          GROUP BY lp.id
-         ORDER BY rep.capture_time ASC NULLS LAST, lp.id ASC",
+         ORDER BY rep.capture_time ASC NULLS LAST, rep.sequence_number ASC NULLS LAST, lp.id ASC",
         params![round_id],
         |row| {
             let has_raw: i64 = row.get(4)?;
@@ -299,6 +348,7 @@ pub fn query_logical_photos_by_round(
                 shutter_speed: row.get(7)?,
                 iso: row.get(8)?,
                 focal_length: row.get(9)?,
+                sharpness: row.get(10)?,
             })
         },
     )
@@ -347,17 +397,118 @@ pub fn list_source_folders(
 ) -> rusqlite::Result<Vec<SourceFolderRow>> {
     collect_rows(
         conn,
-        "SELECT id, path FROM source_folders WHERE project_id = ?1 ORDER BY added_at ASC",
+        "SELECT id, path, volume_uuid, volume_label, relative_path
+         FROM source_folders WHERE project_id = ?1 ORDER BY added_at ASC",
         params![project_id],
         |row| {
+            let path: String = row.get(1)?;
             Ok(SourceFolderRow {
                 id: row.get(0)?,
-                path: row.get(1)?,
+                online: std::path::Path::new(&path).is_dir(),
+                path,
+                volume_uuid: row.get(2)?,
+                volume_label: row.get(3)?,
+                relative_path: row.get(4)?,
             })
         },
     )
 }
 
+/// Record the volume a source folder lives on.
+pub fn set_source_folder_volume(
+    conn: &Connection,
+    folder_id: i64,
+    volume_uuid: Option<&str>,
+    volume_label: Option<&str>,
+    relative_path: &str,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE source_folders SET volume_uuid = ?1, volume_label = ?2, relative_path = ?3
+         WHERE id = ?4",
+        params![volume_uuid, volume_label, relative_path, folder_id],
+    )?;
+    Ok(())
+}
+
+/// Move a source folder to `new_path`, rewriting the paths of the project's photos
+/// stored below it. Returns the number of photo paths rewritten.
+pub fn relocate_source_folder(
+    conn: &Connection,
+    project_id: i64,
+    folder_id: i64,
+    old_path: &str,
+    new_path: &str,
+) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE source_folders SET path = ?1 WHERE id = ?2 AND project_id = ?3",
+        params![new_path, folder_id, project_id],
+    )?;
+    let old_prefix = format!("{}{}", old_path, std::path::MAIN_SEPARATOR);
+    conn.execute(
+        "UPDATE photos SET path = ?1 || substr(path, length(?2) + 1)
+         WHERE substr(path, 1, length(?3)) = ?3
+           AND logical_photo_id IN (SELECT id FROM logical_photos WHERE project_id = ?4)",
+        params![new_path, old_path, old_prefix, project_id],
+    )
+}
+
+/// Assign a tag (created on first use) to a logical photo. Idempotent.
+/// Returns the tag id.
+pub fn add_tag_to_logical_photo(
+    conn: &Connection,
+    project_id: i64,
+    logical_photo_id: i64,
+    name: &str,
+) -> rusqlite::Result<i64> {
+    let now = chrono::Utc::now().to_rfc3339();
+    conn.execute(
+        "INSERT OR IGNORE INTO tags (project_id, name, created_at) VALUES (?1, ?2, ?3)",
+        params![project_id, name, now],
+    )?;
+    let tag_id: i64 = conn.query_row(
+        "SELECT id FROM tags WHERE project_id = ?1 AND name = ?2",
+        params![project_id, name],
+        |row| row.get(0),
+    )?;
+    conn.execute(
+        "INSERT OR IGNORE INTO logical_photo_tags (logical_photo_id, tag_id) VALUES (?1, ?2)",
+        params![logical_photo_id, tag_id],
+    )?;
+    Ok(tag_id)
+}
+
+/// Remove a tag from a logical photo. The tag itself is kept for reuse.
+pub fn remove_tag_from_logical_photo(
+    conn: &Connection,
+    project_id: i64,
+    logical_photo_id: i64,
+    name: &str,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "DELETE FROM logical_photo_tags
+         WHERE logical_photo_id = ?1
+           AND tag_id IN (SELECT id FROM tags WHERE project_id = ?2 AND name = ?3)",
+        params![logical_photo_id, project_id, name],
+    )?;
+    Ok(())
+}
+
+/// Tag names assigned to a logical photo, alphabetically.
+pub fn list_tags_for_logical_photo(
+    conn: &Connection,
+    logical_photo_id: i64,
+) -> rusqlite::Result<Vec<String>> {
+    collect_rows(
+        conn,
+        "SELECT t.name FROM tags t
+         JOIN logical_photo_tags lpt ON lpt.tag_id = t.id
+         WHERE lpt.logical_photo_id = ?1
+         ORDER BY t.name",
+        params![logical_photo_id],
+        |row| row.get(0),
+    )
+}
+
 /// Check whether a given absolute path is already attached to this project.
 pub fn folder_already_attached(
     conn: &Connection,
@@ -520,16 +671,98 @@ pub fn clear_stacks_only(conn: &Connection, project_id: i64) -> rusqlite::Result
 pub fn load_logical_photos_for_restack(
     conn: &Connection,
     project_id: i64,
-) -> rusqlite::Result<Vec<(i64, Option<String>)>> {
+) -> rusqlite::Result<Vec<(i64, Option<String>, Option<u32>)>> {
     collect_rows(
         conn,
-        "SELECT lp.id, p.capture_time
+        "SELECT lp.id, p.capture_time, p.sequence_number
          FROM logical_photos lp
          JOIN photos p ON p.id = lp.representative_photo_id
          WHERE lp.project_id = ?1
-         ORDER BY p.capture_time ASC NULLS LAST, lp.id ASC",
+         ORDER BY p.capture_time ASC NULLS LAST, p.sequence_number ASC NULLS LAST, lp.id ASC",
         params![project_id],
-        |row| Ok((row.get(0)?, row.get(1)?)),
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    )
+}
+
+/// Suggest a burst gap from the capture times of a project's logical photos,
+/// measured per camera body (model and serial). Untimed logical photos are left out.
+pub fn suggest_burst_gap(
+    conn: &Connection,
+    project_id: i64,
+) -> rusqlite::Result<BurstGapSuggestion> {
+    let rows: Vec<((Option<String>, Option<String>), String)> = collect_rows(
+        conn,
+        "SELECT p.camera_model, p.camera_serial, p.capture_time
+         FROM logical_photos lp
+         JOIN photos p ON p.id = lp.representative_photo_id
+         WHERE lp.project_id = ?1 AND p.capture_time IS NOT NULL",
+        params![project_id],
+        |row| Ok(((row.get(0)?, row.get(1)?), row.get(2)?)),
+    )?;
+    let shots = rows.into_iter().filter_map(|(camera, capture_time)| {
+        let time = chrono::DateTime::parse_from_rfc3339(&capture_time).ok()?;
+        Some((camera, time.with_timezone(&chrono::Utc)))
+    });
+    Ok(stacks::suggest_burst_gap(shots))
+}
+
+/// Logical photos of a project that have no perceptual hash yet.
+pub fn list_logical_photos_without_phash(
+    conn: &Connection,
+    project_id: i64,
+) -> rusqlite::Result<Vec<i64>> {
+    collect_rows(
+        conn,
+        "SELECT id FROM logical_photos WHERE project_id = ?1 AND phash IS NULL ORDER BY id",
+        params![project_id],
+        |row| row.get(0),
+    )
+}
+
+/// Store the perceptual hash of a logical photo's thumbnail (bits kept as i64).
+pub fn set_logical_photo_phash(conn: &Connection, lp_id: i64, phash: u64) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE logical_photos SET phash = ?1 WHERE id = ?2",
+        params![phash as i64, lp_id],
+    )?;
+    Ok(())
+}
+
+/// Perceptual hashes of a project's logical photos, keyed by logical photo id.
+pub fn load_logical_photo_phashes(
+    conn: &Connection,
+    project_id: i64,
+) -> rusqlite::Result<std::collections::HashMap<i64, u64>> {
+    Ok(collect_rows(
+        conn,
+        "SELECT id, phash FROM logical_photos WHERE project_id = ?1 AND phash IS NOT NULL",
+        params![project_id],
+        |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)? as u64)),
+    )?
+    .into_iter()
+    .collect())
+}
+
+/// Store sharpness scores `(logical_photo_id, score)` from the thumbnail pass.
+pub fn set_sharpness_scores(conn: &Connection, scores: &[(i64, f64)]) -> rusqlite::Result<()> {
+    let mut stmt = conn.prepare("UPDATE logical_photos SET sharpness = ?1 WHERE id = ?2")?;
+    for (lp_id, score) in scores {
+        stmt.execute(params![score, lp_id])?;
+    }
+    Ok(())
+}
+
+/// Logical photos of a project that have no sharpness score yet.
+pub fn list_unscored_lp_ids(conn: &Connection, project_id: i64) -> rusqlite::Result<Vec<i64>> {
+    collect_rows(
+        conn,
+        "SELECT lp.id \
+         FROM logical_photos lp \
+         INNER JOIN stacks s ON lp.stack_id = s.id \
+         WHERE s.project_id = ?1 AND lp.sharpness IS NULL \
+         ORDER BY lp.id ASC",
+        params![project_id],
+        |row| row.get(0),
     )
 }
 
@@ -552,7 +785,8 @@ pub fn update_logical_photo_stack(
 pub fn load_existing_scanned_files(conn: &Connection, project_id: i64) -> Vec<ScannedFile> {
     let mut stmt = match conn.prepare(
         "SELECT p.path, p.format, p.capture_time, p.orientation, p.camera_model, p.lens, \
-                p.aperture, p.shutter_speed, p.iso, p.focal_length, p.exposure_comp \
+                p.aperture, p.shutter_speed, p.iso, p.focal_length, p.exposure_comp, \
+                p.sequence_number, p.camera_serial, p.clock_offset_secs \
          FROM photos p \
          INNER JOIN logical_photos lp ON p.logical_photo_id = lp.id \
          WHERE lp.project_id = ?1",
@@ -576,6 +810,9 @@ pub fn load_existing_scanned_files(conn: &Connection, project_id: i64) -> Vec<Sc
         let iso: Option<u32> = row.get(8)?;
         let focal_length: Option<f64> = row.get(9)?;
         let exposure_comp: Option<f64> = row.get(10)?;
+        let sequence_number: Option<u32> = row.get(11)?;
+        let camera_serial: Option<String> = row.get(12)?;
+        let clock_offset_secs: i64 = row.get(13)?;
         Ok((
             path_str,
             format_str,
@@ -588,6 +825,9 @@ pub fn load_existing_scanned_files(conn: &Connection, project_id: i64) -> Vec<Sc
             iso,
             focal_length,
             exposure_comp,
+            sequence_number,
+            camera_serial,
+            clock_offset_secs,
         ))
     });
 
@@ -613,6 +853,9 @@ pub fn load_existing_scanned_files(conn: &Connection, project_id: i64) -> Vec<Sc
             iso,
             focal_length,
             exposure_comp,
+            sequence_number,
+            camera_serial,
+            clock_offset_secs,
         ) = row;
         let path = PathBuf::from(&path_str);
         let format = match format_str.as_str() {
@@ -646,6 +889,10 @@ pub fn load_existing_scanned_files(conn: &Connection, project_id: i64) -> Vec<Sc
             exposure_comp,
             base_name,
             dir,
+            fingerprint: None, // already stored on the existing photos row
+            sequence_number,
+            camera_serial,
+            clock_offset_secs,
         });
     }
 
@@ -668,9 +915,247 @@ pub fn list_photo_paths_for_project(
     )
 }
 
+/// Store the content fingerprint of a photo file.
+pub fn set_photo_fingerprint(
+    conn: &Connection,
+    photo_id: i64,
+    file_size: u64,
+    fingerprint: &str,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE photos SET file_size = ?1, fingerprint = ?2 WHERE id = ?3",
+        params![file_size as i64, fingerprint, photo_id],
+    )?;
+    Ok(())
+}
+
+/// Store the camera image/sequence number of a photo.
+pub fn set_photo_sequence_number(
+    conn: &Connection,
+    photo_id: i64,
+    sequence_number: u32,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE photos SET sequence_number = ?1 WHERE id = ?2",
+        params![sequence_number, photo_id],
+    )?;
+    Ok(())
+}
+
+/// Store the body serial number of a photo's camera and the clock offset already
+/// applied to its capture time.
+pub fn set_photo_camera_clock(
+    conn: &Connection,
+    photo_id: i64,
+    camera_serial: Option<&str>,
+    clock_offset_secs: i64,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE photos SET camera_serial = ?1, clock_offset_secs = ?2 WHERE id = ?3",
+        params![camera_serial, clock_offset_secs, photo_id],
+    )?;
+    Ok(())
+}
+
+/// Point a photo row at a new path (moved/renamed file). Decisions are keyed on
+/// logical photos, so they are unaffected.
+pub fn update_photo_path(conn: &Connection, photo_id: i64, path: &str) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE photos SET path = ?1 WHERE id = ?2",
+        params![path, photo_id],
+    )?;
+    Ok(())
+}
+
+/// All photo files of a project with their stored fingerprints.
+pub fn list_photo_files_for_project(
+    conn: &Connection,
+    project_id: i64,
+) -> rusqlite::Result<Vec<PhotoFileRow>> {
+    collect_rows(
+        conn,
+        "SELECT p.id, p.logical_photo_id, p.path, p.file_size, p.fingerprint
+         FROM photos p
+         JOIN logical_photos lp ON lp.id = p.logical_photo_id
+         WHERE lp.project_id = ?1
+         ORDER BY p.path",
+        params![project_id],
+        |row| {
+            let size: Option<i64> = row.get(3)?;
+            Ok(PhotoFileRow {
+                photo_id: row.get(0)?,
+                logical_photo_id: row.get(1)?,
+                path: row.get(2)?,
+                file_size: size.map(|s| s as u64),
+                fingerprint: row.get(4)?,
+            })
+        },
+    )
+}
+
+/// The file of `format` in a logical photo, as (path, orientation).
+/// None if the logical photo has no such file (e.g. a JPEG-only photo has no RAW).
+pub fn find_photo_file(
+    conn: &Connection,
+    logical_photo_id: i64,
+    format: &PhotoFormat,
+) -> rusqlite::Result<Option<(String, Option<u16>)>> {
+    use rusqlite::OptionalExtension;
+    conn.query_row(
+        "SELECT path, orientation FROM photos
+         WHERE logical_photo_id = ?1 AND format = ?2
+         ORDER BY id LIMIT 1",
+        params![logical_photo_id, format.as_str()],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )
+    .optional()
+}
+
+/// Logical photos that consist of a single file, as (lp_id, path, format).
+/// Used by incremental import to complete RAW+JPEG pairs across imports.
+pub fn list_single_file_logical_photos(
+    conn: &Connection,
+    project_id: i64,
+) -> rusqlite::Result<Vec<(i64, String, PhotoFormat)>> {
+    collect_rows(
+        conn,
+        "SELECT lp.id, MIN(p.path), MIN(p.format)
+         FROM logical_photos lp
+         JOIN photos p ON p.logical_photo_id = lp.id
+         WHERE lp.project_id = ?1
+         GROUP BY lp.id
+         HAVING COUNT(p.id) = 1",
+        params![project_id],
+        |row| {
+            let format: String = row.get(2)?;
+            let format = if format == "jpeg" {
+                PhotoFormat::Jpeg
+            } else {
+                PhotoFormat::Raw
+            };
+            Ok((row.get(0)?, row.get(1)?, format))
+        },
+    )
+}
+
+/// Capture-time span (earliest, latest) of each active stack, for slotting new photos.
+#[allow(clippy::type_complexity)]
+pub fn list_active_stack_time_spans(
+    conn: &Connection,
+    project_id: i64,
+) -> rusqlite::Result<Vec<(i64, Option<String>, Option<String>)>> {
+    collect_rows(
+        conn,
+        "SELECT s.id, MIN(p.capture_time), MAX(p.capture_time)
+         FROM stacks s
+         JOIN logical_photos lp ON lp.stack_id = s.id
+         JOIN photos p ON p.id = lp.representative_photo_id
+         WHERE s.project_id = ?1 AND s.active = 1
+         GROUP BY s.id
+         ORDER BY MIN(p.capture_time) ASC",
+        params![project_id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    )
+}
+
+/// Add a stack's logical photos that are in none of its rounds to its open round.
+/// Creates round 1 when the stack has no rounds yet. Returns the round id.
+pub fn add_new_logical_photos_to_open_round(
+    conn: &Connection,
+    project_id: i64,
+    stack_id: i64,
+) -> anyhow::Result<i64> {
+    use rusqlite::OptionalExtension;
+
+    let open_round: Option<i64> = conn
+        .query_row(
+            "SELECT id FROM rounds
+             WHERE project_id = ?1 AND scope = 'stack' AND scope_id = ?2 AND state = 'open'
+             ORDER BY round_number DESC LIMIT 1",
+            params![project_id, stack_id],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    let Some(round_id) = open_round else {
+        return init_round_for_stack(conn, project_id, stack_id);
+    };
+
+    conn.execute(
+        "INSERT INTO round_photos (round_id, logical_photo_id)
+         SELECT ?1, lp.id FROM logical_photos lp
+         WHERE lp.stack_id = ?2
+           AND NOT EXISTS (
+               SELECT 1 FROM round_photos rp
+               JOIN rounds r ON r.id = rp.round_id
+               WHERE rp.logical_photo_id = lp.id AND r.scope = 'stack' AND r.scope_id = ?2
+           )",
+        params![round_id, stack_id],
+    )?;
+    Ok(round_id)
+}
+
+/// Create round 1 for a stack whose logical photos come from other stacks,
+/// pre-seeded with their latest decisions: kept photos get a keep decision in the
+/// new round, eliminated photos stay eliminated and out of it.
+/// Returns `(carried keeps, carried eliminations, photos in the round)`.
+fn init_carried_round_for_stack(
+    conn: &Connection,
+    project_id: i64,
+    stack_id: i64,
+) -> anyhow::Result<(usize, usize, usize)> {
+    use crate::decisions::{engine, model::DecisionAction};
+
+    let round_id = init_round_for_stack(conn, project_id, stack_id)?;
+    let eliminated = conn.execute(
+        "DELETE FROM round_photos WHERE round_id = ?1 AND logical_photo_id IN
+         (SELECT id FROM logical_photos WHERE stack_id = ?2 AND current_status = 'eliminate')",
+        params![round_id, stack_id],
+    )?;
+    let kept: Vec<i64> = collect_rows(
+        conn,
+        "SELECT id FROM logical_photos WHERE stack_id = ?1 AND current_status = 'keep'",
+        params![stack_id],
+        |row| row.get(0),
+    )?;
+    for &lp_id in &kept {
+        engine::record_decision(conn, lp_id, round_id, &DecisionAction::Keep)?;
+    }
+    let in_round: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM round_photos WHERE round_id = ?1",
+        params![round_id],
+        |row| row.get(0),
+    )?;
+    Ok((kept.len(), eliminated, in_round as usize))
+}
+
 // ── Stack merge operations ───────────────────────────────────────────────────
 
-use crate::photos::model::{MergeResult, StackTransaction};
+use crate::photos::model::{
+    ClockShift, ManualGroupKind, ManualGroupToggle, MergeResult, RestackMerge, RestackPreview,
+    RestackSplit, RestackedStack, SplitResult, StackChange, StackTransaction,
+};
+
+/// Append an entry to the project's stack history and return its id.
+/// Recording a new change discards any undone entries: redo only ever replays
+/// the branch that was just undone.
+pub fn log_stack_transaction(
+    conn: &Connection,
+    project_id: i64,
+    action: &str,
+    details: &serde_json::Value,
+) -> rusqlite::Result<i64> {
+    conn.execute(
+        "UPDATE stack_transactions SET state = 'discarded' WHERE project_id = ?1 AND state = 'undone'",
+        params![project_id],
+    )?;
+    let now = chrono::Utc::now().to_rfc3339();
+    conn.execute(
+        "INSERT INTO stack_transactions (project_id, action, details, created_at) VALUES (?1, ?2, ?3, ?4)",
+        params![project_id, action, details.to_string(), now],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
 
 /// Create round 1 for a stack and populate round_photos with all its logical photos.
 /// Used after merge, restack, and undo operations to ensure the decision engine is ready.
@@ -812,18 +1297,29 @@ pub fn merge_stacks(
         let manual_merge_id = conn.last_insert_rowid();
 
         // 9. INSERT INTO stack_transactions
+        let change = StackChange {
+            before: rows.iter().copied().collect(),
+            after: rows
+                .iter()
+                .map(|(lp_id, _)| (*lp_id, new_stack_id))
+                .collect(),
+            stacks_activated: vec![new_stack_id],
+            stacks_deactivated: stack_ids.to_vec(),
+            manual_groups: vec![ManualGroupToggle {
+                kind: ManualGroupKind::Merge,
+                id: manual_merge_id,
+                active: true,
+            }],
+            clock_shift: None,
+        };
         let details = serde_json::json!({
             "source_stack_ids": stack_ids,
             "target_stack_id": new_stack_id,
             "photo_assignments": photo_assignments,
             "manual_merge_id": manual_merge_id,
+            "change": change,
         });
-        let details_str = serde_json::to_string(&details)?;
-        conn.execute(
-            "INSERT INTO stack_transactions (project_id, action, details, created_at) VALUES (?1, 'merge', ?2, ?3)",
-            params![project_id, details_str, now],
-        )?;
-        let transaction_id = conn.last_insert_rowid();
+        let transaction_id = log_stack_transaction(conn, project_id, "merge", &details)?;
 
         // Create round 1 for the merged stack
         init_round_for_stack(conn, project_id, new_stack_id)?;
@@ -858,7 +1354,7 @@ pub fn undo_last_merge(conn: &Connection, project_id: i64) -> anyhow::Result<()>
     // 1. Find last merge transaction
     let row: Option<(i64, String)> = conn
         .query_row(
-            "SELECT id, details FROM stack_transactions WHERE project_id = ?1 AND action = 'merge' ORDER BY id DESC LIMIT 1",
+            "SELECT id, details FROM stack_transactions WHERE project_id = ?1 AND action = 'merge' AND state = 'applied' ORDER BY id DESC LIMIT 1",
             params![project_id],
             |row| Ok((row.get(0)?, row.get(1)?)),
         )
@@ -915,7 +1411,11 @@ pub fn undo_last_merge(conn: &Connection, project_id: i64) -> anyhow::Result<()>
     conn.execute("BEGIN", [])?;
 
     let result = (|| -> anyhow::Result<()> {
-        let now = chrono::Utc::now().to_rfc3339();
+        let mut change = StackChange {
+            stacks_activated: source_stack_ids.clone(),
+            stacks_deactivated: vec![target_stack_id],
+            ..Default::default()
+        };
 
         // 5. Reactivate source stacks
         for &sid in &source_stack_ids {
@@ -932,6 +1432,8 @@ pub fn undo_last_merge(conn: &Connection, project_id: i64) -> anyhow::Result<()>
                 "UPDATE logical_photos SET stack_id = ?1 WHERE id = ?2",
                 params![orig_stack_id, lp_id],
             )?;
+            change.before.insert(lp_id, target_stack_id);
+            change.after.insert(lp_id, orig_stack_id);
         }
 
         // 7. Deactivate the merged stack (soft-delete)
@@ -946,6 +1448,11 @@ pub fn undo_last_merge(conn: &Connection, project_id: i64) -> anyhow::Result<()>
                 "UPDATE manual_merges SET active = 0 WHERE id = ?1",
                 params![manual_merge_id],
             )?;
+            change.manual_groups.push(ManualGroupToggle {
+                kind: ManualGroupKind::Merge,
+                id: manual_merge_id,
+                active: false,
+            });
         } else {
             // Fallback for transactions created before manual_merge_id was stored:
             // deactivate the most recently created active manual_merges row for this project.
@@ -965,12 +1472,301 @@ pub fn undo_last_merge(conn: &Connection, project_id: i64) -> anyhow::Result<()>
             "undone_transaction_id": tx_id,
             "source_stack_ids": source_stack_ids,
             "target_stack_id": target_stack_id,
+            "change": change,
         });
-        let undo_details_str = serde_json::to_string(&undo_details)?;
+        log_stack_transaction(conn, project_id, "undo_merge", &undo_details)?;
+
+        Ok(())
+    })();
+
+    match result {
+        Ok(()) => {
+            conn.execute("COMMIT", [])?;
+            Ok(())
+        }
+        Err(e) => {
+            let _ = conn.execute("ROLLBACK", []);
+            Err(e)
+        }
+    }
+}
+
+/// Split logical photos out of a stack into a new stack of their own.
+/// Removes them from the source stack's open round, creates round 1 for the
+/// new stack, logs a `split` transaction and records a manual_splits group so
+/// restack keeps them apart.
+///
+/// Validation:
+/// - logical_photo_ids must be non-empty and all belong to the (active) stack
+/// - at least one logical photo must stay behind in the source stack
+pub fn split_stack(
+    conn: &Connection,
+    project_id: i64,
+    stack_id: i64,
+    logical_photo_ids: &[i64],
+) -> anyhow::Result<SplitResult> {
+    use anyhow::anyhow;
+
+    // 1. Validate the selection against the stack's members
+    if logical_photo_ids.is_empty() {
+        return Err(anyhow!("split_stack requires at least 1 logical photo id"));
+    }
+    let active: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM stacks WHERE id = ?1 AND project_id = ?2 AND active = 1",
+        params![stack_id, project_id],
+        |row| row.get(0),
+    )?;
+    if active == 0 {
+        return Err(anyhow!(
+            "Stack {} does not exist for project {}",
+            stack_id,
+            project_id
+        ));
+    }
+    let members: Vec<i64> = collect_rows(
+        conn,
+        "SELECT id FROM logical_photos WHERE stack_id = ?1",
+        params![stack_id],
+        |row| row.get(0),
+    )?;
+    let mut lp_ids: Vec<i64> = Vec::new();
+    for &lp_id in logical_photo_ids {
+        if !members.contains(&lp_id) {
+            return Err(anyhow!(
+                "Logical photo {} is not in stack {}",
+                lp_id,
+                stack_id
+            ));
+        }
+        if !lp_ids.contains(&lp_id) {
+            lp_ids.push(lp_id);
+        }
+    }
+    if lp_ids.len() == members.len() {
+        return Err(anyhow!(
+            "Cannot split every photo out of stack {}",
+            stack_id
+        ));
+    }
+
+    // 2. BEGIN TRANSACTION
+    conn.execute("BEGIN", [])?;
+
+    let result = (|| -> anyhow::Result<SplitResult> {
+        // 3. Create new stack row and move the logical photos into it
+        let now = chrono::Utc::now().to_rfc3339();
+        conn.execute(
+            "INSERT INTO stacks (project_id, created_at) VALUES (?1, ?2)",
+            params![project_id, now],
+        )?;
+        let new_stack_id = conn.last_insert_rowid();
+
+        let mut photo_assignments = serde_json::Map::new();
+        for &lp_id in &lp_ids {
+            conn.execute(
+                "UPDATE logical_photos SET stack_id = ?1 WHERE id = ?2",
+                params![new_stack_id, lp_id],
+            )?;
+            photo_assignments.insert(
+                lp_id.to_string(),
+                serde_json::Value::Number(serde_json::Number::from(stack_id)),
+            );
+        }
+
+        // 4. The source stack's open round no longer covers them
+        for &lp_id in &lp_ids {
+            conn.execute(
+                "DELETE FROM round_photos WHERE logical_photo_id = ?1 AND round_id IN
+                 (SELECT id FROM rounds WHERE scope = 'stack' AND scope_id = ?2 AND state = 'open')",
+                params![lp_id, stack_id],
+            )?;
+        }
+
+        // 5. Sticky split group for restack
+        let split_group_json = serde_json::to_string(&lp_ids)?;
         conn.execute(
-            "INSERT INTO stack_transactions (project_id, action, details, created_at) VALUES (?1, 'undo_merge', ?2, ?3)",
-            params![project_id, undo_details_str, now],
+            "INSERT INTO manual_splits (project_id, split_group, created_at, active) VALUES (?1, ?2, ?3, 1)",
+            params![project_id, split_group_json, now],
         )?;
+        let manual_split_id = conn.last_insert_rowid();
+
+        // 6. INSERT INTO stack_transactions
+        let change = StackChange {
+            before: lp_ids.iter().map(|id| (*id, stack_id)).collect(),
+            after: lp_ids.iter().map(|id| (*id, new_stack_id)).collect(),
+            stacks_activated: vec![new_stack_id],
+            stacks_deactivated: Vec::new(),
+            manual_groups: vec![ManualGroupToggle {
+                kind: ManualGroupKind::Split,
+                id: manual_split_id,
+                active: true,
+            }],
+            clock_shift: None,
+        };
+        let details = serde_json::json!({
+            "source_stack_id": stack_id,
+            "target_stack_id": new_stack_id,
+            "photo_assignments": photo_assignments,
+            "manual_split_id": manual_split_id,
+            "change": change,
+        });
+        let transaction_id = log_stack_transaction(conn, project_id, "split", &details)?;
+
+        // Create round 1 for the new stack
+        init_round_for_stack(conn, project_id, new_stack_id)?;
+
+        Ok(SplitResult {
+            new_stack_id,
+            logical_photos_moved: lp_ids.len(),
+            source_stack_id: stack_id,
+            transaction_id,
+        })
+    })();
+
+    match result {
+        Ok(split_result) => {
+            conn.execute("COMMIT", [])?;
+            Ok(split_result)
+        }
+        Err(e) => {
+            let _ = conn.execute("ROLLBACK", []);
+            Err(e)
+        }
+    }
+}
+
+/// Undo the most recent split for this project.
+/// Moves the split-out logical photos back into their source stack (and its
+/// open round) and deactivates the split stack.
+pub fn undo_last_split(conn: &Connection, project_id: i64) -> anyhow::Result<()> {
+    use anyhow::anyhow;
+    use rusqlite::OptionalExtension;
+
+    // 1. Find last split transaction
+    let row: Option<(i64, String)> = conn
+        .query_row(
+            "SELECT id, details FROM stack_transactions WHERE project_id = ?1 AND action = 'split' AND state = 'applied' ORDER BY id DESC LIMIT 1",
+            params![project_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+
+    let (tx_id, details_json) = row.ok_or_else(|| anyhow!("No split transactions to undo"))?;
+
+    // 2. Parse details JSON
+    let details: serde_json::Value = serde_json::from_str(&details_json)?;
+    let source_stack_id = details["source_stack_id"]
+        .as_i64()
+        .ok_or_else(|| anyhow!("Missing source_stack_id in split details"))?;
+    let target_stack_id = details["target_stack_id"]
+        .as_i64()
+        .ok_or_else(|| anyhow!("Missing target_stack_id in split details"))?;
+    let lp_ids: Vec<i64> = details["photo_assignments"]
+        .as_object()
+        .ok_or_else(|| anyhow!("Missing photo_assignments in split details"))?
+        .keys()
+        .map(|k| k.parse())
+        .collect::<Result<_, _>>()?;
+
+    // 3a. Guard: both stacks must still be the live ones (no later merge/restack)
+    for sid in [source_stack_id, target_stack_id] {
+        let active: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM stacks WHERE id = ?1 AND active = 1",
+            params![sid],
+            |row| row.get(0),
+        )?;
+        if active == 0 {
+            return Err(anyhow!(
+                "Cannot undo split: stack {} has since been merged or restacked",
+                sid
+            ));
+        }
+    }
+
+    // 3b. Guard: no culling progress on the split stack
+    let max_round: Option<i32> = conn
+        .query_row(
+            "SELECT MAX(round_number) FROM rounds WHERE scope = 'stack' AND scope_id = ?1",
+            params![target_stack_id],
+            |row| row.get(0),
+        )
+        .unwrap_or(None);
+    if matches!(max_round, Some(rn) if rn > 1) {
+        return Err(anyhow!(
+            "Cannot undo split: stack has progressed beyond round 1"
+        ));
+    }
+    let decision_count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM decisions d
+         JOIN rounds r ON r.id = d.round_id
+         WHERE r.scope = 'stack' AND r.scope_id = ?1",
+        params![target_stack_id],
+        |row| row.get(0),
+    )?;
+    if decision_count > 0 {
+        return Err(anyhow!(
+            "Cannot undo split: decisions have been made on the split stack"
+        ));
+    }
+
+    // 4. BEGIN TRANSACTION
+    conn.execute("BEGIN", [])?;
+
+    let result = (|| -> anyhow::Result<()> {
+        let mut change = StackChange {
+            before: lp_ids.iter().map(|id| (*id, target_stack_id)).collect(),
+            after: lp_ids.iter().map(|id| (*id, source_stack_id)).collect(),
+            stacks_deactivated: vec![target_stack_id],
+            ..Default::default()
+        };
+
+        // 5. Move logical photos back, into the source stack's open round too
+        let open_round: Option<i64> = conn
+            .query_row(
+                "SELECT id FROM rounds WHERE scope = 'stack' AND scope_id = ?1 AND state = 'open'
+                 ORDER BY round_number DESC LIMIT 1",
+                params![source_stack_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        for &lp_id in &lp_ids {
+            conn.execute(
+                "UPDATE logical_photos SET stack_id = ?1 WHERE id = ?2",
+                params![source_stack_id, lp_id],
+            )?;
+            if let Some(round_id) = open_round {
+                conn.execute(
+                    "INSERT OR IGNORE INTO round_photos (round_id, logical_photo_id) VALUES (?1, ?2)",
+                    params![round_id, lp_id],
+                )?;
+            }
+        }
+
+        // 6. Deactivate the split stack (soft-delete) and its manual_splits record
+        conn.execute(
+            "UPDATE stacks SET active = 0 WHERE id = ?1",
+            params![target_stack_id],
+        )?;
+        if let Some(manual_split_id) = details.get("manual_split_id").and_then(|v| v.as_i64()) {
+            conn.execute(
+                "UPDATE manual_splits SET active = 0 WHERE id = ?1",
+                params![manual_split_id],
+            )?;
+            change.manual_groups.push(ManualGroupToggle {
+                kind: ManualGroupKind::Split,
+                id: manual_split_id,
+                active: false,
+            });
+        }
+
+        // 7. Log undo_split transaction
+        let undo_details = serde_json::json!({
+            "undone_transaction_id": tx_id,
+            "source_stack_id": source_stack_id,
+            "target_stack_id": target_stack_id,
+            "change": change,
+        });
+        log_stack_transaction(conn, project_id, "undo_split", &undo_details)?;
 
         Ok(())
     })();
@@ -987,154 +1783,403 @@ pub fn undo_last_merge(conn: &Connection, project_id: i64) -> anyhow::Result<()>
     }
 }
 
-/// Re-stack all existing photos for a project, preserving manual merges.
-/// Manual merge groups are kept together in a single stack; free (non-merged)
-/// logical photos are re-grouped by the burst-gap algorithm.
+/// Load the active manual groups (merges and splits) of a project.
+/// Each logical photo belongs to the group of the most recent operation that
+/// touched it: a later split takes photos out of an earlier merge and vice versa.
+/// Returns the groups oldest first, empty ones dropped.
+fn load_manual_groups(conn: &Connection, project_id: i64) -> rusqlite::Result<Vec<Vec<i64>>> {
+    // (created_at, id, group) — merges sort before splits made in the same instant
+    let mut groups: Vec<(String, usize, i64, Vec<i64>)> = Vec::new();
+    for (kind, sql) in [
+        (
+            0,
+            "SELECT id, merge_group, created_at FROM manual_merges WHERE project_id = ?1 AND active = 1",
+        ),
+        (
+            1,
+            "SELECT id, split_group, created_at FROM manual_splits WHERE project_id = ?1 AND active = 1",
+        ),
+    ] {
+        let rows: Vec<(i64, String, String)> = collect_rows(conn, sql, params![project_id], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })?;
+        for (id, json, created_at) in rows {
+            if let Ok(lp_ids) = serde_json::from_str::<Vec<i64>>(&json) {
+                groups.push((created_at, kind, id, lp_ids));
+            }
+        }
+    }
+    groups.sort_by(|a, b| (&a.0, a.1, a.2).cmp(&(&b.0, b.1, b.2)));
+
+    // Newest claim wins
+    let mut claimed: std::collections::HashSet<i64> = std::collections::HashSet::new();
+    let mut result: Vec<Vec<i64>> = Vec::new();
+    for (_, _, _, mut lp_ids) in groups.into_iter().rev() {
+        lp_ids.retain(|id| !claimed.contains(id));
+        claimed.extend(lp_ids.iter().copied());
+        if !lp_ids.is_empty() {
+            result.push(lp_ids);
+        }
+    }
+    result.reverse();
+    Ok(result)
+}
+
+/// Re-stack all existing photos for a project, preserving manual merges and splits.
+/// Manual merge and split groups are each kept together in a single stack; free
+/// (non-manual) logical photos are re-grouped by the burst-gap algorithm.
+/// Culling decisions survive: a stack whose members are unchanged keeps its
+/// rounds, any other stack opens a round seeded with its photos' keep/eliminate.
 pub fn restack_merge_aware(
     conn: &Connection,
     project_id: i64,
     burst_gap_secs: u64,
 ) -> anyhow::Result<()> {
-    // 1. Load active manual merge groups
-    let merge_groups: Vec<(i64, Vec<i64>)> = {
-        let mut stmt = conn.prepare(
-            "SELECT id, merge_group FROM manual_merges WHERE project_id = ?1 AND active = 1",
-        )?;
-        let rows: Vec<(i64, String)> = stmt
-            .query_map(params![project_id], |row| Ok((row.get(0)?, row.get(1)?)))?
-            .collect::<rusqlite::Result<_>>()?;
-        rows.into_iter()
-            .filter_map(|(id, json)| {
-                let lp_ids: Vec<i64> = serde_json::from_str(&json).ok()?;
-                Some((id, lp_ids))
+    restack_with_rules(conn, project_id, &StackingRules::burst(burst_gap_secs))
+}
+
+/// How a restack would regroup a project's logical photos: applied by
+/// `restack_with_rules`, reported by `preview_restack`.
+struct RestackPlan {
+    manual_groups: Vec<Vec<i64>>,
+    free_groups: Vec<Vec<i64>>,
+    /// logical_photo_id → stack_id before the restack
+    current: std::collections::BTreeMap<i64, i64>,
+}
+
+impl RestackPlan {
+    /// Every new group, free ones first, with the current stack whose members it
+    /// matches exactly, if any. Such a stack is kept as it is.
+    fn groups(&self) -> Vec<(&Vec<i64>, Option<i64>)> {
+        use std::collections::{BTreeSet, HashMap};
+
+        let mut stacks: HashMap<i64, BTreeSet<i64>> = HashMap::new();
+        for (&lp_id, &stack_id) in &self.current {
+            stacks.entry(stack_id).or_default().insert(lp_id);
+        }
+        let stack_by_members: HashMap<BTreeSet<i64>, i64> = stacks
+            .into_iter()
+            .map(|(stack_id, members)| (members, stack_id))
+            .collect();
+        self.free_groups
+            .iter()
+            .chain(self.manual_groups.iter())
+            .map(|group| {
+                let members: BTreeSet<i64> = group.iter().copied().collect();
+                (group, stack_by_members.get(&members).copied())
             })
             .collect()
-    };
+    }
+}
 
-    // 2. Build set of manually merged LP IDs
-    let merged_lp_ids: std::collections::HashSet<i64> = merge_groups
-        .iter()
-        .flat_map(|(_, lps)| lps.iter().copied())
-        .collect();
+/// Group a project's logical photos the way a restack with `rules` would.
+/// Returns `None` when the project has no logical photos.
+fn plan_restack(
+    conn: &Connection,
+    project_id: i64,
+    rules: &StackingRules,
+) -> anyhow::Result<Option<RestackPlan>> {
+    // 1. Load active manual merge and split groups
+    let manual_groups = load_manual_groups(conn, project_id)?;
+
+    // 2. Build set of manually grouped LP IDs
+    let merged_lp_ids: std::collections::HashSet<i64> =
+        manual_groups.iter().flatten().copied().collect();
 
     // 3. Load all LPs with capture times
     let all_lps = load_logical_photos_for_restack(conn, project_id)?;
 
     if all_lps.is_empty() {
-        return Ok(());
+        return Ok(None);
     }
 
-    // 4. Separate into merged_lps and free_lps
-    let mut free_timed: Vec<(i64, chrono::DateTime<chrono::Utc>)> = Vec::new();
-    let mut free_untimed: Vec<i64> = Vec::new();
+    // 4. Free LPs: everything not manually grouped
+    let free_lps: Vec<(i64, Option<chrono::DateTime<chrono::Utc>>, Option<u32>)> = all_lps
+        .iter()
+        .filter(|(lp_id, _, _)| !merged_lp_ids.contains(lp_id))
+        .map(|(lp_id, capture_time_str, sequence_number)| {
+            let time = capture_time_str
+                .as_deref()
+                .and_then(|ct| chrono::DateTime::parse_from_rfc3339(ct).ok())
+                .map(|dt| dt.with_timezone(&chrono::Utc));
+            (*lp_id, time, *sequence_number)
+        })
+        .collect();
 
-    for (lp_id, capture_time_str) in &all_lps {
-        if merged_lp_ids.contains(lp_id) {
-            continue; // Skip merged LPs — they'll be handled by merge groups
+    // 5. Auto-stack free LPs; untimed ones each get their own solo stack
+    let assigned = match rules.strategy {
+        StackingStrategy::Burst => stacks::burst_group(
+            free_lps,
+            rules.burst_gap_secs,
+            |lp| lp.0,
+            |lp| lp.1,
+            |lp| lp.2,
+        ),
+        StackingStrategy::Similarity => {
+            let hashes = load_logical_photo_phashes(conn, project_id)?;
+            stacks::similarity_group(
+                free_lps,
+                rules,
+                |lp| lp.0,
+                |lp| lp.1,
+                |lp| lp.2,
+                |lp| hashes.get(&lp.0).copied(),
+            )
         }
-        if let Some(ref ct_str) = capture_time_str {
-            if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(ct_str) {
-                free_timed.push((*lp_id, dt.with_timezone(&chrono::Utc)));
-                continue;
-            }
+    };
+    let mut free_groups: Vec<Vec<i64>> = Vec::new();
+    for (lp_id, index) in assigned {
+        if free_groups.len() <= index {
+            free_groups.resize_with(index + 1, Vec::new);
         }
-        free_untimed.push(*lp_id);
+        free_groups[index].push(lp_id);
     }
+    free_groups.retain(|group| !group.is_empty());
 
-    // 5. Auto-stack free_lps by burst gap
-    free_timed.sort_by_key(|(_, t)| *t);
+    let current = collect_rows(
+        conn,
+        "SELECT id, stack_id FROM logical_photos WHERE project_id = ?1 AND stack_id IS NOT NULL",
+        params![project_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?
+    .into_iter()
+    .collect();
+
+    Ok(Some(RestackPlan {
+        manual_groups,
+        free_groups,
+        current,
+    }))
+}
 
-    let mut free_groups: Vec<Vec<i64>> = Vec::new();
-    let mut current_group: Vec<i64> = Vec::new();
-    let mut last_time: Option<chrono::DateTime<chrono::Utc>> = None;
-
-    for (lp_id, t) in &free_timed {
-        if let Some(prev) = last_time {
-            let gap = (*t - prev).num_seconds().unsigned_abs();
-            if gap > burst_gap_secs && !current_group.is_empty() {
-                free_groups.push(std::mem::take(&mut current_group));
+/// Work out what `restack_with_rules` would do without writing anything: which
+/// current stacks would be combined or split and which logical photos would land
+/// in a new stack. The similarity strategy only sees hashes already stored.
+pub fn preview_restack(
+    conn: &Connection,
+    project_id: i64,
+    rules: &StackingRules,
+) -> anyhow::Result<RestackPreview> {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    let stacks_before: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM stacks WHERE project_id = ?1 AND active = 1",
+        params![project_id],
+        |row| row.get(0),
+    )?;
+    let mut preview = RestackPreview {
+        strategy: rules.strategy,
+        burst_gap_secs: rules.burst_gap_secs,
+        stacks_before: stacks_before as usize,
+        ..Default::default()
+    };
+    let Some(plan) = plan_restack(conn, project_id, rules)? else {
+        return Ok(preview);
+    };
+    let decided: std::collections::HashSet<i64> = collect_rows(
+        conn,
+        "SELECT id FROM logical_photos WHERE project_id = ?1 AND current_status != 'undecided'",
+        params![project_id],
+        |row| row.get(0),
+    )?
+    .into_iter()
+    .collect();
+
+    // current stack → the new groups its logical photos end up in
+    let mut destinations: BTreeMap<i64, BTreeSet<usize>> = BTreeMap::new();
+    let groups = plan.groups();
+    preview.stacks_after = groups.len();
+    for (index, (group, kept)) in groups.into_iter().enumerate() {
+        if kept.is_some() {
+            preview.stacks_unchanged += 1;
+            continue;
+        }
+        let mut from_stacks: BTreeSet<i64> = BTreeSet::new();
+        for lp_id in group {
+            if let Some(&stack_id) = plan.current.get(lp_id) {
+                from_stacks.insert(stack_id);
+                destinations.entry(stack_id).or_default().insert(index);
+            }
+            preview.moved_photos.push(*lp_id);
+            if decided.contains(lp_id) {
+                preview.decided_photos_affected += 1;
             }
         }
-        current_group.push(*lp_id);
-        last_time = Some(*t);
-    }
-    if !current_group.is_empty() {
-        free_groups.push(current_group);
+        if from_stacks.len() > 1 {
+            preview.merges.push(RestackMerge {
+                from_stacks: from_stacks.into_iter().collect(),
+                photo_count: group.len(),
+            });
+        }
     }
+    preview.splits = destinations
+        .into_iter()
+        .filter(|(_, groups)| groups.len() > 1)
+        .map(|(stack_id, groups)| RestackSplit {
+            stack_id,
+            into_stacks: groups.len(),
+        })
+        .collect();
+    preview.moved_photos.sort_unstable();
+    Ok(preview)
+}
 
-    // Each untimed free LP gets its own solo stack
-    for lp_id in &free_untimed {
-        free_groups.push(vec![*lp_id]);
-    }
+/// Re-stack like `restack_merge_aware`, grouping free logical photos with the
+/// given strategy. The similarity strategy uses the stored perceptual hashes;
+/// logical photos without one are grouped by burst gap.
+pub fn restack_with_rules(
+    conn: &Connection,
+    project_id: i64,
+    rules: &StackingRules,
+) -> anyhow::Result<()> {
+    restack_with_clock_shift(conn, project_id, rules, |_| Ok(None)).map(|_| ())
+}
+
+/// `restack_with_rules` after correcting capture times: `shift` runs first, in
+/// the restack's transaction, and the shift it reports is logged with the
+/// restack so undo puts the times back too. A shift that regroups nothing is
+/// still logged, as a restack without moves. Returns the shift.
+pub fn restack_with_clock_shift(
+    conn: &Connection,
+    project_id: i64,
+    rules: &StackingRules,
+    shift: impl FnOnce(&Connection) -> anyhow::Result<Option<ClockShift>>,
+) -> anyhow::Result<Option<ClockShift>> {
+    use std::collections::BTreeSet;
 
     // 6. BEGIN TRANSACTION
     conn.execute("BEGIN", [])?;
 
-    let result = (|| -> anyhow::Result<()> {
+    let result = (|| -> anyhow::Result<Option<ClockShift>> {
+        let clock_shift = shift(conn)?;
+        let Some(plan) = plan_restack(conn, project_id, rules)? else {
+            if let Some(clock_shift) = &clock_shift {
+                let change = StackChange {
+                    clock_shift: Some(clock_shift.clone()),
+                    ..Default::default()
+                };
+                let details = serde_json::json!({
+                    "strategy": rules.strategy.as_str(),
+                    "burst_gap_secs": rules.burst_gap_secs,
+                    "mapping": Vec::<RestackedStack>::new(),
+                    "change": change,
+                });
+                log_stack_transaction(conn, project_id, "restack", &details)?;
+            }
+            return Ok(clock_shift);
+        };
         let now = chrono::Utc::now().to_rfc3339();
 
-        // 7. Mark all existing stacks as inactive (soft-delete), NULL out stack_id,
-        //    and reset current_status (decisions belonged to old stack's round)
-        conn.execute(
-            "UPDATE logical_photos SET stack_id = NULL, current_status = 'undecided' WHERE project_id = ?1",
-            params![project_id],
-        )?;
-        conn.execute(
-            "UPDATE stacks SET active = 0 WHERE project_id = ?1",
-            params![project_id],
-        )?;
+        // Snapshot the current layout so the restack can be undone
+        let mut change = StackChange {
+            before: plan.current.clone(),
+            stacks_deactivated: collect_rows(
+                conn,
+                "SELECT id FROM stacks WHERE project_id = ?1 AND active = 1",
+                params![project_id],
+                |row| row.get(0),
+            )?,
+            clock_shift: clock_shift.clone(),
+            ..Default::default()
+        };
 
-        // 8. Create stacks for free LP groups
-        for group in &free_groups {
+        // 7. A group with exactly the members of an existing stack keeps that
+        //    stack untouched, round history and decisions included
+        let mut kept_stacks: BTreeSet<i64> = BTreeSet::new();
+        let mut mapping: Vec<RestackedStack> = Vec::new();
+        let mut moved_groups: Vec<&Vec<i64>> = Vec::new();
+        for (group, kept) in plan.groups() {
+            match kept {
+                Some(stack_id) => {
+                    kept_stacks.insert(stack_id);
+                    mapping.push(RestackedStack {
+                        stack_id,
+                        from_stacks: vec![stack_id],
+                        history_kept: true,
+                        ..Default::default()
+                    });
+                }
+                None => moved_groups.push(group),
+            }
+        }
+        change
+            .before
+            .retain(|_, stack_id| !kept_stacks.contains(stack_id));
+        change
+            .stacks_deactivated
+            .retain(|stack_id| !kept_stacks.contains(stack_id));
+
+        // 8. Deactivate every other stack (soft-delete) and NULL out its members'
+        //    stack_id; current_status is kept and carried into the new stacks
+        for &lp_id in change.before.keys() {
             conn.execute(
-                "INSERT INTO stacks (project_id, created_at) VALUES (?1, ?2)",
-                params![project_id, now],
+                "UPDATE logical_photos SET stack_id = NULL WHERE id = ?1",
+                params![lp_id],
+            )?;
+        }
+        for &stack_id in &change.stacks_deactivated {
+            conn.execute(
+                "UPDATE stacks SET active = 0 WHERE id = ?1",
+                params![stack_id],
             )?;
-            let stack_id = conn.last_insert_rowid();
-            for &lp_id in group {
-                conn.execute(
-                    "UPDATE logical_photos SET stack_id = ?1 WHERE id = ?2",
-                    params![stack_id, lp_id],
-                )?;
-            }
-            init_round_for_stack(conn, project_id, stack_id)?;
         }
 
-        // 9. Create stacks for each merge group
-        for (_merge_id, lp_ids) in &merge_groups {
+        // 9. Create a stack for every split, combined or new group, with a fresh
+        //    round seeded from its members' latest decisions
+        for group in moved_groups {
             conn.execute(
                 "INSERT INTO stacks (project_id, created_at) VALUES (?1, ?2)",
                 params![project_id, now],
             )?;
             let stack_id = conn.last_insert_rowid();
-            for &lp_id in lp_ids {
+            let mut from_stacks: BTreeSet<i64> = BTreeSet::new();
+            for &lp_id in group {
                 conn.execute(
                     "UPDATE logical_photos SET stack_id = ?1 WHERE id = ?2",
                     params![stack_id, lp_id],
                 )?;
+                change.after.insert(lp_id, stack_id);
+                from_stacks.extend(change.before.get(&lp_id));
+            }
+            let (carried_keeps, carried_eliminations, in_round) =
+                init_carried_round_for_stack(conn, project_id, stack_id)?;
+            // Nothing left to cull: inactive, like a stack whose round was committed
+            if in_round == 0 {
+                conn.execute(
+                    "UPDATE stacks SET active = 0 WHERE id = ?1",
+                    params![stack_id],
+                )?;
+            } else {
+                change.stacks_activated.push(stack_id);
             }
-            init_round_for_stack(conn, project_id, stack_id)?;
+            mapping.push(RestackedStack {
+                stack_id,
+                from_stacks: from_stacks.into_iter().collect(),
+                history_kept: false,
+                carried_keeps,
+                carried_eliminations,
+            });
         }
 
         // 10. Log restack transaction
         let details = serde_json::json!({
-            "burst_gap_secs": burst_gap_secs,
-            "merge_groups_preserved": merge_groups.len(),
-            "free_groups": free_groups.len(),
+            "strategy": rules.strategy.as_str(),
+            "burst_gap_secs": rules.burst_gap_secs,
+            "manual_groups_preserved": plan.manual_groups.len(),
+            "free_groups": plan.free_groups.len(),
+            "stacks_kept": kept_stacks.len(),
+            "mapping": mapping,
+            "change": change,
         });
-        let details_str = serde_json::to_string(&details)?;
-        conn.execute(
-            "INSERT INTO stack_transactions (project_id, action, details, created_at) VALUES (?1, 'restack', ?2, ?3)",
-            params![project_id, details_str, now],
-        )?;
+        log_stack_transaction(conn, project_id, "restack", &details)?;
 
-        Ok(())
+        Ok(clock_shift)
     })();
 
     match result {
-        Ok(()) => {
+        Ok(clock_shift) => {
             conn.execute("COMMIT", [])?;
-            Ok(())
+            Ok(clock_shift)
         }
         Err(e) => {
             let _ = conn.execute("ROLLBACK", []);
@@ -1150,7 +2195,7 @@ pub fn list_stack_transactions(
 ) -> rusqlite::Result<Vec<StackTransaction>> {
     collect_rows(
         conn,
-        "SELECT id, project_id, action, details, created_at FROM stack_transactions WHERE project_id = ?1 ORDER BY created_at DESC",
+        "SELECT id, project_id, action, details, created_at, state FROM stack_transactions WHERE project_id = ?1 ORDER BY created_at DESC",
         params![project_id],
         |row| {
             Ok(StackTransaction {
@@ -1159,6 +2204,7 @@ pub fn list_stack_transactions(
                 action: row.get(2)?,
                 details: row.get(3)?,
                 created_at: row.get(4)?,
+                state: row.get(5)?,
             })
         },
     )
@@ -1198,6 +2244,31 @@ mod tests {
         assert_eq!(*row_orientation, Some(1u16), "orientation must be Some(1)");
     }
 
+    #[test]
+    fn test_find_photo_file_by_format() {
+        let project = TestLibraryBuilder::new()
+            .add_photo(PhotoSpec {
+                camera: Camera::Canon,
+                orientation: 6,
+                file_type: FileType::Jpeg,
+                capture_time: None,
+                camera_params: None,
+            })
+            .build_db_only();
+        let lp_id = project.lp_ids[0];
+
+        let (path, orientation) = find_photo_file(&project.conn, lp_id, &PhotoFormat::Jpeg)
+            .unwrap()
+            .expect("JPEG file must be found");
+        assert!(path.to_lowercase().ends_with(".jpg"), "path: {}", path);
+        assert_eq!(orientation, Some(6));
+        assert_eq!(
+            find_photo_file(&project.conn, lp_id, &PhotoFormat::Raw).unwrap(),
+            None,
+            "a JPEG-only photo has no RAW"
+        );
+    }
+
     #[test]
     fn test_list_representative_photos_for_lp_ids_empty_input() {
         // Minimal DB — no photos needed, just testing empty-input edge case
@@ -2027,6 +3098,37 @@ mod tests {
         assert_eq!(s.focal_length, None, "focal_length must be None");
     }
 
+    #[test]
+    fn test_query_logical_photos_orders_same_time_frames_by_sequence_number() {
+        // Burst frames within one second: equal capture times, camera numbers
+        // counting down against the row order
+        let (project, _, stacks) = setup_merge_test_db(1, &[3]);
+        let conn = &project.conn;
+        let (stack_id, lps) = &stacks[0];
+        for (i, lp) in lps.iter().enumerate() {
+            conn.execute(
+                "UPDATE photos SET capture_time = '2024-01-01T10:00:00+00:00' WHERE logical_photo_id = ?1",
+                params![lp],
+            )
+            .unwrap();
+            let photo_id: i64 = conn
+                .query_row(
+                    "SELECT representative_photo_id FROM logical_photos WHERE id = ?1",
+                    params![lp],
+                    |row| row.get(0),
+                )
+                .unwrap();
+            set_photo_sequence_number(conn, photo_id, 900 - i as u32).unwrap();
+        }
+
+        let summaries = query_logical_photos_by_stack(conn, *stack_id).unwrap();
+
+        let order: Vec<i64> = summaries.iter().map(|s| s.logical_photo_id).collect();
+        let mut expected = lps.clone();
+        expected.reverse();
+        assert_eq!(order, expected);
+    }
+
     #[test]
     fn test_query_logical_photos_partial_camera_params() {
         use crate::import::test_fixtures::CameraParams;
@@ -2060,6 +3162,24 @@ mod tests {
         assert_eq!(s.focal_length, None, "focal_length must be None");
     }
 
+    #[test]
+    fn test_sort_by_sharpness_puts_sharpest_first_and_unscored_last() {
+        let project = TestLibraryBuilder::new()
+            .with_stack_layout(&[4])
+            .build_db_only();
+        let conn = &project.conn;
+        let lps = project.lp_ids();
+        set_sharpness_scores(conn, &[(lps[0], 12.5), (lps[2], 480.0), (lps[3], 95.0)]).unwrap();
+
+        let mut summaries = query_logical_photos_by_stack(conn, project.stack_id()).unwrap();
+        sort_by_sharpness(&mut summaries);
+
+        let order: Vec<i64> = summaries.iter().map(|s| s.logical_photo_id).collect();
+        assert_eq!(order, vec![lps[2], lps[3], lps[0], lps[1]]);
+        assert_eq!(summaries[0].sharpness, Some(480.0));
+        assert_eq!(summaries[3].sharpness, None, "not scored yet");
+    }
+
     /// BUG: re-indexing a project that has decisions fails with
     /// "Foreign key constraint failed" because clear_stacks_and_logical_photos
     /// deletes logical_photos without first deleting decisions that reference them.
@@ -2484,56 +3604,280 @@ mod tests {
         assert_structural_invariants(conn, project_id);
     }
 
-    // ── §S10 restack must reset current_status for moved photos ────────────
+    // ── §S10 restack carries decisions into the stacks photos move to ──────
+
+    fn latest_restack_mapping(conn: &Connection, project_id: i64) -> Vec<RestackedStack> {
+        let details: String = conn
+            .query_row(
+                "SELECT details FROM stack_transactions WHERE project_id = ?1 AND action = 'restack'
+                 ORDER BY id DESC LIMIT 1",
+                params![project_id],
+                |row| row.get(0),
+            )
+            .unwrap();
+        let details: serde_json::Value = serde_json::from_str(&details).unwrap();
+        serde_json::from_value(details["mapping"].clone()).unwrap()
+    }
+
+    fn current_status_of(conn: &Connection, lp_id: i64) -> String {
+        conn.query_row(
+            "SELECT current_status FROM logical_photos WHERE id = ?1",
+            params![lp_id],
+            |row| row.get(0),
+        )
+        .unwrap()
+    }
 
     #[test]
-    fn test_restack_resets_current_status_for_moved_photos() {
-        use crate::decisions::engine::{find_or_create_round, record_decision};
+    fn test_restack_carries_decisions_into_split_stacks() {
+        use crate::decisions::engine::{
+            find_or_create_round, get_round_decisions, record_decision,
+        };
         use crate::decisions::model::DecisionAction;
 
+        // Untimed photos: restack splits the stack into three solo stacks
         let (project, project_id, stacks) = setup_merge_test_db(1, &[3]);
         let conn = &project.conn;
-        let stack_id = stacks[0].0;
+        let (stack_id, lps) = &stacks[0];
 
-        // Init round, make decisions
-        init_round_for_stack(conn, project_id, stack_id).unwrap();
-        let (round_id, _) = find_or_create_round(conn, project_id, stack_id).unwrap();
-        record_decision(conn, stacks[0].1[0], round_id, &DecisionAction::Keep).unwrap();
-        record_decision(conn, stacks[0].1[1], round_id, &DecisionAction::Eliminate).unwrap();
+        init_round_for_stack(conn, project_id, *stack_id).unwrap();
+        let (round_id, _) = find_or_create_round(conn, project_id, *stack_id).unwrap();
+        record_decision(conn, lps[0], round_id, &DecisionAction::Keep).unwrap();
+        record_decision(conn, lps[1], round_id, &DecisionAction::Eliminate).unwrap();
+
+        restack_merge_aware(conn, project_id, 3600).unwrap();
 
-        // Verify current_status is set
-        let status_before: String = conn
+        // The kept photo is kept in its new stack's fresh round
+        let kept_stack = stack_of(conn, lps[0]);
+        assert_ne!(kept_stack, *stack_id);
+        assert_eq!(current_status_of(conn, lps[0]), "keep");
+        assert_eq!(open_round_members(conn, kept_stack), vec![lps[0]]);
+        let (new_round_id, _) = find_or_create_round(conn, project_id, kept_stack).unwrap();
+        let decisions = get_round_decisions(conn, kept_stack, new_round_id).unwrap();
+        assert!(decisions
+            .iter()
+            .any(|d| d.logical_photo_id == lps[0] && d.current_status == "keep"));
+
+        // The eliminated photo stays eliminated and out of the round; with nothing
+        // left to cull its stack is inactive
+        let eliminated_stack = stack_of(conn, lps[1]);
+        assert_eq!(current_status_of(conn, lps[1]), "eliminate");
+        assert!(open_round_members(conn, eliminated_stack).is_empty());
+        let active: bool = conn
             .query_row(
-                "SELECT current_status FROM logical_photos WHERE id = ?1",
-                params![stacks[0].1[0]],
+                "SELECT active FROM stacks WHERE id = ?1",
+                params![eliminated_stack],
                 |row| row.get(0),
             )
             .unwrap();
+        assert!(!active);
+
+        assert_eq!(current_status_of(conn, lps[2]), "undecided");
+
+        let mapping = latest_restack_mapping(conn, project_id);
+        assert_eq!(mapping.len(), 3);
+        for entry in &mapping {
+            assert_eq!(entry.from_stacks, vec![*stack_id]);
+            assert!(!entry.history_kept);
+        }
+        assert_eq!(mapping.iter().map(|m| m.carried_keeps).sum::<usize>(), 1);
         assert_eq!(
-            status_before, "keep",
-            "status must be 'keep' before restack"
+            mapping
+                .iter()
+                .map(|m| m.carried_eliminations)
+                .sum::<usize>(),
+            1
         );
+    }
 
-        // Restack — photos move to new stacks
-        restack_merge_aware(conn, project_id, 3600).unwrap();
+    #[test]
+    fn test_restack_keeps_unchanged_stack_and_its_rounds() {
+        use crate::decisions::engine::{commit_round, find_or_create_round, record_decision};
+        use crate::decisions::model::DecisionAction;
 
-        // After restack, ALL photos must have current_status = 'undecided'
-        // because their decisions belonged to the old stack's round
-        let statuses: Vec<(i64, String)> = conn
-            .prepare("SELECT id, current_status FROM logical_photos WHERE project_id = ?1")
-            .unwrap()
-            .query_map(params![project_id], |row| Ok((row.get(0)?, row.get(1)?)))
+        let (project, project_id, stacks) = setup_merge_test_db(1, &[3]);
+        let conn = &project.conn;
+        let (stack_id, lps) = &stacks[0];
+        set_burst_times(conn, lps, 1);
+
+        init_round_for_stack(conn, project_id, *stack_id).unwrap();
+        let (r1_id, _) = find_or_create_round(conn, project_id, *stack_id).unwrap();
+        record_decision(conn, lps[2], r1_id, &DecisionAction::Eliminate).unwrap();
+        commit_round(conn, r1_id).unwrap();
+        let (r2_id, _) = find_or_create_round(conn, project_id, *stack_id).unwrap();
+        record_decision(conn, lps[0], r2_id, &DecisionAction::Keep).unwrap();
+
+        restack_merge_aware(conn, project_id, 60).unwrap();
+
+        for lp in lps {
+            assert_eq!(stack_of(conn, *lp), *stack_id);
+        }
+        let (round_id, _) = find_or_create_round(conn, project_id, *stack_id).unwrap();
+        assert_eq!(round_id, r2_id, "round history must be kept");
+        assert_eq!(open_round_members(conn, *stack_id), vec![lps[0], lps[1]]);
+        assert_eq!(current_status_of(conn, lps[0]), "keep");
+        assert_eq!(current_status_of(conn, lps[2]), "eliminate");
+        assert_eq!(
+            latest_restack_mapping(conn, project_id),
+            vec![RestackedStack {
+                stack_id: *stack_id,
+                from_stacks: vec![*stack_id],
+                history_kept: true,
+                ..Default::default()
+            }]
+        );
+        assert_structural_invariants(conn, project_id);
+    }
+
+    #[test]
+    fn test_restack_seeds_combined_stack_from_both_sources() {
+        use crate::decisions::engine::{find_or_create_round, record_decision};
+        use crate::decisions::model::DecisionAction;
+
+        let (project, project_id, stacks) = setup_merge_test_db(2, &[2, 2]);
+        let conn = &project.conn;
+        let ids: Vec<i64> = stacks.iter().map(|(id, _)| *id).collect();
+        let lps: Vec<i64> = stacks.iter().flat_map(|(_, l)| l.clone()).collect();
+        set_burst_times(conn, &lps, 1);
+
+        for (stack_id, members) in &stacks {
+            init_round_for_stack(conn, project_id, *stack_id).unwrap();
+            let (round_id, _) = find_or_create_round(conn, project_id, *stack_id).unwrap();
+            let action = if *stack_id == ids[0] {
+                DecisionAction::Keep
+            } else {
+                DecisionAction::Eliminate
+            };
+            record_decision(conn, members[0], round_id, &action).unwrap();
+        }
+
+        restack_merge_aware(conn, project_id, 60).unwrap();
+
+        let combined = stack_of(conn, lps[0]);
+        assert!(!ids.contains(&combined));
+        for lp in &lps {
+            assert_eq!(stack_of(conn, *lp), combined);
+        }
+        assert_eq!(
+            open_round_members(conn, combined),
+            vec![lps[0], lps[1], lps[3]],
+            "the eliminated photo stays out of the fresh round"
+        );
+        assert_eq!(current_status_of(conn, lps[0]), "keep");
+        assert_eq!(current_status_of(conn, lps[2]), "eliminate");
+        assert_eq!(
+            latest_restack_mapping(conn, project_id),
+            vec![RestackedStack {
+                stack_id: combined,
+                from_stacks: ids.clone(),
+                history_kept: false,
+                carried_keeps: 1,
+                carried_eliminations: 1,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_preview_restack_reports_merge_without_writing() {
+        use crate::decisions::engine::{find_or_create_round, record_decision};
+        use crate::decisions::model::DecisionAction;
+
+        let (project, project_id, stacks) = setup_merge_test_db(2, &[2, 2]);
+        let conn = &project.conn;
+        let ids: Vec<i64> = stacks.iter().map(|(id, _)| *id).collect();
+        let lps: Vec<i64> = stacks.iter().flat_map(|(_, l)| l.clone()).collect();
+        set_burst_times(conn, &lps, 1);
+        init_round_for_stack(conn, project_id, ids[0]).unwrap();
+        let (round_id, _) = find_or_create_round(conn, project_id, ids[0]).unwrap();
+        record_decision(conn, lps[0], round_id, &DecisionAction::Keep).unwrap();
+
+        let preview = preview_restack(conn, project_id, &StackingRules::burst(60)).unwrap();
+
+        assert_eq!(preview.stacks_before, 2);
+        assert_eq!(preview.stacks_after, 1);
+        assert_eq!(preview.stacks_unchanged, 0);
+        assert_eq!(
+            preview.merges,
+            vec![RestackMerge {
+                from_stacks: ids.clone(),
+                photo_count: 4,
+            }]
+        );
+        assert!(preview.splits.is_empty());
+        assert_eq!(preview.moved_photos, lps);
+        assert_eq!(preview.decided_photos_affected, 1);
+
+        for (i, lp) in lps.iter().enumerate() {
+            assert_eq!(stack_of(conn, *lp), ids[i / 2], "preview must not restack");
+        }
+        assert!(list_stack_transactions(conn, project_id)
             .unwrap()
-            .collect::<rusqlite::Result<_>>()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_preview_restack_reports_split_and_unchanged_stacks() {
+        let (project, project_id, stacks) = setup_merge_test_db(2, &[3, 2]);
+        let conn = &project.conn;
+        let (split_id, split_lps) = &stacks[0];
+        set_burst_times(conn, split_lps, 30);
+        // Far from the first stack, one second apart: stays as it is
+        let start = chrono::DateTime::parse_from_rfc3339("2024-01-02T10:00:00Z").unwrap();
+        for (i, lp) in stacks[1].1.iter().enumerate() {
+            conn.execute(
+                "UPDATE photos SET capture_time = ?1 WHERE logical_photo_id = ?2",
+                params![
+                    (start + chrono::Duration::seconds(i as i64)).to_rfc3339(),
+                    lp
+                ],
+            )
             .unwrap();
+        }
 
-        for (lp_id, status) in &statuses {
-            assert_eq!(
-                status, "undecided",
-                "LP {} must have current_status='undecided' after restack, got '{}'",
-                lp_id, status
-            );
+        let preview = preview_restack(conn, project_id, &StackingRules::burst(10)).unwrap();
+
+        assert_eq!(preview.stacks_after, 4);
+        assert_eq!(preview.stacks_unchanged, 1);
+        assert!(preview.merges.is_empty());
+        assert_eq!(
+            preview.splits,
+            vec![RestackSplit {
+                stack_id: *split_id,
+                into_stacks: 3,
+            }]
+        );
+        assert_eq!(&preview.moved_photos, split_lps);
+        assert_eq!(preview.decided_photos_affected, 0);
+    }
+
+    #[test]
+    fn test_suggest_burst_gap_reads_representative_capture_times() {
+        // Two bursts of shots 1s apart, a minute between them; one untimed photo
+        let (project, project_id, stacks) = setup_merge_test_db(1, &[7]);
+        let conn = &project.conn;
+        let lps = &stacks[0].1;
+        set_burst_times(conn, &lps[..3], 1);
+        let start = chrono::DateTime::parse_from_rfc3339("2024-01-01T10:01:00Z").unwrap();
+        for (i, lp) in lps[3..6].iter().enumerate() {
+            conn.execute(
+                "UPDATE photos SET capture_time = ?1 WHERE logical_photo_id = ?2",
+                params![
+                    (start + chrono::Duration::seconds(i as i64)).to_rfc3339(),
+                    lp
+                ],
+            )
+            .unwrap();
         }
+        conn.execute(
+            "UPDATE photos SET capture_time = NULL WHERE logical_photo_id = ?1",
+            params![lps[6]],
+        )
+        .unwrap();
+
+        let suggestion = suggest_burst_gap(conn, project_id).unwrap();
+
+        assert_eq!(suggestion.gaps, 5);
+        assert_eq!(suggestion.suggested_gap_secs, Some(1));
     }
 
     // ── §R1 init_round_for_stack is idempotent — never creates duplicates ──
@@ -3205,4 +4549,195 @@ mod tests {
             all_photos.len()
         );
     }
+
+    // ── Stack split ─────────────────────────────────────────────────────────
+
+    fn stack_of(conn: &Connection, lp_id: i64) -> i64 {
+        conn.query_row(
+            "SELECT stack_id FROM logical_photos WHERE id = ?1",
+            params![lp_id],
+            |row| row.get(0),
+        )
+        .unwrap()
+    }
+
+    fn open_round_members(conn: &Connection, stack_id: i64) -> Vec<i64> {
+        collect_rows(
+            conn,
+            "SELECT rp.logical_photo_id FROM round_photos rp
+             JOIN rounds r ON r.id = rp.round_id
+             WHERE r.scope = 'stack' AND r.scope_id = ?1 AND r.state = 'open'
+             ORDER BY rp.logical_photo_id",
+            params![stack_id],
+            |row| row.get(0),
+        )
+        .unwrap()
+    }
+
+    /// Give the logical photos RFC 3339 capture times `secs` apart, so restack
+    /// treats them as one burst.
+    fn set_burst_times(conn: &Connection, lp_ids: &[i64], secs: i64) {
+        for (i, lp_id) in lp_ids.iter().enumerate() {
+            let t = chrono::DateTime::parse_from_rfc3339("2024-01-01T10:00:00Z").unwrap()
+                + chrono::Duration::seconds(i as i64 * secs);
+            conn.execute(
+                "UPDATE photos SET capture_time = ?1 WHERE logical_photo_id = ?2",
+                params![t.to_rfc3339(), lp_id],
+            )
+            .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_split_stack_moves_photos_into_new_stack_with_round() {
+        // User story 3: eject a photo (or pair) from a stack into its own stack.
+        let (project, project_id, stacks) = setup_merge_test_db(1, &[4]);
+        let conn = &project.conn;
+        let (stack_id, lps) = &stacks[0];
+        init_round_for_stack(conn, project_id, *stack_id).unwrap();
+
+        let result = split_stack(conn, project_id, *stack_id, &[lps[1], lps[3]]).unwrap();
+        assert_eq!(result.logical_photos_moved, 2);
+        assert_eq!(result.source_stack_id, *stack_id);
+        assert_eq!(stack_of(conn, lps[1]), result.new_stack_id);
+        assert_eq!(stack_of(conn, lps[3]), result.new_stack_id);
+        assert_eq!(count_lps_in_stack(conn, *stack_id), 2);
+
+        // Each stack's open round covers exactly its own photos
+        assert_eq!(open_round_members(conn, *stack_id), vec![lps[0], lps[2]]);
+        assert_eq!(
+            open_round_members(conn, result.new_stack_id),
+            vec![lps[1], lps[3]]
+        );
+
+        let txs = list_stack_transactions(conn, project_id).unwrap();
+        let split = txs.iter().find(|t| t.action == "split").unwrap();
+        let details: serde_json::Value = serde_json::from_str(&split.details).unwrap();
+        assert_eq!(details["target_stack_id"], result.new_stack_id);
+        assert_eq!(details["photo_assignments"][lps[1].to_string()], *stack_id);
+    }
+
+    #[test]
+    fn test_split_stack_validation() {
+        let (project, project_id, stacks) = setup_merge_test_db(2, &[2, 2]);
+        let conn = &project.conn;
+        let (stack_a, lps_a) = &stacks[0];
+        let (_, lps_b) = &stacks[1];
+
+        assert!(split_stack(conn, project_id, *stack_a, &[]).is_err());
+        assert!(
+            split_stack(conn, project_id, *stack_a, &[lps_b[0]]).is_err(),
+            "photos of another stack cannot be split out"
+        );
+        assert!(
+            split_stack(conn, project_id, *stack_a, lps_a).is_err(),
+            "splitting out every photo would leave an empty stack"
+        );
+        assert_eq!(count_lps_in_stack(conn, *stack_a), 2, "nothing moved");
+    }
+
+    #[test]
+    fn test_undo_split_restores_stack() {
+        let (project, project_id, stacks) = setup_merge_test_db(1, &[3]);
+        let conn = &project.conn;
+        let (stack_id, lps) = &stacks[0];
+        init_round_for_stack(conn, project_id, *stack_id).unwrap();
+
+        let result = split_stack(conn, project_id, *stack_id, &[lps[2]]).unwrap();
+        undo_last_split(conn, project_id).unwrap();
+
+        assert!(!stack_exists(conn, result.new_stack_id));
+        assert_eq!(stack_of(conn, lps[2]), *stack_id);
+        assert_eq!(open_round_members(conn, *stack_id), lps.clone());
+        let active_splits: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM manual_splits WHERE active = 1",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(active_splits, 0);
+        assert!(
+            undo_last_split(conn, project_id).is_err(),
+            "the split stack is gone, so the same split cannot be undone twice"
+        );
+    }
+
+    #[test]
+    fn test_restack_keeps_manual_split_apart() {
+        // Without the split, a burst restack would regroup all four photos.
+        let (project, project_id, stacks) = setup_merge_test_db(1, &[4]);
+        let conn = &project.conn;
+        let (stack_id, lps) = &stacks[0];
+        set_burst_times(conn, lps, 1);
+
+        split_stack(conn, project_id, *stack_id, &[lps[1]]).unwrap();
+        restack_merge_aware(conn, project_id, 60).unwrap();
+
+        let ejected = stack_of(conn, lps[1]);
+        assert_eq!(count_lps_in_stack(conn, ejected), 1);
+        assert_eq!(stack_of(conn, lps[0]), stack_of(conn, lps[2]));
+        assert_eq!(stack_of(conn, lps[0]), stack_of(conn, lps[3]));
+        assert_ne!(stack_of(conn, lps[0]), ejected);
+    }
+
+    #[test]
+    fn test_split_out_of_manual_merge_survives_restack() {
+        // The most recent manual operation wins: a photo split out of a merged
+        // stack stays out; the rest of the merge stays together.
+        let (project, project_id, stacks) = setup_merge_test_db(2, &[2, 2]);
+        let conn = &project.conn;
+        let ids: Vec<i64> = stacks.iter().map(|(id, _)| *id).collect();
+        let lps: Vec<i64> = stacks.iter().flat_map(|(_, l)| l.clone()).collect();
+        set_burst_times(conn, &lps, 1);
+
+        let merged = merge_stacks(conn, project_id, &ids).unwrap();
+        split_stack(conn, project_id, merged.merged_stack_id, &[lps[3]]).unwrap();
+        restack_merge_aware(conn, project_id, 10).unwrap();
+
+        let merged_stack = stack_of(conn, lps[0]);
+        for lp in &lps[1..3] {
+            assert_eq!(stack_of(conn, *lp), merged_stack);
+        }
+        assert_ne!(stack_of(conn, lps[3]), merged_stack);
+        assert_eq!(count_lps_in_stack(conn, merged_stack), 3);
+    }
+
+    #[test]
+    fn test_similarity_restack_groups_by_look_and_keeps_manual_merge() {
+        // Six photos 30s apart (beyond the burst gap): three of scene A, then
+        // three of scene B. Photos 0 and 5 were merged by hand.
+        let (project, project_id, stacks) = setup_merge_test_db(6, &[1, 1, 1, 1, 1, 1]);
+        let conn = &project.conn;
+        let lps: Vec<i64> = stacks.iter().flat_map(|(_, l)| l.clone()).collect();
+        set_burst_times(conn, &lps, 30);
+        let scene_a = 0x00ff_00ff_00ff_00ffu64;
+        for (i, lp) in lps.iter().enumerate() {
+            let hash = if i < 3 { scene_a } else { !scene_a };
+            set_logical_photo_phash(conn, *lp, hash).unwrap();
+        }
+        merge_stacks(conn, project_id, &[stacks[0].0, stacks[5].0]).unwrap();
+
+        let rules = StackingRules {
+            strategy: StackingStrategy::Similarity,
+            burst_gap_secs: 3,
+            similarity_window_secs: 60,
+            similarity_threshold: 8,
+        };
+        restack_with_rules(conn, project_id, &rules).unwrap();
+
+        assert_eq!(
+            stack_of(conn, lps[0]),
+            stack_of(conn, lps[5]),
+            "manual merge"
+        );
+        assert_eq!(stack_of(conn, lps[1]), stack_of(conn, lps[2]));
+        assert_eq!(stack_of(conn, lps[3]), stack_of(conn, lps[4]));
+        assert_ne!(stack_of(conn, lps[1]), stack_of(conn, lps[3]));
+
+        // Burst with the same gap: every free photo ends up alone
+        restack_with_rules(conn, project_id, &StackingRules::burst(3)).unwrap();
+        assert_eq!(stack_of(conn, lps[0]), stack_of(conn, lps[5]));
+        assert_ne!(stack_of(conn, lps[1]), stack_of(conn, lps[2]));
+    }
 }
@@ -3,6 +3,8 @@ use serde::{Deserialize, Serialize};
 use std::io::Write;
 use std::path::{Path, PathBuf};
 
+use crate::projects::model::ProjectSettings;
+
 fn default_burst_gap() -> u64 {
     3
 }
@@ -24,6 +26,16 @@ impl Default for Config {
     }
 }
 
+impl Config {
+    /// Settings a project inherits until it overrides them.
+    pub fn project_defaults(&self) -> ProjectSettings {
+        ProjectSettings {
+            burst_gap_secs: self.burst_gap_secs,
+            ..ProjectSettings::default()
+        }
+    }
+}
+
 pub fn gemkeep_home() -> PathBuf {
     dirs::home_dir()
         .expect("home dir must exist")
@@ -1,5 +1,9 @@
 use serde::{Deserialize, Serialize};
 
+use crate::export::model::{ExportFilter, ExportMode};
+use crate::import::stacks::StackingRules;
+use crate::photos::model::StackingStrategy;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Project {
     pub id: i64,
@@ -8,3 +12,176 @@ pub struct Project {
     pub created_at: String,
     pub last_opened_at: Option<String>,
 }
+
+/// Effective settings of one project: its stored overrides on top of the
+/// defaults inherited from the global config.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProjectSettings {
+    pub burst_gap_secs: u64,
+    /// Edge length in pixels of the square grid thumbnails.
+    pub thumbnail_size: u32,
+    /// Long edges in pixels of the aspect-preserving thumbnails for comparison
+    /// and single views.
+    pub thumbnail_fit_sizes: Vec<u32>,
+    pub export_mode: ExportMode,
+    pub export_filter: ExportFilter,
+    pub export_preserve_hierarchy: bool,
+    /// Combine a JPEG and a RAW with the same base name into one logical photo.
+    pub pair_raw_jpeg: bool,
+    /// How restack groups photos that are not in a manual group.
+    pub stacking_strategy: StackingStrategy,
+    /// Similarity stacking: how far apart look-alike photos may be and still share a stack.
+    pub similarity_window_secs: u64,
+    /// Similarity stacking: maximum perceptual-hash distance (bits out of 64).
+    pub similarity_threshold: u32,
+}
+
+pub const BURST_GAP_RANGE: std::ops::RangeInclusive<u64> = 1..=3600;
+pub const THUMBNAIL_SIZE_RANGE: std::ops::RangeInclusive<u32> = 64..=1024;
+pub const THUMBNAIL_FIT_SIZE_RANGE: std::ops::RangeInclusive<u32> = 128..=4096;
+pub const MAX_THUMBNAIL_FIT_SIZES: usize = 4;
+pub const SIMILARITY_WINDOW_RANGE: std::ops::RangeInclusive<u64> = 1..=3600;
+pub const SIMILARITY_THRESHOLD_RANGE: std::ops::RangeInclusive<u32> = 0..=32;
+
+impl Default for ProjectSettings {
+    fn default() -> Self {
+        Self {
+            burst_gap_secs: 3,
+            thumbnail_size: 256,
+            thumbnail_fit_sizes: vec![512, 1600],
+            export_mode: ExportMode::Copy,
+            export_filter: ExportFilter::Both,
+            export_preserve_hierarchy: false,
+            pair_raw_jpeg: true,
+            stacking_strategy: StackingStrategy::Burst,
+            similarity_window_secs: 120,
+            similarity_threshold: 10,
+        }
+    }
+}
+
+impl ProjectSettings {
+    /// The rules restack groups free logical photos by.
+    pub fn stacking_rules(&self) -> StackingRules {
+        StackingRules {
+            strategy: self.stacking_strategy,
+            burst_gap_secs: self.burst_gap_secs,
+            similarity_window_secs: self.similarity_window_secs,
+            similarity_threshold: self.similarity_threshold,
+        }
+    }
+}
+
+/// Values a project sets for itself. `None` inherits the global default.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProjectSettingsOverrides {
+    pub burst_gap_secs: Option<u64>,
+    pub thumbnail_size: Option<u32>,
+    pub thumbnail_fit_sizes: Option<Vec<u32>>,
+    pub export_mode: Option<ExportMode>,
+    pub export_filter: Option<ExportFilter>,
+    pub export_preserve_hierarchy: Option<bool>,
+    pub pair_raw_jpeg: Option<bool>,
+    pub stacking_strategy: Option<StackingStrategy>,
+    pub similarity_window_secs: Option<u64>,
+    pub similarity_threshold: Option<u32>,
+}
+
+impl ProjectSettingsOverrides {
+    /// Reject values outside the ranges the pipeline and UI support.
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(gap) = self.burst_gap_secs {
+            if !BURST_GAP_RANGE.contains(&gap) {
+                return Err(format!(
+                    "burst_gap_secs must be between {} and {}, got {}",
+                    BURST_GAP_RANGE.start(),
+                    BURST_GAP_RANGE.end(),
+                    gap
+                ));
+            }
+        }
+        if let Some(size) = self.thumbnail_size {
+            if !THUMBNAIL_SIZE_RANGE.contains(&size) {
+                return Err(format!(
+                    "thumbnail_size must be between {} and {}, got {}",
+                    THUMBNAIL_SIZE_RANGE.start(),
+                    THUMBNAIL_SIZE_RANGE.end(),
+                    size
+                ));
+            }
+        }
+        if let Some(sizes) = &self.thumbnail_fit_sizes {
+            if sizes.len() > MAX_THUMBNAIL_FIT_SIZES {
+                return Err(format!(
+                    "thumbnail_fit_sizes allows at most {} sizes, got {}",
+                    MAX_THUMBNAIL_FIT_SIZES,
+                    sizes.len()
+                ));
+            }
+            if let Some(size) = sizes.iter().find(|s| !THUMBNAIL_FIT_SIZE_RANGE.contains(s)) {
+                return Err(format!(
+                    "thumbnail_fit_sizes must be between {} and {}, got {}",
+                    THUMBNAIL_FIT_SIZE_RANGE.start(),
+                    THUMBNAIL_FIT_SIZE_RANGE.end(),
+                    size
+                ));
+            }
+        }
+        if let Some(window) = self.similarity_window_secs {
+            if !SIMILARITY_WINDOW_RANGE.contains(&window) {
+                return Err(format!(
+                    "similarity_window_secs must be between {} and {}, got {}",
+                    SIMILARITY_WINDOW_RANGE.start(),
+                    SIMILARITY_WINDOW_RANGE.end(),
+                    window
+                ));
+            }
+        }
+        if let Some(threshold) = self.similarity_threshold {
+            if !SIMILARITY_THRESHOLD_RANGE.contains(&threshold) {
+                return Err(format!(
+                    "similarity_threshold must be between {} and {}, got {}",
+                    SIMILARITY_THRESHOLD_RANGE.start(),
+                    SIMILARITY_THRESHOLD_RANGE.end(),
+                    threshold
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// The effective settings: each override replaces the inherited value.
+    pub fn apply(&self, inherited: &ProjectSettings) -> ProjectSettings {
+        ProjectSettings {
+            burst_gap_secs: self.burst_gap_secs.unwrap_or(inherited.burst_gap_secs),
+            thumbnail_size: self.thumbnail_size.unwrap_or(inherited.thumbnail_size),
+            thumbnail_fit_sizes: self
+                .thumbnail_fit_sizes
+                .clone()
+                .unwrap_or_else(|| inherited.thumbnail_fit_sizes.clone()),
+            export_mode: self.export_mode.unwrap_or(inherited.export_mode),
+            export_filter: self.export_filter.unwrap_or(inherited.export_filter),
+            export_preserve_hierarchy: self
+                .export_preserve_hierarchy
+                .unwrap_or(inherited.export_preserve_hierarchy),
+            pair_raw_jpeg: self.pair_raw_jpeg.unwrap_or(inherited.pair_raw_jpeg),
+            stacking_strategy: self
+                .stacking_strategy
+                .unwrap_or(inherited.stacking_strategy),
+            similarity_window_secs: self
+                .similarity_window_secs
+                .unwrap_or(inherited.similarity_window_secs),
+            similarity_threshold: self
+                .similarity_threshold
+                .unwrap_or(inherited.similarity_threshold),
+        }
+    }
+}
+
+/// What `get_project_settings` returns: the values in effect and which of
+/// them the project overrides.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectSettingsView {
+    pub effective: ProjectSettings,
+    pub overrides: ProjectSettingsOverrides,
+}
@@ -1,4 +1,7 @@
-use crate::projects::model::Project;
+use crate::export::model::{ExportFilter, ExportMode};
+use crate::photos::model::StackingStrategy;
+use crate::projects::model::{Project, ProjectSettingsOverrides};
+use rusqlite::OptionalExtension;
 use rusqlite::{params, Connection};
 
 pub fn insert_project(conn: &Connection, name: &str, slug: &str) -> anyhow::Result<Project> {
@@ -58,6 +61,133 @@ pub fn update_last_opened(conn: &Connection, id: i64) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// The settings a project overrides. No row means it inherits everything.
+pub fn get_settings_overrides(
+    conn: &Connection,
+    project_id: i64,
+) -> anyhow::Result<ProjectSettingsOverrides> {
+    let row = conn
+        .query_row(
+            "SELECT burst_gap_secs, thumbnail_size, export_mode, export_filter,
+                    export_preserve_hierarchy, pair_raw_jpeg, stacking_strategy,
+                    similarity_window_secs, similarity_threshold, thumbnail_fit_sizes
+             FROM project_settings WHERE project_id = ?1",
+            params![project_id],
+            |row| {
+                Ok((
+                    row.get::<_, Option<i64>>(0)?,
+                    row.get::<_, Option<i64>>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, Option<String>>(3)?,
+                    row.get::<_, Option<bool>>(4)?,
+                    row.get::<_, Option<bool>>(5)?,
+                    row.get::<_, Option<String>>(6)?,
+                    row.get::<_, Option<i64>>(7)?,
+                    row.get::<_, Option<i64>>(8)?,
+                    row.get::<_, Option<String>>(9)?,
+                ))
+            },
+        )
+        .optional()?;
+    let Some((gap, size, mode, filter, hierarchy, pair, strategy, window, threshold, fit_sizes)) =
+        row
+    else {
+        return Ok(ProjectSettingsOverrides::default());
+    };
+    Ok(ProjectSettingsOverrides {
+        burst_gap_secs: gap.map(|v| v as u64),
+        thumbnail_size: size.map(|v| v as u32),
+        thumbnail_fit_sizes: fit_sizes
+            .map(|s| {
+                parse_size_list(&s)
+                    .ok_or_else(|| anyhow::anyhow!("bad thumbnail_fit_sizes '{}'", s))
+            })
+            .transpose()?,
+        export_mode: mode
+            .map(|m| {
+                ExportMode::parse(&m).ok_or_else(|| anyhow::anyhow!("bad export_mode '{}'", m))
+            })
+            .transpose()?,
+        export_filter: filter
+            .map(|f| {
+                ExportFilter::parse(&f).ok_or_else(|| anyhow::anyhow!("bad export_filter '{}'", f))
+            })
+            .transpose()?,
+        export_preserve_hierarchy: hierarchy,
+        pair_raw_jpeg: pair,
+        stacking_strategy: strategy
+            .map(|s| {
+                StackingStrategy::parse(&s)
+                    .ok_or_else(|| anyhow::anyhow!("bad stacking_strategy '{}'", s))
+            })
+            .transpose()?,
+        similarity_window_secs: window.map(|v| v as u64),
+        similarity_threshold: threshold.map(|v| v as u32),
+    })
+}
+
+/// Sizes stored as comma-separated text, e.g. `512,1600`. Empty is no sizes.
+fn parse_size_list(s: &str) -> Option<Vec<u32>> {
+    s.split(',')
+        .filter(|part| !part.is_empty())
+        .map(|part| part.trim().parse().ok())
+        .collect()
+}
+
+fn format_size_list(sizes: &[u32]) -> String {
+    sizes
+        .iter()
+        .map(|s| s.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Replace a project's overrides after validating them. `None` fields go back
+/// to inheriting the global default.
+pub fn set_settings_overrides(
+    conn: &Connection,
+    project_id: i64,
+    overrides: &ProjectSettingsOverrides,
+) -> anyhow::Result<()> {
+    overrides.validate().map_err(anyhow::Error::msg)?;
+    conn.execute(
+        "INSERT INTO project_settings (project_id, burst_gap_secs, thumbnail_size, export_mode,
+             export_filter, export_preserve_hierarchy, pair_raw_jpeg, stacking_strategy,
+             similarity_window_secs, similarity_threshold, thumbnail_fit_sizes, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
+         ON CONFLICT(project_id) DO UPDATE SET
+             burst_gap_secs = excluded.burst_gap_secs,
+             thumbnail_size = excluded.thumbnail_size,
+             export_mode = excluded.export_mode,
+             export_filter = excluded.export_filter,
+             export_preserve_hierarchy = excluded.export_preserve_hierarchy,
+             pair_raw_jpeg = excluded.pair_raw_jpeg,
+             stacking_strategy = excluded.stacking_strategy,
+             similarity_window_secs = excluded.similarity_window_secs,
+             similarity_threshold = excluded.similarity_threshold,
+             thumbnail_fit_sizes = excluded.thumbnail_fit_sizes,
+             updated_at = excluded.updated_at",
+        params![
+            project_id,
+            overrides.burst_gap_secs.map(|v| v as i64),
+            overrides.thumbnail_size.map(|v| v as i64),
+            overrides.export_mode.map(|m| m.as_str()),
+            overrides.export_filter.map(|f| f.as_str()),
+            overrides.export_preserve_hierarchy,
+            overrides.pair_raw_jpeg,
+            overrides.stacking_strategy.map(|s| s.as_str()),
+            overrides.similarity_window_secs.map(|v| v as i64),
+            overrides.similarity_threshold.map(|v| v as i64),
+            overrides
+                .thumbnail_fit_sizes
+                .as_deref()
+                .map(format_size_list),
+            chrono::Utc::now().to_rfc3339(),
+        ],
+    )?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -124,4 +254,78 @@ mod tests {
         let result = get_project_by_slug(&conn, "nonexistent");
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_settings_overrides_round_trip_and_inherit() {
+        use crate::projects::manager::Config;
+
+        let conn = in_memory_conn();
+        let project = insert_project(&conn, "Venice", "venice").unwrap();
+        let config = Config {
+            burst_gap_secs: 7,
+            ..Config::default()
+        };
+
+        // Nothing stored: every value comes from the global config
+        let overrides = get_settings_overrides(&conn, project.id).unwrap();
+        assert_eq!(overrides, ProjectSettingsOverrides::default());
+        assert_eq!(
+            overrides.apply(&config.project_defaults()).burst_gap_secs,
+            7
+        );
+
+        let stored = ProjectSettingsOverrides {
+            burst_gap_secs: Some(12),
+            export_mode: Some(ExportMode::Hardlink),
+            pair_raw_jpeg: Some(false),
+            stacking_strategy: Some(StackingStrategy::Similarity),
+            thumbnail_fit_sizes: Some(vec![800, 2048]),
+            ..Default::default()
+        };
+        set_settings_overrides(&conn, project.id, &stored).unwrap();
+        assert_eq!(get_settings_overrides(&conn, project.id).unwrap(), stored);
+
+        let effective = stored.apply(&config.project_defaults());
+        assert_eq!(effective.burst_gap_secs, 12);
+        assert_eq!(effective.export_mode, ExportMode::Hardlink);
+        assert!(!effective.pair_raw_jpeg);
+        assert_eq!(effective.stacking_strategy, StackingStrategy::Similarity);
+        assert_eq!(effective.thumbnail_size, 256, "not overridden: inherited");
+
+        // Clearing an override goes back to inheriting
+        set_settings_overrides(&conn, project.id, &ProjectSettingsOverrides::default()).unwrap();
+        let cleared = get_settings_overrides(&conn, project.id).unwrap();
+        assert_eq!(cleared.apply(&config.project_defaults()).burst_gap_secs, 7);
+    }
+
+    #[test]
+    fn test_set_settings_overrides_rejects_out_of_range_values() {
+        let conn = in_memory_conn();
+        let project = insert_project(&conn, "Venice", "venice").unwrap();
+        for bad in [
+            ProjectSettingsOverrides {
+                burst_gap_secs: Some(0),
+                ..Default::default()
+            },
+            ProjectSettingsOverrides {
+                thumbnail_size: Some(16),
+                ..Default::default()
+            },
+            ProjectSettingsOverrides {
+                similarity_threshold: Some(64),
+                ..Default::default()
+            },
+            ProjectSettingsOverrides {
+                thumbnail_fit_sizes: Some(vec![512, 32]),
+                ..Default::default()
+            },
+        ] {
+            assert!(set_settings_overrides(&conn, project.id, &bad).is_err());
+        }
+        assert_eq!(
+            get_settings_overrides(&conn, project.id).unwrap(),
+            ProjectSettingsOverrides::default(),
+            "rejected values must not be stored"
+        );
+    }
 }
@@ -0,0 +1,540 @@
+//! Background job scheduler.
+//!
+//! Long-running work (indexing, thumbnail generation, …) runs as typed jobs with
+//! ids, progress and cancellation. Each project has its own queue: one job runs
+//! at a time per project, the highest-priority queued job goes next (oldest first
+//! within a priority), and different projects run side by side. Every state or
+//! progress change is emitted as a `job-progress` event carrying the `JobInfo`.
+
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Finished jobs kept for `list_jobs`; older ones are dropped.
+const MAX_FINISHED_JOBS: usize = 50;
+
+/// How often `JobHandle::track` samples a job's progress.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobKind {
+    /// Scan, EXIF, pairing, stacking and thumbnails (`start_indexing`).
+    Indexing,
+    /// Thumbnails of photos that lack them (`resume_thumbnails`).
+    Thumbnails,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobPriority {
+    Low,
+    Normal,
+    High,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Completed,
+    Cancelled,
+    Failed,
+}
+
+impl JobStatus {
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            JobStatus::Completed | JobStatus::Cancelled | JobStatus::Failed
+        )
+    }
+}
+
+/// A job as reported by `list_jobs` and the `job-progress` event.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JobInfo {
+    pub id: u64,
+    pub slug: String,
+    pub kind: JobKind,
+    pub priority: JobPriority,
+    pub status: JobStatus,
+    pub done: usize,
+    pub total: usize,
+    /// Set when the job failed.
+    pub error: Option<String>,
+}
+
+/// What to run and how to schedule it.
+pub struct JobSpec {
+    pub slug: String,
+    pub kind: JobKind,
+    pub priority: JobPriority,
+    /// Set by `cancel`; the job must poll it. Jobs that already have a flag
+    /// (such as the project's indexing cancel flag) pass it here.
+    pub cancel: Arc<AtomicBool>,
+    /// Pass `Some(handle)` to emit `job-progress` events; `None` in tests.
+    pub app_handle: Option<tauri::AppHandle>,
+    /// Run instead of the task when the job is cancelled before it starts, to
+    /// undo what the submitter set up for it (such as the project's running flag).
+    pub on_cancel: Option<Cleanup>,
+}
+
+type Task = Box<dyn FnOnce(&JobHandle) -> Result<(), String> + Send>;
+
+pub type Cleanup = Box<dyn FnOnce() + Send>;
+
+struct JobEntry {
+    info: JobInfo,
+    cancel: Arc<AtomicBool>,
+    app_handle: Option<tauri::AppHandle>,
+    /// Taken when the job starts.
+    task: Option<Task>,
+    on_cancel: Option<Cleanup>,
+}
+
+#[derive(Default)]
+struct Queue {
+    next_id: u64,
+    /// Oldest first.
+    jobs: Vec<JobEntry>,
+}
+
+/// Passed to a running job to report progress and check for cancellation.
+pub struct JobHandle {
+    id: u64,
+    cancel: Arc<AtomicBool>,
+    queue: Arc<Mutex<Queue>>,
+}
+
+impl JobHandle {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::SeqCst)
+    }
+
+    /// Report progress. Emits `job-progress` when it changed.
+    pub fn progress(&self, done: usize, total: usize) {
+        update_job(&self.queue, self.id, |info| {
+            if (info.done, info.total) == (done, total) {
+                return false;
+            }
+            info.done = done;
+            info.total = total;
+            true
+        });
+    }
+
+    /// Run `body`, sampling `probe` for `(done, total)` in the background until
+    /// it returns. For jobs that keep their progress elsewhere, such as the
+    /// indexing status.
+    pub fn track<R>(
+        &self,
+        probe: impl Fn() -> (usize, usize) + Sync,
+        body: impl FnOnce() -> R,
+    ) -> R {
+        let finished = AtomicBool::new(false);
+        let result = std::thread::scope(|scope| {
+            scope.spawn(|| {
+                while !finished.load(Ordering::SeqCst) {
+                    let (done, total) = probe();
+                    self.progress(done, total);
+                    std::thread::sleep(PROGRESS_INTERVAL);
+                }
+            });
+            let result = body();
+            finished.store(true, Ordering::SeqCst);
+            result
+        });
+        let (done, total) = probe();
+        self.progress(done, total);
+        result
+    }
+}
+
+/// Per-project job queues, owned by `AppState`.
+#[derive(Default)]
+pub struct Scheduler {
+    queue: Arc<Mutex<Queue>>,
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue a job and start it if its project is idle. Returns the job id.
+    pub fn submit(
+        &self,
+        spec: JobSpec,
+        task: impl FnOnce(&JobHandle) -> Result<(), String> + Send + 'static,
+    ) -> u64 {
+        let (id, info, app_handle) = {
+            let mut queue = self.queue.lock().unwrap();
+            queue.next_id += 1;
+            let id = queue.next_id;
+            let info = JobInfo {
+                id,
+                slug: spec.slug.clone(),
+                kind: spec.kind,
+                priority: spec.priority,
+                status: JobStatus::Queued,
+                done: 0,
+                total: 0,
+                error: None,
+            };
+            queue.jobs.push(JobEntry {
+                info: info.clone(),
+                cancel: spec.cancel,
+                app_handle: spec.app_handle.clone(),
+                task: Some(Box::new(task)),
+                on_cancel: spec.on_cancel,
+            });
+            (id, info, spec.app_handle)
+        };
+        emit_progress(app_handle.as_ref(), &info);
+        tracing::info!(
+            "scheduler: job {} ({:?}) queued for {}",
+            id,
+            spec.kind,
+            spec.slug
+        );
+        dispatch(&self.queue, &spec.slug);
+        id
+    }
+
+    /// Jobs of project `slug` when given, else of every project; oldest first.
+    pub fn list(&self, slug: Option<&str>) -> Vec<JobInfo> {
+        let queue = self.queue.lock().unwrap();
+        queue
+            .jobs
+            .iter()
+            .filter(|job| slug.is_none_or(|s| job.info.slug == s))
+            .map(|job| job.info.clone())
+            .collect()
+    }
+
+    /// Cancel a job: a queued job is dropped and its `on_cancel` run, a running
+    /// one is signalled and stops at its next checkpoint. Returns false if the
+    /// job is unknown or already finished.
+    pub fn cancel(&self, id: u64) -> bool {
+        let (cancelled, slug, dropped) = {
+            let mut queue = self.queue.lock().unwrap();
+            let Some(job) = queue.jobs.iter_mut().find(|job| job.info.id == id) else {
+                return false;
+            };
+            match job.info.status {
+                JobStatus::Queued => {
+                    job.task = None;
+                    job.info.status = JobStatus::Cancelled;
+                    let dropped = (
+                        job.app_handle.clone(),
+                        job.info.clone(),
+                        job.on_cancel.take(),
+                    );
+                    (true, None, Some(dropped))
+                }
+                JobStatus::Running => {
+                    job.cancel.store(true, Ordering::SeqCst);
+                    (true, Some(job.info.slug.clone()), None)
+                }
+                _ => (false, None, None),
+            }
+        };
+        if let Some((app_handle, info, on_cancel)) = dropped {
+            if let Some(on_cancel) = on_cancel {
+                on_cancel();
+            }
+            emit_progress(app_handle.as_ref(), &info);
+        }
+        if let Some(slug) = slug {
+            tracing::info!("scheduler: cancel signalled to job {} of {}", id, slug);
+        }
+        cancelled
+    }
+}
+
+/// Apply `f` to a job's info and emit it if `f` returns true.
+fn update_job(queue: &Mutex<Queue>, id: u64, f: impl FnOnce(&mut JobInfo) -> bool) {
+    let (app_handle, info) = {
+        let mut queue = queue.lock().unwrap();
+        let Some(job) = queue.jobs.iter_mut().find(|job| job.info.id == id) else {
+            return;
+        };
+        if !f(&mut job.info) {
+            return;
+        }
+        (job.app_handle.clone(), job.info.clone())
+    };
+    emit_progress(app_handle.as_ref(), &info);
+}
+
+/// Start the next queued job of `slug` unless one is already running.
+fn dispatch(queue_arc: &Arc<Mutex<Queue>>, slug: &str) {
+    let (task, handle, info, app_handle) = {
+        let mut queue = queue_arc.lock().unwrap();
+        let busy = queue
+            .jobs
+            .iter()
+            .any(|job| job.info.slug == slug && job.info.status == JobStatus::Running);
+        if busy {
+            return;
+        }
+        // Highest priority first; `max_by_key` keeps the last maximum, so
+        // iterate newest first to start the oldest of equal priority
+        let Some(job) = queue
+            .jobs
+            .iter_mut()
+            .rev()
+            .filter(|job| job.info.slug == slug && job.info.status == JobStatus::Queued)
+            .max_by_key(|job| job.info.priority)
+        else {
+            return;
+        };
+        job.info.status = JobStatus::Running;
+        job.on_cancel = None;
+        let handle = JobHandle {
+            id: job.info.id,
+            cancel: Arc::clone(&job.cancel),
+            queue: Arc::clone(queue_arc),
+        };
+        (
+            job.task.take(),
+            handle,
+            job.info.clone(),
+            job.app_handle.clone(),
+        )
+    };
+    emit_progress(app_handle.as_ref(), &info);
+
+    let queue_arc = Arc::clone(queue_arc);
+    let slug = slug.to_string();
+    std::thread::spawn(move || {
+        let result = match task {
+            Some(task) => task(&handle),
+            None => Ok(()),
+        };
+        finish(&queue_arc, &handle, result);
+        dispatch(&queue_arc, &slug);
+    });
+}
+
+/// Record how a job ended and prune old finished jobs.
+fn finish(queue_arc: &Mutex<Queue>, handle: &JobHandle, result: Result<(), String>) {
+    let (app_handle, info) = {
+        let mut queue = queue_arc.lock().unwrap();
+        let Some(job) = queue.jobs.iter_mut().find(|job| job.info.id == handle.id) else {
+            return;
+        };
+        match result {
+            Ok(()) if handle.is_cancelled() => job.info.status = JobStatus::Cancelled,
+            Ok(()) => job.info.status = JobStatus::Completed,
+            Err(e) => {
+                tracing::warn!("scheduler: job {} failed: {}", handle.id, e);
+                job.info.status = JobStatus::Failed;
+                job.info.error = Some(e);
+            }
+        }
+        let ended = (job.app_handle.clone(), job.info.clone());
+
+        let finished = queue
+            .jobs
+            .iter()
+            .filter(|job| job.info.status.is_finished())
+            .count();
+        let mut excess = finished.saturating_sub(MAX_FINISHED_JOBS);
+        queue.jobs.retain(|job| {
+            if excess > 0 && job.info.status.is_finished() {
+                excess -= 1;
+                return false;
+            }
+            true
+        });
+        ended
+    };
+    emit_progress(app_handle.as_ref(), &info);
+}
+
+fn emit_progress(app_handle: Option<&tauri::AppHandle>, info: &JobInfo) {
+    if let Some(handle) = app_handle {
+        use tauri::Emitter;
+        let _ = handle.emit("job-progress", info);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::time::Instant;
+
+    fn spec(slug: &str, priority: JobPriority) -> JobSpec {
+        JobSpec {
+            slug: slug.to_string(),
+            kind: JobKind::Thumbnails,
+            priority,
+            cancel: Arc::new(AtomicBool::new(false)),
+            app_handle: None,
+            on_cancel: None,
+        }
+    }
+
+    fn status_of(scheduler: &Scheduler, id: u64) -> JobStatus {
+        scheduler
+            .list(None)
+            .into_iter()
+            .find(|job| job.id == id)
+            .unwrap()
+            .status
+    }
+
+    fn wait_finished(scheduler: &Scheduler, id: u64) -> JobStatus {
+        let start = Instant::now();
+        loop {
+            let status = status_of(scheduler, id);
+            if status.is_finished() {
+                return status;
+            }
+            assert!(
+                start.elapsed() < Duration::from_secs(5),
+                "job {} never finished",
+                id
+            );
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    /// A job that blocks until the returned sender fires.
+    fn blocker(scheduler: &Scheduler, slug: &str) -> (u64, mpsc::Sender<()>) {
+        let (tx, rx) = mpsc::channel::<()>();
+        let id = scheduler.submit(spec(slug, JobPriority::Normal), move |_| {
+            let _ = rx.recv();
+            Ok(())
+        });
+        (id, tx)
+    }
+
+    #[test]
+    fn test_project_queue_runs_one_job_at_a_time_by_priority() {
+        let scheduler = Scheduler::new();
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let (first, release) = blocker(&scheduler, "iceland");
+
+        let mut ids = vec![];
+        for (name, priority) in [
+            ("low", JobPriority::Low),
+            ("high", JobPriority::High),
+            ("normal", JobPriority::Normal),
+            ("high-2", JobPriority::High),
+        ] {
+            let order = Arc::clone(&order);
+            ids.push(scheduler.submit(spec("iceland", priority), move |_| {
+                order.lock().unwrap().push(name);
+                Ok(())
+            }));
+        }
+        assert_eq!(status_of(&scheduler, first), JobStatus::Running);
+        assert!(ids
+            .iter()
+            .all(|&id| status_of(&scheduler, id) == JobStatus::Queued));
+
+        release.send(()).unwrap();
+        for id in ids {
+            assert_eq!(wait_finished(&scheduler, id), JobStatus::Completed);
+        }
+        assert_eq!(
+            *order.lock().unwrap(),
+            vec!["high", "high-2", "normal", "low"]
+        );
+    }
+
+    #[test]
+    fn test_other_projects_are_not_blocked() {
+        let scheduler = Scheduler::new();
+        let (first, release) = blocker(&scheduler, "iceland");
+
+        let other = scheduler.submit(spec("venice", JobPriority::Low), |_| Ok(()));
+        assert_eq!(wait_finished(&scheduler, other), JobStatus::Completed);
+        assert_eq!(status_of(&scheduler, first), JobStatus::Running);
+        release.send(()).unwrap();
+        wait_finished(&scheduler, first);
+    }
+
+    #[test]
+    fn test_cancel_drops_queued_job_and_signals_running_one() {
+        let scheduler = Scheduler::new();
+        let (tx, rx) = mpsc::channel::<()>();
+        let running = scheduler.submit(spec("iceland", JobPriority::Normal), move |job| {
+            let _ = rx.recv();
+            assert!(job.is_cancelled());
+            Ok(())
+        });
+        let ran = Arc::new(AtomicBool::new(false));
+        let queued = {
+            let ran = Arc::clone(&ran);
+            scheduler.submit(spec("iceland", JobPriority::High), move |_| {
+                ran.store(true, Ordering::SeqCst);
+                Ok(())
+            })
+        };
+
+        assert!(scheduler.cancel(queued));
+        assert_eq!(status_of(&scheduler, queued), JobStatus::Cancelled);
+        assert!(scheduler.cancel(running));
+        tx.send(()).unwrap();
+
+        assert_eq!(wait_finished(&scheduler, running), JobStatus::Cancelled);
+        assert!(!ran.load(Ordering::SeqCst), "cancelled before it started");
+        assert!(!scheduler.cancel(running), "already finished");
+        assert!(!scheduler.cancel(999), "unknown job");
+    }
+
+    #[test]
+    fn test_failed_job_records_error_and_progress_is_kept() {
+        let scheduler = Scheduler::new();
+        let id = scheduler.submit(spec("iceland", JobPriority::Normal), |job| {
+            job.progress(3, 10);
+            Err("disk full".to_string())
+        });
+
+        assert_eq!(wait_finished(&scheduler, id), JobStatus::Failed);
+        let info = scheduler.list(Some("iceland")).pop().unwrap();
+        assert_eq!((info.done, info.total), (3, 10));
+        assert_eq!(info.error.as_deref(), Some("disk full"));
+        assert!(scheduler.list(Some("venice")).is_empty());
+    }
+
+    #[test]
+    fn test_cancelling_queued_job_runs_its_cleanup_only() {
+        let scheduler = Scheduler::new();
+        let (running, release) = blocker(&scheduler, "iceland");
+        let cleaned = Arc::new(Mutex::new(Vec::new()));
+        let with_cleanup = |name: &'static str| JobSpec {
+            on_cancel: Some(Box::new({
+                let cleaned = Arc::clone(&cleaned);
+                move || cleaned.lock().unwrap().push(name)
+            })),
+            ..spec("iceland", JobPriority::Normal)
+        };
+        let queued = scheduler.submit(with_cleanup("queued"), |_| Ok(()));
+        let started = scheduler.submit(with_cleanup("started"), |_| Ok(()));
+
+        assert!(scheduler.cancel(queued));
+        assert_eq!(*cleaned.lock().unwrap(), vec!["queued"]);
+
+        release.send(()).unwrap();
+        wait_finished(&scheduler, running);
+        assert_eq!(wait_finished(&scheduler, started), JobStatus::Completed);
+        assert!(!scheduler.cancel(started));
+        assert_eq!(
+            *cleaned.lock().unwrap(),
+            vec!["queued"],
+            "started jobs keep theirs unrun"
+        );
+    }
+}
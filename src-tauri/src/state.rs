@@ -1,10 +1,11 @@
 use crate::photos::model::IndexingStatus;
 use crate::projects::model::Project;
+use crate::scheduler::Scheduler;
 use rusqlite::Connection;
 use std::collections::HashMap;
 use std::path::PathBuf;
-use std::sync::atomic::{AtomicBool, AtomicUsize};
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize};
+use std::sync::{Arc, Mutex, OnceLock};
 
 /// Per-project indexing context. Each project gets its own isolated set of
 /// indexing state so that operations on one project do not interfere with another.
@@ -13,6 +14,9 @@ pub struct ProjectContext {
     pub cancel_indexing: Arc<AtomicBool>,
     pub pause_indexing: Arc<AtomicBool>,
     pub thumbnails_done_counter: Arc<AtomicUsize>,
+    /// Bumped by every prefetch request; queued prefetch jobs of an older
+    /// generation are dropped.
+    pub prefetch_generation: Arc<AtomicU64>,
 }
 
 impl Default for ProjectContext {
@@ -22,6 +26,7 @@ impl Default for ProjectContext {
             cancel_indexing: Arc::new(AtomicBool::new(false)),
             pause_indexing: Arc::new(AtomicBool::new(false)),
             thumbnails_done_counter: Arc::new(AtomicUsize::new(0)),
+            prefetch_generation: Arc::new(AtomicU64::new(0)),
         }
     }
 }
@@ -37,8 +42,12 @@ pub struct AppState {
     pub db: Mutex<Option<Connection>>,
     pub active_project: Mutex<Option<Project>>,
     pub gemkeep_home: PathBuf,
+    /// Background jobs of every project. Its lock is never held while taking another.
+    pub scheduler: Scheduler,
     /// Per-project indexing contexts. Keyed by project slug.
     project_contexts: Mutex<HashMap<String, Arc<ProjectContext>>>,
+    /// Set once the app is built; unset under the mock runtime in tests.
+    app_handle: OnceLock<tauri::AppHandle>,
 }
 
 impl AppState {
@@ -47,10 +56,22 @@ impl AppState {
             db: Mutex::new(None),
             active_project: Mutex::new(None),
             gemkeep_home,
+            scheduler: Scheduler::new(),
             project_contexts: Mutex::new(HashMap::new()),
+            app_handle: OnceLock::new(),
         }
     }
 
+    /// Record the app handle for work started outside a command, such as
+    /// resuming an interrupted import when a project is opened.
+    pub fn set_app_handle(&self, app_handle: tauri::AppHandle) {
+        let _ = self.app_handle.set(app_handle);
+    }
+
+    pub fn app_handle(&self) -> Option<&tauri::AppHandle> {
+        self.app_handle.get()
+    }
+
     /// Returns the `ProjectContext` for the given slug, creating one if it doesn't exist.
     pub fn get_or_create_context(&self, slug: &str) -> Arc<ProjectContext> {
         let mut contexts = self.project_contexts.lock().unwrap();
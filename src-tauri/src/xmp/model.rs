@@ -0,0 +1,82 @@
+use std::path::{Path, PathBuf};
+
+/// Sidecar file naming convention.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SidecarNaming {
+    /// `IMG_0001.CR2.xmp` — darktable. Each file of a pair gets its own sidecar.
+    Append,
+    /// `IMG_0001.xmp` — Lightroom / Capture One. A pair shares one sidecar.
+    Replace,
+}
+
+impl SidecarNaming {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "append" => Some(SidecarNaming::Append),
+            "replace" => Some(SidecarNaming::Replace),
+            _ => None,
+        }
+    }
+
+    /// Sidecar path for an image file.
+    pub fn sidecar_path(&self, file: &Path) -> PathBuf {
+        match self {
+            SidecarNaming::Append => {
+                let mut name = file.file_name().unwrap_or_default().to_os_string();
+                name.push(".xmp");
+                file.with_file_name(name)
+            }
+            SidecarNaming::Replace => file.with_extension("xmp"),
+        }
+    }
+}
+
+/// Everything GemKeep writes into a sidecar for one logical photo.
+#[derive(Debug, Clone, PartialEq)]
+pub struct XmpMetadata {
+    /// xmp:Rating: -1 = rejected, 0..=5 stars.
+    pub rating: i32,
+    /// xmp:Label; None leaves an existing label untouched.
+    pub label: Option<String>,
+    /// dc:subject keywords (merged with existing ones, never removed).
+    pub subjects: Vec<String>,
+    /// gemkeep:Status — "undecided" | "keep" | "eliminate".
+    pub status: String,
+    /// gemkeep:RoundReached — highest stack round the photo was a member of.
+    pub round_reached: i32,
+    /// gemkeep:GemRoundReached — highest GemStack round, if promoted.
+    pub gem_round_reached: Option<i32>,
+}
+
+/// Result of writing sidecars.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct XmpWriteResult {
+    pub sidecars_written: usize,
+    pub errors: Vec<String>,
+}
+
+/// Culling-relevant properties read from an existing sidecar.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SidecarInfo {
+    /// xmp:Rating: -1 = rejected, 0..=5 stars (darktable's legacy 6 = rejected is mapped to -1).
+    pub rating: Option<i32>,
+    pub label: Option<String>,
+    /// xmpDM:pick: 1 = picked, -1 = rejected, 0 = unflagged.
+    pub pick: Option<i32>,
+    /// xmpDM:good.
+    pub good: Option<bool>,
+    /// gemkeep:Status, present when GemKeep wrote this sidecar.
+    pub gemkeep_status: Option<String>,
+    /// dc:subject keywords.
+    pub subjects: Vec<String>,
+}
+
+/// Result of seeding decisions and tags from sidecars during import.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct XmpImportResult {
+    pub sidecars_read: usize,
+    pub decisions_seeded: usize,
+    pub tags_imported: usize,
+    pub errors: Vec<String>,
+}
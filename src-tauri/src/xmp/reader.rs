@@ -0,0 +1,439 @@
+use quick_xml::events::Event;
+use quick_xml::name::ResolveResult;
+use quick_xml::NsReader;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::{Path, PathBuf};
+
+use super::model::{SidecarInfo, SidecarNaming, XmpImportResult};
+use super::writer::{NS_DC, NS_GEMKEEP, NS_RDF, NS_XMP};
+use crate::decisions::engine;
+use crate::decisions::model::DecisionAction;
+use crate::photos::repository;
+
+pub const NS_XMP_DM: &str = "http://ns.adobe.com/xmp/1.0/DynamicMedia/";
+
+/// Properties we read, identified by (namespace, local name) so any prefix works.
+#[derive(Clone, Copy, PartialEq)]
+enum Property {
+    Rating,
+    Label,
+    Pick,
+    Good,
+    Status,
+    Subject,
+}
+
+fn property_for(ns: &[u8], local: &[u8]) -> Option<Property> {
+    match (ns, local) {
+        (ns, b"Rating") if ns == NS_XMP.as_bytes() => Some(Property::Rating),
+        (ns, b"Label") if ns == NS_XMP.as_bytes() => Some(Property::Label),
+        (ns, b"pick") if ns == NS_XMP_DM.as_bytes() => Some(Property::Pick),
+        (ns, b"good") if ns == NS_XMP_DM.as_bytes() => Some(Property::Good),
+        (ns, b"Status") if ns == NS_GEMKEEP.as_bytes() => Some(Property::Status),
+        (ns, b"subject") if ns == NS_DC.as_bytes() => Some(Property::Subject),
+        _ => None,
+    }
+}
+
+fn apply(info: &mut SidecarInfo, prop: Property, value: &str) {
+    let value = value.trim();
+    match prop {
+        Property::Rating => {
+            // darktable < 3.0 stored "rejected" as rating 6
+            info.rating = value
+                .parse::<f64>()
+                .ok()
+                .map(|r| if r >= 6.0 { -1 } else { r as i32 });
+        }
+        Property::Label => info.label = Some(value.to_string()).filter(|v| !v.is_empty()),
+        Property::Pick => info.pick = value.parse().ok(),
+        Property::Good => info.good = Some(value.eq_ignore_ascii_case("true")),
+        Property::Status => info.gemkeep_status = Some(value.to_string()),
+        Property::Subject => {
+            if !value.is_empty() && !info.subjects.iter().any(|s| s == value) {
+                info.subjects.push(value.to_string());
+            }
+        }
+    }
+}
+
+/// Parse the culling-relevant properties of an XMP packet.
+/// Properties are accepted in both attribute and element form, in any rdf:Description.
+pub fn parse_sidecar(xml: &str) -> anyhow::Result<SidecarInfo> {
+    let xml = xml.strip_prefix('\u{feff}').unwrap_or(xml);
+    let mut reader = NsReader::from_str(xml);
+    let mut info = SidecarInfo::default();
+    let mut saw_description = false;
+
+    // Open elements that are one of our properties (None for anything else)
+    let mut open: Vec<Option<Property>> = Vec::new();
+
+    loop {
+        let (ns, event) = reader.read_resolved_event()?;
+        match event {
+            Event::Eof => break,
+            Event::Start(ref e) | Event::Empty(ref e) => {
+                let is_empty = matches!(event, Event::Empty(_));
+                let element = match ns {
+                    ResolveResult::Bound(n) => property_for(n.as_ref(), e.local_name().as_ref()),
+                    _ => None,
+                };
+
+                if matches!(ns, ResolveResult::Bound(n) if n.as_ref() == NS_RDF.as_bytes())
+                    && e.local_name().as_ref() == b"Description"
+                {
+                    saw_description = true;
+                    for attr in e.attributes() {
+                        let attr = attr?;
+                        if let (ResolveResult::Bound(n), local) = reader.resolve_attribute(attr.key)
+                        {
+                            if let Some(prop) = property_for(n.as_ref(), local.as_ref()) {
+                                apply(&mut info, prop, &attr.unescape_value()?);
+                            }
+                        }
+                    }
+                }
+
+                if !is_empty {
+                    // rdf:li inherits dc:subject so keyword bags resolve to Subject
+                    let inherited = open
+                        .last()
+                        .copied()
+                        .flatten()
+                        .filter(|p| *p == Property::Subject);
+                    open.push(element.or(inherited));
+                }
+            }
+            Event::Text(ref t) => {
+                if let Some(Some(prop)) = open.last() {
+                    apply(&mut info, *prop, &t.unescape()?);
+                }
+            }
+            Event::End(_) => {
+                open.pop();
+            }
+            _ => {}
+        }
+    }
+
+    if !saw_description {
+        anyhow::bail!("no rdf:Description found");
+    }
+    Ok(info)
+}
+
+/// Read one sidecar file.
+pub fn read_sidecar(path: &Path) -> anyhow::Result<SidecarInfo> {
+    parse_sidecar(&std::fs::read_to_string(path)?)
+}
+
+/// Existing sidecars for an image file: darktable's `IMG.CR2.xmp` first, then
+/// Lightroom's `IMG.xmp` (either extension case).
+pub fn sidecar_candidates(file: &Path) -> Vec<PathBuf> {
+    let mut found = Vec::new();
+    for naming in [SidecarNaming::Append, SidecarNaming::Replace] {
+        let lower = naming.sidecar_path(file);
+        for candidate in [lower.clone(), lower.with_extension("XMP")] {
+            if candidate.is_file() && !found.contains(&candidate) {
+                found.push(candidate);
+            }
+        }
+    }
+    found
+}
+
+/// The decision a sidecar implies, if any.
+///
+/// A GemKeep-written status wins outright (including "undecided"). Otherwise a
+/// reject (rating -1, explicit 0 stars, pick -1) eliminates and a pick flag
+/// (xmpDM:pick 1 or xmpDM:good) keeps; plain star ratings and labels are not a decision.
+pub fn decision_from_sidecar(info: &SidecarInfo) -> Option<DecisionAction> {
+    if let Some(status) = &info.gemkeep_status {
+        return match status.as_str() {
+            "keep" => Some(DecisionAction::Keep),
+            "eliminate" => Some(DecisionAction::Eliminate),
+            _ => None,
+        };
+    }
+    if matches!(info.rating, Some(r) if r <= 0) || info.pick == Some(-1) {
+        return Some(DecisionAction::Eliminate);
+    }
+    if info.pick == Some(1) || info.good == Some(true) {
+        return Some(DecisionAction::Keep);
+    }
+    None
+}
+
+/// Seed round-1 decisions and tags for freshly imported logical photos from any
+/// sidecars next to their files. When the files of a pair disagree the photo is
+/// left undecided; keywords from all sidecars are merged.
+pub fn import_sidecars_for_logical_photos(
+    conn: &Connection,
+    project_id: i64,
+    logical_photo_ids: &[i64],
+) -> rusqlite::Result<XmpImportResult> {
+    let mut result = XmpImportResult::default();
+
+    for &lp_id in logical_photo_ids {
+        let paths: Vec<String> = repository::collect_rows(
+            conn,
+            "SELECT path FROM photos WHERE logical_photo_id = ?1 ORDER BY path",
+            params![lp_id],
+            |row| row.get(0),
+        )?;
+
+        let mut decisions: Vec<Option<DecisionAction>> = Vec::new();
+        let mut subjects: Vec<String> = Vec::new();
+        for path in &paths {
+            for sidecar in sidecar_candidates(Path::new(path)) {
+                match read_sidecar(&sidecar) {
+                    Ok(info) => {
+                        result.sidecars_read += 1;
+                        decisions.push(decision_from_sidecar(&info));
+                        for s in info.subjects {
+                            if !subjects.contains(&s) {
+                                subjects.push(s);
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        tracing::warn!("xmp read {} failed: {}", sidecar.display(), e);
+                        result
+                            .errors
+                            .push(format!("{}: {}", sidecar.to_string_lossy(), e));
+                    }
+                }
+            }
+        }
+
+        for tag in &subjects {
+            repository::add_tag_to_logical_photo(conn, project_id, lp_id, tag)?;
+            result.tags_imported += 1;
+        }
+
+        let decided: Vec<&DecisionAction> = decisions.iter().flatten().collect();
+        let Some(action) = decided.first() else {
+            continue;
+        };
+        if decided.iter().any(|d| d != action) {
+            continue;
+        }
+
+        // Only the open first round is seeded — never a later or committed one
+        let round_id: Option<i64> = conn
+            .query_row(
+                "SELECT r.id FROM round_photos rp
+                 JOIN rounds r ON r.id = rp.round_id
+                 WHERE rp.logical_photo_id = ?1 AND r.scope = 'stack'
+                   AND r.round_number = 1 AND r.state = 'open'",
+                params![lp_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if let Some(round_id) = round_id {
+            engine::record_decision(conn, lp_id, round_id, action)?;
+            result.decisions_seeded += 1;
+        }
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::test_fixtures::TestLibraryBuilder;
+    use crate::photos::model::PhotoFormat;
+    use crate::photos::repository::init_round_for_stack;
+
+    fn packet(description: &str) -> String {
+        format!(
+            r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+ <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+  {}
+ </rdf:RDF>
+</x:xmpmeta>"#,
+            description
+        )
+    }
+
+    #[test]
+    fn test_parse_lightroom_attribute_form() {
+        let xml = packet(
+            r#"<rdf:Description rdf:about="" xmlns:xmp="http://ns.adobe.com/xap/1.0/"
+                 xmlns:dc="http://purl.org/dc/elements/1.1/"
+                 xmp:Rating="3" xmp:Label="Blue">
+                 <dc:subject><rdf:Bag><rdf:li>venice</rdf:li><rdf:li>canal</rdf:li></rdf:Bag></dc:subject>
+               </rdf:Description>"#,
+        );
+        let info = parse_sidecar(&xml).unwrap();
+        assert_eq!(info.rating, Some(3));
+        assert_eq!(info.label.as_deref(), Some("Blue"));
+        assert_eq!(info.subjects, vec!["venice", "canal"]);
+        assert_eq!(
+            decision_from_sidecar(&info),
+            None,
+            "stars alone are not a decision"
+        );
+    }
+
+    #[test]
+    fn test_parse_element_form_with_other_prefixes() {
+        // Prefixes are resolved by namespace URI, not by their spelling
+        let xml = packet(
+            r#"<rdf:Description rdf:about="" xmlns:xap="http://ns.adobe.com/xap/1.0/"
+                 xmlns:xmpDM="http://ns.adobe.com/xmp/1.0/DynamicMedia/">
+                 <xap:Rating>-1</xap:Rating>
+                 <xmpDM:pick>1</xmpDM:pick>
+               </rdf:Description>"#,
+        );
+        let info = parse_sidecar(&xml).unwrap();
+        assert_eq!(info.rating, Some(-1));
+        assert_eq!(info.pick, Some(1));
+        assert_eq!(
+            decision_from_sidecar(&info),
+            Some(DecisionAction::Eliminate),
+            "an explicit reject wins over a pick"
+        );
+    }
+
+    #[test]
+    fn test_decision_mapping() {
+        let with = |f: &dyn Fn(&mut SidecarInfo)| {
+            let mut info = SidecarInfo::default();
+            f(&mut info);
+            decision_from_sidecar(&info)
+        };
+        assert_eq!(
+            with(&|i| i.rating = Some(0)),
+            Some(DecisionAction::Eliminate)
+        );
+        assert_eq!(with(&|i| i.pick = Some(1)), Some(DecisionAction::Keep));
+        assert_eq!(with(&|i| i.good = Some(true)), Some(DecisionAction::Keep));
+        assert_eq!(with(&|i| i.rating = Some(5)), None);
+        assert_eq!(with(&|_| {}), None);
+        // GemKeep's own status overrides the rating it wrote alongside
+        assert_eq!(
+            with(&|i| {
+                i.rating = Some(0);
+                i.gemkeep_status = Some("undecided".to_string());
+            }),
+            None
+        );
+    }
+
+    #[test]
+    fn test_darktable_legacy_reject_rating() {
+        let xml = packet(
+            r#"<rdf:Description rdf:about="" xmlns:xmp="http://ns.adobe.com/xap/1.0/" xmp:Rating="6"/>"#,
+        );
+        assert_eq!(parse_sidecar(&xml).unwrap().rating, Some(-1));
+    }
+
+    #[test]
+    fn test_parse_rejects_packet_without_description() {
+        assert!(parse_sidecar("<x:xmpmeta xmlns:x=\"adobe:ns:meta/\"/>").is_err());
+        assert!(parse_sidecar("not xml <<<").is_err());
+    }
+
+    #[test]
+    fn test_import_seeds_round_one_and_tags() {
+        // Sprint 12: pre-culled photos arrive with their decisions and keywords.
+        let project = TestLibraryBuilder::new()
+            .with_stack_layout(&[3])
+            .build_db_only();
+        let conn = &project.conn;
+        let lp_ids = project.lp_ids.clone();
+        init_round_for_stack(conn, project.project_id, project.stack_ids[0]).unwrap();
+
+        // Point photos at real files so sidecars can sit next to them
+        for (i, lp_id) in lp_ids.iter().enumerate() {
+            let path = project.dir.path().join(format!("IMG_{}.CR2", i));
+            std::fs::write(&path, b"raw").unwrap();
+            conn.execute(
+                "UPDATE photos SET path = ?1 WHERE logical_photo_id = ?2",
+                params![path.to_string_lossy(), lp_id],
+            )
+            .unwrap();
+        }
+        let dir = project.dir.path();
+        std::fs::write(
+            dir.join("IMG_0.CR2.xmp"),
+            packet(r#"<rdf:Description rdf:about="" xmlns:xmp="http://ns.adobe.com/xap/1.0/" xmp:Rating="-1"/>"#),
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("IMG_1.xmp"),
+            packet(
+                r#"<rdf:Description rdf:about="" xmlns:xmpDM="http://ns.adobe.com/xmp/1.0/DynamicMedia/"
+                     xmlns:dc="http://purl.org/dc/elements/1.1/" xmpDM:pick="1">
+                     <dc:subject><rdf:Bag><rdf:li>portfolio</rdf:li></rdf:Bag></dc:subject>
+                   </rdf:Description>"#,
+            ),
+        )
+        .unwrap();
+
+        let result = import_sidecars_for_logical_photos(conn, project.project_id, &lp_ids).unwrap();
+        assert_eq!(result.sidecars_read, 2);
+        assert_eq!(result.decisions_seeded, 2);
+        assert_eq!(result.tags_imported, 1);
+
+        let status = |id: i64| -> String {
+            conn.query_row(
+                "SELECT current_status FROM logical_photos WHERE id = ?1",
+                [id],
+                |r| r.get(0),
+            )
+            .unwrap()
+        };
+        assert_eq!(status(lp_ids[0]), "eliminate");
+        assert_eq!(status(lp_ids[1]), "keep");
+        assert_eq!(status(lp_ids[2]), "undecided");
+        assert_eq!(
+            repository::list_tags_for_logical_photo(conn, lp_ids[1]).unwrap(),
+            vec!["portfolio"]
+        );
+    }
+
+    #[test]
+    fn test_import_leaves_conflicting_pair_undecided() {
+        let project = TestLibraryBuilder::new()
+            .with_stack_layout(&[1])
+            .build_db_only();
+        let conn = &project.conn;
+        let lp_id = project.lp_ids[0];
+        let dir = project.dir.path();
+        init_round_for_stack(conn, project.project_id, project.stack_ids[0]).unwrap();
+
+        // Give the logical photo a RAW + JPEG pair with opposite verdicts
+        let raw = dir.join("IMG.CR2");
+        let jpg = dir.join("IMG.JPG");
+        std::fs::write(&raw, b"raw").unwrap();
+        std::fs::write(&jpg, b"jpg").unwrap();
+        conn.execute(
+            "UPDATE photos SET path = ?1 WHERE logical_photo_id = ?2",
+            params![raw.to_string_lossy(), lp_id],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO photos (path, format, logical_photo_id) VALUES (?1, ?2, ?3)",
+            params![jpg.to_string_lossy(), PhotoFormat::Jpeg.as_str(), lp_id],
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("IMG.CR2.xmp"),
+            packet(r#"<rdf:Description rdf:about="" xmlns:xmp="http://ns.adobe.com/xap/1.0/" xmp:Rating="0"/>"#),
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("IMG.JPG.xmp"),
+            packet(r#"<rdf:Description rdf:about="" xmlns:xmpDM="http://ns.adobe.com/xmp/1.0/DynamicMedia/" xmpDM:good="True"/>"#),
+        )
+        .unwrap();
+
+        let result =
+            import_sidecars_for_logical_photos(conn, project.project_id, &[lp_id]).unwrap();
+        assert_eq!(result.sidecars_read, 2);
+        assert_eq!(result.decisions_seeded, 0);
+    }
+}
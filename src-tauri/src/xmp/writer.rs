@@ -0,0 +1,497 @@
+use quick_xml::events::{BytesEnd, BytesStart, BytesText, Event};
+use quick_xml::{Reader, Writer};
+use rusqlite::{params, Connection};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use super::model::{SidecarNaming, XmpMetadata, XmpWriteResult};
+use crate::photos::repository;
+
+pub const NS_XMP: &str = "http://ns.adobe.com/xap/1.0/";
+pub const NS_DC: &str = "http://purl.org/dc/elements/1.1/";
+pub const NS_RDF: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+pub const NS_GEMKEEP: &str = "https://gemkeep.app/ns/xmp/1.0/";
+
+/// Minimal packet used when no sidecar exists yet; the metadata is merged into it.
+const EMPTY_PACKET: &str = "<?xpacket begin=\"\u{feff}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>
+<x:xmpmeta xmlns:x=\"adobe:ns:meta/\" x:xmptk=\"GemKeep\">
+ <rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">
+  <rdf:Description rdf:about=\"\"/>
+ </rdf:RDF>
+</x:xmpmeta>
+<?xpacket end=\"w\"?>
+";
+
+/// Derive the sidecar metadata for a logical photo from the decisions engine state.
+///
+/// Rating: eliminated → -1 (reject); keep → rounds reached (1..=5);
+/// undecided → rounds fully survived (0..=5). GemStack rounds count on top of
+/// stack rounds. Label: keep → "Green", eliminate → "Red".
+pub fn load_xmp_metadata(
+    conn: &Connection,
+    logical_photo_id: i64,
+) -> rusqlite::Result<XmpMetadata> {
+    let status: String = conn.query_row(
+        "SELECT current_status FROM logical_photos WHERE id = ?1",
+        params![logical_photo_id],
+        |row| row.get(0),
+    )?;
+
+    let max_round = |scope: &str| -> rusqlite::Result<Option<i32>> {
+        conn.query_row(
+            "SELECT MAX(r.round_number) FROM round_photos rp
+             JOIN rounds r ON r.id = rp.round_id
+             WHERE rp.logical_photo_id = ?1 AND r.scope = ?2",
+            params![logical_photo_id, scope],
+            |row| row.get(0),
+        )
+    };
+    let round_reached = max_round("stack")?.unwrap_or(0);
+    let gem_round_reached = max_round("gem")?;
+
+    let reached = round_reached + gem_round_reached.unwrap_or(0);
+    let (rating, label) = match status.as_str() {
+        "eliminate" => (-1, Some("Red".to_string())),
+        "keep" => (reached.clamp(1, 5), Some("Green".to_string())),
+        _ => ((reached - 1).clamp(0, 5), None),
+    };
+
+    Ok(XmpMetadata {
+        rating,
+        label,
+        subjects: repository::list_tags_for_logical_photo(conn, logical_photo_id)?,
+        status,
+        round_reached,
+        gem_round_reached,
+    })
+}
+
+/// Write (or merge into) one sidecar. An unparsable existing sidecar is left
+/// untouched and reported as an error rather than overwritten.
+pub fn write_sidecar(path: &Path, meta: &XmpMetadata) -> anyhow::Result<()> {
+    let existing = match std::fs::read_to_string(path) {
+        Ok(s) => Some(s),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e.into()),
+    };
+    let merged = merge_xmp(existing.as_deref().unwrap_or(EMPTY_PACKET), meta)?;
+
+    // Write to a temp file and rename so a crash never leaves a half-written sidecar
+    let tmp = path.with_extension("xmp.tmp");
+    std::fs::write(&tmp, merged)?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// Write sidecars for a logical photo next to the given image files.
+/// Files sharing a sidecar (pairs with `Replace` naming) are written once.
+pub fn write_sidecars_for_files(
+    meta: &XmpMetadata,
+    files: &[PathBuf],
+    naming: SidecarNaming,
+) -> XmpWriteResult {
+    let mut result = XmpWriteResult::default();
+    let mut seen = HashSet::new();
+    for file in files {
+        let sidecar = naming.sidecar_path(file);
+        if !seen.insert(sidecar.clone()) {
+            continue;
+        }
+        match write_sidecar(&sidecar, meta) {
+            Ok(()) => result.sidecars_written += 1,
+            Err(e) => {
+                tracing::warn!("xmp write {} failed: {}", sidecar.display(), e);
+                result
+                    .errors
+                    .push(format!("{}: {}", sidecar.to_string_lossy(), e));
+            }
+        }
+    }
+    result
+}
+
+/// Write sidecars next to the source files of each logical photo.
+pub fn write_sidecars_for_logical_photos(
+    conn: &Connection,
+    logical_photo_ids: &[i64],
+    naming: SidecarNaming,
+) -> rusqlite::Result<XmpWriteResult> {
+    let mut result = XmpWriteResult::default();
+    for &lp_id in logical_photo_ids {
+        let meta = load_xmp_metadata(conn, lp_id)?;
+        let files: Vec<PathBuf> = repository::collect_rows(
+            conn,
+            "SELECT path FROM photos WHERE logical_photo_id = ?1 ORDER BY path",
+            params![lp_id],
+            |row| row.get::<_, String>(0),
+        )?
+        .into_iter()
+        .map(PathBuf::from)
+        .collect();
+        let r = write_sidecars_for_files(&meta, &files, naming);
+        result.sidecars_written += r.sidecars_written;
+        result.errors.extend(r.errors);
+    }
+    Ok(result)
+}
+
+/// Merge GemKeep's properties into an existing XMP packet.
+///
+/// Only the first `rdf:Description` is touched. Our properties (xmp:Rating,
+/// xmp:Label when set, gemkeep:*) replace existing values in either attribute or
+/// element form; dc:subject keywords are unioned; everything else is copied
+/// through unchanged.
+pub fn merge_xmp(existing: &str, meta: &XmpMetadata) -> anyhow::Result<String> {
+    let existing = existing.strip_prefix('\u{feff}').unwrap_or(existing);
+    let mut reader = Reader::from_str(existing);
+    let mut writer = Writer::new(Vec::new());
+
+    let owned = owned_properties(meta);
+
+    let mut done = false;
+    let mut in_description = false;
+    let mut depth = 0usize; // element depth below the rdf:Description
+    let mut skip_depth = 0usize; // > 0 while dropping one of our elements
+    let mut in_subject = false;
+    let mut in_li = false;
+    let mut subjects: Vec<String> = Vec::new();
+
+    loop {
+        let event = reader.read_event()?;
+        if let Event::Eof = event {
+            break;
+        }
+
+        if !done && !in_description {
+            match &event {
+                Event::Start(e) if e.name().as_ref() == b"rdf:Description" => {
+                    writer.write_event(Event::Start(rewrite_description(e, meta, &owned)?))?;
+                    in_description = true;
+                    continue;
+                }
+                Event::Empty(e) if e.name().as_ref() == b"rdf:Description" => {
+                    let start = rewrite_description(e, meta, &owned)?;
+                    writer.write_event(Event::Start(start))?;
+                    write_subject(&mut writer, &merge_subjects(&subjects, &meta.subjects))?;
+                    writer.write_event(Event::End(BytesEnd::new("rdf:Description")))?;
+                    done = true;
+                    continue;
+                }
+                _ => {}
+            }
+            writer.write_event(event)?;
+            continue;
+        }
+
+        if in_description {
+            if skip_depth > 0 {
+                match &event {
+                    Event::Start(e) => {
+                        skip_depth += 1;
+                        in_li = in_subject && e.name().as_ref() == b"rdf:li";
+                    }
+                    Event::End(_) => {
+                        skip_depth -= 1;
+                        in_li = false;
+                        if skip_depth == 0 {
+                            in_subject = false;
+                        }
+                    }
+                    Event::Text(t) if in_li => {
+                        let text = t.unescape()?.trim().to_string();
+                        if !text.is_empty() {
+                            subjects.push(text);
+                        }
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+
+            match &event {
+                Event::Start(e) if depth == 0 && is_owned(e.name().as_ref(), &owned) => {
+                    skip_depth = 1;
+                    in_subject = e.name().as_ref() == b"dc:subject";
+                    continue;
+                }
+                Event::Empty(e) if depth == 0 && is_owned(e.name().as_ref(), &owned) => {
+                    continue;
+                }
+                Event::Start(_) => depth += 1,
+                Event::End(_) if depth == 0 => {
+                    // Closing rdf:Description: emit merged keywords first
+                    write_subject(&mut writer, &merge_subjects(&subjects, &meta.subjects))?;
+                    writer.write_event(event)?;
+                    in_description = false;
+                    done = true;
+                    continue;
+                }
+                Event::End(_) => depth -= 1,
+                _ => {}
+            }
+        }
+        writer.write_event(event)?;
+    }
+
+    if !done {
+        anyhow::bail!("no rdf:Description found in existing sidecar");
+    }
+    Ok(String::from_utf8(writer.into_inner())?)
+}
+
+/// Qualified names GemKeep owns for this write. dc:subject is always handled
+/// (merged), xmp:Label only when we have a label to write.
+fn owned_properties(meta: &XmpMetadata) -> Vec<&'static str> {
+    let mut owned = vec!["xmp:Rating", "dc:subject"];
+    if meta.label.is_some() {
+        owned.push("xmp:Label");
+    }
+    owned
+}
+
+fn is_owned(name: &[u8], owned: &[&str]) -> bool {
+    name.starts_with(b"gemkeep:") || owned.iter().any(|o| o.as_bytes() == name)
+}
+
+/// Copy the description's attributes minus the ones we own, make sure our
+/// namespaces are declared, then add our simple-valued properties as attributes.
+fn rewrite_description(
+    e: &BytesStart<'_>,
+    meta: &XmpMetadata,
+    owned: &[&str],
+) -> anyhow::Result<BytesStart<'static>> {
+    let mut start = BytesStart::new("rdf:Description");
+    let mut declared: HashSet<Vec<u8>> = HashSet::new();
+    for attr in e.attributes() {
+        let attr = attr?;
+        let key = attr.key.as_ref();
+        if is_owned(key, owned) {
+            continue;
+        }
+        if key.starts_with(b"xmlns:") {
+            declared.insert(key.to_vec());
+        }
+        start.push_attribute(attr);
+    }
+
+    for (prefix, ns) in [
+        ("xmlns:xmp", NS_XMP),
+        ("xmlns:dc", NS_DC),
+        ("xmlns:gemkeep", NS_GEMKEEP),
+    ] {
+        if !declared.contains(prefix.as_bytes()) {
+            start.push_attribute((prefix, ns));
+        }
+    }
+
+    start.push_attribute(("xmp:Rating", meta.rating.to_string().as_str()));
+    if let Some(label) = &meta.label {
+        start.push_attribute(("xmp:Label", label.as_str()));
+    }
+    start.push_attribute(("gemkeep:Status", meta.status.as_str()));
+    start.push_attribute((
+        "gemkeep:RoundReached",
+        meta.round_reached.to_string().as_str(),
+    ));
+    if let Some(gem_round) = meta.gem_round_reached {
+        start.push_attribute(("gemkeep:GemRoundReached", gem_round.to_string().as_str()));
+    }
+    Ok(start)
+}
+
+/// Existing keywords first (order preserved), then new tags not already present.
+fn merge_subjects(existing: &[String], ours: &[String]) -> Vec<String> {
+    let mut merged: Vec<String> = Vec::new();
+    for s in existing.iter().chain(ours.iter()) {
+        if !merged.contains(s) {
+            merged.push(s.clone());
+        }
+    }
+    merged
+}
+
+fn write_subject(writer: &mut Writer<Vec<u8>>, subjects: &[String]) -> anyhow::Result<()> {
+    if subjects.is_empty() {
+        return Ok(());
+    }
+    writer.write_event(Event::Start(BytesStart::new("dc:subject")))?;
+    writer.write_event(Event::Start(BytesStart::new("rdf:Bag")))?;
+    for s in subjects {
+        writer.write_event(Event::Start(BytesStart::new("rdf:li")))?;
+        writer.write_event(Event::Text(BytesText::new(s)))?;
+        writer.write_event(Event::End(BytesEnd::new("rdf:li")))?;
+    }
+    writer.write_event(Event::End(BytesEnd::new("rdf:Bag")))?;
+    writer.write_event(Event::End(BytesEnd::new("dc:subject")))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decisions::engine::{commit_round, find_or_create_round, record_decision};
+    use crate::decisions::model::DecisionAction;
+    use crate::import::test_fixtures::TestLibraryBuilder;
+
+    fn meta(rating: i32, label: Option<&str>, subjects: &[&str]) -> XmpMetadata {
+        XmpMetadata {
+            rating,
+            label: label.map(str::to_string),
+            subjects: subjects.iter().map(|s| s.to_string()).collect(),
+            status: "keep".to_string(),
+            round_reached: 2,
+            gem_round_reached: None,
+        }
+    }
+
+    /// Pull the value of `name` from either attribute or element form.
+    fn property(xml: &str, name: &str) -> Option<String> {
+        let attr = format!("{}=\"", name);
+        if let Some(i) = xml.find(&attr) {
+            let rest = &xml[i + attr.len()..];
+            return Some(rest[..rest.find('"').unwrap()].to_string());
+        }
+        let open = format!("<{}>", name);
+        let i = xml.find(&open)?;
+        let rest = &xml[i + open.len()..];
+        Some(rest[..rest.find('<').unwrap()].to_string())
+    }
+
+    #[test]
+    fn test_new_sidecar_has_rating_label_and_namespaces() {
+        let xml = merge_xmp(EMPTY_PACKET, &meta(2, Some("Green"), &["print"])).unwrap();
+        assert_eq!(property(&xml, "xmp:Rating").as_deref(), Some("2"));
+        assert_eq!(property(&xml, "xmp:Label").as_deref(), Some("Green"));
+        assert_eq!(property(&xml, "gemkeep:RoundReached").as_deref(), Some("2"));
+        assert!(xml.contains(NS_XMP) && xml.contains(NS_DC) && xml.contains(NS_GEMKEEP));
+        assert!(xml.contains("<rdf:li>print</rdf:li>"));
+    }
+
+    #[test]
+    fn test_merge_preserves_foreign_properties_and_keywords() {
+        // Lightroom-style sidecar with element-form rating and keywords
+        let existing = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+ <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+  <rdf:Description rdf:about=""
+    xmlns:xmp="http://ns.adobe.com/xap/1.0/"
+    xmlns:dc="http://purl.org/dc/elements/1.1/"
+    xmlns:crs="http://ns.adobe.com/camera-raw-settings/1.0/"
+    crs:Exposure2012="+0.35">
+   <xmp:Rating>5</xmp:Rating>
+   <dc:subject>
+    <rdf:Bag>
+     <rdf:li>holiday</rdf:li>
+     <rdf:li>print</rdf:li>
+    </rdf:Bag>
+   </dc:subject>
+   <crs:ToneCurvePV2012>
+    <rdf:Seq><rdf:li>0, 0</rdf:li></rdf:Seq>
+   </crs:ToneCurvePV2012>
+  </rdf:Description>
+ </rdf:RDF>
+</x:xmpmeta>"#;
+
+        let xml = merge_xmp(existing, &meta(3, Some("Green"), &["print", "family"])).unwrap();
+
+        assert_eq!(property(&xml, "xmp:Rating").as_deref(), Some("3"));
+        assert_eq!(
+            xml.matches("xmp:Rating").count(),
+            1,
+            "old element-form rating must be replaced, not duplicated: {}",
+            xml
+        );
+        assert!(xml.contains("crs:Exposure2012=\"+0.35\""));
+        assert!(
+            xml.contains("<rdf:li>0, 0</rdf:li>"),
+            "tone curve must survive"
+        );
+        assert_eq!(xml.matches("<rdf:li>print</rdf:li>").count(), 1);
+        assert!(xml.contains("<rdf:li>holiday</rdf:li>"));
+        assert!(xml.contains("<rdf:li>family</rdf:li>"));
+        assert_eq!(xml.matches("xmlns:xmp=").count(), 1);
+    }
+
+    #[test]
+    fn test_merge_without_label_keeps_existing_label() {
+        let existing = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/"><rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#"><rdf:Description xmlns:xmp="http://ns.adobe.com/xap/1.0/" xmp:Label="Purple" xmp:Rating="4"/></rdf:RDF></x:xmpmeta>"#;
+        let xml = merge_xmp(existing, &meta(1, None, &[])).unwrap();
+        assert_eq!(property(&xml, "xmp:Label").as_deref(), Some("Purple"));
+        assert_eq!(property(&xml, "xmp:Rating").as_deref(), Some("1"));
+    }
+
+    #[test]
+    fn test_merge_is_idempotent() {
+        let m = meta(2, Some("Green"), &["print"]);
+        let once = merge_xmp(EMPTY_PACKET, &m).unwrap();
+        let twice = merge_xmp(&once, &m).unwrap();
+        assert_eq!(once, twice);
+    }
+
+    #[test]
+    fn test_unparsable_sidecar_is_not_clobbered() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("IMG_0001.xmp");
+        std::fs::write(&path, "not xml at all").unwrap();
+        assert!(write_sidecar(&path, &meta(1, None, &[])).is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "not xml at all");
+    }
+
+    #[test]
+    fn test_sidecar_naming() {
+        let f = Path::new("/photos/IMG_0001.CR2");
+        assert_eq!(
+            SidecarNaming::Append.sidecar_path(f),
+            PathBuf::from("/photos/IMG_0001.CR2.xmp")
+        );
+        assert_eq!(
+            SidecarNaming::Replace.sidecar_path(f),
+            PathBuf::from("/photos/IMG_0001.xmp")
+        );
+    }
+
+    #[test]
+    fn test_load_metadata_maps_decisions_to_rating() {
+        // keep after surviving round 1 → reached round 2 → 2 stars, Green;
+        // eliminated → -1 (reject), Red; undecided in round 2 → 1 star.
+        let project = TestLibraryBuilder::new()
+            .with_stack_layout(&[3])
+            .build_db_only();
+        let conn = &project.conn;
+        let lps = &project.lp_ids;
+        let (r1, _) = find_or_create_round(conn, project.project_id, project.stack_ids[0]).unwrap();
+        record_decision(conn, lps[0], r1, &DecisionAction::Eliminate).unwrap();
+        commit_round(conn, r1).unwrap();
+        let (r2, _) = find_or_create_round(conn, project.project_id, project.stack_ids[0]).unwrap();
+        record_decision(conn, lps[1], r2, &DecisionAction::Keep).unwrap();
+        repository::add_tag_to_logical_photo(conn, project.project_id, lps[1], "portfolio")
+            .unwrap();
+
+        let eliminated = load_xmp_metadata(conn, lps[0]).unwrap();
+        assert_eq!(eliminated.rating, -1);
+        assert_eq!(eliminated.label.as_deref(), Some("Red"));
+        assert_eq!(eliminated.round_reached, 1);
+
+        let kept = load_xmp_metadata(conn, lps[1]).unwrap();
+        assert_eq!(kept.rating, 2);
+        assert_eq!(kept.label.as_deref(), Some("Green"));
+        assert_eq!(kept.subjects, vec!["portfolio".to_string()]);
+
+        let undecided = load_xmp_metadata(conn, lps[2]).unwrap();
+        assert_eq!(undecided.rating, 1);
+        assert_eq!(undecided.label, None);
+    }
+
+    #[test]
+    fn test_pair_with_replace_naming_writes_one_sidecar() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = vec![dir.path().join("IMG_1.CR2"), dir.path().join("IMG_1.JPG")];
+        let r = write_sidecars_for_files(&meta(1, None, &[]), &files, SidecarNaming::Replace);
+        assert_eq!(r.sidecars_written, 1);
+        assert!(dir.path().join("IMG_1.xmp").exists());
+
+        let r = write_sidecars_for_files(&meta(1, None, &[]), &files, SidecarNaming::Append);
+        assert_eq!(r.sidecars_written, 2);
+        assert!(dir.path().join("IMG_1.CR2.xmp").exists());
+        assert!(dir.path().join("IMG_1.JPG.xmp").exists());
+    }
+}
@@ -33,6 +33,40 @@ export async function deleteProject(slug: string): Promise<void> {
   return invoke('delete_project', { slug })
 }
 
+export type StackingStrategy = 'burst' | 'similarity'
+
+export interface ProjectSettings {
+  burst_gap_secs: number
+  thumbnail_size: number
+  thumbnail_fit_sizes: number[]
+  export_mode: ExportMode
+  export_filter: ExportFilter
+  export_preserve_hierarchy: boolean
+  pair_raw_jpeg: boolean
+  stacking_strategy: StackingStrategy
+  similarity_window_secs: number
+  similarity_threshold: number
+}
+
+// null = inherit the global default
+export type ProjectSettingsOverrides = { [K in keyof ProjectSettings]: ProjectSettings[K] | null }
+
+export interface ProjectSettingsView {
+  effective: ProjectSettings
+  overrides: ProjectSettingsOverrides
+}
+
+export async function getProjectSettings(slug: string): Promise<ProjectSettingsView> {
+  return invoke('get_project_settings', { slug })
+}
+
+export async function setProjectSettings(
+  slug: string,
+  overrides: ProjectSettingsOverrides,
+): Promise<ProjectSettingsView> {
+  return invoke('set_project_settings', { slug, overrides })
+}
+
 export interface ImportStats {
   total_files_scanned: number
   imported: number
@@ -42,7 +76,11 @@ export interface ImportStats {
   pairs_detected: number
   stacks_generated: number
   logical_photos: number
+  sidecar_decisions: number
+  relinked: number
+  offline_folders: number
   error_log: string[]
+  failed: boolean
 }
 
 export interface IndexingStatus {
@@ -61,6 +99,40 @@ export interface IndexingStatus {
 export interface SourceFolder {
   id: number
   path: string
+  volume_uuid: string | null
+  volume_label: string | null
+  relative_path: string | null
+  online: boolean
+}
+
+export interface ReconnectedFolder {
+  folder_id: number
+  old_path: string
+  new_path: string
+  photos_updated: number
+}
+
+export interface SourceFileIssue {
+  photo_id: number
+  logical_photo_id: number | null
+  path: string
+  found_at: string | null
+}
+
+export interface SourceVerification {
+  checked: number
+  ok: number
+  missing: SourceFileIssue[]
+  moved: SourceFileIssue[]
+  changed: SourceFileIssue[]
+  fingerprints_added: number
+  offline: number
+}
+
+export interface RelinkResult {
+  relinked: number
+  still_missing: number
+  folders_added: string[]
 }
 
 export interface StackSummary {
@@ -84,8 +156,20 @@ export async function listSourceFolders(slug: string): Promise<SourceFolder[]> {
   return invoke('list_source_folders', { slug })
 }
 
-export async function startIndexing(slug: string): Promise<void> {
-  return invoke('start_indexing', { slug })
+export async function reconnectSources(slug: string): Promise<ReconnectedFolder[]> {
+  return invoke('reconnect_sources', { slug })
+}
+
+export async function verifySources(slug: string, searchPaths: string[] | null = null): Promise<SourceVerification> {
+  return invoke('verify_sources', { slug, searchPaths })
+}
+
+export async function relinkSources(slug: string, searchPaths: string[] | null = null): Promise<RelinkResult> {
+  return invoke('relink_sources', { slug, searchPaths })
+}
+
+export async function startIndexing(slug: string, fullRebuild?: boolean): Promise<void> {
+  return invoke('start_indexing', { slug, fullRebuild })
 }
 
 export async function cancelIndexing(slug: string): Promise<void> {
@@ -104,6 +188,26 @@ export async function getIndexingStatus(slug: string): Promise<IndexingStatus> {
   return invoke('get_indexing_status', { slug })
 }
 
+// Background jobs; each change is also emitted as a 'job-progress' event carrying a JobInfo
+export interface JobInfo {
+  id: number
+  slug: string
+  kind: 'indexing' | 'thumbnails'
+  priority: 'low' | 'normal' | 'high'
+  status: 'queued' | 'running' | 'completed' | 'cancelled' | 'failed'
+  done: number
+  total: number
+  error: string | null
+}
+
+export async function listJobs(slug: string | null = null): Promise<JobInfo[]> {
+  return invoke<JobInfo[]>('list_jobs', { slug })
+}
+
+export async function cancelJob(jobId: number): Promise<boolean> {
+  return invoke<boolean>('cancel_job', { jobId })
+}
+
 export async function listStacks(slug: string): Promise<StackSummary[]> {
   return invoke('list_stacks', { slug })
 }
@@ -120,10 +224,18 @@ export interface LogicalPhotoSummary {
   shutter_speed:    string | null
   iso:              number | null
   focal_length:     number | null
+  sharpness:        number | null  // higher = sharper; null until the thumbnail pass scores it
 }
 
-export function listLogicalPhotos(slug: string, stackId: number, roundId?: number): Promise<LogicalPhotoSummary[]> {
-  return invoke('list_logical_photos', { slug, stackId, roundId })
+export type PhotoOrder = 'capture_time' | 'sharpness'
+
+export function listLogicalPhotos(
+  slug: string,
+  stackId: number,
+  roundId?: number,
+  order?: PhotoOrder,
+): Promise<LogicalPhotoSummary[]> {
+  return invoke('list_logical_photos', { slug, stackId, roundId, order })
 }
 
 export function getThumbnailUrl(path: string): string {
@@ -134,18 +246,56 @@ export async function resumeThumbnails(slug: string): Promise<void> {
   return invoke('resume_thumbnails', { slug })
 }
 
-export async function getBurstGap(): Promise<number> {
-  return await invoke<number>('get_burst_gap')
+// With a slug these read/write the project's burst gap; without, the global default
+export async function getBurstGap(slug: string | null = null): Promise<number> {
+  return await invoke<number>('get_burst_gap', { slug })
+}
+
+export async function setBurstGap(secs: number, slug: string | null = null): Promise<void> {
+  await invoke('set_burst_gap', { slug, secs })
+}
+
+export interface GapBucket {
+  min_secs: number
+  max_secs: number | null
+  count: number
 }
 
-export async function setBurstGap(secs: number): Promise<void> {
-  await invoke('set_burst_gap', { secs })
+export interface BurstGapSuggestion {
+  suggested_gap_secs: number | null
+  gaps: number
+  cameras: number
+  histogram: GapBucket[]
+}
+
+export async function suggestBurstGap(slug: string): Promise<BurstGapSuggestion> {
+  return invoke('suggest_burst_gap', { slug })
 }
 
 export async function restack(slug: string): Promise<void> {
   await invoke('restack', { slug })
 }
 
+export interface RestackPreview {
+  strategy: StackingStrategy
+  burst_gap_secs: number
+  stacks_before: number
+  stacks_after: number
+  stacks_unchanged: number
+  merges: { from_stacks: number[]; photo_count: number }[]
+  splits: { stack_id: number; into_stacks: number }[]
+  moved_photos: number[]
+  decided_photos_affected: number
+}
+
+export async function previewRestack(
+  slug: string,
+  burstGapSecs: number,
+  strategy: StackingStrategy,
+): Promise<RestackPreview> {
+  return invoke('preview_restack', { slug, burstGapSecs, strategy })
+}
+
 export async function expandSourceScopes(slug: string): Promise<void> {
   return invoke('expand_source_scopes', { slug })
 }
@@ -195,14 +345,31 @@ export interface PhotoDetail {
   iso: number | null
   focal_length: number | null
   exposure_comp: number | null
+  sharpness: number | null
+  histogram: PhotoHistogram | null  // null until the thumbnail pass caches it
   jpeg_path: string | null
   raw_path: string | null
   preview_path: string | null  // full-size RAW embedded preview (SingleView fallback)
+  source_online: boolean       // false while the source drive is unplugged
+}
+
+// OOC JPEG, the RAW's embedded preview, or the RAW demosaiced on demand
+export type PreviewVariant = 'ooc_jpeg' | 'embedded_preview' | 'raw_render'
+
+export interface PhotoHistogram {
+  luminance: number[]  // 256 bins each
+  red: number[]
+  green: number[]
+  blue: number[]
+  highlight_clipping_pct: number  // 0–100
+  shadow_clipping_pct: number
 }
 
 export interface PhotoDecisionStatus {
   logical_photo_id: number
   current_status: DecisionStatus
+  suggestion: QualityFlagKind | null  // the suggestion the decision was applied from
+  suggestion_confidence: number | null  // 0–1
 }
 
 export interface MergeResult {
@@ -212,12 +379,20 @@ export interface MergeResult {
   transaction_id: number
 }
 
+export interface SplitResult {
+  new_stack_id: number
+  logical_photos_moved: number
+  source_stack_id: number
+  transaction_id: number
+}
+
 export interface StackTransaction {
   id: number
   project_id: number
   action: string
   details: string
   created_at: string
+  state: 'applied' | 'undone' | 'discarded'
 }
 
 // Sprint 7: Decision commands
@@ -245,6 +420,23 @@ export async function getPhotoDetail(slug: string, logicalPhotoId: number): Prom
   return invoke('get_photo_detail', { slug, logicalPhotoId })
 }
 
+export async function getPhotoHistogram(slug: string, logicalPhotoId: number): Promise<PhotoHistogram | null> {
+  return invoke('get_photo_histogram', { slug, logicalPhotoId })
+}
+
+export async function getFullresPreview(slug: string, logicalPhotoId: number, variant: PreviewVariant): Promise<string | null> {
+  return invoke('get_fullres_preview', { slug, logicalPhotoId, variant })
+}
+
+// Warms neighbours of the focused photo; listen for 'preview-ready' events
+export async function prefetchPreviews(slug: string, roundId: number, logicalPhotoId: number): Promise<void> {
+  return invoke('prefetch_previews', { slug, roundId, logicalPhotoId })
+}
+
+export async function cancelPrefetch(slug: string): Promise<void> {
+  return invoke('cancel_prefetch', { slug })
+}
+
 export async function getRoundDecisions(slug: string, stackId: number, roundId: number): Promise<PhotoDecisionStatus[]> {
   return invoke('get_round_decisions', { slug, stackId, roundId })
 }
@@ -258,10 +450,54 @@ export async function undoLastMerge(slug: string): Promise<void> {
   return invoke('undo_last_merge', { slug })
 }
 
+export async function splitStack(slug: string, stackId: number, logicalPhotoIds: number[]): Promise<SplitResult> {
+  return invoke('split_stack', { slug, stackId, logicalPhotoIds })
+}
+
+export async function undoLastSplit(slug: string): Promise<void> {
+  return invoke('undo_last_split', { slug })
+}
+
+export async function undoStackChange(slug: string): Promise<StackTransaction> {
+  return invoke('undo_stack_change', { slug })
+}
+
+export async function redoStackChange(slug: string): Promise<StackTransaction> {
+  return invoke('redo_stack_change', { slug })
+}
+
 export async function listStackTransactions(slug: string): Promise<StackTransaction[]> {
   return invoke('list_stack_transactions', { slug })
 }
 
+export interface CameraClock {
+  camera_model: string
+  camera_serial: string | null
+  photo_count: number
+  offset_secs: number
+}
+
+export async function listCameraClocks(slug: string): Promise<CameraClock[]> {
+  return invoke('list_camera_clocks', { slug })
+}
+
+export async function setCameraClockOffset(
+  slug: string,
+  cameraModel: string,
+  cameraSerial: string | null,
+  offsetSecs: number,
+): Promise<number> {
+  return invoke('set_camera_clock_offset', { slug, cameraModel, cameraSerial, offsetSecs })
+}
+
+export async function shiftCaptureTimes(
+  slug: string,
+  logicalPhotoIds: number[],
+  deltaSecs: number,
+): Promise<number> {
+  return invoke('shift_capture_times', { slug, logicalPhotoIds, deltaSecs })
+}
+
 // Sprint 10 Phase C: Multi-round navigation types and commands
 
 export interface RoundSummary {
@@ -280,6 +516,8 @@ export interface PhotoSnapshot {
   logical_photo_id: number
   status_in_round: DecisionStatus
   thumbnail_path: string | null
+  suggestion: QualityFlagKind | null  // the suggestion the decision was applied from
+  suggestion_confidence: number | null  // 0–1
 }
 
 export async function listRounds(slug: string, stackId: number): Promise<RoundSummary[]> {
@@ -302,3 +540,154 @@ export async function restoreEliminatedPhoto(slug: string, logicalPhotoId: numbe
   return invoke('restore_eliminated_photo', { slug, logicalPhotoId, roundId })
 }
 
+export async function addPhotoTag(slug: string, logicalPhotoId: number, tag: string): Promise<void> {
+  return invoke('add_photo_tag', { slug, logicalPhotoId, tag })
+}
+
+export async function removePhotoTag(slug: string, logicalPhotoId: number, tag: string): Promise<void> {
+  return invoke('remove_photo_tag', { slug, logicalPhotoId, tag })
+}
+
+export async function listPhotoTags(slug: string, logicalPhotoId: number): Promise<string[]> {
+  return invoke('list_photo_tags', { slug, logicalPhotoId })
+}
+
+
+// Sprint 11: GemStack — final curation
+
+export interface PromotionResult {
+  gem_stack_id: number
+  source_stack_id: number
+  source_round_id: number
+  promoted: number[]
+  removed: number[]
+}
+
+export interface GemPromotion {
+  logical_photo_id: number
+  source_stack_id: number
+  source_round_id: number
+  source_round_number: number | null
+  promoted_at: string
+}
+
+export interface GemStackSummary {
+  gem_stack_id: number
+  photo_count: number
+  promoted_stack_count: number
+}
+
+export async function promoteSurvivors(slug: string, stackId: number): Promise<PromotionResult> {
+  return invoke('promote_survivors', { slug, stackId })
+}
+
+export async function getGemStack(slug: string): Promise<GemStackSummary> {
+  return invoke('get_gem_stack', { slug })
+}
+
+export async function listGemPromotions(slug: string): Promise<GemPromotion[]> {
+  return invoke('list_gem_promotions', { slug })
+}
+
+export async function makeGemDecision(slug: string, logicalPhotoId: number, action: DecisionAction): Promise<DecisionResult> {
+  return invoke('make_gem_decision', { slug, logicalPhotoId, action })
+}
+
+export async function undoGemDecision(slug: string, logicalPhotoId: number): Promise<void> {
+  return invoke('undo_gem_decision', { slug, logicalPhotoId })
+}
+
+export async function commitGemRound(slug: string): Promise<void> {
+  return invoke('commit_gem_round', { slug })
+}
+
+export async function getGemRoundStatus(slug: string): Promise<RoundStatus> {
+  return invoke('get_gem_round_status', { slug })
+}
+
+export async function listGemRounds(slug: string): Promise<RoundSummary[]> {
+  return invoke('list_gem_rounds', { slug })
+}
+
+// Advisory quality flags: suggestions only, until applied as eliminate decisions
+
+export type QualityFlagKind = 'likely_blurred' | 'underexposed' | 'clipped_highlights'
+
+export interface QualityFlag {
+  logical_photo_id: number
+  kind: QualityFlagKind
+  confidence: number  // 0–1
+  created_at: string
+}
+
+export interface SuggestionApplyResult {
+  round_id: number
+  eliminated: number[]
+  skipped_decided: number[]  // already decided by the user this round
+}
+
+export async function listQualityFlags(slug: string, stackId: number): Promise<QualityFlag[]> {
+  return invoke('list_quality_flags', { slug, stackId })
+}
+
+export async function applyQualitySuggestions(
+  slug: string,
+  stackId: number,
+  kinds: QualityFlagKind[],
+  minConfidence: number,
+): Promise<SuggestionApplyResult> {
+  return invoke('apply_quality_suggestions', { slug, stackId, kinds, minConfidence })
+}
+
+// Sprint 12: Export
+
+export type ExportMode = 'copy' | 'hardlink' | 'reflink'
+
+export type ExportFilter = 'jpeg' | 'raw' | 'both'
+
+export interface ExportItem {
+  logical_photo_id: number
+  format: 'jpeg' | 'raw'
+  source_path: string
+  destination_path: string
+  renamed: boolean
+}
+
+export interface ExportResult {
+  dry_run: boolean
+  mode: ExportMode
+  logical_photos: number
+  items: ExportItem[]
+  files_exported: number
+  sidecars_written: number
+  errors: string[]
+}
+
+/** 'append' → IMG.CR2.xmp (darktable), 'replace' → IMG.xmp (Lightroom) */
+export type XmpNaming = 'append' | 'replace'
+
+export interface XmpWriteResult {
+  sidecars_written: number
+  errors: string[]
+}
+
+export async function exportSurvivors(
+  slug: string,
+  destination: string,
+  mode: ExportMode,
+  filter: ExportFilter,
+  roundId: number | null,
+  preserveHierarchy: boolean,
+  dryRun: boolean,
+  xmpNaming: XmpNaming | null = null,
+): Promise<ExportResult> {
+  return invoke('export_survivors', { slug, destination, mode, filter, roundId, preserveHierarchy, dryRun, xmpNaming })
+}
+
+export async function writeXmpSidecars(
+  slug: string,
+  logicalPhotoIds: number[] | null,
+  naming: XmpNaming,
+): Promise<XmpWriteResult> {
+  return invoke('write_xmp_sidecars', { slug, logicalPhotoIds, naming })
+}
@@ -574,7 +574,7 @@ describe('StackOverview — burst gap panel (BT-07..BT-11)', () => {
     await fireEvent.click(screen.getByRole('button', { name: /save/i }))
 
     await waitFor(() => {
-      expect(mockInvoke).toHaveBeenCalledWith('set_burst_gap', { secs: 10 })
+      expect(mockInvoke).toHaveBeenCalledWith('set_burst_gap', { slug: 'iceland-2024', secs: 10 })
     })
   })
 
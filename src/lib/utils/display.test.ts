@@ -33,6 +33,19 @@ describe('resolveDisplaySrc', () => {
       })
     })
 
+    it('falls back to preview_path while the source is offline', () => {
+      const photo = makePhotoDetail({
+        jpeg_path: '/media/ssd/img.jpg',
+        preview_path: '/cache/preview.jpg',
+        source_online: false,
+      })
+      const result = resolveDisplaySrc('full', photo)
+      expect(result).toEqual({
+        url: 'asset://localhost/cache/preview.jpg',
+        quality: 'preview',
+      })
+    })
+
     it('falls back to thumbnail_path when no jpeg or preview', () => {
       const photo = makePhotoDetail({
         jpeg_path: null,
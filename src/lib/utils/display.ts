@@ -19,6 +19,7 @@ type PhotoLike = PhotoDetail | LogicalPhotoSummary | null
  * Resolve a photo to its best available display source for the given mode.
  *
  * - 'full': jpeg_path → preview_path → thumbnail_path → none
+ *   (jpeg_path is skipped while the source drive is offline)
  * - 'thumbnail': thumbnail_path → none
  */
 export function resolveDisplaySrc(mode: DisplayMode, photo: PhotoLike): ResolvedDisplaySrc {
@@ -28,7 +29,7 @@ export function resolveDisplaySrc(mode: DisplayMode, photo: PhotoLike): Resolved
 
   if (mode === 'full') {
     // Full mode: try best quality first, fall back through chain
-    if ('jpeg_path' in photo && photo.jpeg_path) {
+    if ('jpeg_path' in photo && photo.jpeg_path && photo.source_online) {
       return { url: convertFileSrc(photo.jpeg_path), quality: 'jpeg' }
     }
     if ('preview_path' in photo && photo.preview_path) {
@@ -9,7 +9,7 @@ import type { PhotoDetail } from '$lib/api/index.js'
  * Returns only the single path that would actually be displayed. */
 export function getDisplayedPath(photo: PhotoDetail | null): string | null {
   if (!photo) return null
-  if (photo.jpeg_path) return photo.jpeg_path
+  if (photo.jpeg_path && photo.source_online) return photo.jpeg_path
   if (photo.preview_path) return photo.preview_path
   if (photo.thumbnail_path) return photo.thumbnail_path
   return null
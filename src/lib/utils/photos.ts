@@ -3,6 +3,7 @@ import type { PhotoDecisionStatus, DecisionStatus, LogicalPhotoSummary } from '$
 /**
  * Update a decision in a decisions array (mutates the array in-place for Svelte reactivity).
  * If the photo already has a decision, updates it; otherwise appends a new entry.
+ * A decision made by hand carries no quality suggestion.
  */
 export function updateDecisionState(
   decisions: PhotoDecisionStatus[],
@@ -11,10 +12,18 @@ export function updateDecisionState(
 ): PhotoDecisionStatus[] {
   const existing = decisions.findIndex(d => d.logical_photo_id === photoId)
   if (existing >= 0) {
-    decisions[existing] = { ...decisions[existing], current_status: status }
+    decisions[existing] = {
+      ...decisions[existing],
+      current_status: status,
+      suggestion: null,
+      suggestion_confidence: null,
+    }
     return decisions
   } else {
-    return [...decisions, { logical_photo_id: photoId, current_status: status }]
+    return [
+      ...decisions,
+      { logical_photo_id: photoId, current_status: status, suggestion: null, suggestion_confidence: null },
+    ]
   }
 }
 
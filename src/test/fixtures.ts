@@ -35,6 +35,7 @@ export const PHOTO_1: LogicalPhotoSummary = {
   shutter_speed: null,
   iso: null,
   focal_length: null,
+  sharpness: null,
 }
 
 export const PHOTO_2: LogicalPhotoSummary = {
@@ -49,6 +50,7 @@ export const PHOTO_2: LogicalPhotoSummary = {
   shutter_speed: null,
   iso: null,
   focal_length: null,
+  sharpness: null,
 }
 
 export const PHOTO_3: LogicalPhotoSummary = {
@@ -63,6 +65,7 @@ export const PHOTO_3: LogicalPhotoSummary = {
   shutter_speed: null,
   iso: null,
   focal_length: null,
+  sharpness: null,
 }
 
 export function makePhoto(overrides?: Partial<LogicalPhotoSummary>): LogicalPhotoSummary {
@@ -78,6 +81,7 @@ export function makePhoto(overrides?: Partial<LogicalPhotoSummary>): LogicalPhot
     shutter_speed: null,
     iso: null,
     focal_length: null,
+    sharpness: null,
     ...overrides,
   }
 }
@@ -90,15 +94,17 @@ export function makePhoto(overrides?: Partial<LogicalPhotoSummary>): LogicalPhot
  *
  * Usage: makeDecisionList(['keep', 'undecided', 'eliminate'])
  * Returns: [
- *   { logical_photo_id: 1, current_status: 'keep' },
- *   { logical_photo_id: 2, current_status: 'undecided' },
- *   { logical_photo_id: 3, current_status: 'eliminate' },
+ *   { logical_photo_id: 1, current_status: 'keep', suggestion: null, suggestion_confidence: null },
+ *   { logical_photo_id: 2, current_status: 'undecided', ... },
+ *   { logical_photo_id: 3, current_status: 'eliminate', ... },
  * ]
  */
 export function makeDecisionList(statuses: DecisionStatus[]): PhotoDecisionStatus[] {
   return statuses.map((current_status, i) => ({
     logical_photo_id: i + 1,
     current_status,
+    suggestion: null,
+    suggestion_confidence: null,
   }))
 }
 
@@ -147,15 +153,17 @@ export function makeRoundStatus(overrides?: Partial<RoundStatus>): RoundStatus {
 // ─── PhotoDecisionStatus fixtures + factory ─────────────────────────────────
 
 export const UNDECIDED_DECISIONS: PhotoDecisionStatus[] = [
-  { logical_photo_id: 1, current_status: 'undecided' },
-  { logical_photo_id: 2, current_status: 'undecided' },
-  { logical_photo_id: 3, current_status: 'undecided' },
+  { logical_photo_id: 1, current_status: 'undecided', suggestion: null, suggestion_confidence: null },
+  { logical_photo_id: 2, current_status: 'undecided', suggestion: null, suggestion_confidence: null },
+  { logical_photo_id: 3, current_status: 'undecided', suggestion: null, suggestion_confidence: null },
 ]
 
 export function makeDecisionStatus(overrides?: Partial<PhotoDecisionStatus>): PhotoDecisionStatus {
   return {
     logical_photo_id: 1,
     current_status: 'undecided',
+    suggestion: null,
+    suggestion_confidence: null,
     ...overrides,
   }
 }
@@ -190,9 +198,12 @@ export const PHOTO_DETAIL: PhotoDetail = {
   iso: 400,
   focal_length: 85.0,
   exposure_comp: 0.7,
+  sharpness: null,
+  histogram: null,
   jpeg_path: '/home/user/Photos/IMG_001.jpg',
   raw_path: '/home/user/Photos/IMG_001.CR3',
   preview_path: null,
+  source_online: true,
 }
 
 export function makePhotoDetail(overrides?: Partial<PhotoDetail>): PhotoDetail {